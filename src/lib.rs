@@ -11,11 +11,14 @@ pub mod threads;    // Game elements that get woven together
 pub mod tapestry;   // Living story narratives
 pub mod patterns;   // Player interface revealing the weaving
 pub mod seamstress; // Persistence across generations
+pub mod loom;       // Game session coordination
 
 /// Foundation Types - Shared domain concepts used across bounded contexts
 pub mod foundation {
+    use serde::{Deserialize, Serialize};
+
     /// Position in the game world (used by Threads and Patterns)
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
     pub struct Position {
         pub x: i32,
         pub y: i32,
@@ -0,0 +1,3236 @@
+//! The Great Loom - Coordinates all bounded contexts during play
+//!
+//! The Loom owns the live game session: the Tapestry being woven, the
+//! player's interface state, and the player's configuration. Keys typed by
+//! the player pass through key mappings, become Commands, and are executed
+//! here.
+
+use crate::patterns::{
+    atlas::{Atlas, ATLAS_FIT},
+    camera::{Camera, CameraMode},
+    cmdline::{CommandHistory, CommandLine, LineContext},
+    commands::{
+        parse_atlas_keys, parse_cmdwin_input, parse_ex_command, parse_ex_input, parse_help_keys, parse_insert_input,
+        parse_look_keys, parse_normal_keys, parse_visual_keys, AtlasAction, CmdWindowAction, Command,
+        Direction, ExCommand, InsertAction, InsertPurpose, LookAction, Motion, ViewAction, VisualAction,
+        VisualOperator,
+    },
+    config::expand_home,
+    help::{HelpAction, HelpIndex, HelpView},
+    input::InputEvent,
+    keymap::{format_keys, parse_keys, KeyMap, DEFAULT_LEADER},
+    marks::{is_mark, Mark, Marks},
+    messages::{MessageLog, Severity},
+    modes::{InsertTarget, Mode},
+    options::{OptionValue, Options},
+    selection::{Selection, VisualKind},
+    theme::Theme,
+};
+use crate::foundation::Position;
+use crate::seamstress::{self, SaveGame};
+use crate::tapestry::calendar::{self, TimeOfDay};
+use crate::tapestry::combat::{self, Exchange, Fate, Style, Weapon};
+use crate::tapestry::exploration::Exploration;
+use crate::tapestry::fov::{field_of_view, vision_radius};
+use crate::tapestry::pathfinding::{find_path, land_cost};
+use crate::tapestry::relationships::{self, Stance};
+use crate::tapestry::resolution::{self, Cause};
+use crate::tapestry::story;
+use crate::tapestry::vitals::{self, Death, Strain, Vital, VitalsEvent};
+use crate::tapestry::weather::{self, WeatherEvent};
+use crate::tapestry::weaving;
+use crate::tapestry::Tapestry;
+use crate::threads::story::{Advance, WorldEvent};
+use crate::threads::{Biome, Thread, ThreadId, ThreadKind, WeatherSystem};
+use crate::weaver::contests::{contest, ContestResult, Contestant};
+use crate::weaver::perception::{perceive, Perception};
+use crate::weaver::properties::{Stat, ThreadProperties};
+use crate::weaver::states::ThreadStates;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// View-models for the screen components
+mod view;
+
+/// Maximum nesting of mappings before giving up (vim's 'maxmapdepth')
+const MAX_MAP_DEPTH: usize = 1000;
+
+/// Maximum nesting of `:source` commands
+const MAX_SOURCE_DEPTH: usize = 50;
+
+/// Salt of the new world's story, see [`Tapestry::turn_rng`]
+const STORY_SALT: u64 = 0x4745_4e45_5349_5321;
+
+/// Salt of the parting blows struck as the player steps away from foes
+const PARTING_SALT: u64 = 0x5041_5254_494e_4721;
+
+/// Salt of the player's own blows
+const FIGHT_SALT: u64 = 0x4649_4748_5449_4e47;
+
+/// Salt of the blows the foes beside the player strike each turn
+const SKIRMISH_SALT: u64 = 0x534b_4952_4d49_5348;
+
+/// Salt of the Charisma contest when the player greets an NPC
+const GREETING_SALT: u64 = 0x4752_4545_5449_4e47;
+
+/// Most steps a single run (HJKL) takes
+const RUN_LIMIT: usize = 100;
+
+/// Most tiles a motion (w, B, 0, $) looks along the row
+const MOTION_LIMIT: usize = 1000;
+
+/// Cost of crossing land hard enough to count as a trial
+const HARD_GROUND: u32 = 3;
+
+/// Roman numerals for an heir's generation, from the second onwards
+const NUMERALS: [&str; 19] = [
+    "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X", "XI", "XII", "XIII", "XIV", "XV", "XVI", "XVII", "XVIII",
+    "XIX", "XX",
+];
+
+/// A key waiting to be processed, and whether mappings may apply to it
+#[derive(Debug, Clone, Copy)]
+struct TypeaheadKey {
+    key: char,
+    remap: bool,
+}
+
+/// What the player perceives of one Thread, for the look panel
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadReport {
+    pub name: String,
+    pub kind: &'static str,
+    /// What an NPC is about at this hour
+    pub activity: Option<&'static str>,
+    /// How the Thread regards the player, if it shows
+    pub attitude: Option<&'static str>,
+    /// Every stat, if the player noticed them
+    pub stats: Option<Vec<(Stat, u8)>>,
+    /// The states the player can see
+    pub states: Vec<(&'static str, f32)>,
+    /// Notes the player wrote about the Thread
+    pub notes: Vec<String>,
+}
+
+/// The live game session
+pub struct Loom {
+    pub tapestry: Tapestry,
+    pub player_id: ThreadId,
+    pub mode: Mode,
+    pub messages: MessageLog,
+    pub options: Options,
+    pub keymap: KeyMap,
+    /// Command lines typed by the player, for recall and the q: window
+    pub history: CommandHistory,
+    /// Variables assigned with `:let`
+    pub variables: BTreeMap<String, String>,
+    /// Every help page and tag
+    pub help: HelpIndex,
+    /// The open help viewer, if any
+    pub help_view: Option<HelpView>,
+    /// Marks set with `m` in Normal and Visual mode
+    pub marks: Marks,
+    /// The last Visual mode selection, for `gv`
+    last_selection: Option<Selection>,
+    /// The message shown while stepping through the log with `g<` and
+    /// `g>`, by its 1-based place; None for the newest
+    message_cursor: Option<usize>,
+    /// The free-look cursor while in Look mode
+    pub look_cursor: Option<Position>,
+    /// How well the player perceived each Thread looked at this time
+    perceptions: HashMap<ThreadId, Perception>,
+    /// Directory holding save files; relative names resolve against it
+    pub save_dir: PathBuf,
+    /// Name the game was last saved or loaded as, reused by `:w`
+    pub save_name: Option<String>,
+    /// The colour scheme drawing the screen
+    pub theme: Theme,
+    /// Which part of the world the map shows
+    pub camera: Camera,
+    /// The overview of the world while it is open (:atlas)
+    pub atlas: Option<Atlas>,
+    /// Where the player chose to travel on the atlas
+    pub destination: Option<Position>,
+    /// What the player's lineage has seen, and what was there
+    pub exploration: Exploration,
+    /// Tiles the player can see now
+    pub visible: HashSet<Position>,
+    /// Where the player stood and how far they saw when `visible` was cast
+    sight: Option<(Position, i32)>,
+    /// The weather system the player stands in, if any
+    weather_underfoot: Option<(ThreadId, WeatherSystem)>,
+    /// The peaceful NPCs beside the player last turn, already greeted
+    alongside: Vec<ThreadId>,
+    /// How strained the player's vitals were, and their level, when last
+    /// told of them
+    checkup: Option<([Strain; 3], u32)>,
+    /// Directory searched for colour schemes before the built-in ones
+    pub colors_dir: PathBuf,
+    running: bool,
+    typeahead: VecDeque<TypeaheadKey>,
+    pending_map: Vec<char>,
+    source_depth: usize,
+}
+
+impl Loom {
+    pub fn new(tapestry: Tapestry, player_id: ThreadId) -> Self {
+        let mut loom = Self {
+            tapestry,
+            player_id,
+            mode: Mode::default(),
+            messages: MessageLog::new(),
+            options: Options::new(),
+            keymap: KeyMap::new(),
+            history: CommandHistory::new(),
+            variables: BTreeMap::new(),
+            help: HelpIndex::new(),
+            help_view: None,
+            marks: Marks::new(),
+            last_selection: None,
+            message_cursor: None,
+            look_cursor: None,
+            perceptions: HashMap::new(),
+            save_dir: PathBuf::new(),
+            save_name: None,
+            theme: Theme::default(),
+            camera: Camera::new(),
+            atlas: None,
+            destination: None,
+            exploration: Exploration::new(),
+            visible: HashSet::new(),
+            sight: None,
+            weather_underfoot: None,
+            alongside: Vec::new(),
+            checkup: None,
+            colors_dir: PathBuf::new(),
+            running: true,
+            typeahead: VecDeque::new(),
+            pending_map: Vec::new(),
+            source_depth: 0,
+        };
+        loom.discover();
+        loom
+    }
+
+    /// A fresh game: the Wanderer in the middle of the Whispering Plains
+    pub fn new_game() -> Self {
+        Self::new_game_with_seed(rand::random())
+    }
+
+    /// A new game whose world follows `seed`
+    pub fn new_game_with_seed(seed: u64) -> Self {
+        let mut tapestry = Tapestry::with_seed(seed);
+
+        // Create the player Thread
+        let player_id = tapestry.next_id();
+        tapestry.add_thread(Thread {
+            id: player_id,
+            kind: ThreadKind::Player {
+                name: "Wanderer".to_string(),
+            },
+            properties: ThreadProperties::default(),
+            states: ThreadStates::default(),
+            position: Some(Position::new(0, 0)),
+            name: None,
+            notes: Vec::new(),
+        });
+        vitals::provision(&mut tapestry, player_id);
+        // And a staff to lean on, or to fight with
+        let staff_id = tapestry.next_id();
+        tapestry.add_thread(Thread {
+            id: staff_id,
+            kind: ThreadKind::Item {
+                name: "Walking Staff".to_string(),
+                carrier: Some(player_id),
+            },
+            properties: ThreadProperties::default(),
+            states: ThreadStates::default(),
+            position: None,
+            name: None,
+            notes: Vec::new(),
+        });
+
+        // Create a simple region Thread
+        for x in -5..=5 {
+            for y in -5..=5 {
+                if x == 0 && y == 0 {
+                    continue; // Player position
+                }
+                let region_id = tapestry.next_id();
+                tapestry.add_thread(Thread {
+                    id: region_id,
+                    kind: ThreadKind::Region {
+                        description: "Whispering Plains".to_string(),
+                    },
+                    properties: ThreadProperties {
+                        intelligence: 8,
+                        charisma: 15,
+                        ..Default::default()
+                    },
+                    states: ThreadStates::default(),
+                    position: Some(Position::new(x, y)),
+                    name: None,
+                    notes: Vec::new(),
+                });
+            }
+        }
+
+        // And the story the Wanderer is caught up in, shaping the land
+        let mut rng = tapestry.turn_rng(STORY_SALT);
+        story::generate(&mut tapestry, &mut rng);
+        weaving::weave(&mut tapestry);
+
+        Loom::new(tapestry, player_id)
+    }
+
+    /// False once the player has quit
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// The current leader key (`:let mapleader = ","`)
+    pub fn leader(&self) -> &str {
+        self.variables
+            .get("mapleader")
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_LEADER)
+    }
+
+    /// The mode to return to after a command: the help viewer while it is
+    /// open, else the atlas while it is open, else Look mode while the
+    /// look cursor is out, otherwise Normal mode
+    fn resting_mode(&self) -> Mode {
+        if self.help_view.is_some() {
+            Mode::help()
+        } else if self.atlas.is_some() {
+            Mode::atlas()
+        } else if self.look_cursor.is_some() {
+            Mode::look()
+        } else {
+            Mode::default()
+        }
+    }
+
+    /// Push option values into the subsystems that cache them
+    fn sync_options(&mut self) {
+        let verbose = self.options.get_number("verbose").clamp(0, u8::MAX as i64) as u8;
+        self.messages
+            .set_filters(self.options.get_bool("quiet"), verbose);
+        self.history
+            .set_max(self.options.get_number("history").max(0) as usize);
+    }
+
+    /// Switch colour scheme, keeping the current one if `name` fails to load
+    fn load_colorscheme(&mut self, name: &str) -> Result<(), String> {
+        let theme = Theme::load(name, &self.colors_dir);
+        let loaded = theme.as_ref().map_or(&self.theme.name, |theme| &theme.name).clone();
+        self.options
+            .set_value("colorscheme", OptionValue::String(loaded))
+            .expect("colour scheme names are valid option values");
+        self.theme = theme?;
+        Ok(())
+    }
+
+    /// The count typed before a command, capped by 'maxcount'
+    fn take_count(&mut self) -> u32 {
+        let max_count = self.options.get_number("maxcount").max(1) as u32;
+        let count = match self.mode.count_buffer() {
+            Some("") | None => 1,
+            // Digits beyond what fits in a u32 are still "a very large count"
+            Some(count_str) => count_str.parse::<u32>().unwrap_or(u32::MAX),
+        };
+
+        // Clear count buffer after executing command
+        if let Some(count_buffer) = self.mode.count_buffer_mut() {
+            count_buffer.clear();
+        }
+
+        count.min(max_count)
+    }
+
+    /// The count typed before a command, or None if none was typed
+    fn take_typed_count(&mut self) -> Option<u32> {
+        let typed = self.mode.count_buffer().is_some_and(|count| !count.is_empty());
+        let count = self.take_count();
+        typed.then_some(count)
+    }
+
+    /// Handle one input event from any frontend
+    ///
+    /// Returns false when the game should exit.
+    pub fn handle_event(&mut self, event: InputEvent) -> bool {
+        match event {
+            InputEvent::Key(key) => match key.to_char() {
+                Some(key) => self.press(key),
+                None => self.running,
+            },
+            // The next frame is drawn at the new size
+            InputEvent::Resize { .. } => self.running,
+        }
+    }
+
+    /// Handle one typed key, applying key mappings in Normal mode
+    ///
+    /// Returns false when the game should exit.
+    pub fn press(&mut self, key: char) -> bool {
+        self.typeahead.push_back(TypeaheadKey { key, remap: true });
+        self.process_typeahead();
+        self.running
+    }
+
+    fn process_typeahead(&mut self) {
+        let mut depth = 0;
+
+        while self.running {
+            let Some(next) = self.typeahead.pop_front() else {
+                break;
+            };
+
+            if !next.remap || !self.mode.is_normal() {
+                if self.pending_map.is_empty() {
+                    self.dispatch_key(next.key);
+                } else {
+                    // Held-back keys were typed first
+                    self.typeahead.push_front(next);
+                    self.flush_pending_map();
+                }
+                continue;
+            }
+
+            self.pending_map.push(next.key);
+            let lookup = self.keymap.lookup(&self.pending_map);
+            if lookup.is_prefix {
+                continue; // Wait for more keys to disambiguate
+            }
+
+            if lookup.exact.is_none() {
+                self.flush_pending_map();
+                continue;
+            }
+
+            depth += 1;
+            if depth > MAX_MAP_DEPTH {
+                self.messages.error("E223: Recursive mapping");
+                self.pending_map.clear();
+                self.typeahead.clear();
+                break;
+            }
+            let keys = std::mem::take(&mut self.pending_map);
+            self.expand_mapping(&keys);
+        }
+    }
+
+    /// Resolve keys held back while waiting for a longer mapping
+    ///
+    /// The longest mapped prefix is expanded; otherwise the first key is
+    /// executed as typed. Any remaining keys are processed again.
+    fn flush_pending_map(&mut self) {
+        if self.pending_map.is_empty() {
+            return;
+        }
+        let mapped_len = (1..=self.pending_map.len())
+            .rev()
+            .find(|&len| self.keymap.lookup(&self.pending_map[..len]).exact.is_some());
+
+        let rest = self.pending_map.split_off(mapped_len.unwrap_or(1));
+        let keys = std::mem::take(&mut self.pending_map);
+        for &key in rest.iter().rev() {
+            self.typeahead.push_front(TypeaheadKey { key, remap: true });
+        }
+
+        match mapped_len {
+            Some(_) => self.expand_mapping(&keys),
+            None => self.dispatch_key(keys[0]),
+        }
+    }
+
+    /// Queue the right-hand side of the mapping for `lhs`
+    fn expand_mapping(&mut self, lhs: &[char]) {
+        if let Some(mapping) = self.keymap.lookup(lhs).exact.cloned() {
+            for &key in mapping.rhs.iter().rev() {
+                self.typeahead.push_front(TypeaheadKey {
+                    key,
+                    remap: !mapping.noremap,
+                });
+            }
+        }
+    }
+
+    /// Translate a key into a Command for the current mode and execute it
+    fn dispatch_key(&mut self, key: char) {
+        let command = match &mut self.mode {
+            Mode::Ex { line } if key == '\n' || key == '\r' => {
+                let line = line.text().to_string();
+                self.mode = self.resting_mode();
+                self.run_from_command_line(&line);
+                return;
+            }
+            Mode::Ex { .. } => parse_ex_input(key),
+            Mode::Normal { pending, .. } => match parse_pending(pending, key, parse_normal_keys) {
+                Some(command) => command,
+                None => return, // Wait for the rest of the command
+            },
+            Mode::Visual { pending, .. } => match parse_pending(pending, key, parse_visual_keys) {
+                Some(command) => command,
+                None => return,
+            },
+            Mode::Look { pending, .. } => match parse_pending(pending, key, parse_look_keys) {
+                Some(command) => command,
+                None => return,
+            },
+            Mode::Atlas { pending, .. } => match parse_pending(pending, key, parse_atlas_keys) {
+                Some(command) => command,
+                None => return,
+            },
+            Mode::Help { pending, .. } => match parse_pending(pending, key, parse_help_keys) {
+                Some(command) => command,
+                None => return,
+            },
+            Mode::CmdWindow { .. } => parse_cmdwin_input(key),
+            Mode::Insert { .. } => parse_insert_input(key),
+        };
+        self.execute_command(command);
+    }
+
+    /// Execute a line typed by the player and remember it in the history
+    fn run_from_command_line(&mut self, line: &str) {
+        self.history.add(line);
+        if let Err(err) = self.run_ex_line(line) {
+            self.messages.error(err);
+        }
+    }
+
+    /// Execute a single Command
+    ///
+    /// Returns false when the game should exit.
+    pub fn execute_command(&mut self, command: Command) -> bool {
+        // Any key but a count or another step returns to the newest message
+        if !matches!(command, Command::OlderMessage | Command::NewerMessage | Command::CountInput(_)) {
+            self.message_cursor = None;
+        }
+        match command {
+            Command::Move(direction) => {
+                let count = self.take_count();
+
+                // Apply movement count times, a turn a step; stepping into
+                // an enemy attacks it, into anyone else trades places
+                for _ in 0..count {
+                    let Some(position) = self.player_position() else {
+                        break;
+                    };
+                    let lineage = self.player_id;
+                    let target = direction.apply_to(position);
+                    let npc = self.npc_at(target).and_then(|id| self.tapestry.get_thread(id));
+                    match npc.map(|npc| (npc.id, combat::are_enemies(&self.tapestry, npc, lineage))) {
+                        Some((npc, true)) => self.fight(npc),
+                        Some((npc, false)) => self.trade_places(npc, position, target),
+                        None => {
+                            self.step(target);
+                        }
+                    }
+                    if self.player_id == lineage {
+                        self.pass_turn();
+                    }
+                    if self.player_id != lineage {
+                        break;
+                    }
+                }
+            }
+            Command::Attack(direction) => {
+                for _ in 0..self.take_count() {
+                    let lineage = self.player_id;
+                    let Some(npc) = self.player_position().and_then(|at| self.npc_at(direction.apply_to(at))) else {
+                        self.messages.error("There is nobody there");
+                        break;
+                    };
+                    self.fight(npc);
+                    if self.player_id == lineage {
+                        self.pass_turn();
+                    }
+                    if self.player_id != lineage {
+                        break;
+                    }
+                }
+            }
+            Command::Run(direction) => {
+                for _ in 0..self.take_count() {
+                    if !self.run(direction) {
+                        break;
+                    }
+                }
+            }
+            Command::Motion(motion) => self.motion(motion),
+            Command::CountInput('0')
+                if matches!(&self.mode, Mode::Normal { count_buffer, .. } if count_buffer.is_empty()) =>
+            {
+                // As in vim, 0 is a motion unless it continues a count
+                self.motion(Motion::RegionStart);
+            }
+            Command::CountInput(digit) => {
+                if let Some(count_buffer) = self.mode.count_buffer_mut() {
+                    count_buffer.push(digit);
+                }
+            }
+            Command::EnterExMode => {
+                self.mode = Mode::Ex {
+                    line: CommandLine::new(),
+                };
+            }
+            Command::ExInput(c) => {
+                if let Mode::Ex { line } = &mut self.mode {
+                    line.insert(c);
+                }
+            }
+            Command::ExBackspace => {
+                if let Mode::Ex { line } = &mut self.mode {
+                    if line.is_empty() {
+                        // Backspace on an empty command line leaves Ex mode, as in vim
+                        self.mode = self.resting_mode();
+                    } else {
+                        line.backspace();
+                    }
+                }
+            }
+            Command::ExEdit(edit) => {
+                if let Mode::Ex { line } = &mut self.mode {
+                    let context = LineContext {
+                        history: &self.history,
+                        help: &self.help,
+                        marks: &self.marks,
+                        save_dir: &self.save_dir,
+                        colors_dir: &self.colors_dir,
+                    };
+                    line.edit(edit, &context);
+                }
+            }
+            Command::CancelEx => {
+                self.mode = self.resting_mode();
+            }
+            Command::ExCommand(ex_cmd) => {
+                self.mode = self.resting_mode(); // Return to normal mode after command
+                if let Err(err) = self.execute_ex(ex_cmd) {
+                    self.messages.error(err);
+                }
+            }
+            Command::OpenCmdWindow => {
+                self.take_count();
+                if self.history.is_empty() {
+                    self.mode = self.resting_mode();
+                    self.messages.echo("No command history");
+                } else {
+                    self.mode = Mode::CmdWindow {
+                        selected: self.history.len() - 1,
+                    };
+                }
+            }
+            Command::CmdWindow(action) => self.cmd_window(action),
+            Command::Help(action) => self.help_action(action),
+            Command::EnterInsert(purpose) => {
+                self.take_count();
+                if let Err(err) = self.enter_insert(purpose) {
+                    self.messages.error(err);
+                }
+            }
+            Command::Insert(action) => self.insert(action),
+            Command::EnterVisual(kind) => {
+                self.take_count();
+                if let Some(anchor) = self.cursor_position() {
+                    self.mode = Mode::visual(Selection::new(kind, anchor));
+                }
+            }
+            Command::Visual(action) => self.visual(action),
+            Command::View(action) => {
+                let count = self.take_count() as i32;
+                match action {
+                    ViewAction::Align(align) => self.camera.align(align),
+                    ViewAction::Scroll { dx, dy } => self.camera.scroll(dx * count, dy * count),
+                    ViewAction::Zoom(steps) => self.camera.zoom_by(steps * count),
+                }
+            }
+            Command::Atlas(action) => self.atlas_action(action),
+            Command::EnterLook => {
+                self.take_count();
+                if let Some(position) = self.player_position() {
+                    // Each look is a fresh chance to notice things
+                    self.perceptions.clear();
+                    self.mode = Mode::look();
+                    self.move_look_cursor(position);
+                }
+            }
+            Command::Look(action) => {
+                let count = self.take_count();
+                match (action, self.look_cursor) {
+                    (LookAction::Move(direction), Some(mut cursor)) => {
+                        for _ in 0..count {
+                            cursor = direction.apply_to(cursor);
+                        }
+                        self.move_look_cursor(cursor);
+                    }
+                    _ => {
+                        self.look_cursor = None;
+                        self.mode = self.resting_mode();
+                    }
+                }
+            }
+            Command::Reselect => {
+                self.take_count();
+                match self.last_selection {
+                    Some(selection) => self.mode = Mode::visual(selection),
+                    None => self.messages.error("E20: Mark not set"),
+                }
+            }
+            Command::OlderMessage => {
+                let count = self.take_count() as usize;
+                let shown = self.message_cursor.unwrap_or(self.messages.len());
+                self.show_message(shown.saturating_sub(count).max(1));
+            }
+            Command::NewerMessage => {
+                let count = self.take_count() as usize;
+                let shown = self.message_cursor.unwrap_or(self.messages.len());
+                self.show_message(shown.saturating_add(count));
+            }
+            Command::SetMark(key) => {
+                self.take_count();
+                if let Some(position) = self.cursor_position() {
+                    self.marks.set(key, Mark { position, area: None });
+                }
+            }
+            Command::GotoMark(key) => {
+                self.take_count();
+                match self.marks.get(key).map(|mark| mark.position) {
+                    Some(position) if self.look_cursor.is_some() => self.move_look_cursor(position),
+                    Some(position) if self.atlas.is_some() => {
+                        if let Some(atlas) = &mut self.atlas {
+                            atlas.cursor = position;
+                        }
+                    }
+                    Some(position) => {
+                        if let Err(err) = self.travel(position) {
+                            self.messages.error(err);
+                        }
+                    }
+                    None => self.messages.error("E20: Mark not set"),
+                }
+            }
+            Command::Unknown => {
+                // Ignore unknown commands, but forget any count typed before them
+                if let Some(count_buffer) = self.mode.count_buffer_mut() {
+                    count_buffer.clear();
+                }
+            }
+        }
+        self.discover();
+        self.running
+    }
+
+    fn cmd_window(&mut self, action: CmdWindowAction) {
+        let Mode::CmdWindow { selected } = &mut self.mode else {
+            return;
+        };
+        let last = self.history.len().saturating_sub(1);
+        match action {
+            CmdWindowAction::Down => *selected = (*selected + 1).min(last),
+            CmdWindowAction::Up => *selected = selected.saturating_sub(1),
+            CmdWindowAction::First => *selected = 0,
+            CmdWindowAction::Last => *selected = last,
+            CmdWindowAction::Execute => {
+                let line = self.history.entries()[*selected].clone();
+                self.mode = self.resting_mode();
+                self.run_from_command_line(&line);
+            }
+            CmdWindowAction::Edit => {
+                let text = &self.history.entries()[*selected];
+                self.mode = Mode::Ex {
+                    line: CommandLine::with_text(text),
+                };
+            }
+            CmdWindowAction::Close => self.mode = self.resting_mode(),
+        }
+    }
+
+    fn atlas_action(&mut self, action: AtlasAction) {
+        let count = self.take_count() as i32;
+        let Some(atlas) = &mut self.atlas else {
+            return;
+        };
+        match action {
+            AtlasAction::Move(direction) => {
+                let (dx, dy) = direction.to_delta();
+                atlas.move_by(dx * count, dy * count);
+            }
+            AtlasAction::Zoom(steps) => atlas.zoom_by(steps * count),
+            AtlasAction::SetDestination => {
+                let cursor = atlas.cursor;
+                if self.exploration.is_seen(cursor) {
+                    self.destination = Some(cursor);
+                    self.messages
+                        .echo(format!("Destination set [{},{}]", cursor.x, cursor.y));
+                    self.atlas = None;
+                    self.mode = self.resting_mode();
+                } else {
+                    self.messages.error("You do not know the way there");
+                }
+            }
+            AtlasAction::Exit => {
+                self.atlas = None;
+                self.mode = self.resting_mode();
+            }
+        }
+    }
+
+    /// Cast the player's sight after a move and remember what it reaches,
+    /// warning of hostiles that come into view
+    fn discover(&mut self) {
+        let Some(player) = self.tapestry.get_thread(self.player_id) else {
+            return;
+        };
+        let Some(origin) = player.position else {
+            return;
+        };
+        let daylight = self.tapestry.date().time_of_day().daylight();
+        let radius = vision_radius(player.properties.wisdom, daylight, weather::clarity(&self.tapestry, origin));
+        if self.sight == Some((origin, radius)) {
+            return;
+        }
+        let before = self.hostiles_in_sight();
+        self.visible = field_of_view(&self.tapestry, origin, radius);
+        self.exploration.record(&self.tapestry, &self.visible);
+        self.sight = Some((origin, radius));
+        for id in self.hostiles_in_sight() {
+            if !before.contains(&id) {
+                if let Some(hostile) = self.tapestry.get_thread(id) {
+                    self.messages.warn(format!("{} comes into view", hostile.display_name()));
+                }
+            }
+        }
+    }
+
+    /// Let a turn pass in the world after the player acts, announcing
+    /// nightfall and daybreak, letting the foes beside the player strike,
+    /// tending to vitals, and telling of the weather the player sees form,
+    /// die out or close in
+    fn pass_turn(&mut self) {
+        let before = self.tapestry.date().time_of_day();
+        self.tapestry.pass_turn();
+        let now = self.tapestry.date().time_of_day();
+        if now != before {
+            self.messages.info(match now {
+                TimeOfDay::Dawn => "Dawn breaks",
+                TimeOfDay::Day => "The sun is up",
+                TimeOfDay::Dusk => "Dusk falls",
+                TimeOfDay::Night => "Night falls",
+            });
+        }
+        self.skirmish();
+        self.tend_vitals();
+        self.witness_surroundings();
+        relationships::advance(&mut self.tapestry);
+        for event in weather::advance(&mut self.tapestry) {
+            match event {
+                WeatherEvent::Formed(id) => {
+                    let Some(system) = self.tapestry.get_thread(id) else {
+                        continue;
+                    };
+                    if system.position.is_some_and(|position| self.visible.contains(&position)) {
+                        let name = system.display_name().to_lowercase();
+                        self.messages.info(format!("A {} gathers in the distance", name));
+                    }
+                }
+                WeatherEvent::Dissipated { system, position } => {
+                    if self.visible.contains(&position) {
+                        self.messages.info(format!("The {} dies away", system.name().to_lowercase()));
+                    }
+                }
+            }
+        }
+
+        let underfoot = self
+            .player_position()
+            .and_then(|position| self.tapestry.weather_at(position))
+            .and_then(|thread| Some((thread.id, thread.weather()?)));
+        if underfoot.map(|(id, _)| id) == self.weather_underfoot.map(|(id, _)| id) {
+            return;
+        }
+        match (underfoot, self.weather_underfoot) {
+            (Some((id, system)), _) => {
+                let text = format!("You are caught in a {}", system.name().to_lowercase());
+                if system.is_severe() {
+                    self.messages.warn(text);
+                    let biome = self.player_position().and_then(|at| land_at(&self.tapestry, at)).and_then(Thread::biome);
+                    self.witness(WorldEvent::Endured { trial: id, biome });
+                } else {
+                    self.messages.info(text);
+                }
+            }
+            (None, Some((_, system))) => {
+                self.messages.info(format!("The {} is behind you", system.name().to_lowercase()));
+            }
+            (None, None) => {}
+        }
+        self.weather_underfoot = underfoot;
+    }
+
+    /// NPCs at odds with the player that the player can see, oldest first:
+    /// the corrupted, and those bearing a grudge
+    fn hostiles_in_sight(&self) -> Vec<ThreadId> {
+        let mut hostiles: Vec<ThreadId> = self
+            .tapestry
+            .threads()
+            .filter(|thread| matches!(thread.kind, ThreadKind::Npc { .. }))
+            .filter(|thread| combat::are_enemies(&self.tapestry, thread, self.player_id))
+            .filter(|thread| thread.position.is_some_and(|position| self.visible.contains(&position)))
+            .map(|thread| thread.id)
+            .collect();
+        hostiles.sort_by_key(|id| id.0);
+        hostiles
+    }
+
+    /// Record what the player met with where they stand: peaceful NPCs
+    /// alongside, hard ground underfoot and items lying there, which they
+    /// take
+    fn witness_surroundings(&mut self) {
+        let Some(position) = self.player_position() else {
+            return;
+        };
+        let mut events = Vec::new();
+        let biome = land_at(&self.tapestry, position).and_then(Thread::biome);
+        let items: Vec<ThreadId> = self
+            .tapestry
+            .threads_at(position)
+            .into_iter()
+            .filter(|thread| matches!(thread.kind, ThreadKind::Item { .. }))
+            .map(|thread| thread.id)
+            .collect();
+        for id in items {
+            let Some(item) = self.tapestry.get_thread_mut(id) else {
+                continue;
+            };
+            if let ThreadKind::Item { carrier, .. } = &mut item.kind {
+                *carrier = Some(self.player_id);
+            }
+            item.position = None;
+            self.messages.success(format!("You take the {}", item.display_name()));
+            events.push(WorldEvent::Obtained { item: id, biome });
+        }
+        if let Some(land) = land_at(&self.tapestry, position) {
+            if land.biome().is_some_and(|biome| biome.move_cost() >= HARD_GROUND) {
+                events.push(WorldEvent::Endured {
+                    trial: land.id,
+                    biome: land.biome(),
+                });
+            }
+        }
+        let mut neighbours: Vec<&Thread> = self
+            .tapestry
+            .threads()
+            .filter(|thread| matches!(thread.kind, ThreadKind::Npc { .. }) && !thread.is_hostile())
+            .filter(|thread| !self.tapestry.relationships().get(thread.id, self.player_id).is_hostile())
+            .filter(|thread| {
+                thread
+                    .position
+                    .is_some_and(|at| (at.x - position.x).abs() <= 1 && (at.y - position.y).abs() <= 1)
+            })
+            .collect();
+        neighbours.sort_by_key(|thread| thread.id.0);
+        let mut alongside = Vec::new();
+        for npc in neighbours {
+            let biome = npc.position.and_then(|at| land_at(&self.tapestry, at)).and_then(Thread::biome);
+            events.push(WorldEvent::Met { npc: npc.id, biome });
+            alongside.push(npc.id);
+        }
+        let arrivals: Vec<ThreadId> = alongside.iter().copied().filter(|id| !self.alongside.contains(id)).collect();
+        self.alongside = alongside;
+        for npc in arrivals {
+            self.greet(npc);
+        }
+        for event in events {
+            self.witness(event);
+        }
+    }
+
+    /// The NPC standing at `position`, if any
+    fn npc_at(&self, position: Position) -> Option<ThreadId> {
+        self.tapestry
+            .threads_at(position)
+            .into_iter()
+            .find(|thread| matches!(thread.kind, ThreadKind::Npc { .. }))
+            .map(|thread| thread.id)
+    }
+
+    /// Move the player to `to`, breaking away from the foes beside them
+    /// that would be left behind; each that keeps up gets a parting blow.
+    /// Hard ground tires them.
+    ///
+    /// Returns false if the player fell on the way.
+    fn step(&mut self, to: Position) -> bool {
+        let lineage = self.player_id;
+        let nerve = self.nerve();
+        let mut rng = self.tapestry.turn_rng(PARTING_SALT);
+        for foe in combat::foes_beside(&self.tapestry, lineage) {
+            let Some(thread) = self.tapestry.get_thread(foe) else {
+                continue;
+            };
+            if thread.position.is_some_and(|at| combat::is_beside(at, to)) {
+                continue;
+            }
+            let (name, style) = (thread.display_name().to_string(), Style::suited(&thread.properties));
+            if combat::break_away(&self.tapestry, &mut rng, lineage, foe) {
+                self.messages.echo(format!("You break away from {}", name));
+            } else if let Some(exchange) = combat::attack(&mut self.tapestry, &mut rng, foe, lineage, style) {
+                self.narrate(exchange);
+            }
+            if self.player_id != lineage {
+                return false;
+            }
+        }
+        self.check_nerve(nerve);
+        if let Some(player) = self.tapestry.get_thread_mut(lineage) {
+            player.position = Some(to);
+        }
+        let toll = vitals::toll(land_at(&self.tapestry, to).and_then(Thread::biome));
+        if let Some(death) = vitals::exert(&mut self.tapestry, lineage, toll) {
+            self.succeed(death);
+            return false;
+        }
+        true
+    }
+
+    /// Step the player from `from` onto `to` where the peaceful `npc`
+    /// stands, and it onto where they stood
+    fn trade_places(&mut self, npc: ThreadId, from: Position, to: Position) {
+        if !self.step(to) {
+            return;
+        }
+        if let Some(thread) = self.tapestry.get_thread_mut(npc) {
+            thread.position = Some(from);
+            let name = thread.display_name().to_string();
+            self.messages.info(format!("You trade places with {}", name));
+        }
+    }
+
+    /// Strike at `npc` in the player's 'fightstyle'
+    fn fight(&mut self, npc: ThreadId) {
+        let style = Style::parse(self.options.get_str("fightstyle")).unwrap_or_default();
+        let nerve = self.nerve();
+        let mut rng = self.tapestry.turn_rng(FIGHT_SALT);
+        if let Some(exchange) = combat::attack(&mut self.tapestry, &mut rng, self.player_id, npc, style) {
+            self.narrate(exchange);
+        }
+        self.check_nerve(nerve);
+    }
+
+    /// Let every foe beside the player strike at them, oldest first
+    fn skirmish(&mut self) {
+        let lineage = self.player_id;
+        let foes = combat::foes_beside(&self.tapestry, lineage);
+        if foes.is_empty() {
+            return;
+        }
+        let nerve = self.nerve();
+        let mut rng = self.tapestry.turn_rng(SKIRMISH_SALT);
+        for foe in foes {
+            let Some(style) = self.tapestry.get_thread(foe).map(|thread| Style::suited(&thread.properties)) else {
+                continue;
+            };
+            if let Some(exchange) = combat::attack(&mut self.tapestry, &mut rng, foe, lineage, style) {
+                self.narrate(exchange);
+            }
+            if self.player_id != lineage {
+                return;
+            }
+        }
+        self.check_nerve(nerve);
+    }
+
+    /// How Stressed the player is
+    fn nerve(&self) -> f32 {
+        self.tapestry
+            .get_thread(self.player_id)
+            .map_or(0.0, |player| player.states.stressed)
+    }
+
+    /// Warn the player if their morale broke since it stood at `before`
+    fn check_nerve(&mut self, before: f32) {
+        if before < combat::BREAKING && self.nerve() >= combat::BREAKING {
+            self.messages.warn("Your nerve is failing");
+        }
+    }
+
+    /// Let the world tend to everyone's vitals this turn, telling the
+    /// player what they ate and how they fare
+    fn tend_vitals(&mut self) {
+        for event in vitals::advance(&mut self.tapestry) {
+            match event {
+                VitalsEvent::Ate { supplies: 0, .. } => self.messages.warn("You eat the last of your supplies"),
+                VitalsEvent::Ate { supplies, .. } => {
+                    self.messages.info(format!("You eat from your supplies ({} left)", supplies))
+                }
+                VitalsEvent::Perished { thread, death } if thread == self.player_id => self.succeed(death),
+                VitalsEvent::Perished { .. } => {}
+            }
+        }
+        self.check_vitals();
+    }
+
+    /// Tell the player of each vital that crossed a threshold, and of any
+    /// level gained, since they were last told
+    fn check_vitals(&mut self) {
+        let Some(now) = vitals::strains(&self.tapestry, self.player_id) else {
+            return;
+        };
+        let level = self
+            .tapestry
+            .get_thread(self.player_id)
+            .map_or(1, |player| vitals::level(&player.states));
+        if let Some((before, was)) = self.checkup {
+            for (vital, (before, now)) in Vital::ALL.into_iter().zip(before.into_iter().zip(now)) {
+                if now > before {
+                    self.messages.warn(vital.report(now, true));
+                } else if now < before {
+                    self.messages.info(vital.report(now, false));
+                }
+            }
+            if level > was {
+                self.messages.success(format!("You reach level {}", level));
+            }
+        }
+        self.checkup = Some((now, level));
+    }
+
+    /// Tell the player how an exchange of blows went, and see to the
+    /// fallen
+    fn narrate(&mut self, exchange: Exchange) {
+        let by_player = exchange.attacker == self.player_id;
+        let (verb, verbs) = exchange.weapon.verbs();
+        let with = match exchange.weapon {
+            Weapon::Fists => String::new(),
+            weapon => format!(" with {} {}", if by_player { "your" } else { "their" }, weapon.name()),
+        };
+        let (attacker, defender) = (&exchange.attacker_name, &exchange.defender_name);
+        match (by_player, exchange.damage) {
+            (true, Some(damage)) => self
+                .messages
+                .echo(format!("You {} {}{} ({} damage)", verb, defender, with, damage)),
+            (true, None) => self.messages.echo(format!("You miss {}", defender)),
+            (false, Some(damage)) => {
+                let (hp, max) = self
+                    .tapestry
+                    .get_thread(self.player_id)
+                    .map_or((0, 0), |player| (combat::hp(player), combat::max_hp(player)));
+                self.messages.warn(format!(
+                    "{} {} you{} ({} damage, {}/{} HP)",
+                    attacker, verbs, with, damage, hp, max
+                ));
+            }
+            (false, None) => self.messages.echo(format!("{} misses you", attacker)),
+        }
+        match exchange.fate {
+            Some(Fate::Slain) if exchange.defender == self.player_id => self.succeed(Death::Slain(exchange.attacker)),
+            Some(Fate::Slain) => {
+                combat::fall(&mut self.tapestry, exchange.defender, exchange.attacker);
+                self.alongside.retain(|id| *id != exchange.defender);
+                self.messages.success(format!("{} falls", defender));
+            }
+            Some(Fate::Fled(_)) => self.messages.echo(format!("{} flees", defender)),
+            Some(Fate::Surrendered) => self.messages.success(format!("{} yields", defender)),
+            None => {}
+        }
+    }
+
+    /// The player has died: their heir takes up the lineage at its home in
+    /// the heart of the world, with what they carried, the regions the
+    /// lineage knew well and what the world made of them
+    fn succeed(&mut self, death: Death) {
+        let ancestor = self.player_id;
+        let Some(fallen) = self.tapestry.get_thread(ancestor).cloned() else {
+            return;
+        };
+        let heir = self.tapestry.next_id();
+        let name = heir_name(fallen.display_name());
+        self.tapestry.add_thread(Thread {
+            id: heir,
+            kind: ThreadKind::Player { name: name.clone() },
+            properties: fallen.properties,
+            states: ThreadStates::default(),
+            position: Some(Position::new(0, 0)),
+            name: None,
+            notes: fallen.notes,
+        });
+        for item in combat::carried(&self.tapestry, ancestor) {
+            if let Some(ThreadKind::Item { carrier, .. }) = self.tapestry.get_thread_mut(item).map(|item| &mut item.kind) {
+                *carrier = Some(heir);
+            }
+        }
+        vitals::provision(&mut self.tapestry, heir);
+        self.tapestry.relationships_mut().inherit(ancestor, heir);
+        self.exploration = self.exploration.inherit(&self.tapestry);
+        if let Some(entry) = vitals::perish(&mut self.tapestry, ancestor, death) {
+            self.messages.error(entry);
+        }
+        let entry = format!("{} took up the lineage", name);
+        self.tapestry.write_chronicle(entry.clone());
+        self.messages.echo(entry);
+
+        self.player_id = heir;
+        self.alongside.clear();
+        self.perceptions.clear();
+        self.sight = None;
+        self.weather_underfoot = None;
+        self.destination = None;
+        self.checkup = None;
+    }
+
+    /// Greet an NPC who has just come alongside: the player's Charisma
+    /// against its Wisdom decides whether it warms to them
+    fn greet(&mut self, npc: ThreadId) {
+        let (Some(player), Some(thread)) = (self.tapestry.get_thread(self.player_id), self.tapestry.get_thread(npc)) else {
+            return;
+        };
+        let graph = self.tapestry.relationships();
+        let greeter = Contestant::new(&player.properties, &player.states, Stat::Charisma)
+            .with_modifier(graph.modifier(self.player_id, npc));
+        let greeted = Contestant::new(&thread.properties, &thread.states, Stat::Wisdom)
+            .with_modifier(graph.modifier(npc, self.player_id));
+        let won = contest(&mut self.tapestry.turn_rng(GREETING_SALT), greeter, greeted) == ContestResult::Success;
+        let name = thread.display_name().to_string();
+        self.tapestry
+            .relationships_mut()
+            .contested(self.player_id, npc, won, Stance::Friendly);
+        if won {
+            self.messages.info(format!("{} greets you warmly", name));
+            if vitals::restock(&mut self.tapestry, self.player_id, 1) > 0 {
+                self.messages.info(format!("{} shares their food with you", name));
+            }
+        } else {
+            self.messages.info(format!("{} keeps their distance", name));
+        }
+    }
+
+    /// Record a world event against the stories and tell the player how
+    /// they moved on
+    fn witness(&mut self, event: WorldEvent) {
+        for (id, advance) in story::record(&mut self.tapestry, &event) {
+            let title = self
+                .tapestry
+                .get_thread(id)
+                .map(|thread| thread.display_name().to_string())
+                .unwrap_or_default();
+            match advance {
+                Advance::GoalMet(goal) => self.messages.success(format!("Goal met: {}", goal)),
+                Advance::StageCompleted(stage) => self.messages.success(format!("{}: {} is over", title, stage)),
+                Advance::Completed => self.messages.success(format!("{} has reached its end", title)),
+            }
+        }
+    }
+
+    /// Walk the player to `goal` over ground the lineage has seen, a step
+    /// a turn, stopping early if anything warns the player
+    fn travel(&mut self, goal: Position) -> Result<(), String> {
+        let start = self.player_position().ok_or("You are nowhere")?;
+        if !self.exploration.is_seen(goal) {
+            return Err("You do not know the way there".to_string());
+        }
+        let occupied = self.occupied_in_sight();
+        let route = find_path(start, goal, |position| self.route_cost(position, &occupied))
+            .ok_or("You know of no way there")?;
+        if self.walk(route) && self.destination == Some(goal) {
+            self.destination = None;
+            self.messages.info("You reach your destination");
+        }
+        Ok(())
+    }
+
+    /// Step the player along `route` a tile a turn, returning false if a
+    /// step was blocked or anything warned the player on the way
+    fn walk(&mut self, route: impl IntoIterator<Item = Position>) -> bool {
+        for step in route {
+            if self.route_cost(step, &self.occupied_in_sight()).is_none() {
+                self.messages.warn("Something blocks the way");
+                return false;
+            }
+            let seen = self.messages.len();
+            if !self.step(step) {
+                return false;
+            }
+            self.pass_turn();
+            self.discover();
+            let warned = self.messages.iter().skip(seen).any(|message| message.severity == Severity::Warning);
+            if warned {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Run in a direction until something interesting appears: the land
+    /// changes, someone comes into sight, the way is blocked or anything
+    /// warns the player
+    ///
+    /// Returns false if the run was cut short by a block or warning.
+    fn run(&mut self, direction: Direction) -> bool {
+        let Some(mut position) = self.player_position() else {
+            return false;
+        };
+        let land = self.exploration.land(position).map(str::to_string);
+        for _ in 0..RUN_LIMIT {
+            let next = direction.apply_to(position);
+            if self.route_cost(next, &self.occupied_in_sight()).is_none() {
+                return false;
+            }
+            let company = self.others_in_sight();
+            if !self.walk([next]) {
+                return false;
+            }
+            position = next;
+            let newcomer = self.others_in_sight().iter().any(|id| !company.contains(id));
+            if newcomer || self.exploration.land(position) != land.as_deref() {
+                break;
+            }
+        }
+        true
+    }
+
+    /// Travel by a motion over the remembered map
+    fn motion(&mut self, motion: Motion) {
+        let typed = self.take_typed_count();
+        let count = typed.unwrap_or(1);
+        let Some(start) = self.player_position() else {
+            return;
+        };
+        let goal = match motion {
+            Motion::NextBoundary => (0..count).try_fold(start, |from, _| self.boundary(from, 1)),
+            Motion::PrevBoundary => (0..count).try_fold(start, |from, _| self.boundary(from, -1)),
+            Motion::RegionStart => Some(self.region_edge(start, -1)),
+            Motion::RegionEnd => (1..count).try_fold(self.region_edge(start, 1), |edge, _| {
+                self.boundary(edge, 1).map(|next| self.region_edge(next, 1))
+            }),
+            Motion::MapTop | Motion::MapBottom => self.exploration.bounds().and_then(|(low, high)| {
+                let row = match (motion, typed) {
+                    (_, Some(count)) => (low.y + count as i32 - 1).min(high.y),
+                    (Motion::MapTop, None) => low.y,
+                    _ => high.y,
+                };
+                self.nearest_seen_in_row(start.x, row)
+            }),
+        };
+        match goal {
+            Some(goal) if goal != start => {
+                if let Err(err) = self.travel(goal) {
+                    self.messages.error(err);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The first tile `dx` along the row from `from` where the remembered
+    /// land changes, or None if memory runs out first
+    fn boundary(&self, from: Position, dx: i32) -> Option<Position> {
+        let land = self.exploration.land(from);
+        let mut position = from;
+        for _ in 0..MOTION_LIMIT {
+            position.x += dx;
+            if !self.exploration.is_seen(position) {
+                return None;
+            }
+            if self.exploration.land(position) != land {
+                return Some(position);
+            }
+        }
+        None
+    }
+
+    /// The last remembered tile `dx` along the row from `from` on the same
+    /// land
+    fn region_edge(&self, from: Position, dx: i32) -> Position {
+        let land = self.exploration.land(from);
+        let mut edge = from;
+        for _ in 0..MOTION_LIMIT {
+            let next = Position::new(edge.x + dx, edge.y);
+            if !self.exploration.is_seen(next) || self.exploration.land(next) != land {
+                break;
+            }
+            edge = next;
+        }
+        edge
+    }
+
+    /// The remembered tile in `row` closest to column `x`
+    fn nearest_seen_in_row(&self, x: i32, row: i32) -> Option<Position> {
+        self.exploration
+            .tiles()
+            .filter(|tile| tile.y == row)
+            .min_by_key(|tile| ((tile.x - x).abs(), tile.x))
+    }
+
+    /// Tiles where the player can see someone else standing
+    fn occupied_in_sight(&self) -> HashSet<Position> {
+        self.others_in_sight()
+            .into_iter()
+            .filter_map(|id| self.tapestry.get_thread(id)?.position)
+            .collect()
+    }
+
+    /// NPCs the player can see
+    fn others_in_sight(&self) -> HashSet<ThreadId> {
+        self.tapestry
+            .threads()
+            .filter(|thread| thread.id != self.player_id && matches!(thread.kind, ThreadKind::Npc { .. }))
+            .filter(|thread| thread.position.is_some_and(|position| self.visible.contains(&position)))
+            .map(|thread| thread.id)
+            .collect()
+    }
+
+    /// What the player expects a tile to cost to enter: the land as last
+    /// seen, unless someone can be seen standing there
+    fn route_cost(&self, position: Position, occupied: &HashSet<Position>) -> Option<u32> {
+        if !self.exploration.is_seen(position) || occupied.contains(&position) {
+            return None;
+        }
+        Some(land_cost(self.exploration.land(position).and_then(Biome::of)))
+    }
+
+    fn player_position(&self) -> Option<Position> {
+        self.tapestry.get_thread(self.player_id)?.position
+    }
+
+    fn visual(&mut self, action: VisualAction) {
+        let count = self.take_count();
+        let Mode::Visual { selection, .. } = &mut self.mode else {
+            return;
+        };
+        match action {
+            VisualAction::Move(direction) => {
+                for _ in 0..count {
+                    selection.cursor = direction.apply_to(selection.cursor);
+                }
+            }
+            VisualAction::SwapEnds => selection.swap_ends(),
+            VisualAction::Switch(kind) if kind != selection.kind => selection.kind = kind,
+            VisualAction::Switch(_) | VisualAction::Exit => {
+                self.last_selection = Some(*selection);
+                self.mode = self.resting_mode();
+            }
+            VisualAction::Operator(operator) => {
+                let selection = *selection;
+                self.last_selection = Some(selection);
+                self.mode = self.resting_mode();
+                self.apply_operator(operator, selection);
+            }
+        }
+    }
+
+    /// Apply a Visual mode operator to the selected area
+    fn apply_operator(&mut self, operator: VisualOperator, selection: Selection) {
+        match operator {
+            VisualOperator::Look => {
+                let positions = selection.positions();
+                let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+                for position in &positions {
+                    for thread in self.tapestry.threads_at(*position) {
+                        if thread.id != self.player_id {
+                            *seen.entry(thread.display_name().to_string()).or_default() += 1;
+                        }
+                    }
+                }
+                let (width, height) = selection.size();
+                self.messages
+                    .echo(format!("{}x{} area, {} tiles", width, height, positions.len()));
+                if seen.is_empty() {
+                    self.messages.echo("  Nothing there");
+                }
+                for (name, count) in seen {
+                    self.messages.echo(format!("  {} ({})", name, count));
+                }
+            }
+            VisualOperator::Mark(key) => self.marks.set(
+                key,
+                Mark {
+                    position: selection.anchor,
+                    area: Some(selection),
+                },
+            ),
+        }
+    }
+
+    /// What the camera keeps in view: the cursor, or the mark it is
+    /// locked to while that mark is set
+    pub fn camera_target(&self) -> Option<Position> {
+        match self.camera.mode {
+            CameraMode::Locked(key) => self
+                .marks
+                .get(key)
+                .map(|mark| mark.position)
+                .or_else(|| self.cursor_position()),
+            _ => self.cursor_position(),
+        }
+    }
+
+    /// The atlas cursor while the atlas is open, the look cursor in Look
+    /// mode, otherwise the player's position
+    pub fn cursor_position(&self) -> Option<Position> {
+        self.atlas
+            .map(|atlas| atlas.cursor)
+            .or(self.look_cursor)
+            .or_else(|| self.player_position())
+    }
+
+    /// Move the look cursor, contesting perception of anything new there;
+    /// whatever the player sees through completely is studied for the
+    /// stories
+    fn move_look_cursor(&mut self, position: Position) {
+        self.look_cursor = Some(position);
+        let Some(player) = self.tapestry.get_thread(self.player_id) else {
+            return;
+        };
+        let (props, states) = (player.properties, player.states);
+        // Weather around either side clouds or sharpens the look
+        let sight = player.position.map_or(0, |at| weather::modifier(&self.tapestry, at, Stat::Wisdom));
+        let cover = weather::modifier(&self.tapestry, position, Stat::Dexterity);
+        let mut studied = Vec::new();
+        let mut watched = Vec::new();
+        for thread in threads_seen_at(&self.tapestry, position) {
+            if self.perceptions.contains_key(&thread.id) {
+                continue;
+            }
+            let perception = if thread.id == self.player_id {
+                Perception::Full
+            } else {
+                // The sleeping are easy to study, prowlers hard to pin down
+                let cover = cover + calendar::activity(&self.tapestry, thread).map_or(0, |a| a.concealment());
+                // Those the player knows are easier to read
+                let sight = sight + self.tapestry.relationships().modifier(self.player_id, thread.id);
+                let perception = perceive(&props, &states, sight, &thread.properties, &thread.states, cover);
+                watched.push((thread.id, perception == Perception::Full));
+                if perception == Perception::Full {
+                    let land = thread.position.and_then(|at| land_at(&self.tapestry, at));
+                    let biome = thread.biome().or_else(|| land.and_then(Thread::biome));
+                    studied.push(WorldEvent::Studied {
+                        subject: thread.id,
+                        biome,
+                    });
+                }
+                perception
+            };
+            self.perceptions.insert(thread.id, perception);
+        }
+        for (thread, won) in watched {
+            self.tapestry
+                .relationships_mut()
+                .contested(self.player_id, thread, won, Stance::Watching);
+        }
+        for event in studied {
+            self.witness(event);
+        }
+    }
+
+    /// What the player perceives of each Thread at `position`
+    pub fn inspect(&self, position: Position) -> Vec<ThreadReport> {
+        threads_seen_at(&self.tapestry, position)
+            .into_iter()
+            .map(|thread| {
+                let perception = self
+                    .perceptions
+                    .get(&thread.id)
+                    .copied()
+                    .unwrap_or(Perception::Surface);
+                ThreadReport {
+                    name: thread.display_name().to_string(),
+                    kind: thread.kind.label(),
+                    activity: calendar::activity(&self.tapestry, thread).map(|activity| activity.name()),
+                    attitude: (thread.id != self.player_id)
+                        .then(|| self.tapestry.relationships().get(thread.id, self.player_id).attitude())
+                        .flatten(),
+                    stats: (perception == Perception::Full).then(|| {
+                        Stat::ALL
+                            .iter()
+                            .map(|stat| (*stat, thread.properties.get_stat(*stat)))
+                            .collect()
+                    }),
+                    states: thread
+                        .states
+                        .named()
+                        .into_iter()
+                        .filter(|(_, value)| perception.reveals_state(*value))
+                        .collect(),
+                    notes: thread.notes.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// The Thread under the cursor: an NPC if there is one, else the tile
+    fn thread_under_cursor(&self) -> Option<&Thread> {
+        let position = self.cursor_position()?;
+        self.tapestry
+            .threads_at(position)
+            .into_iter()
+            .filter(|thread| thread.id != self.player_id)
+            .min_by_key(|thread| matches!(thread.kind, ThreadKind::Region { .. }))
+    }
+
+    /// The region Thread (tile) under the cursor
+    fn tile_under_cursor(&self) -> Option<&Thread> {
+        let position = self.cursor_position()?;
+        self.tapestry
+            .threads_at(position)
+            .into_iter()
+            .find(|thread| matches!(thread.kind, ThreadKind::Region { .. }))
+    }
+
+    fn enter_insert(&mut self, purpose: InsertPurpose) -> Result<(), String> {
+        let (target, text) = match purpose {
+            InsertPurpose::Rename => {
+                let thread = self
+                    .thread_under_cursor()
+                    .ok_or_else(|| "Nothing here to name".to_string())?;
+                (InsertTarget::Rename(thread.id), thread.display_name().to_string())
+            }
+            InsertPurpose::Annotate => {
+                let tile = self
+                    .tile_under_cursor()
+                    .ok_or_else(|| "No tile here to annotate".to_string())?;
+                (InsertTarget::Annotate(tile.id), String::new())
+            }
+            InsertPurpose::Journal => (InsertTarget::Journal(self.player_id), String::new()),
+        };
+        self.mode = Mode::Insert {
+            target,
+            line: CommandLine::with_text(&text),
+        };
+        Ok(())
+    }
+
+    fn insert(&mut self, action: InsertAction) {
+        let Mode::Insert { target, line } = &mut self.mode else {
+            return;
+        };
+        match action {
+            InsertAction::Char(c) => line.insert(c),
+            InsertAction::Backspace => line.backspace(),
+            InsertAction::Edit(edit) => {
+                let context = LineContext {
+                    history: &self.history,
+                    help: &self.help,
+                    marks: &self.marks,
+                    save_dir: &self.save_dir,
+                    colors_dir: &self.colors_dir,
+                };
+                line.edit(edit, &context);
+            }
+            InsertAction::Commit => {
+                let target = *target;
+                let text = line.text().trim().to_string();
+                self.mode = self.resting_mode();
+                if let Err(err) = self.commit_insert(target, text) {
+                    self.messages.error(err);
+                }
+            }
+            InsertAction::Cancel => self.mode = self.resting_mode(),
+        }
+    }
+
+    /// Store text typed in Insert mode on its target
+    fn commit_insert(&mut self, target: InsertTarget, text: String) -> Result<(), String> {
+        match target {
+            InsertTarget::Rename(id) => {
+                if let Some(thread) = self.tapestry.get_thread_mut(id) {
+                    // An empty name restores the Thread's own name
+                    thread.name = (!text.is_empty()).then(|| text.clone());
+                    let name = thread.display_name().to_string();
+                    self.messages.success(format!("Named \"{}\"", name));
+                }
+            }
+            InsertTarget::Annotate(id) | InsertTarget::Journal(id) => {
+                if text.is_empty() {
+                    return Ok(());
+                }
+                if let Some(thread) = self.tapestry.get_thread_mut(id) {
+                    thread.notes.push(text);
+                    self.messages.success(match target {
+                        InsertTarget::Journal(_) => "Journal entry added",
+                        _ => "Note added",
+                    });
+                }
+            }
+            InsertTarget::SaveName => {
+                if text.is_empty() {
+                    return Err("E32: No file name".to_string());
+                }
+                self.write_save(&text)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Save the game as `name`, remembering the name for later `:w`
+    fn write_save(&mut self, name: &str) -> Result<(), String> {
+        let path = seamstress::save_path(&self.save_dir, name);
+        let game = SaveGame::new(self.tapestry.clone(), self.player_id, self.exploration.clone());
+        seamstress::save(&path, &game)
+            .map_err(|err| format!("E212: Can't open file for writing: {}: {}", name, err))?;
+        self.save_name = Some(name.to_string());
+        self.messages.echo(format!("\"{}\" written", name));
+        Ok(())
+    }
+
+    /// Replace the current game with the save called `name`
+    fn load_save(&mut self, name: &str) -> Result<(), String> {
+        let path = seamstress::save_path(&self.save_dir, name);
+        let game = seamstress::load(&path)
+            .map_err(|err| format!("E484: Can't open file {}: {}", name, err))?;
+        let years = game.years_away(self.options.get_number("worldyear").max(0) as u64);
+        self.tapestry = game.tapestry;
+        self.player_id = game.player_id;
+        self.exploration = game.exploration;
+        self.perceptions.clear();
+        self.look_cursor = None;
+        self.atlas = None;
+        self.mode = self.resting_mode();
+        self.destination = None;
+        self.sight = None;
+        self.weather_underfoot = None;
+        self.alongside.clear();
+        self.checkup = None;
+        self.discover();
+        self.save_name = Some(name.to_string());
+        self.messages.echo(format!("\"{}\" loaded", name));
+
+        // The world went on while the save lay unloaded
+        if years > 0 {
+            let turns = years * calendar::TURNS_PER_YEAR;
+            self.tapestry.pass_time(turns);
+            self.tapestry
+                .relationships_mut()
+                .decay(turns / relationships::DECAY_INTERVAL);
+            let passed = if years == 1 { "A year".to_string() } else { format!("{} years", years) };
+            self.messages.echo(format!("{} passed while you were away", passed));
+        }
+        if years >= self.options.get_number("absence").max(1) as u64 {
+            self.resolve_stories(Cause::Absence { years });
+        }
+        Ok(())
+    }
+
+    /// Show the message at 1-based `place` on the message line, or the
+    /// newest from there on
+    fn show_message(&mut self, place: usize) {
+        self.message_cursor = (place < self.messages.len()).then_some(place);
+    }
+
+    /// Let every story under way resolve itself, telling the player what
+    /// became of each; false if there were none
+    fn resolve_stories(&mut self, cause: Cause) -> bool {
+        let resolutions = resolution::resolve(&mut self.tapestry, cause);
+        for resolution in &resolutions {
+            self.messages.warn(resolution.entry.clone());
+        }
+        !resolutions.is_empty()
+    }
+
+    fn help_action(&mut self, action: HelpAction) {
+        let count = self.take_count() as usize;
+        if action == HelpAction::Close {
+            self.close_help();
+            return;
+        }
+        let Some(view) = &mut self.help_view else {
+            return;
+        };
+        if let Err(err) = view.apply(action, count, &self.help) {
+            self.messages.error(err);
+        } else if matches!(action, HelpAction::NextMatch | HelpAction::PrevMatch) {
+            self.echo_help_match();
+        }
+    }
+
+    fn close_help(&mut self) {
+        self.help_view = None;
+        self.mode = self.resting_mode();
+    }
+
+    /// Report the current :helpgrep match, e.g. "(2 of 5): text"
+    fn echo_help_match(&mut self) {
+        let Some(view) = &self.help_view else {
+            return;
+        };
+        let Some((current, total)) = view.match_position() else {
+            return;
+        };
+        let line = self
+            .help
+            .page(view.page)
+            .and_then(|page| page.lines.get(view.cursor))
+            .map(|line| line.trim().to_string())
+            .unwrap_or_default();
+        self.messages
+            .echo(format!("({} of {}): {}", current, total, line));
+    }
+
+    /// Parse and execute one line of ex commands (without the leading `:`)
+    pub fn run_ex_line(&mut self, line: &str) -> Result<(), String> {
+        let line = line.trim_start().trim_start_matches(':');
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        match parse_ex_command(line) {
+            Some(ex_cmd) => self.execute_ex(ex_cmd),
+            None => Err(format!("E492: Not an editor command: {}", line.trim())),
+        }
+    }
+
+    /// Execute a parsed ex command
+    pub fn execute_ex(&mut self, ex_cmd: ExCommand) -> Result<(), String> {
+        match ex_cmd {
+            ExCommand::Quit => {
+                // Like closing vim's help window, :q leaves help before the game
+                if self.help_view.is_some() {
+                    self.close_help();
+                } else {
+                    self.running = false;
+                }
+            }
+            ExCommand::Set(args) => {
+                let listing = match args.as_str() {
+                    "" => Some(self.options.changed()),
+                    "all" => Some(self.options.all()),
+                    _ => None,
+                };
+                if let Some(listing) = listing {
+                    for line in listing {
+                        self.messages.echo(line);
+                    }
+                    return Ok(());
+                }
+                for arg in args.split_whitespace() {
+                    let result = self.options.apply(arg);
+                    self.sync_options();
+                    if let Some(report) = result? {
+                        self.messages.echo(report);
+                    }
+                    let colorscheme = self.options.get_str("colorscheme").to_string();
+                    if colorscheme != self.theme.name {
+                        self.load_colorscheme(&colorscheme)?;
+                    }
+                }
+            }
+            ExCommand::Map { lhs, rhs, noremap } => {
+                let leader = self.leader().to_string();
+                let lhs = parse_keys(&lhs, &leader);
+                let rhs = parse_keys(&rhs, &leader);
+                self.keymap.insert(lhs, rhs, noremap);
+            }
+            ExCommand::ListMaps => {
+                if self.keymap.is_empty() {
+                    self.messages.echo("No mapping found");
+                }
+                let listing: Vec<String> = self
+                    .keymap
+                    .iter()
+                    .map(|(lhs, mapping)| {
+                        let marker = if mapping.noremap { "*" } else { " " };
+                        format!("{:<12}{} {}", format_keys(lhs), marker, format_keys(&mapping.rhs))
+                    })
+                    .collect();
+                for line in listing {
+                    self.messages.echo(line);
+                }
+            }
+            ExCommand::Unmap(lhs) => {
+                let keys = parse_keys(&lhs, self.leader());
+                if !self.keymap.remove(&keys) {
+                    return Err(format!("E31: No such mapping: {}", lhs));
+                }
+            }
+            ExCommand::Let { name, value } => {
+                let name = name.strip_prefix("g:").unwrap_or(&name).to_string();
+                let value = parse_let_value(&value)?;
+                self.variables.insert(name, value);
+            }
+            ExCommand::Source(file) => {
+                let path = expand_home(&file);
+                self.source(&path)
+                    .map_err(|err| format!("E484: Can't open file {}: {}", file, err))?;
+            }
+            ExCommand::Help(topic) => {
+                let topic = if topic.is_empty() { "help" } else { topic.as_str() };
+                let location = self
+                    .help
+                    .find(topic)
+                    .ok_or_else(|| format!("E149: Sorry, no help for {}", topic))?;
+                match &mut self.help_view {
+                    Some(view) => view.jump(location),
+                    None => self.help_view = Some(HelpView::at(location)),
+                }
+                self.mode = Mode::help();
+            }
+            ExCommand::Write(name) => match name.or_else(|| self.save_name.clone()) {
+                Some(name) => self.write_save(&name)?,
+                None => {
+                    // First save: ask for a name
+                    self.mode = Mode::Insert {
+                        target: InsertTarget::SaveName,
+                        line: CommandLine::new(),
+                    };
+                }
+            },
+            ExCommand::Edit(name) => self.load_save(&name)?,
+            ExCommand::Colorscheme(Some(name)) => self.load_colorscheme(&name)?,
+            ExCommand::Colorscheme(None) => self.messages.echo(self.theme.name.clone()),
+            ExCommand::Camera(None) => self.messages.echo(format!("camera {}", self.camera.mode)),
+            ExCommand::Camera(Some(arg)) => {
+                let mode = CameraMode::parse(&arg)
+                    .ok_or_else(|| format!("E475: Invalid argument: {}", arg))?;
+                if let CameraMode::Locked(key) = mode {
+                    if self.marks.get(key).is_none() {
+                        return Err("E20: Mark not set".to_string());
+                    }
+                }
+                self.camera.mode = mode;
+                self.camera.recenter();
+            }
+            ExCommand::Travel(None) => {
+                let destination = self.destination.ok_or("No destination; choose one on the atlas")?;
+                self.travel(destination)?;
+            }
+            ExCommand::Travel(Some(arg)) => {
+                let mut keys = arg.chars();
+                let key = match (keys.next(), keys.next()) {
+                    (Some(key), None) if is_mark(key) => key,
+                    _ => return Err(format!("E475: Invalid argument: {}", arg)),
+                };
+                let mark = self.marks.get(key).ok_or("E20: Mark not set")?;
+                self.travel(mark.position)?;
+            }
+            ExCommand::Atlas => {
+                let cursor = self.player_position().unwrap_or(Position::new(0, 0));
+                self.look_cursor = None;
+                self.atlas = Some(Atlas::open(cursor, self.exploration.bounds(), ATLAS_FIT));
+                self.mode = Mode::atlas();
+            }
+            ExCommand::Calendar => {
+                let date = self.tapestry.date();
+                self.messages.echo(format!("{}, {}", date, date.time_of_day().name().to_lowercase()));
+            }
+            ExCommand::Story => {
+                let stories = story::stories(&self.tapestry);
+                if stories.is_empty() {
+                    self.messages.echo("No story has begun");
+                }
+                for id in stories {
+                    let Some(story) = self.tapestry.get_thread(id).and_then(Thread::story) else {
+                        continue;
+                    };
+                    if let Some(outcome) = story.outcome {
+                        self.messages.echo(format!("{}: {} without its hero", story.title, outcome.describe()));
+                        continue;
+                    }
+                    let Some(stage) = story.current() else {
+                        self.messages.echo(format!("{}: complete", story.title));
+                        continue;
+                    };
+                    self.messages.echo(format!(
+                        "{}: stage {} of {}, {}",
+                        story.title,
+                        story.stage + 1,
+                        story.stages.len(),
+                        stage.name
+                    ));
+                    for goal in &stage.goals {
+                        let mark = if goal.is_met() { '✓' } else { '·' };
+                        let (progress, needed) = (goal.progress(), goal.needed);
+                        self.messages.echo(format!("  {} {} {}/{}", mark, goal.describe(), progress, needed));
+                    }
+                }
+            }
+            ExCommand::Abandon | ExCommand::Retire => {
+                let cause = if ex_cmd == ExCommand::Retire { Cause::Retired } else { Cause::Abandoned };
+                if !self.resolve_stories(cause) {
+                    self.messages.echo("No story is under way");
+                }
+            }
+            ExCommand::Yield => {
+                let foes = combat::foes_beside(&self.tapestry, self.player_id);
+                if foes.is_empty() {
+                    return Err("Nobody is fighting you".to_string());
+                }
+                for foe in foes {
+                    let name = self
+                        .tapestry
+                        .get_thread(foe)
+                        .map(|thread| thread.display_name().to_string())
+                        .unwrap_or_default();
+                    if combat::yield_to(&mut self.tapestry, self.player_id, foe) {
+                        self.messages.echo(format!("{} spares you", name));
+                    } else {
+                        self.messages.warn(format!("{} will not hear of it", name));
+                    }
+                }
+                self.pass_turn();
+            }
+            ExCommand::Chronicle => {
+                let entries: Vec<String> = self
+                    .tapestry
+                    .chronicle()
+                    .iter()
+                    .map(|entry| format!("{}: {}", entry.date().day_name(), entry.text))
+                    .collect();
+                if entries.is_empty() {
+                    self.messages.echo("The chronicle is empty");
+                }
+                for entry in entries {
+                    self.messages.echo(entry);
+                }
+            }
+            ExCommand::Claims => {
+                let claims = weaving::claims(&self.tapestry);
+                if claims.is_empty() {
+                    self.messages.echo("No story has claimed any land");
+                }
+                for claim in claims {
+                    let Some(story) = self.tapestry.get_thread(claim.story).and_then(Thread::story) else {
+                        continue;
+                    };
+                    let stage = story.stages.get(claim.stage).map_or("", |stage| stage.name.as_str());
+                    let region = self
+                        .tapestry
+                        .get_thread(claim.region)
+                        .map_or("", |region| region.display_name());
+                    self.messages.echo(format!(
+                        "{}, {}: {} at {},{} ({})",
+                        story.title,
+                        stage,
+                        region,
+                        claim.position.x,
+                        claim.position.y,
+                        claim.goal.name()
+                    ));
+                }
+            }
+            ExCommand::Zoom(None) => self.messages.echo(format!("zoom {}", self.camera.zoom())),
+            ExCommand::Zoom(Some(arg)) => {
+                let zoomed = arg.parse().is_ok_and(|zoom| self.camera.set_zoom(zoom));
+                if !zoomed {
+                    return Err(format!("E475: Invalid argument: {}", arg));
+                }
+            }
+            ExCommand::Journal => {
+                let entries = self
+                    .tapestry
+                    .get_thread(self.player_id)
+                    .map(|player| player.notes.clone())
+                    .unwrap_or_default();
+                if entries.is_empty() {
+                    self.messages.echo("Journal is empty");
+                }
+                for (index, entry) in entries.iter().enumerate() {
+                    self.messages.echo(format!("{:>3}. {}", index + 1, entry));
+                }
+            }
+            ExCommand::Marks => {
+                if self.marks.is_empty() {
+                    self.messages.echo("No marks set");
+                    return Ok(());
+                }
+                let listing: Vec<String> = self
+                    .marks
+                    .iter()
+                    .map(|(key, mark)| {
+                        let area = match mark.area {
+                            Some(area) => {
+                                let (width, height) = area.size();
+                                let kind = match area.kind {
+                                    VisualKind::Char => "visual",
+                                    VisualKind::Block => "block",
+                                };
+                                format!("{}x{} {}", width, height, kind)
+                            }
+                            None => String::new(),
+                        };
+                        format!(" {}  {:>5} {:>5}  {}", key, mark.position.x, mark.position.y, area)
+                    })
+                    .collect();
+                self.messages.echo("mark     x     y  area");
+                for line in listing {
+                    self.messages.echo(line.trim_end().to_string());
+                }
+            }
+            ExCommand::DelMarks(keys) => {
+                if keys == "!" {
+                    self.marks.clear();
+                    return Ok(());
+                }
+                for key in keys.chars().filter(|c| !c.is_whitespace()) {
+                    if !is_mark(key) {
+                        return Err(format!("E475: Invalid argument: {}", key));
+                    }
+                    self.marks.remove(key);
+                }
+            }
+            ExCommand::HelpGrep(pattern) => {
+                let matches = self.help.grep(&pattern);
+                let Some(first) = matches.first().copied() else {
+                    return Err(format!("E480: No match: {}", pattern));
+                };
+                self.help_view
+                    .get_or_insert_with(|| HelpView::at(first))
+                    .set_matches(matches);
+                self.mode = Mode::help();
+                self.echo_help_match();
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute every line of a file as an ex command
+    ///
+    /// Errors in individual lines are reported in the message log with the
+    /// file and line number; only failing to read the file is an error.
+    pub fn source(&mut self, path: &Path) -> io::Result<()> {
+        if self.source_depth >= MAX_SOURCE_DEPTH {
+            self.messages
+                .error(format!("E169: Command too recursive: {}", path.display()));
+            return Ok(());
+        }
+        let contents = fs::read_to_string(path)?;
+        self.messages.debug(1, format!("sourcing \"{}\"", path.display()));
+
+        self.source_depth += 1;
+        for (index, line) in contents.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('"') {
+                continue; // Blank line or comment
+            }
+            if let Err(err) = self.run_ex_line(trimmed) {
+                self.messages
+                    .error(format!("{} line {}: {}", path.display(), index + 1, err));
+            }
+            if !self.running {
+                break;
+            }
+        }
+        self.source_depth -= 1;
+
+        Ok(())
+    }
+}
+
+/// Append `key` to the keys of a multi-key command and parse them
+///
+/// Returns None, keeping the keys pending, while the command is incomplete.
+fn parse_pending(
+    pending: &mut String,
+    key: char,
+    parse: fn(&str) -> Option<Command>,
+) -> Option<Command> {
+    let mut keys = std::mem::take(pending);
+    keys.push(key);
+    let command = parse(&keys);
+    if command.is_none() {
+        *pending = keys;
+    }
+    command
+}
+
+/// The land at `position`: its oldest region, which names the tile
+fn land_at(tapestry: &Tapestry, position: Position) -> Option<&Thread> {
+    tapestry
+        .threads_at(position)
+        .into_iter()
+        .filter(|thread| matches!(thread.kind, ThreadKind::Region { .. }))
+        .min_by_key(|thread| thread.id.0)
+}
+
+/// Every Thread at `position` and the weather over it
+fn threads_seen_at(tapestry: &Tapestry, position: Position) -> Vec<&Thread> {
+    let mut threads = tapestry.threads_at(position);
+    threads.extend(tapestry.weather_at(position));
+    threads
+}
+
+/// The name of the heir to `name`: Wanderer II, then Wanderer III
+fn heir_name(name: &str) -> String {
+    let (stem, generation) = name
+        .rsplit_once(' ')
+        .and_then(|(stem, last)| Some((stem, NUMERALS.iter().position(|numeral| *numeral == last)? + 1)))
+        .unwrap_or((name, 0));
+    match NUMERALS.get(generation) {
+        Some(numeral) => format!("{} {}", stem, numeral),
+        None => format!("{} {}", stem, generation + 2),
+    }
+}
+
+/// Evaluate the right-hand side of `:let`: a quoted string or a bare value
+fn parse_let_value(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        // Literal string: only '' is special
+        return Ok(inner.replace("''", "'"));
+    }
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        let mut result = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                match chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some(other) => result.push(other),
+                    None => return Err(format!("E114: Missing quote: {}", value)),
+                }
+            } else {
+                result.push(c);
+            }
+        }
+        return Ok(result);
+    }
+    if value.is_empty() || value.contains(char::is_whitespace) || value.contains(['"', '\'']) {
+        return Err(format!("E15: Invalid expression: {}", value));
+    }
+    Ok(value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::foundation::Position;
+    use crate::patterns::keys;
+    use crate::patterns::messages::Severity;
+    use crate::threads::{Thread, ThreadKind};
+    use crate::weaver::{properties::ThreadProperties, states::ThreadStates};
+
+    pub(super) fn test_loom() -> Loom {
+        let mut tapestry = Tapestry::new();
+        let player_id = tapestry.next_id();
+        tapestry.add_thread(Thread {
+            id: player_id,
+            kind: ThreadKind::Player {
+                name: "Tester".to_string(),
+            },
+            properties: ThreadProperties::default(),
+            states: ThreadStates::default(),
+            position: Some(Position::new(0, 0)),
+            name: None,
+            notes: Vec::new(),
+        });
+        Loom::new(tapestry, player_id)
+    }
+
+    pub(super) fn player_position(loom: &Loom) -> Position {
+        loom.tapestry.get_thread(loom.player_id).unwrap().position.unwrap()
+    }
+
+    pub(super) fn type_keys(loom: &mut Loom, keys: &str) {
+        for key in keys.chars() {
+            loom.press(key);
+        }
+    }
+
+    #[test]
+    fn typed_keys_move_player_with_counts() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, "3lj");
+        assert_eq!(player_position(&loom), Position::new(3, 1));
+    }
+
+    #[test]
+    fn ex_quit_stops_the_loom() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, ":q\r");
+        assert!(!loom.is_running());
+    }
+
+    #[test]
+    fn unknown_ex_command_is_reported() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, ":frobnicate\r");
+        assert!(loom.is_running());
+        assert_eq!(
+            loom.messages.latest().unwrap().text,
+            "E492: Not an editor command: frobnicate"
+        );
+    }
+
+    #[test]
+    fn colorscheme_switches_theme() {
+        let mut loom = test_loom();
+        loom.colors_dir = std::env::temp_dir().join("vitalis-no-colors");
+        loom.run_ex_line("colorscheme mono").unwrap();
+        assert_eq!(loom.theme.name, "mono");
+        assert_eq!(loom.options.get_str("colorscheme"), "mono");
+
+        // :set loads the scheme too; an unknown one changes nothing
+        let err = loom.run_ex_line("set colo=plaid").unwrap_err();
+        assert_eq!(err, "E185: Cannot find color scheme 'plaid'");
+        assert_eq!(loom.options.get_str("colorscheme"), "mono");
+        loom.run_ex_line("set colorscheme=default").unwrap();
+        assert_eq!(loom.theme.name, "default");
+
+        loom.run_ex_line("colo").unwrap();
+        assert_eq!(loom.messages.latest().unwrap().text, "default");
+    }
+
+    #[test]
+    fn colorscheme_files_come_before_builtins() {
+        let dir = std::env::temp_dir().join(format!("vitalis-colors-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("mono.theme"), "hi Player glyph=☺\n").unwrap();
+        let mut loom = test_loom();
+        loom.colors_dir = dir.clone();
+        loom.run_ex_line("colorscheme mono").unwrap();
+        assert_eq!(loom.theme.highlight("Player").unwrap().glyphs, vec!['☺']);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mappings_expand_typed_keys() {
+        let mut loom = test_loom();
+        loom.run_ex_line("map L 5l").unwrap();
+        type_keys(&mut loom, "L");
+        assert_eq!(player_position(&loom), Position::new(5, 0));
+    }
+
+    #[test]
+    fn leader_is_expanded_when_mapping_is_defined() {
+        let mut loom = test_loom();
+        loom.run_ex_line("let mapleader = \",\"").unwrap();
+        loom.run_ex_line("map <leader>q :q<CR>").unwrap();
+        type_keys(&mut loom, ",q");
+        assert!(!loom.is_running());
+    }
+
+    #[test]
+    fn prefix_mapping_waits_then_falls_back_to_typed_keys() {
+        let mut loom = test_loom();
+        loom.run_ex_line("map Ql 2l").unwrap();
+        type_keys(&mut loom, "Q");
+        assert_eq!(player_position(&loom), Position::new(0, 0));
+        type_keys(&mut loom, "j");
+        assert_eq!(player_position(&loom), Position::new(0, 1));
+        type_keys(&mut loom, "Ql");
+        assert_eq!(player_position(&loom), Position::new(2, 1));
+    }
+
+    #[test]
+    fn noremap_does_not_remap_its_right_hand_side() {
+        let mut loom = test_loom();
+        loom.run_ex_line("map l h").unwrap();
+        loom.run_ex_line("noremap L l").unwrap();
+        type_keys(&mut loom, "L");
+        assert_eq!(player_position(&loom), Position::new(1, 0));
+    }
+
+    #[test]
+    fn recursive_mapping_is_stopped() {
+        let mut loom = test_loom();
+        loom.run_ex_line("map x x").unwrap();
+        type_keys(&mut loom, "x");
+        assert_eq!(loom.messages.latest().unwrap().text, "E223: Recursive mapping");
+    }
+
+    #[test]
+    fn set_changes_typed_options() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, ":set ch=2 quiet\r");
+        assert_eq!(loom.options.get_number("cmdheight"), 2);
+        assert!(loom.options.get_bool("quiet"));
+
+        type_keys(&mut loom, ":set cmdheight?\r");
+        assert_eq!(loom.messages.latest().unwrap().text, "cmdheight=2");
+
+        type_keys(&mut loom, ":set cmdheight=0\r");
+        assert_eq!(loom.messages.latest().unwrap().severity, Severity::Error);
+        assert_eq!(loom.options.get_number("cmdheight"), 2);
+    }
+
+    #[test]
+    fn quiet_option_filters_the_message_log() {
+        let mut loom = test_loom();
+        loom.run_ex_line("set quiet").unwrap();
+        loom.messages.info("You hear faint muttering");
+        assert!(loom.messages.is_empty());
+    }
+
+    #[test]
+    fn maxcount_caps_count_prefixes() {
+        let mut loom = test_loom();
+        loom.run_ex_line("set maxcount=5").unwrap();
+        type_keys(&mut loom, "20l");
+        assert_eq!(player_position(&loom), Position::new(5, 0));
+        type_keys(&mut loom, "99999999999j");
+        assert_eq!(player_position(&loom), Position::new(5, 5));
+    }
+
+    #[test]
+    fn executed_lines_are_recalled_from_history() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, ":map L 5l\r:set ch=2\r");
+        assert_eq!(loom.history.entries(), ["map L 5l", "set ch=2"]);
+
+        type_keys(&mut loom, ":m");
+        loom.press(keys::UP);
+        assert_eq!(loom.mode.command_buffer(), Some("map L 5l"));
+        loom.press(keys::ctrl('w'));
+        type_keys(&mut loom, "3l\rL");
+        assert_eq!(player_position(&loom), Position::new(3, 0));
+    }
+
+    #[test]
+    fn cursor_editing_inserts_mid_line() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, ":qit");
+        loom.press(keys::LEFT);
+        loom.press(keys::LEFT);
+        type_keys(&mut loom, "u\r");
+        assert!(!loom.is_running());
+    }
+
+    #[test]
+    fn backspace_on_empty_command_line_returns_to_normal() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, ":x\x7f\x7f");
+        assert!(loom.mode.is_normal());
+    }
+
+    #[test]
+    fn command_window_executes_and_edits_history() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, ":map L 2l\r:set ch=2\r");
+
+        type_keys(&mut loom, "q:");
+        assert_eq!(loom.mode, Mode::CmdWindow { selected: 1 });
+        type_keys(&mut loom, "k\rL");
+        assert_eq!(player_position(&loom), Position::new(2, 0));
+
+        // Ctrl-f opens the window from the command line; `e` edits the entry
+        type_keys(&mut loom, ":\x06ke");
+        assert_eq!(loom.mode.command_buffer(), Some("set ch=2"));
+        loom.press(keys::ESC);
+        assert!(loom.mode.is_normal());
+    }
+
+    #[test]
+    fn help_opens_follows_links_and_closes() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, ":h contests\r");
+        assert!(matches!(loom.mode, Mode::Help { .. }));
+        let view = loom.help_view.as_ref().unwrap();
+        assert_eq!(loom.help.page(view.page).unwrap().topic, "contests");
+
+        // Tab to the |properties| link, follow it, and come back
+        type_keys(&mut loom, "gg\t\x1d");
+        let view = loom.help_view.as_ref().unwrap();
+        assert_eq!(loom.help.page(view.page).unwrap().topic, "properties");
+        type_keys(&mut loom, "\x14");
+        let view = loom.help_view.as_ref().unwrap();
+        assert_eq!(loom.help.page(view.page).unwrap().topic, "contests");
+
+        // Movement keys scroll help instead of moving the player
+        type_keys(&mut loom, "gg3j");
+        assert_eq!(loom.help_view.as_ref().unwrap().cursor, 3);
+        assert_eq!(player_position(&loom), Position::new(0, 0));
+
+        // :q closes help rather than quitting
+        type_keys(&mut loom, ":q\r");
+        assert!(loom.is_running());
+        assert!(loom.mode.is_normal());
+        assert!(loom.help_view.is_none());
+    }
+
+    #[test]
+    fn help_reports_unknown_topics_and_greps() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, ":help xyzzy\r");
+        assert_eq!(
+            loom.messages.latest().unwrap().text,
+            "E149: Sorry, no help for xyzzy"
+        );
+        assert!(loom.help_view.is_none());
+
+        type_keys(&mut loom, ":helpgrep Corrupted\r");
+        assert!(loom.messages.latest().unwrap().text.starts_with("(1 of "));
+        type_keys(&mut loom, "n");
+        assert!(loom.messages.latest().unwrap().text.starts_with("(2 of "));
+        type_keys(&mut loom, "q");
+        assert!(loom.mode.is_normal());
+    }
+
+    pub(super) fn add_region(loom: &mut Loom, position: Position) -> ThreadId {
+        let id = loom.tapestry.next_id();
+        loom.tapestry.add_thread(Thread {
+            id,
+            kind: ThreadKind::Region {
+                description: "Whispering Plains".to_string(),
+            },
+            properties: ThreadProperties::default(),
+            states: ThreadStates::default(),
+            position: Some(position),
+            name: None,
+            notes: Vec::new(),
+        });
+        id
+    }
+
+    #[test]
+    fn insert_mode_renames_and_annotates_the_tile() {
+        let mut loom = test_loom();
+        let tile = add_region(&mut loom, Position::new(0, 0));
+
+        // c starts with the current name; Ctrl-u clears it
+        type_keys(&mut loom, "c\x15Old Well\x1b");
+        assert!(loom.mode.is_normal());
+        assert_eq!(loom.tapestry.get_thread(tile).unwrap().display_name(), "Old Well");
+
+        type_keys(&mut loom, "iwater is foul\x17clean\r");
+        assert_eq!(
+            loom.tapestry.get_thread(tile).unwrap().notes,
+            vec!["water is clean".to_string()]
+        );
+
+        // Ctrl-c discards the text
+        type_keys(&mut loom, "ilost\x03");
+        assert_eq!(loom.tapestry.get_thread(tile).unwrap().notes.len(), 1);
+    }
+
+    #[test]
+    fn insert_mode_needs_something_under_the_cursor() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, "c");
+        assert!(loom.mode.is_normal());
+        assert_eq!(loom.messages.latest().unwrap().text, "Nothing here to name");
+    }
+
+    #[test]
+    fn npcs_remember_being_greeted() {
+        use crate::tapestry::relationships::TRUST_STEP;
+
+        let mut loom = test_loom();
+        let mira = add_region(&mut loom, Position::new(2, 0));
+        let thread = loom.tapestry.get_thread_mut(mira).unwrap();
+        thread.kind = ThreadKind::Npc {
+            name: "Mira".to_string(),
+        };
+        thread.properties.wisdom = 0;
+        thread.properties.luck = 0;
+        let player = loom.tapestry.get_thread_mut(loom.player_id).unwrap();
+        player.properties.charisma = 20;
+        player.properties.luck = 20;
+
+        // Greeted on coming alongside, not on every turn spent there
+        type_keys(&mut loom, "lkjhl");
+        let greetings = loom.messages.iter().filter(|message| message.text == "Mira greets you warmly").count();
+        assert_eq!(greetings, 2);
+        let regard = loom.tapestry.relationships().get(mira, loom.player_id);
+        assert_eq!(regard.trust, 2.0 * TRUST_STEP);
+        assert_eq!(loom.tapestry.relationships().get(loom.player_id, mira).debt, 0.2);
+        // Two meetings are too few for it to show
+        assert_eq!(loom.inspect(Position::new(2, 0))[0].attitude, None);
+
+        loom.tapestry
+            .relationships_mut()
+            .update(mira, loom.player_id, |edge| edge.trust = -1.0);
+        assert_eq!(loom.tapestry.relationships().hostile_to(loom.player_id), vec![mira]);
+        assert_eq!(loom.inspect(Position::new(2, 0))[0].attitude, Some("hostile"));
+        assert_eq!(loom.inspect(Position::new(1, 0))[0].attitude, None);
+    }
+
+    #[test]
+    fn bumping_into_npcs_fights_them() {
+        let mut loom = test_loom();
+        let mira = add_region(&mut loom, Position::new(1, 0));
+        let thread = loom.tapestry.get_thread_mut(mira).unwrap();
+        thread.kind = ThreadKind::Npc {
+            name: "Mira".to_string(),
+        };
+        thread.properties = ThreadProperties::new(0);
+        let player = loom.tapestry.get_thread_mut(loom.player_id).unwrap();
+        player.properties.strength = 20;
+        player.properties.luck = 20;
+
+        // Stepping into the peaceful trades places with them
+        type_keys(&mut loom, "lh");
+        assert_eq!(loom.messages.latest().unwrap().text, "You trade places with Mira");
+        assert_eq!(loom.tapestry.get_thread(mira).unwrap().position, Some(Position::new(1, 0)));
+
+        // Strength 20 and Luck 20 fell a Thread of Constitution 0 at a blow
+        type_keys(&mut loom, "Fl");
+        assert_eq!(player_position(&loom), Position::new(0, 0));
+        let told: Vec<&str> = loom.messages.iter().map(|message| message.text.as_str()).collect();
+        assert!(told.contains(&"You punch Mira (5 damage)"));
+        assert!(told.contains(&"Mira falls"));
+        assert!(loom.tapestry.get_thread(mira).is_none());
+        assert_eq!(loom.tapestry.chronicle()[0].text, "Mira was slain by Tester");
+
+        // An enemy may spare you; the corrupted never do
+        let fenn = add_region(&mut loom, Position::new(1, 0));
+        let thread = loom.tapestry.get_thread_mut(fenn).unwrap();
+        thread.kind = ThreadKind::Npc {
+            name: "Fenn".to_string(),
+        };
+        loom.tapestry
+            .relationships_mut()
+            .update(fenn, loom.player_id, |edge| edge.trust = -1.0);
+        assert_eq!(combat::foes_beside(&loom.tapestry, loom.player_id), vec![fenn]);
+        loom.run_ex_line("yield").unwrap();
+        assert!(loom.messages.iter().any(|message| message.text == "Fenn spares you"));
+        assert!(combat::foes_beside(&loom.tapestry, loom.player_id).is_empty());
+        assert_eq!(loom.tapestry.relationships().get(loom.player_id, fenn).fear, 1.0);
+        assert!(loom.run_ex_line("yield").is_err());
+        type_keys(&mut loom, "l");
+        assert_eq!(player_position(&loom), Position::new(1, 0));
+        type_keys(&mut loom, "Fl");
+        assert_eq!(loom.messages.latest().unwrap().text, "There is nobody there");
+    }
+
+    #[test]
+    fn the_fallen_player_passes_on_their_lineage() {
+        let mut loom = test_loom();
+        for x in -1..=2 {
+            add_region(&mut loom, Position::new(x, 1));
+        }
+        let staff = add_region(&mut loom, Position::new(0, 0));
+        let thread = loom.tapestry.get_thread_mut(staff).unwrap();
+        thread.kind = ThreadKind::Item {
+            name: "Oak Club".to_string(),
+            carrier: Some(loom.player_id),
+        };
+        thread.position = None;
+        let corwin = add_region(&mut loom, Position::new(1, 0));
+        let thread = loom.tapestry.get_thread_mut(corwin).unwrap();
+        thread.kind = ThreadKind::Npc {
+            name: "Corwin".to_string(),
+        };
+        thread.properties = ThreadProperties::new(20);
+        thread.states.corrupted = 1.0;
+        let player = loom.tapestry.get_thread_mut(loom.player_id).unwrap();
+        player.properties = ThreadProperties::new(0);
+        player.notes.push("Beware the dark".to_string());
+        let ancestor = loom.player_id;
+        loom.discover();
+
+        // Corrupted NPCs sleep by day
+        assert!(combat::foes_beside(&loom.tapestry, ancestor).is_empty());
+        loom.tapestry.pass_time(13 * calendar::TURNS_PER_HOUR);
+        assert_eq!(combat::foes_beside(&loom.tapestry, ancestor), vec![corwin]);
+
+        // Too slow to break away, and too frail to take the parting blow
+        type_keys(&mut loom, "h");
+        let told: Vec<&str> = loom.messages.iter().map(|message| message.text.as_str()).collect();
+        assert!(told.contains(&"Corwin punches you (5 damage, 0/5 HP)"));
+        assert!(told.contains(&"Tester was slain by Corwin"));
+        assert!(told.contains(&"Tester II took up the lineage"));
+        assert!(loom.tapestry.get_thread(ancestor).is_none());
+        let heir = loom.tapestry.get_thread(loom.player_id).unwrap();
+        assert_eq!(heir.display_name(), "Tester II");
+        assert_eq!(heir.position, Some(Position::new(0, 0)));
+        assert_eq!(heir.properties, ThreadProperties::new(0));
+        assert_eq!(heir.notes, vec!["Beware the dark".to_string()]);
+        assert_eq!(combat::carried(&loom.tapestry, loom.player_id), vec![staff]);
+        assert!(loom.exploration.is_seen(Position::new(2, 1)));
+        assert_eq!(loom.tapestry.chronicle().len(), 2);
+
+        assert_eq!(heir_name("Tester II"), "Tester III");
+        assert_eq!(heir_name("Tester XX"), "Tester 21");
+    }
+
+    #[test]
+    fn vitals_show_on_the_status_bar_and_cross_thresholds() {
+        let mut loom = test_loom();
+        let peak = add_region(&mut loom, Position::new(1, 0));
+        loom.tapestry.get_thread_mut(peak).unwrap().kind = ThreadKind::Region {
+            description: "Iron Mountains".to_string(),
+        };
+        let status = loom.status_view();
+        assert_eq!((status.hp, status.level), (Some((25, 25)), Some(1)));
+        assert!(status.conditions.is_empty());
+        let told = |loom: &Loom, text: &str| loom.messages.iter().any(|message| message.text == text);
+
+        // Climbing tires the player faster than the way back rests them
+        type_keys(&mut loom, &"lh".repeat(4));
+        assert!(!told(&loom, "You are growing tired"));
+        type_keys(&mut loom, "l");
+        assert_eq!(loom.messages.latest().unwrap().text, "You are growing tired");
+        assert_eq!(loom.status_view().conditions, vec!["Tired"]);
+        type_keys(&mut loom, "hhh");
+        assert!(told(&loom, "You have your breath back"));
+        assert!(loom.status_view().conditions.is_empty());
+
+        // Hungry players eat what they carry, then go hungry
+        let vitals = loom.tapestry.vitals_mut(loom.player_id).unwrap();
+        (vitals.hunger, vitals.supplies) = (vitals::HUNGRY - 1, 1);
+        type_keys(&mut loom, "l");
+        assert!(told(&loom, "You eat the last of your supplies"));
+        loom.tapestry.vitals_mut(loom.player_id).unwrap().hunger = vitals::HUNGRY - 1;
+        type_keys(&mut loom, "h");
+        assert!(told(&loom, "You are hungry"));
+        assert_eq!(loom.status_view().conditions, vec!["Hungry"]);
+
+        // Experience brings levels, and levels hit points
+        loom.tapestry.get_thread_mut(loom.player_id).unwrap().states.experienced = 0.1;
+        type_keys(&mut loom, "l");
+        assert!(told(&loom, "You reach level 2"));
+        let status = loom.status_view();
+        assert_eq!((status.hp, status.level), (Some((28, 28)), Some(2)));
+
+        // The starving waste away by the hour
+        loom.tapestry.vitals_mut(loom.player_id).unwrap().hunger = vitals::STARVING;
+        loom.tapestry.get_thread_mut(loom.player_id).unwrap().states.damaged = 0.97;
+        let ancestor = loom.player_id;
+        for _ in 0..calendar::TURNS_PER_HOUR {
+            type_keys(&mut loom, "h");
+        }
+        assert_ne!(loom.player_id, ancestor);
+        assert!(told(&loom, "You are near death"));
+        assert!(told(&loom, "Tester starved to death"));
+        assert!(told(&loom, "Tester II took up the lineage"));
+        assert_eq!(loom.tapestry.vitals(loom.player_id).unwrap().supplies, 10);
+    }
+
+    #[test]
+    fn stories_resolve_themselves_when_left_behind() {
+        use crate::seamstress::SECONDS_PER_HOUR;
+        use crate::threads::story::{Goal, GoalKind, Stage, Story};
+
+        let dir = std::env::temp_dir().join(format!("vitalis-loom-absence-{}", std::process::id()));
+        let mut loom = test_loom();
+        loom.save_dir = dir.clone();
+        add_region(&mut loom, Position::new(1, 0));
+        let add_story = |loom: &mut Loom, title: &str| {
+            let id = add_region(loom, Position::new(0, 0));
+            let story = loom.tapestry.get_thread_mut(id).unwrap();
+            story.position = None;
+            story.properties = ThreadProperties::new(20);
+            story.kind = ThreadKind::Story(Story {
+                title: title.to_string(),
+                stages: vec![Stage {
+                    name: "The Call".to_string(),
+                    goals: vec![Goal::new(GoalKind::Knowledge, Biome::Plains, 1)],
+                }],
+                stage: 0,
+                woven: true,
+                outcome: None,
+            });
+        };
+        add_story(&mut loom, "The Quiet Road");
+        type_keys(&mut loom, ":chronicle\r");
+        assert_eq!(loom.messages.latest().unwrap().text, "The chronicle is empty");
+
+        // Nothing stops a story this strong
+        type_keys(&mut loom, ":retire\r");
+        assert_eq!(
+            loom.messages.latest().unwrap().text,
+            "The Quiet Road ended well when its hero retired"
+        );
+        type_keys(&mut loom, ":abandon\r");
+        assert_eq!(loom.messages.latest().unwrap().text, "No story is under way");
+        type_keys(&mut loom, ":sto\r");
+        assert_eq!(
+            loom.messages.latest().unwrap().text,
+            "The Quiet Road: ended well without its hero"
+        );
+        type_keys(&mut loom, ":chr\r");
+        assert_eq!(
+            loom.messages.latest().unwrap().text,
+            "Spring 1, Year 1: The Quiet Road ended well when its hero retired"
+        );
+
+        // A world left alone long enough goes on without you
+        add_story(&mut loom, "The Iron Road");
+        let away = |loom: &Loom, name: &str, years: u64| {
+            let mut game = SaveGame::new(loom.tapestry.clone(), loom.player_id, loom.exploration.clone());
+            game.saved_at -= years * 168 * SECONDS_PER_HOUR;
+            seamstress::save(&seamstress::save_path(&dir, name), &game).unwrap();
+        };
+        away(&loom, "year", 1);
+        away(&loom, "decade", 10);
+        type_keys(&mut loom, ":atlas\r:e year\r");
+        assert_eq!(loom.messages.latest().unwrap().text, "A year passed while you were away");
+        assert_eq!(loom.tapestry.date().year, 2);
+        assert!(loom.atlas.is_none());
+        assert!(loom.mode.is_normal());
+        type_keys(&mut loom, ":e decade\r");
+        assert_eq!(
+            loom.messages.latest().unwrap().text,
+            "The Iron Road ended well after 10 years untold"
+        );
+        assert_eq!(loom.tapestry.date().year, 11);
+        assert_eq!(loom.tapestry.chronicle().len(), 2);
+        // With 'worldyear' at 0 the world waits for you
+        type_keys(&mut loom, ":set wy=0\r:e decade\r");
+        assert_eq!(loom.messages.latest().unwrap().text, "\"decade\" loaded");
+        assert_eq!(loom.tapestry.date().year, 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn journal_names_and_exploration_survive_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("vitalis-loom-saves-{}", std::process::id()));
+        let mut loom = test_loom();
+        loom.save_dir = dir.clone();
+        let tile = add_region(&mut loom, Position::new(0, 0));
+        type_keys(&mut loom, "AFound the river\rc\x15Ford\r");
+
+        // The first :w asks for a name
+        type_keys(&mut loom, ":w\r");
+        assert!(matches!(loom.mode, Mode::Insert { target: InsertTarget::SaveName, .. }));
+        type_keys(&mut loom, "river\r");
+        assert_eq!(loom.messages.latest().unwrap().text, "\"river\" written");
+
+        // Ground seen after saving is forgotten on loading
+        type_keys(&mut loom, "30l");
+        assert!(loom.exploration.is_seen(Position::new(39, 0)));
+        type_keys(&mut loom, "ASecond entry\r:e river\r");
+        assert!(!loom.exploration.is_seen(Position::new(39, 0)));
+        assert!(loom.exploration.is_seen(Position::new(9, 0)));
+        let player = loom.tapestry.get_thread(loom.player_id).unwrap();
+        assert_eq!(player.notes, vec!["Found the river".to_string()]);
+        assert_eq!(loom.tapestry.get_thread(tile).unwrap().display_name(), "Ford");
+
+        type_keys(&mut loom, ":e missing\r");
+        assert!(loom.messages.latest().unwrap().text.starts_with("E484"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn visual_mode_extends_and_looks_at_an_area() {
+        let mut loom = test_loom();
+        for x in 0..3 {
+            add_region(&mut loom, Position::new(x, 1));
+        }
+
+        type_keys(&mut loom, "\x162lj");
+        let selection = *loom.mode.selection().unwrap();
+        assert_eq!(selection.kind, VisualKind::Block);
+        assert_eq!(selection.size(), (3, 2));
+        assert_eq!(loom.mode.pending_keys(), "3x2");
+        assert_eq!(player_position(&loom), Position::new(0, 0));
+
+        type_keys(&mut loom, "K");
+        assert!(loom.mode.is_normal());
+        assert_eq!(loom.messages.latest().unwrap().text, "  Whispering Plains (3)");
+
+        // gv reselects the same area
+        type_keys(&mut loom, "gv");
+        assert_eq!(loom.mode.selection(), Some(&selection));
+        type_keys(&mut loom, "\x1b");
+        assert!(loom.mode.is_normal());
+    }
+
+    #[test]
+    fn marks_remember_positions_and_areas() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, "m;3l");
+        type_keys(&mut loom, "vjm'");
+        type_keys(&mut loom, "g;");
+        assert_eq!(player_position(&loom), Position::new(0, 0));
+        assert_eq!(loom.marks.get('\'').unwrap().area.unwrap().size(), (1, 2));
+
+        type_keys(&mut loom, "g-");
+        assert_eq!(loom.messages.latest().unwrap().text, "E20: Mark not set");
+        type_keys(&mut loom, ":delm ;\r");
+        assert!(loom.marks.get(';').is_none());
+    }
+
+    #[test]
+    fn travel_takes_the_cheapest_way_over_known_ground() {
+        let mut loom = test_loom();
+        let mountain = add_region(&mut loom, Position::new(2, 0));
+        loom.tapestry.get_thread_mut(mountain).unwrap().kind = ThreadKind::Region {
+            description: "Stubborn Mountains".to_string(),
+        };
+        type_keys(&mut loom, "jk");
+
+        // Over the mountain costs 7; round it, 4
+        let route = find_path(Position::new(0, 0), Position::new(4, 0), |position| {
+            loom.route_cost(position, &HashSet::new())
+        })
+        .unwrap();
+        assert_eq!(route.len(), 4);
+        assert!(!route.contains(&Position::new(2, 0)));
+
+        type_keys(&mut loom, ":travel\r");
+        assert_eq!(loom.messages.latest().unwrap().text, "No destination; choose one on the atlas");
+        // Two tiles to an atlas cell
+        type_keys(&mut loom, ":atlas\r2l\r:tr\r");
+        assert_eq!(player_position(&loom), Position::new(4, 0));
+        assert_eq!(loom.destination, None);
+        assert_eq!(loom.messages.latest().unwrap().text, "You reach your destination");
+    }
+
+    #[test]
+    fn travel_stops_when_a_hostile_comes_into_view() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, "6jm;6k");
+        let npc = add_region(&mut loom, Position::new(0, 12));
+        let npc = loom.tapestry.get_thread_mut(npc).unwrap();
+        npc.kind = ThreadKind::Npc {
+            name: "Mira".to_string(),
+        };
+        npc.states.corrupted = 0.8;
+
+        type_keys(&mut loom, "g;");
+        assert_eq!(player_position(&loom), Position::new(0, 3));
+        let warning = loom.messages.latest().unwrap();
+        assert_eq!(warning.text, "Mira comes into view");
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(loom.status_view().hostile, Some(true));
+
+        // Once warned, the player may carry on
+        type_keys(&mut loom, ":travel ;\r");
+        assert_eq!(player_position(&loom), Position::new(0, 6));
+    }
+
+    #[test]
+    fn travel_stops_for_someone_bearing_a_grudge() {
+        let mut loom = test_loom();
+        let npc = add_region(&mut loom, Position::new(1, 0));
+        loom.tapestry.get_thread_mut(npc).unwrap().kind = ThreadKind::Npc {
+            name: "Mira".to_string(),
+        };
+        type_keys(&mut loom, "m;Fl");
+        assert_eq!(loom.status_view().hostile, Some(true));
+
+        // A step at a time, seeing the whole way
+        type_keys(&mut loom, &"j".repeat(20));
+        assert_eq!(loom.status_view().hostile, Some(false));
+        type_keys(&mut loom, "g;");
+        assert!(player_position(&loom).y > 1);
+        assert_eq!(loom.messages.latest().unwrap().text, "Mira comes into view");
+        assert_eq!(loom.status_view().hostile, Some(true));
+    }
+
+    /// Plains from x=2 to 4 on the top row and a desert from 5 to 6, with
+    /// wilderness either side
+    fn add_row_of_lands(loom: &mut Loom) {
+        for x in 2..7 {
+            let id = add_region(loom, Position::new(x, 0));
+            if x >= 5 {
+                loom.tapestry.get_thread_mut(id).unwrap().kind = ThreadKind::Region {
+                    description: "Ashen Desert".to_string(),
+                };
+            }
+        }
+        type_keys(loom, "jk");
+    }
+
+    #[test]
+    fn diagonals_and_runs_stop_where_things_change() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, "2yn");
+        assert_eq!(player_position(&loom), Position::new(-1, -1));
+        type_keys(&mut loom, "n");
+        add_row_of_lands(&mut loom);
+
+        type_keys(&mut loom, "L");
+        assert_eq!(player_position(&loom), Position::new(2, 0));
+        type_keys(&mut loom, "2L");
+        assert_eq!(player_position(&loom), Position::new(7, 0));
+
+        // Running on through wilderness until someone comes into sight
+        let npc = add_region(&mut loom, Position::new(20, 0));
+        loom.tapestry.get_thread_mut(npc).unwrap().kind = ThreadKind::Npc {
+            name: "Mira".to_string(),
+        };
+        type_keys(&mut loom, "L");
+        assert_eq!(player_position(&loom), Position::new(11, 0));
+    }
+
+    #[test]
+    fn motions_jump_between_regions_and_map_edges() {
+        let mut loom = test_loom();
+        add_row_of_lands(&mut loom);
+
+        type_keys(&mut loom, "w");
+        assert_eq!(player_position(&loom), Position::new(2, 0));
+        type_keys(&mut loom, "$");
+        assert_eq!(player_position(&loom), Position::new(4, 0));
+        type_keys(&mut loom, "0");
+        assert_eq!(player_position(&loom), Position::new(2, 0));
+        type_keys(&mut loom, "2w");
+        assert_eq!(player_position(&loom), Position::new(7, 0));
+        type_keys(&mut loom, "B");
+        assert_eq!(player_position(&loom), Position::new(6, 0));
+        type_keys(&mut loom, "0B2$");
+        assert_eq!(player_position(&loom), Position::new(6, 0));
+        // A 0 inside a count is a digit
+        type_keys(&mut loom, "10h");
+        assert_eq!(player_position(&loom), Position::new(-4, 0));
+
+        let (low, high) = loom.exploration.bounds().unwrap();
+        type_keys(&mut loom, "G");
+        assert_eq!(player_position(&loom).y, high.y);
+        type_keys(&mut loom, "gg");
+        assert_eq!(player_position(&loom).y, low.y);
+        let (low, _) = loom.exploration.bounds().unwrap();
+        type_keys(&mut loom, "3G");
+        assert_eq!(player_position(&loom).y, low.y + 2);
+    }
+
+    #[test]
+    fn travel_needs_a_known_way() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, "x20lm;q");
+        type_keys(&mut loom, ":tr ;\r");
+        assert_eq!(loom.messages.latest().unwrap().text, "You do not know the way there");
+        type_keys(&mut loom, ":tr x\r");
+        assert_eq!(loom.messages.latest().unwrap().text, "E475: Invalid argument: x");
+        assert_eq!(player_position(&loom), Position::new(0, 0));
+    }
+
+    #[test]
+    fn weather_closes_in_tints_the_map_and_dies_away() {
+        let mut loom = test_loom();
+        let blizzard = add_region(&mut loom, Position::new(8, 0));
+        let blizzard = loom.tapestry.get_thread_mut(blizzard).unwrap();
+        blizzard.kind = ThreadKind::Weather {
+            system: WeatherSystem::Blizzard,
+            heading: (-1, 0),
+        };
+        let map_weather = |loom: &Loom| loom.map_view(21, 11).rows.iter().flatten().find_map(|cell| cell.weather);
+        assert_eq!(map_weather(&loom), Some(WeatherSystem::Blizzard));
+        let radius = loom.visible.iter().map(|position| position.x.abs()).max();
+
+        // It drifts a tile every other turn, reaching the player on the sixth
+        type_keys(&mut loom, "hlhl");
+        assert!(loom.tapestry.weather_at(Position::new(0, 0)).is_none());
+        type_keys(&mut loom, "hl");
+        let warning = loom.messages.latest().unwrap();
+        assert_eq!(warning.text, "You are caught in a blizzard");
+        assert_eq!(warning.severity, Severity::Warning);
+        assert!(loom.visible.iter().map(|position| position.x.abs()).max() < radius);
+        let reports = loom.inspect(Position::new(0, 0));
+        assert!(reports.iter().any(|report| report.kind == "weather" && report.name == "Blizzard"));
+
+        // Wilderness starves it
+        type_keys(&mut loom, "hl");
+        let texts: Vec<&str> = loom.messages.iter().map(|message| message.text.as_str()).collect();
+        assert!(texts.contains(&"The blizzard dies away"));
+        assert!(texts.contains(&"The blizzard is behind you"));
+        assert_eq!(map_weather(&loom), None);
+    }
+
+    #[test]
+    fn night_narrows_sight_and_npcs_keep_their_hours() {
+        let mut loom = test_loom();
+        let npc = add_region(&mut loom, Position::new(2, 0));
+        loom.tapestry.get_thread_mut(npc).unwrap().kind = ThreadKind::Npc {
+            name: "Mira".to_string(),
+        };
+        let radius = |loom: &Loom| loom.visible.iter().map(|position| position.x.abs()).max();
+        let day = radius(&loom);
+        assert_eq!(loom.status_view().time_of_day.as_deref(), Some("08:00 Day ☀"));
+        assert_eq!(loom.status_view().date.as_deref(), Some("Spring 1, Year 1"));
+        assert_eq!(loom.inspect(Position::new(2, 0))[0].activity, Some("working"));
+
+        // Ten hours on, dusk gathers, then night
+        type_keys(&mut loom, &"hl".repeat(50));
+        assert_eq!(loom.messages.latest().unwrap().text, "Dusk falls");
+        assert_eq!(loom.status_view().time_of_day.as_deref(), Some("18:00 Dusk ◒"));
+        type_keys(&mut loom, &"hl".repeat(10));
+        assert_eq!(loom.messages.latest().unwrap().text, "Night falls");
+        assert_eq!(loom.status_view().time_of_day.as_deref(), Some("20:00 Night ☾"));
+        assert!(radius(&loom) < day);
+        type_keys(&mut loom, &"hl".repeat(10));
+        assert_eq!(loom.inspect(Position::new(2, 0))[0].activity, Some("asleep"));
+
+        loom.run_ex_line("calendar").unwrap();
+        assert_eq!(loom.messages.latest().unwrap().text, "22:00 on day 1 of Spring, year 1, night");
+    }
+
+    #[test]
+    fn stories_advance_as_the_player_studies_meets_and_endures() {
+        use crate::threads::story::{Goal, GoalKind, Stage, Story};
+
+        let mut loom = test_loom();
+        let tile = add_region(&mut loom, Position::new(1, 0));
+        let tile = loom.tapestry.get_thread_mut(tile).unwrap();
+        tile.properties.dexterity = 0;
+        tile.properties.luck = 0;
+        let player = loom.tapestry.get_thread_mut(loom.player_id).unwrap();
+        player.properties.wisdom = 20;
+        player.properties.luck = 20;
+        add_region(&mut loom, Position::new(2, 1));
+        let npc = add_region(&mut loom, Position::new(2, 1));
+        loom.tapestry.get_thread_mut(npc).unwrap().kind = ThreadKind::Npc {
+            name: "Mira".to_string(),
+        };
+        let peak = add_region(&mut loom, Position::new(0, 1));
+        loom.tapestry.get_thread_mut(peak).unwrap().kind = ThreadKind::Region {
+            description: "Stubborn Mountains".to_string(),
+        };
+        let story = add_region(&mut loom, Position::new(0, 0));
+        let story = loom.tapestry.get_thread_mut(story).unwrap();
+        story.position = None;
+        story.kind = ThreadKind::Story(Story {
+            title: "The Quiet Road".to_string(),
+            stages: vec![
+                Stage {
+                    name: "The Call".to_string(),
+                    goals: vec![
+                        Goal::new(GoalKind::Knowledge, Biome::Plains, 1),
+                        Goal::new(GoalKind::Relationship, Biome::Plains, 1),
+                    ],
+                },
+                Stage {
+                    name: "The Reckoning".to_string(),
+                    goals: vec![Goal::new(GoalKind::Challenge, Biome::Mountains, 1)],
+                },
+            ],
+            stage: 0,
+            woven: true,
+            outcome: None,
+        });
+
+        type_keys(&mut loom, "xl");
+        assert_eq!(loom.messages.latest().unwrap().text, "Goal met: Study the Plains");
+        type_keys(&mut loom, "\x1b:story\r");
+        let texts: Vec<&str> = loom.messages.iter().map(|message| message.text.as_str()).collect();
+        assert_eq!(
+            texts[texts.len() - 3..],
+            [
+                "The Quiet Road: stage 1 of 2, The Call",
+                "  ✓ Study the Plains 1/1",
+                "  · Meet the folk of the Plains 0/1",
+            ]
+        );
+
+        // Mira stands beside the tile
+        type_keys(&mut loom, "l");
+        assert_eq!(loom.messages.latest().unwrap().text, "The Quiet Road: The Call is over");
+        type_keys(&mut loom, "b");
+        assert_eq!(loom.messages.latest().unwrap().text, "The Quiet Road has reached its end");
+        type_keys(&mut loom, ":sto\r");
+        assert_eq!(loom.messages.latest().unwrap().text, "The Quiet Road: complete");
+    }
+
+    #[test]
+    fn items_are_taken_where_stories_claimed_land() {
+        use crate::threads::story::{Goal, GoalKind, Stage, Story};
+
+        let mut loom = test_loom();
+        type_keys(&mut loom, ":claims\r");
+        assert_eq!(loom.messages.latest().unwrap().text, "No story has claimed any land");
+
+        let tile = add_region(&mut loom, Position::new(1, 0));
+        let key = add_region(&mut loom, Position::new(1, 0));
+        loom.tapestry.get_thread_mut(key).unwrap().kind = ThreadKind::Item {
+            name: "Silver Key".to_string(),
+            carrier: None,
+        };
+        let mut goal = Goal::new(GoalKind::Resource, Biome::Plains, 1);
+        goal.regions.push(tile);
+        let story = add_region(&mut loom, Position::new(0, 0));
+        let story = loom.tapestry.get_thread_mut(story).unwrap();
+        story.position = None;
+        story.kind = ThreadKind::Story(Story {
+            title: "The Gilded Debt".to_string(),
+            stages: vec![Stage {
+                name: "The Call".to_string(),
+                goals: vec![goal],
+            }],
+            stage: 0,
+            woven: true,
+            outcome: None,
+        });
+
+        type_keys(&mut loom, ":claims\r");
+        assert_eq!(
+            loom.messages.latest().unwrap().text,
+            "The Gilded Debt, The Call: Whispering Plains at 1,0 (resource)"
+        );
+        type_keys(&mut loom, "l");
+        let texts: Vec<&str> = loom.messages.iter().map(|message| message.text.as_str()).collect();
+        assert!(texts.contains(&"You take the Silver Key"));
+        assert!(texts.contains(&"Goal met: Find what the Plains hides"));
+        let key = loom.tapestry.get_thread(key).unwrap();
+        assert_eq!(key.position, None);
+        assert_eq!(key.kind, ThreadKind::Item { name: "Silver Key".to_string(), carrier: Some(loom.player_id) });
+    }
+
+    #[test]
+    fn look_mode_moves_a_cursor_and_inspects_tiles() {
+        let mut loom = test_loom();
+        let tile = add_region(&mut loom, Position::new(2, 0));
+        {
+            let tile = loom.tapestry.get_thread_mut(tile).unwrap();
+            // Too clumsy to hide anything from a wise and lucky player
+            tile.properties.dexterity = 0;
+            tile.properties.luck = 0;
+            tile.states.blessed = 0.2;
+            tile.states.damaged = 0.8;
+        }
+        let player = loom.tapestry.get_thread_mut(loom.player_id).unwrap();
+        player.properties.wisdom = 20;
+        player.properties.luck = 20;
+
+        type_keys(&mut loom, "x2l");
+        assert!(matches!(loom.mode, Mode::Look { .. }));
+        assert_eq!(loom.look_cursor, Some(Position::new(2, 0)));
+        assert_eq!(player_position(&loom), Position::new(0, 0));
+
+        let reports = loom.inspect(Position::new(2, 0));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].kind, "region");
+        assert!(reports[0].stats.is_some());
+        assert_eq!(reports[0].states, vec![("Damaged", 0.8), ("Blessed", 0.2)]);
+
+        // Naming from Look mode names the tile under the look cursor
+        type_keys(&mut loom, "c\x15Cairn\r");
+        assert!(matches!(loom.mode, Mode::Look { .. }));
+        assert_eq!(loom.tapestry.get_thread(tile).unwrap().display_name(), "Cairn");
+
+        type_keys(&mut loom, "\x1b");
+        assert!(loom.mode.is_normal());
+        assert_eq!(loom.look_cursor, None);
+    }
+
+    #[test]
+    fn unperceived_threads_show_only_obvious_states() {
+        let mut loom = test_loom();
+        let tile = add_region(&mut loom, Position::new(0, 0));
+        loom.tapestry.get_thread_mut(tile).unwrap().states.blessed = 0.2;
+        let reports = loom.inspect(Position::new(0, 0));
+        let region = reports.iter().find(|r| r.kind == "region").unwrap();
+        assert_eq!(region.stats, None);
+        assert!(region.states.is_empty());
+    }
+
+    #[test]
+    fn let_parses_quoted_values() {
+        assert_eq!(parse_let_value("\"\\\\\"").unwrap(), "\\");
+        assert_eq!(parse_let_value("'it''s'").unwrap(), "it's");
+        assert_eq!(parse_let_value("42").unwrap(), "42");
+        assert!(parse_let_value("\"unterminated\\").is_err());
+    }
+
+    #[test]
+    fn source_reports_errors_with_file_and_line() {
+        let path = std::env::temp_dir().join(format!("vitalisrc-test-{}", std::process::id()));
+        fs::write(&path, "\" team keymap\nmap L 5l\nbogus command\n:set quiet\n").unwrap();
+
+        let mut loom = test_loom();
+        loom.source(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let errors: Vec<_> = loom.messages.iter().map(|m| m.text.clone()).collect();
+        assert_eq!(
+            errors,
+            vec![format!(
+                "{} line 3: E492: Not an editor command: bogus command",
+                path.display()
+            )]
+        );
+        assert!(loom.options.get_bool("quiet"));
+        type_keys(&mut loom, "L");
+        assert_eq!(player_position(&loom), Position::new(5, 0));
+    }
+
+    #[test]
+    fn source_missing_file_is_an_error() {
+        let mut loom = test_loom();
+        let err = loom.run_ex_line("source /nonexistent/vitalisrc").unwrap_err();
+        assert!(err.starts_with("E484: Can't open file /nonexistent/vitalisrc"));
+    }
+}
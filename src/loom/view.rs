@@ -0,0 +1,713 @@
+//! View-models for the screen, built from the live session
+
+use super::Loom;
+use crate::foundation::Position;
+use crate::patterns::atlas::{self, MINIMAP_SCALE};
+use crate::patterns::view::{
+    AtlasCell, AtlasView, CommandAreaView, MapCell, MapHeaderView, MapView, Marker,
+    MessageLineView, ModeLineView, StatusView, Tile, Visibility,
+};
+use crate::tapestry::combat::{self, Weapon};
+use crate::tapestry::vitals::{self, Vital};
+use crate::tapestry::weaving;
+use crate::threads::{Biome, Thread, ThreadKind};
+use crate::weaver::perception::OBVIOUS_STATE;
+use std::collections::{HashMap, HashSet};
+
+/// Map name shown where no region has been woven
+const WILDERNESS: &str = "wilderness";
+
+/// Map name shown while the atlas is open
+const ATLAS: &str = "atlas";
+
+impl Loom {
+    pub fn status_view(&self) -> StatusView {
+        let player = self.tapestry.get_thread(self.player_id);
+        let name = player.map(|player| player.display_name().to_string()).unwrap_or_default();
+        let conditions = player.map_or_else(Vec::new, |player| {
+            Vital::ALL
+                .into_iter()
+                .filter_map(|vital| vital.condition(vitals::strain(&self.tapestry, player, vital)))
+                .collect()
+        });
+        let date = self.tapestry.date();
+        let time = date.time_of_day();
+        StatusView {
+            name,
+            hp: player.map(|player| (combat::hp(player), combat::max_hp(player))),
+            level: player.map(|player| vitals::level(&player.states)),
+            armed: player.map(|player| combat::weapon(&self.tapestry, player.id) != Weapon::Fists),
+            conditions,
+            hostile: Some(!self.hostiles_in_sight().is_empty()),
+            explored: self.exploration.explored(&self.tapestry),
+            date: Some(date.day_name()),
+            time_of_day: Some(format!("{} {} {}", date.clock(), time.name(), time.symbol())),
+        }
+    }
+
+    /// The message being read, the newest unless stepping through the log
+    /// with `g<`, and its place in the log
+    pub fn message_line_view(&self) -> MessageLineView {
+        let index = self.message_cursor.unwrap_or(self.messages.len());
+        MessageLineView {
+            message: index.checked_sub(1).and_then(|index| self.messages.iter().nth(index)).cloned(),
+            index,
+            total: self.messages.len(),
+        }
+    }
+
+    /// The region the player stands in, as a file-like name
+    pub fn map_header_view(&self) -> MapHeaderView {
+        if let Some(atlas) = self.atlas {
+            return MapHeaderView {
+                name: ATLAS.to_string(),
+                zoom: atlas.scale,
+                explored: None,
+            };
+        }
+        let region = self.player_position().and_then(|position| {
+            self.tapestry
+                .threads_at(position)
+                .into_iter()
+                .find_map(|thread| match &thread.kind {
+                    ThreadKind::Region { description } => Some(description.clone()),
+                    _ => None,
+                })
+        });
+        let name = match &region {
+            Some(description) => description.to_lowercase().replace(' ', "_"),
+            None => WILDERNESS.to_string(),
+        };
+        MapHeaderView {
+            name,
+            zoom: self.camera.zoom(),
+            explored: region.and_then(|description| self.exploration.region_explored(&self.tapestry, &description)),
+        }
+    }
+
+    /// A `width` by `height` window of the map, placed by the camera
+    ///
+    /// Zoomed out, each cell stands for a square of tiles: the player or an
+    /// NPC if one is there, otherwise its most common biome. Out of sight,
+    /// the land is drawn as the player last saw it.
+    pub fn map_view(&self, width: u16, height: u16) -> MapView {
+        let player_position = self.player_position();
+        let target = self.camera_target().unwrap_or(Position::new(0, 0));
+        let margins = (
+            self.options.get_number("sidescrolloff").clamp(0, i64::from(u16::MAX)) as u16,
+            self.options.get_number("scrolloff").clamp(0, i64::from(u16::MAX)) as u16,
+        );
+        let origin = self.camera.frame(target, width, height, margins);
+        let zoom = i32::from(self.camera.zoom());
+        let selection = self.mode.selection();
+
+        // Threads in sight by the cell they fall in, oldest first
+        let (columns, rows) = (i32::from(width), i32::from(height));
+        let mut cells: HashMap<(i32, i32), Vec<&Thread>> = HashMap::new();
+        let mut systems: Vec<&Thread> = Vec::new();
+        for thread in self.tapestry.threads() {
+            if thread.weather().is_some() {
+                systems.push(thread);
+                continue;
+            }
+            let Some(position) = thread.position.filter(|position| self.visible.contains(position)) else {
+                continue;
+            };
+            let column = (position.x - origin.x).div_euclid(zoom);
+            let row = (position.y - origin.y).div_euclid(zoom);
+            if (0..columns).contains(&column) && (0..rows).contains(&row) {
+                cells.entry((column, row)).or_default().push(thread);
+            }
+        }
+        for threads in cells.values_mut() {
+            threads.sort_by_key(|thread| thread.id.0);
+        }
+        // The oldest system wins where several overlap, as in weather_at
+        systems.sort_by_key(|thread| thread.id.0);
+        let weather_over = |tile: Position| systems.iter().find(|system| system.covers(tile))?.weather();
+
+        let rows = (0..rows)
+            .map(|row| {
+                (0..columns)
+                    .map(|column| {
+                        let position = Position::new(origin.x + column * zoom, origin.y + row * zoom);
+                        let covers = |p: Position| {
+                            (position.x..position.x + zoom).contains(&p.x)
+                                && (position.y..position.y + zoom).contains(&p.y)
+                        };
+                        let tiles = || {
+                            (0..zoom * zoom).map(|i| Position::new(position.x + i % zoom, position.y + i / zoom))
+                        };
+                        let highlighted = self.look_cursor.is_some_and(covers)
+                            || selection.is_some_and(|selection| tiles().any(|tile| selection.contains(tile)));
+                        let visibility = if tiles().any(|tile| self.visible.contains(&tile)) {
+                            Visibility::Visible
+                        } else if tiles().any(|tile| self.exploration.is_seen(tile)) {
+                            Visibility::Remembered
+                        } else {
+                            Visibility::Unknown
+                        };
+                        let mut cell = match visibility {
+                            Visibility::Remembered => {
+                                remembered_cell(position, tiles().filter_map(|tile| self.exploration.land(tile)))
+                            }
+                            _ => map_cell(position, cells.get(&(column, row)).map_or(&[][..], Vec::as_slice)),
+                        };
+                        cell.visibility = visibility;
+                        cell.weather = tiles().filter(|tile| self.visible.contains(tile)).find_map(weather_over);
+                        if player_position.is_some_and(covers) {
+                            cell.tile = Tile::Player;
+                        }
+                        cell.highlighted = highlighted;
+                        cell
+                    })
+                    .collect()
+            })
+            .collect();
+        MapView { rows }
+    }
+
+    /// The atlas filling a `width` by `height` area, if it is open
+    pub fn atlas_view(&self, width: u16, height: u16) -> Option<AtlasView> {
+        let atlas = self.atlas?;
+        Some(self.overview(atlas.cursor, atlas.scale, width, height, Some(atlas.cursor)))
+    }
+
+    /// The minimap: the atlas around the player
+    pub fn minimap_view(&self, width: u16, height: u16) -> AtlasView {
+        let centre = self.player_position().unwrap_or(Position::new(0, 0));
+        self.overview(centre, MINIMAP_SCALE, width, height, None)
+    }
+
+    /// Discovered ground around `centre`, `scale` tiles to a cell
+    fn overview(
+        &self,
+        centre: Position,
+        scale: u8,
+        width: u16,
+        height: u16,
+        cursor: Option<Position>,
+    ) -> AtlasView {
+        let origin = atlas::origin(centre, scale, width, height);
+        let size = i32::from(scale);
+        let (columns, rows) = (i32::from(width), i32::from(height));
+        let cell_of = |position: Position| {
+            let cell = (
+                (position.x - origin.x).div_euclid(size),
+                (position.y - origin.y).div_euclid(size),
+            );
+            ((0..columns).contains(&cell.0) && (0..rows).contains(&cell.1)).then_some(cell)
+        };
+
+        // The land as remembered, in tile order
+        let mut discovered: HashSet<(i32, i32)> = HashSet::new();
+        let mut biomes: HashMap<(i32, i32), Vec<Biome>> = HashMap::new();
+        for tile in self.exploration.tiles() {
+            let Some(cell) = cell_of(tile) else {
+                continue;
+            };
+            discovered.insert(cell);
+            let biome = self.exploration.land(tile).and_then(Biome::of);
+            biomes.entry(cell).or_default().extend(biome);
+        }
+
+        // Markers from least to most important, later ones covering earlier
+        let mut markers: HashMap<(i32, i32), Marker> = HashMap::new();
+        let unfolding = |claim: &weaving::Claim| {
+            let story = self.tapestry.get_thread(claim.story).and_then(Thread::story);
+            story.is_some_and(|story| !story.is_over())
+        };
+        let sites = weaving::claims(&self.tapestry).into_iter().filter(unfolding).map(|claim| claim.position);
+        let graves = self.tapestry.chronicle().iter().filter_map(|entry| entry.place);
+        let known = |position: Position| cell_of(position).filter(|cell| discovered.contains(cell));
+        for cell in sites.filter_map(known) {
+            markers.insert(cell, Marker::Story);
+        }
+        for cell in graves.filter_map(known) {
+            markers.insert(cell, Marker::Grave);
+        }
+        for thread in self.tapestry.threads() {
+            let in_sight = thread.position.filter(|position| self.visible.contains(position));
+            if let (ThreadKind::Npc { .. }, Some(cell)) = (&thread.kind, in_sight.and_then(cell_of)) {
+                markers.insert(cell, Marker::Npc);
+            }
+        }
+        let marks: Vec<(char, Position)> = self.marks.iter().map(|(key, mark)| (key, mark.position)).collect();
+        for (key, position) in marks.into_iter().rev() {
+            if let Some(cell) = cell_of(position) {
+                markers.insert(cell, Marker::Mark(key));
+            }
+        }
+        let mut place = |position: Option<Position>, marker: Marker| {
+            if let Some(cell) = position.and_then(cell_of) {
+                markers.insert(cell, marker);
+            }
+        };
+        place(self.destination, Marker::Destination);
+        place(self.player_position(), Marker::Player);
+
+        let cursor = cursor.and_then(cell_of);
+        let rows = (0..rows)
+            .map(|row| {
+                (0..columns)
+                    .map(|column| AtlasCell {
+                        position: Position::new(origin.x + column * size, origin.y + row * size),
+                        discovered: discovered.contains(&(column, row)),
+                        biome: biomes
+                            .get(&(column, row))
+                            .and_then(|biomes| dominant_biome(biomes.iter().copied())),
+                        marker: markers.get(&(column, row)).copied(),
+                        highlighted: cursor == Some((column, row)),
+                    })
+                    .collect()
+            })
+            .collect();
+        AtlasView { rows, scale }
+    }
+
+    pub fn mode_line_view(&self) -> ModeLineView {
+        ModeLineView {
+            mode: self.mode.mode_name().to_string(),
+            pending: self.mode.pending_keys(),
+            position: self.cursor_position(),
+        }
+    }
+
+    /// The `rows` rows below the frame: earlier messages and the command line
+    ///
+    /// The newest message is on the message line, so only the ones before it
+    /// are repeated here.
+    pub fn command_area_view(&self, rows: usize) -> CommandAreaView {
+        let command_line = self.mode.command_line();
+        let message_rows = rows.saturating_sub(1);
+        let earlier = &self.messages.recent(message_rows + 1);
+        let earlier = &earlier[..earlier.len().saturating_sub(1)];
+        CommandAreaView {
+            messages: earlier.to_vec(),
+            cursor: self.mode.command_line_cursor(),
+            command_line,
+        }
+    }
+}
+
+/// The cell for a square of tiles holding `threads`, oldest first
+fn map_cell(position: Position, threads: &[&Thread]) -> MapCell {
+    // NPCs stand out over the items and region they stand in, items over
+    // the region
+    let thread = threads
+        .iter()
+        .find(|thread| matches!(thread.kind, ThreadKind::Npc { .. }))
+        .or_else(|| threads.iter().find(|thread| matches!(thread.kind, ThreadKind::Item { .. })))
+        .or(threads.first())
+        .copied();
+    let regions = || threads.iter().filter(|thread| matches!(thread.kind, ThreadKind::Region { .. }));
+    let (tile, biome) = match thread {
+        Some(thread) => match thread.kind {
+            ThreadKind::Region { .. } => {
+                let annotated = regions().any(|region| !region.notes.is_empty());
+                let tile = if annotated { Tile::AnnotatedRegion } else { Tile::Region };
+                (tile, dominant_biome(regions().filter_map(|region| region.biome())))
+            }
+            ThreadKind::Npc { .. } => (Tile::Npc, thread.biome()),
+            ThreadKind::Item { .. } => (Tile::Item, None),
+            ThreadKind::Player { .. } => (Tile::Player, thread.biome()),
+            // Weather is drawn over cells, never in them; stories are nowhere
+            ThreadKind::Weather { .. } | ThreadKind::Story(_) => (Tile::Empty, None),
+        },
+        None => (Tile::Empty, None),
+    };
+    let state = thread
+        .and_then(|thread| thread.states.strongest())
+        .filter(|(_, value)| *value >= OBVIOUS_STATE)
+        .map(|(name, _)| name);
+    MapCell {
+        position,
+        tile,
+        biome,
+        state,
+        visibility: Visibility::Visible,
+        highlighted: false,
+        weather: None,
+    }
+}
+
+/// The cell for a square of tiles out of sight, from the land last seen
+/// there
+fn remembered_cell<'a>(position: Position, lands: impl Iterator<Item = &'a str>) -> MapCell {
+    let lands: Vec<&str> = lands.collect();
+    MapCell {
+        position,
+        tile: if lands.is_empty() { Tile::Empty } else { Tile::Region },
+        biome: dominant_biome(lands.into_iter().filter_map(Biome::of)),
+        state: None,
+        visibility: Visibility::Remembered,
+        highlighted: false,
+        weather: None,
+    }
+}
+
+/// The most common biome, the first seen winning ties
+fn dominant_biome(biomes: impl Iterator<Item = Biome>) -> Option<Biome> {
+    let mut counts: Vec<(Biome, usize)> = Vec::new();
+    for biome in biomes {
+        match counts.iter_mut().find(|(seen, _)| *seen == biome) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((biome, 1)),
+        }
+    }
+    counts
+        .iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(biome, _)| *biome)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::foundation::Position;
+    use crate::loom::tests::{add_region, player_position, test_loom, type_keys};
+    use crate::loom::Loom;
+    use crate::threads::ThreadKind;
+    use crate::patterns::view::{Marker, Tile, Visibility};
+    use crate::tapestry::vitals::{self, Death};
+    use crate::threads::story::{Goal, GoalKind, Outcome, Stage, Story};
+    use crate::threads::Biome;
+
+    #[test]
+    fn mode_line_shows_the_cursor_position() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, "2j");
+        let line = loom.mode_line_view();
+        assert_eq!(line.mode, "-- NORMAL --");
+        assert_eq!(line.position, Some(Position::new(0, 2)));
+    }
+
+    #[test]
+    fn map_view_is_centred_on_the_player() {
+        let mut loom = test_loom();
+        add_region(&mut loom, Position::new(1, 0));
+        let map = loom.map_view(5, 3);
+        assert_eq!(map.rows.len(), 3);
+        assert_eq!(map.rows[1][2].tile, Tile::Player);
+        assert_eq!(map.rows[1][3].tile, Tile::Region);
+        assert_eq!(map.rows[0][0].tile, Tile::Empty);
+
+        // Items lie on top of the land
+        let item = add_region(&mut loom, Position::new(1, 0));
+        loom.tapestry.get_thread_mut(item).unwrap().kind = ThreadKind::Item {
+            name: "Silver Key".to_string(),
+            carrier: None,
+        };
+        assert_eq!(loom.map_view(5, 3).rows[1][3].tile, Tile::Item);
+    }
+
+    #[test]
+    fn map_cells_carry_biome_and_obvious_state() {
+        let mut loom = test_loom();
+        let id = add_region(&mut loom, Position::new(1, 0));
+        let map = loom.map_view(3, 1);
+        assert_eq!(map.rows[0][2].biome, Some(Biome::Plains));
+        assert_eq!(map.rows[0][2].state, None);
+
+        // Only states obvious at a glance change how a tile looks
+        let thread = loom.tapestry.get_thread_mut(id).unwrap();
+        thread.states.damaged = 0.3;
+        assert_eq!(loom.map_view(3, 1).rows[0][2].state, None);
+        let thread = loom.tapestry.get_thread_mut(id).unwrap();
+        thread.states.damaged = 0.8;
+        assert_eq!(loom.map_view(3, 1).rows[0][2].state, Some("Damaged"));
+    }
+
+    #[test]
+    fn map_scrolls_within_scrolloff() {
+        let mut loom = test_loom();
+        loom.run_ex_line("set so=1 siso=2").unwrap();
+        let player_column = |loom: &Loom| {
+            let map = loom.map_view(9, 5);
+            map.rows[2].iter().position(|cell| cell.tile == Tile::Player)
+        };
+        assert_eq!(player_column(&loom), Some(4));
+        type_keys(&mut loom, "2l");
+        assert_eq!(player_column(&loom), Some(6));
+        // Two columns from the edge the map scrolls instead
+        type_keys(&mut loom, "3l");
+        assert_eq!(player_column(&loom), Some(6));
+        type_keys(&mut loom, "h");
+        assert_eq!(player_column(&loom), Some(5));
+    }
+
+    #[test]
+    fn view_commands_pan_without_moving_the_player() {
+        let mut loom = test_loom();
+        loom.map_view(5, 5);
+        type_keys(&mut loom, "2\x05");
+        assert_eq!(player_position(&loom), Position::new(0, 0));
+        assert_eq!(loom.map_view(5, 5).rows[0][2].tile, Tile::Player);
+        type_keys(&mut loom, "zz");
+        assert_eq!(loom.map_view(5, 5).rows[2][2].tile, Tile::Player);
+    }
+
+    #[test]
+    fn camera_locks_to_a_mark() {
+        let mut loom = test_loom();
+        assert_eq!(loom.run_ex_line("camera lock ;"), Err("E20: Mark not set".to_string()));
+        type_keys(&mut loom, "m;");
+        loom.run_ex_line("camera lock ;").unwrap();
+        type_keys(&mut loom, "j");
+        let map = loom.map_view(3, 3);
+        assert_eq!(map.rows[1][1].position, Position::new(0, 0));
+        assert_eq!(map.rows[2][1].tile, Tile::Player);
+    }
+
+    #[test]
+    fn zooming_out_folds_tiles_into_cells() {
+        let mut loom = test_loom();
+        add_region(&mut loom, Position::new(2, 0));
+        let npc = add_region(&mut loom, Position::new(3, 1));
+        loom.tapestry.get_thread_mut(npc).unwrap().kind = ThreadKind::Npc {
+            name: "Mira".to_string(),
+        };
+        loom.run_ex_line("zoom 2").unwrap();
+        assert_eq!(loom.map_header_view().zoom, 2);
+
+        let map = loom.map_view(3, 3);
+        assert_eq!(map.rows[1][1].tile, Tile::Player);
+        assert_eq!(map.rows[1][1].position, Position::new(0, 0));
+        assert_eq!(map.rows[1][2].tile, Tile::Npc);
+        assert_eq!(map.rows[1][2].position, Position::new(2, 0));
+
+        type_keys(&mut loom, "zi");
+        assert_eq!(loom.camera.zoom(), 1);
+        assert!(loom.run_ex_line("zoom 3").is_err());
+    }
+
+    #[test]
+    fn forests_hide_what_lies_behind_them() {
+        let mut loom = test_loom();
+        let forest = add_region(&mut loom, Position::new(1, 0));
+        loom.tapestry.get_thread_mut(forest).unwrap().kind = ThreadKind::Region {
+            description: "Scheming Forests".to_string(),
+        };
+        let npc = add_region(&mut loom, Position::new(3, 0));
+        loom.tapestry.get_thread_mut(npc).unwrap().kind = ThreadKind::Npc {
+            name: "Mira".to_string(),
+        };
+        // Step down and back so the player looks again
+        type_keys(&mut loom, "jk");
+
+        let row = &loom.map_view(7, 1).rows[0];
+        assert_eq!(row[4].tile, Tile::Region);
+        assert_eq!(row[4].visibility, Visibility::Visible);
+        // Mira was glimpsed from below; the tile is remembered, she is not
+        assert_eq!(row[6].tile, Tile::Empty);
+        assert_eq!(row[6].visibility, Visibility::Remembered);
+        assert_eq!(row[2].visibility, Visibility::Visible);
+    }
+
+    #[test]
+    fn unseen_ground_is_unknown() {
+        let mut loom = test_loom();
+        add_region(&mut loom, Position::new(12, 0));
+        type_keys(&mut loom, "3l");
+        let row = &loom.map_view(25, 1).rows[0];
+        // From x=3 the player sees as far as x=12
+        assert_eq!(row[21].tile, Tile::Region);
+        assert_eq!(row[21].visibility, Visibility::Visible);
+        type_keys(&mut loom, "3h");
+        let row = &loom.map_view(25, 1).rows[0];
+        assert_eq!(row[24].visibility, Visibility::Remembered);
+        assert_eq!(row[24].tile, Tile::Region);
+        assert_eq!(row[3].visibility, Visibility::Visible);
+        assert_eq!(row[2].visibility, Visibility::Unknown);
+        // Nor out past the corner of its sight
+        type_keys(&mut loom, "zz");
+        let corner = &loom.map_view(25, 9).rows[0][24];
+        assert_eq!(corner.position, Position::new(12, -4));
+        assert_eq!(corner.visibility, Visibility::Unknown);
+    }
+
+    #[test]
+    fn remembered_land_goes_stale_out_of_sight() {
+        let mut loom = test_loom();
+        let region = add_region(&mut loom, Position::new(1, 0));
+        type_keys(&mut loom, "jk");
+        type_keys(&mut loom, "20h");
+        loom.tapestry.get_thread_mut(region).unwrap().kind = ThreadKind::Region {
+            description: "Scheming Forests".to_string(),
+        };
+        type_keys(&mut loom, "zz");
+        let cell = &loom.map_view(43, 1).rows[0][42];
+        assert_eq!(cell.position, Position::new(1, 0));
+        assert_eq!(cell.visibility, Visibility::Remembered);
+        assert_eq!(cell.biome, Some(Biome::Plains));
+
+        type_keys(&mut loom, "20l");
+        assert_eq!(loom.map_view(3, 1).rows[0][2].biome, Some(Biome::Forest));
+    }
+
+    #[test]
+    fn exploration_shows_per_map_and_per_region() {
+        let mut loom = test_loom();
+        for x in 1..=20 {
+            add_region(&mut loom, Position::new(x, 0));
+        }
+        // Sight reaches x=9 from the start and x=10 a step along
+        assert_eq!(loom.status_view().explored, Some(45));
+        type_keys(&mut loom, "l");
+        assert_eq!(loom.status_view().explored, Some(50));
+        assert_eq!(loom.map_header_view().explored, Some(50));
+        type_keys(&mut loom, "h");
+        assert_eq!(loom.map_header_view().explored, None);
+    }
+
+    #[test]
+    fn atlas_shows_only_discovered_ground() {
+        let mut loom = test_loom();
+        add_region(&mut loom, Position::new(1, 0));
+        add_region(&mut loom, Position::new(4, 0));
+        type_keys(&mut loom, "jk");
+        loom.run_ex_line("atlas").unwrap();
+        assert_eq!(loom.mode.mode_name(), "-- ATLAS --");
+        assert_eq!(loom.map_header_view().name, "atlas");
+
+        // Two tiles to a cell fits all the player has seen
+        let atlas = loom.atlas_view(23, 1).unwrap();
+        assert_eq!(atlas.scale, 2);
+        let row = &atlas.rows[0];
+        assert_eq!(row[11].marker, Some(Marker::Player));
+        assert!(row[11].highlighted);
+        assert_eq!(row[13].biome, Some(Biome::Plains));
+        assert!(row[15].discovered);
+        // Ten tiles away is beyond the player's sight and has never been seen
+        assert!(!row[16].discovered);
+        assert_eq!(row[16].biome, None);
+    }
+
+    #[test]
+    fn atlas_marks_story_sites_and_graves() {
+        let mut loom = test_loom();
+        let site = add_region(&mut loom, Position::new(4, 0));
+        let story = add_region(&mut loom, Position::new(0, 0));
+        let mut goal = Goal::new(GoalKind::Resource, Biome::Plains, 1);
+        goal.regions.push(site);
+        let story = loom.tapestry.get_thread_mut(story).unwrap();
+        story.position = None;
+        story.kind = ThreadKind::Story(Story {
+            title: "The Quiet Road".to_string(),
+            stages: vec![Stage {
+                name: "The Call".to_string(),
+                goals: vec![goal],
+            }],
+            stage: 0,
+            woven: true,
+            outcome: None,
+        });
+        let npc = add_region(&mut loom, Position::new(-4, 0));
+        loom.tapestry.get_thread_mut(npc).unwrap().kind = ThreadKind::Npc {
+            name: "Mira".to_string(),
+        };
+        type_keys(&mut loom, "jk");
+        vitals::perish(&mut loom.tapestry, npc, Death::Slain(loom.player_id));
+
+        let atlas = |loom: &Loom| loom.atlas_view(23, 1).unwrap().rows.remove(0);
+        loom.run_ex_line("atlas").unwrap();
+        let row = atlas(&loom);
+        assert_eq!(row[13].marker, Some(Marker::Story));
+        assert_eq!(row[9].marker, Some(Marker::Grave));
+        assert_eq!(row[11].marker, Some(Marker::Player));
+        // Only a story still unfolding keeps its land marked
+        let story = loom.tapestry.threads().find(|thread| thread.story().is_some()).unwrap().id;
+        if let ThreadKind::Story(story) = &mut loom.tapestry.get_thread_mut(story).unwrap().kind {
+            story.outcome = Some(Outcome::Success);
+        }
+        assert_eq!(atlas(&loom)[13].marker, None);
+        // Nor are graves marked on ground never seen
+        loom.tapestry.write_chronicle_at("Oren was slain", Some(Position::new(40, 0)));
+        assert!(loom.minimap_view(23, 1).rows[0].iter().all(|cell| cell.discovered || cell.marker.is_none()));
+    }
+
+    #[test]
+    fn atlas_sets_a_travel_destination() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, "m;");
+        loom.run_ex_line("atlas").unwrap();
+        type_keys(&mut loom, "6l\r");
+        assert_eq!(loom.destination, None);
+        assert!(loom.atlas.is_some());
+
+        type_keys(&mut loom, "g;l\r");
+        assert_eq!(loom.destination, Some(Position::new(2, 0)));
+        assert!(loom.atlas.is_none());
+        assert!(loom.mode.is_normal());
+        assert_eq!(player_position(&loom), Position::new(0, 0));
+    }
+
+    #[test]
+    fn minimap_marks_the_player_and_marks() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, "m;8l");
+        let minimap = loom.minimap_view(5, 3);
+        assert_eq!(minimap.scale, 4);
+        assert_eq!(minimap.rows[1][2].marker, Some(Marker::Player));
+        assert_eq!(minimap.rows[1][0].marker, Some(Marker::Mark(';')));
+    }
+
+    #[test]
+    fn map_view_of_an_empty_area_does_not_panic() {
+        let loom = test_loom();
+        assert!(loom.map_view(0, 0).rows.is_empty());
+    }
+
+    #[test]
+    fn header_names_the_region_underfoot() {
+        let mut loom = test_loom();
+        assert_eq!(loom.map_header_view().name, "wilderness");
+        add_region(&mut loom, Position::new(1, 0));
+        type_keys(&mut loom, "l");
+        assert_eq!(loom.map_header_view().name, "whispering_plains");
+    }
+
+    #[test]
+    fn message_line_counts_the_log() {
+        let mut loom = test_loom();
+        assert!(loom.message_line_view().message.is_none());
+        loom.messages.info("one");
+        loom.messages.info("two");
+        let line = loom.message_line_view();
+        assert_eq!(line.message.unwrap().text, "two");
+        assert_eq!((line.index, line.total), (2, 2));
+
+        // The newest message is not repeated below the frame
+        let area = loom.command_area_view(2);
+        assert_eq!(area.messages.len(), 1);
+        assert_eq!(area.messages[0].text, "one");
+    }
+
+    #[test]
+    fn message_line_steps_through_the_log() {
+        let mut loom = test_loom();
+        type_keys(&mut loom, "g<");
+        assert_eq!((loom.message_line_view().index, loom.message_line_view().total), (0, 0));
+        for text in ["one", "two", "three"] {
+            loom.messages.info(text);
+        }
+        let shown = |loom: &Loom| {
+            let line = loom.message_line_view();
+            (line.message.unwrap().text, line.index, line.total)
+        };
+        type_keys(&mut loom, "g<");
+        assert_eq!(shown(&loom), ("two".to_string(), 2, 3));
+        type_keys(&mut loom, "5g<");
+        assert_eq!(shown(&loom), ("one".to_string(), 1, 3));
+        type_keys(&mut loom, "g>");
+        assert_eq!(shown(&loom), ("two".to_string(), 2, 3));
+        type_keys(&mut loom, "9g>");
+        assert_eq!(shown(&loom), ("three".to_string(), 3, 3));
+
+        // Anything else returns to the newest
+        type_keys(&mut loom, "g<");
+        loom.messages.info("four");
+        type_keys(&mut loom, "\x1b");
+        assert_eq!(shown(&loom), ("four".to_string(), 4, 4));
+    }
+}
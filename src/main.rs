@@ -3,218 +3,86 @@
 //! This is the bootstrap system that wires together all bounded contexts
 //! and runs the main game loop.
 
-use crossterm::event::{self, Event, KeyCode};
 use std::io;
+use std::process::ExitCode;
 use vitalis::{
-    foundation::Position,
+    loom::Loom,
     patterns::{
-        commands::{parse_ex_command, parse_ex_input, parse_normal_command, Command, ExCommand},
-        display::{init_terminal, render, restore_terminal},
-        modes::Mode,
+        config::{default_colors_dir, default_save_dir, RcSource, StartupOptions},
+        display::{init_terminal, restore_terminal},
+        game_loop,
+        input::{CrosstermInput, InputSource, RecordingInput, ReplayInput},
     },
-    tapestry::Tapestry,
-    threads::{Thread, ThreadId, ThreadKind},
-    weaver::{properties::ThreadProperties, states::ThreadStates},
 };
 
-fn main() -> io::Result<()> {
+fn main() -> io::Result<ExitCode> {
+    let startup = match StartupOptions::from_args(std::env::args().skip(1)) {
+        Ok(startup) => startup,
+        Err(err) => {
+            eprintln!("vitalis: {}", err);
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
     // Initialize the terminal
     let mut terminal = init_terminal()?;
 
-    // Create the initial Tapestry
-    let mut tapestry = Tapestry::new();
+    let mut loom = Loom::new_game();
+    loom.save_dir = default_save_dir().unwrap_or_default();
+    loom.colors_dir = default_colors_dir().unwrap_or_default();
 
-    // Create the player Thread
-    let player_id = tapestry.next_id();
-    let player = Thread {
-        id: player_id,
-        kind: ThreadKind::Player {
-            name: "Wanderer".to_string(),
-        },
-        properties: ThreadProperties::default(),
-        states: ThreadStates::default(),
-        position: Some(Position::new(0, 0)),
-    };
-    tapestry.add_thread(player);
+    // Honour NO_COLOR (https://no-color.org); the vitalisrc may still override it
+    if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        let _ = loom.run_ex_line("colorscheme mono");
+    }
+
+    // Execute the startup configuration (vitalisrc)
+    if let Some(rc_path) = startup.rc_path() {
+        if let Err(err) = loom.source(&rc_path) {
+            // A missing default vitalisrc is normal; an explicit -u file is not
+            if startup.rc != RcSource::Default || err.kind() != io::ErrorKind::NotFound {
+                loom.messages
+                    .error(format!("E484: Can't open file {}: {}", rc_path.display(), err));
+            }
+        }
+    }
 
-    // Create a simple region Thread
-    for x in -5..=5 {
-        for y in -5..=5 {
-            if x == 0 && y == 0 {
-                continue; // Player position
+    // Recall command-line history from previous sessions
+    let history_path = startup.history_path();
+    if let Some(path) = &history_path {
+        if let Err(err) = loom.history.load(path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                loom.messages
+                    .error(format!("E886: Can't read history {}: {}", path.display(), err));
             }
-            let region_id = tapestry.next_id();
-            let region = Thread {
-                id: region_id,
-                kind: ThreadKind::Region {
-                    description: "Whispering Plains".to_string(),
-                },
-                properties: ThreadProperties {
-                    intelligence: 8,
-                    charisma: 15,
-                    ..Default::default()
-                },
-                states: ThreadStates::default(),
-                position: Some(Position::new(x, y)),
-            };
-            tapestry.add_thread(region);
         }
     }
 
-    // Game loop
-    let mut mode = Mode::default();
-    let result = run_game_loop(&mut terminal, &mut tapestry, player_id, &mut mode);
+    // Game loop, reading the keyboard after any -s script
+    let result = open_input(&startup).and_then(|mut input| {
+        game_loop::run(&mut terminal, &mut loom, &mut input)
+    });
 
     // Restore terminal
     restore_terminal(&mut terminal)?;
 
-    result
-}
-
-fn run_game_loop(
-    terminal: &mut vitalis::patterns::display::TerminalType,
-    tapestry: &mut Tapestry,
-    player_id: ThreadId,
-    mode: &mut Mode,
-) -> io::Result<()> {
-    loop {
-        // Render current state
-        render(terminal, tapestry, player_id, mode)?;
-
-        // Handle input
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                let command = match key.code {
-                    KeyCode::Char(c) => {
-                        if mode.is_ex() {
-                            // Check if Enter key sent as '\n' or '\r'
-                            if c == '\n' || c == '\r' {
-                                // Execute Ex command
-                                if let Some(cmd_str) = mode.command_buffer() {
-                                    if let Some(ex_cmd) = parse_ex_command(cmd_str) {
-                                        Command::ExCommand(ex_cmd)
-                                    } else {
-                                        Command::Unknown
-                                    }
-                                } else {
-                                    Command::Unknown
-                                }
-                            } else {
-                                parse_ex_input(c)
-                            }
-                        } else {
-                            parse_normal_command(c)
-                        }
-                    }
-                    KeyCode::Enter if mode.is_ex() => {
-                        // Execute Ex command
-                        if let Some(cmd_str) = mode.command_buffer() {
-                            if let Some(ex_cmd) = parse_ex_command(cmd_str) {
-                                Command::ExCommand(ex_cmd)
-                            } else {
-                                Command::Unknown
-                            }
-                        } else {
-                            Command::Unknown
-                        }
-                    }
-                    KeyCode::Esc => {
-                        if mode.is_ex() {
-                            Command::CancelEx
-                        } else {
-                            continue; // In normal mode, ESC does nothing
-                        }
-                    }
-                    KeyCode::Backspace => {
-                        if mode.is_ex() {
-                            Command::ExBackspace
-                        } else {
-                            Command::Unknown
-                        }
-                    }
-                    _ => Command::Unknown,
-                };
-
-                if !execute_command(command, tapestry, player_id, mode) {
-                    break; // Quit command received
-                }
-            }
+    if let Some(path) = &history_path {
+        if let Err(err) = loom.history.save(path) {
+            eprintln!("vitalis: can't write history {}: {}", path.display(), err);
         }
     }
 
-    Ok(())
+    result.map(|_| ExitCode::SUCCESS)
 }
 
-fn execute_command(
-    command: Command,
-    tapestry: &mut Tapestry,
-    player_id: ThreadId,
-    mode: &mut Mode,
-) -> bool {
-    match command {
-        Command::Move(direction) => {
-            // Parse count from buffer (default to 1)
-            let count = if let Some(count_str) = mode.count_buffer() {
-                count_str.parse::<u32>().unwrap_or(1)
-            } else {
-                1
-            };
-
-            // Apply movement count times
-            if let Some(player) = tapestry.get_thread_mut(player_id) {
-                if let Some(mut current_pos) = player.position {
-                    for _ in 0..count {
-                        current_pos = direction.apply_to(current_pos);
-                    }
-                    player.position = Some(current_pos);
-                }
-            }
-
-            // Clear count buffer after executing command
-            if let Mode::Normal { count_buffer } = mode {
-                count_buffer.clear();
-            }
-
-            true // Continue game
-        }
-        Command::CountInput(digit) => {
-            if let Mode::Normal { count_buffer } = mode {
-                count_buffer.push(digit);
-            }
-            true
-        }
-        Command::EnterExMode => {
-            *mode = Mode::Ex {
-                command_buffer: String::new(),
-            };
-            true
-        }
-        Command::ExInput(c) => {
-            if let Mode::Ex { command_buffer } = mode {
-                command_buffer.push(c);
-            }
-            true
-        }
-        Command::ExBackspace => {
-            if let Mode::Ex { command_buffer } = mode {
-                command_buffer.pop();
-            }
-            true
-        }
-        Command::CancelEx => {
-            *mode = Mode::Normal {
-                count_buffer: String::new(),
-            };
-            true
-        }
-        Command::ExCommand(ex_cmd) => {
-            *mode = Mode::Normal {
-                count_buffer: String::new(),
-            }; // Return to normal mode after command
-            match ex_cmd {
-                ExCommand::Quit => false, // Exit game
-            }
-        }
-        Command::Unknown => true, // Ignore unknown commands
+/// The keyboard, behind any -s replay and -w recording
+fn open_input(startup: &StartupOptions) -> io::Result<Box<dyn InputSource>> {
+    let mut input: Box<dyn InputSource> = Box::new(CrosstermInput);
+    if let Some(path) = &startup.script_in {
+        input = Box::new(ReplayInput::open(path, input)?);
+    }
+    if let Some(path) = &startup.script_out {
+        input = Box::new(RecordingInput::create(path, input)?);
     }
+    Ok(input)
 }
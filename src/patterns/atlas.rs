@@ -0,0 +1,101 @@
+//! The atlas - a full-screen overview of the discovered world (:atlas)
+//!
+//! Each atlas cell stands for a square of tiles, drawn in the colour of
+//! their most common biome. A cursor moves over the cells; the map is kept
+//! centred on it. The minimap shows the same cells around the player in a
+//! corner of the map ('minimap').
+
+use super::camera::ZOOM_LEVELS;
+use crate::foundation::Position;
+
+/// Tiles per cell on the minimap, along each side
+pub const MINIMAP_SCALE: u8 = 4;
+
+/// Cells the atlas should fit on an 80x24 screen when it opens
+pub const ATLAS_FIT: (u16, u16) = (76, 12);
+
+/// The atlas while it is open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Atlas {
+    /// World position under the atlas cursor
+    pub cursor: Position,
+    /// Tiles per cell along each side, one of [`ZOOM_LEVELS`]
+    pub scale: u8,
+}
+
+impl Atlas {
+    /// Open the atlas at `cursor`, at the closest scale that shows all of
+    /// `bounds` (the corners of the discovered world) in `fit` cells
+    pub fn open(cursor: Position, bounds: Option<(Position, Position)>, fit: (u16, u16)) -> Self {
+        let scale = bounds.map_or(ZOOM_LEVELS[0], |(low, high)| {
+            let (width, height) = (high.x - low.x + 1, high.y - low.y + 1);
+            ZOOM_LEVELS
+                .iter()
+                .copied()
+                .find(|scale| {
+                    let scale = i32::from(*scale);
+                    width <= i32::from(fit.0) * scale && height <= i32::from(fit.1) * scale
+                })
+                .unwrap_or(ZOOM_LEVELS[ZOOM_LEVELS.len() - 1])
+        });
+        Self { cursor, scale }
+    }
+
+    /// Move the cursor by whole cells
+    pub fn move_by(&mut self, dx: i32, dy: i32) {
+        let scale = i32::from(self.scale);
+        self.cursor.x += dx * scale;
+        self.cursor.y += dy * scale;
+    }
+
+    /// Show more tiles per cell, or fewer for negative steps
+    pub fn zoom_by(&mut self, steps: i32) {
+        let current = ZOOM_LEVELS.iter().position(|scale| *scale == self.scale).unwrap_or(0);
+        let last = ZOOM_LEVELS.len() as i32 - 1;
+        self.scale = ZOOM_LEVELS[(current as i32 + steps).clamp(0, last) as usize];
+    }
+}
+
+/// World position of the top-left cell of a `width` by `height` overview
+/// at `scale`, with the cell holding `centre` in the middle
+pub fn origin(centre: Position, scale: u8, width: u16, height: u16) -> Position {
+    let scale = i32::from(scale);
+    let cell = Position::new(centre.x.div_euclid(scale), centre.y.div_euclid(scale));
+    Position::new(
+        (cell.x - i32::from(width) / 2) * scale,
+        (cell.y - i32::from(height) / 2) * scale,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_at_the_closest_scale_that_fits() {
+        let cursor = Position::new(0, 0);
+        assert_eq!(Atlas::open(cursor, None, (10, 5)).scale, 1);
+        let small = Some((Position::new(-4, -2), Position::new(5, 2)));
+        assert_eq!(Atlas::open(cursor, small, (10, 5)).scale, 1);
+        let wide = Some((Position::new(-30, 0), Position::new(9, 0)));
+        assert_eq!(Atlas::open(cursor, wide, (10, 5)).scale, 4);
+        let huge = Some((Position::new(0, 0), Position::new(999, 0)));
+        assert_eq!(Atlas::open(cursor, huge, (10, 5)).scale, 8);
+    }
+
+    #[test]
+    fn cursor_moves_by_cells() {
+        let mut atlas = Atlas::open(Position::new(1, 1), None, (10, 5));
+        atlas.zoom_by(1);
+        atlas.move_by(2, -1);
+        assert_eq!(atlas.cursor, Position::new(5, -1));
+        atlas.zoom_by(-5);
+        assert_eq!(atlas.scale, 1);
+    }
+
+    #[test]
+    fn origin_centres_the_cursor_cell() {
+        assert_eq!(origin(Position::new(0, 0), 1, 5, 3), Position::new(-2, -1));
+        assert_eq!(origin(Position::new(5, -1), 4, 3, 3), Position::new(0, -8));
+    }
+}
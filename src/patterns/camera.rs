@@ -0,0 +1,280 @@
+//! The camera - which part of the world the map shows
+//!
+//! The camera follows a target (the cursor, or a mark it is locked to) in
+//! one of three modes. Following scrolls only when the target comes within
+//! 'scrolloff' rows or 'sidescrolloff' columns of an edge, as in vim; a
+//! margin of half the map or more keeps the target centred. Zooming out
+//! folds several tiles into each map cell.
+//!
+//! Where the view sits depends on the size of the map, which is only known
+//! when it is drawn, so the camera settles its position during drawing.
+//! View commands (zz, zt, zb, CTRL-E, ...) hold until the target moves.
+
+use crate::foundation::Position;
+use std::cell::Cell;
+use std::fmt;
+
+/// Tiles per map cell, along each side, from closest to farthest
+pub const ZOOM_LEVELS: [u8; 4] = [1, 2, 4, 8];
+
+/// How the camera follows its target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Scroll when the cursor nears an edge ('scrolloff')
+    #[default]
+    Follow,
+    /// Keep the cursor in the middle of the map
+    Center,
+    /// Keep a mark in the middle of the map, wherever the cursor goes
+    Locked(char),
+}
+
+impl CameraMode {
+    /// Parse the argument of `:camera` (`follow`, `center`, `lock '`)
+    pub fn parse(arg: &str) -> Option<CameraMode> {
+        let mut words = arg.split_whitespace();
+        let mode = match (words.next()?, words.next()) {
+            ("follow", None) => CameraMode::Follow,
+            ("center" | "centre", None) => CameraMode::Center,
+            ("lock", Some(mark)) if mark.chars().count() == 1 => {
+                CameraMode::Locked(mark.chars().next()?)
+            }
+            _ => return None,
+        };
+        words.next().is_none().then_some(mode)
+    }
+}
+
+impl fmt::Display for CameraMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CameraMode::Follow => write!(f, "follow"),
+            CameraMode::Center => write!(f, "center"),
+            CameraMode::Locked(mark) => write!(f, "lock {}", mark),
+        }
+    }
+}
+
+/// Where zz, zt and zb put the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Center,
+    Top,
+    Bottom,
+}
+
+/// The view as last drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Placement {
+    /// Map cell at the top-left corner, in zoomed cells
+    origin: Position,
+    /// Where the target was, in world tiles
+    target: Position,
+}
+
+/// The camera over the map
+#[derive(Debug, Clone, Default)]
+pub struct Camera {
+    pub mode: CameraMode,
+    /// Index into [`ZOOM_LEVELS`]
+    zoom: usize,
+    placement: Cell<Option<Placement>>,
+    /// Alignment asked for by zz, zt or zb, applied when next drawn
+    align: Cell<Option<Align>>,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tiles per map cell along each side
+    pub fn zoom(&self) -> u8 {
+        ZOOM_LEVELS[self.zoom]
+    }
+
+    /// Set the zoom to one of [`ZOOM_LEVELS`], returning false for others
+    pub fn set_zoom(&mut self, zoom: u8) -> bool {
+        match ZOOM_LEVELS.iter().position(|level| *level == zoom) {
+            Some(index) => {
+                self.zoom = index;
+                self.recenter();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Zoom out `steps` levels, or in for negative steps
+    pub fn zoom_by(&mut self, steps: i32) {
+        let last = ZOOM_LEVELS.len() as i32 - 1;
+        self.zoom = (self.zoom as i32 + steps).clamp(0, last) as usize;
+        self.recenter();
+    }
+
+    /// Forget the view so the next one is centred on the target
+    pub fn recenter(&mut self) {
+        self.placement.set(None);
+        self.align.set(None);
+    }
+
+    /// Put the target at the middle, top or bottom when next drawn
+    pub fn align(&mut self, align: Align) {
+        self.align.set(Some(align));
+    }
+
+    /// Move the view by whole map cells without moving the target
+    ///
+    /// Before the map has been drawn there is nothing to move.
+    pub fn scroll(&mut self, dx: i32, dy: i32) {
+        if let Some(mut placement) = self.placement.get() {
+            placement.origin.x += dx;
+            placement.origin.y += dy;
+            self.placement.set(Some(placement));
+        }
+    }
+
+    /// World position of the top-left map cell for a `width` by `height`
+    /// map following `target`
+    ///
+    /// `margins` are the columns and rows kept between the target and the
+    /// edges ('sidescrolloff', 'scrolloff') when following.
+    pub fn frame(&self, target: Position, width: u16, height: u16, margins: (u16, u16)) -> Position {
+        let zoom = i32::from(self.zoom());
+        let cell = Position::new(target.x.div_euclid(zoom), target.y.div_euclid(zoom));
+        let (width, height) = (i32::from(width), i32::from(height));
+        let (columns, rows) = (i32::from(margins.0), i32::from(margins.1));
+        let centred = Position::new(cell.x - width / 2, cell.y - height / 2);
+
+        let previous = self.placement.get();
+        let origin = match (previous, self.align.take()) {
+            (_, Some(align)) => {
+                let x = match (previous, self.mode) {
+                    (Some(previous), CameraMode::Follow) => follow(previous.origin.x, cell.x, width, columns),
+                    _ => centred.x,
+                };
+                Position::new(x, aligned(align, cell.y, height, rows))
+            }
+            (Some(previous), None) if previous.target == target => previous.origin,
+            (Some(previous), None) if self.mode == CameraMode::Follow => Position::new(
+                follow(previous.origin.x, cell.x, width, columns),
+                follow(previous.origin.y, cell.y, height, rows),
+            ),
+            _ => centred,
+        };
+        self.placement.set(Some(Placement { origin, target }));
+        Position::new(origin.x * zoom, origin.y * zoom)
+    }
+}
+
+/// The margin that fits in `size` cells, or None if it covers half of them
+fn fitted_margin(size: i32, margin: i32) -> Option<i32> {
+    (margin * 2 < size - 1).then_some(margin)
+}
+
+/// First cell along one axis after scrolling just enough to keep `target`
+/// `margin` cells inside a view of `size` cells starting at `origin`
+fn follow(origin: i32, target: i32, size: i32, margin: i32) -> i32 {
+    match fitted_margin(size, margin) {
+        Some(margin) => origin.clamp(target - (size - 1 - margin), target - margin),
+        None => target - size / 2,
+    }
+}
+
+/// First row that puts `target` at the middle, top or bottom of the view,
+/// leaving `margin` rows at the top and bottom
+fn aligned(align: Align, target: i32, size: i32, margin: i32) -> i32 {
+    let margin = fitted_margin(size, margin).unwrap_or((size - 1).max(0) / 2);
+    match align {
+        Align::Center => target - size / 2,
+        Align::Top => target - margin,
+        Align::Bottom => target - (size - 1 - margin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Margins as with the default options: always centred
+    const CENTRED: (u16, u16) = (999, 999);
+
+    #[test]
+    fn large_margins_keep_the_target_centred() {
+        let camera = Camera::new();
+        assert_eq!(camera.frame(Position::new(0, 0), 11, 5, CENTRED), Position::new(-5, -2));
+        assert_eq!(camera.frame(Position::new(3, 1), 11, 5, CENTRED), Position::new(-2, -1));
+    }
+
+    #[test]
+    fn following_scrolls_only_near_the_edges() {
+        let camera = Camera::new();
+        let margins = (2, 1);
+        assert_eq!(camera.frame(Position::new(0, 0), 11, 7, margins), Position::new(-5, -3));
+        // Inside the margins the view stays put
+        assert_eq!(camera.frame(Position::new(3, 1), 11, 7, margins), Position::new(-5, -3));
+        // Two columns from the right edge is as far as the target gets
+        assert_eq!(camera.frame(Position::new(4, 2), 11, 7, margins), Position::new(-4, -3));
+        assert_eq!(camera.frame(Position::new(-10, 3), 11, 7, margins), Position::new(-12, -2));
+    }
+
+    #[test]
+    fn centre_mode_ignores_margins() {
+        let camera = Camera {
+            mode: CameraMode::Center,
+            ..Camera::new()
+        };
+        camera.frame(Position::new(0, 0), 11, 7, (2, 1));
+        assert_eq!(camera.frame(Position::new(1, 0), 11, 7, (2, 1)), Position::new(-4, -3));
+    }
+
+    #[test]
+    fn alignment_and_scrolling_hold_until_the_target_moves() {
+        let mut camera = Camera::new();
+        let target = Position::new(0, 0);
+        camera.frame(target, 11, 7, (0, 1));
+        camera.align(Align::Top);
+        assert_eq!(camera.frame(target, 11, 7, (0, 1)).y, -1);
+        camera.align(Align::Bottom);
+        assert_eq!(camera.frame(target, 11, 7, (0, 1)).y, -5);
+
+        camera.scroll(0, 2);
+        assert_eq!(camera.frame(target, 11, 7, (0, 1)).y, -3);
+        assert_eq!(camera.frame(target, 11, 7, (0, 1)).y, -3);
+
+        // Large margins recentre as soon as the target moves
+        assert_eq!(camera.frame(Position::new(0, 1), 11, 7, CENTRED).y, -2);
+    }
+
+    #[test]
+    fn zooming_folds_tiles_into_cells() {
+        let mut camera = Camera::new();
+        camera.zoom_by(2);
+        assert_eq!(camera.zoom(), 4);
+        // The target's cell is in the middle; the corner is in world tiles
+        assert_eq!(camera.frame(Position::new(5, -1), 3, 3, CENTRED), Position::new(0, -8));
+        camera.zoom_by(9);
+        assert_eq!(camera.zoom(), 8);
+        assert!(!camera.set_zoom(3));
+        assert!(camera.set_zoom(1));
+    }
+
+    #[test]
+    fn empty_maps_do_not_panic() {
+        let mut camera = Camera::new();
+        camera.frame(Position::new(0, 0), 0, 0, (0, 0));
+        camera.align(Align::Bottom);
+        camera.frame(Position::new(0, 0), 0, 0, (999, 999));
+        camera.frame(Position::new(4, 4), 0, 0, (0, 0));
+    }
+
+    #[test]
+    fn modes_parse_and_print() {
+        assert_eq!(CameraMode::parse("follow"), Some(CameraMode::Follow));
+        assert_eq!(CameraMode::parse("centre"), Some(CameraMode::Center));
+        assert_eq!(CameraMode::parse("lock ;"), Some(CameraMode::Locked(';')));
+        assert_eq!(CameraMode::parse("lock"), None);
+        assert_eq!(CameraMode::parse("spin"), None);
+        assert_eq!(CameraMode::Locked(';').to_string(), "lock ;");
+    }
+}
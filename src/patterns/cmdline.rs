@@ -0,0 +1,623 @@
+//! Command-line editing - the Ex mode input line
+//!
+//! Provides cursor movement, word and line deletion, history recall and tab
+//! completion for the `:` command line, following vim's cmdline conventions.
+
+use super::commands::EX_COMMANDS;
+use super::help::HelpIndex;
+use super::marks::Marks;
+use super::options::{OptionKind, OPTIONS};
+use super::theme::Theme;
+use crate::seamstress;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Default number of remembered command lines (the 'history' option)
+pub const DEFAULT_HISTORY_SIZE: usize = 200;
+
+/// What editing a command line draws on: the history to recall, and the
+/// session's help tags, marks and directories to complete from
+#[derive(Debug, Clone, Copy)]
+pub struct LineContext<'a> {
+    pub history: &'a CommandHistory,
+    pub help: &'a HelpIndex,
+    pub marks: &'a Marks,
+    pub save_dir: &'a Path,
+    pub colors_dir: &'a Path,
+}
+
+/// Editing operations on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEdit {
+    /// Move cursor one character left (<Left>)
+    Left,
+    /// Move cursor one character right (<Right>)
+    Right,
+    /// Move cursor to start of line (Ctrl-b, <Home>)
+    Home,
+    /// Move cursor to end of line (Ctrl-e, <End>)
+    End,
+    /// Delete character under the cursor (<Del>)
+    Delete,
+    /// Delete the word before the cursor (Ctrl-w)
+    DeleteWord,
+    /// Delete everything before the cursor (Ctrl-u)
+    DeleteToStart,
+    /// Recall older history entry matching the typed prefix (<Up>, Ctrl-p)
+    HistoryPrev,
+    /// Recall newer history entry matching the typed prefix (<Down>, Ctrl-n)
+    HistoryNext,
+    /// Complete the word before the cursor, cycling forward (<Tab>)
+    Complete,
+    /// Cycle completions backward (<S-Tab>)
+    CompletePrev,
+}
+
+/// In-progress tab completion
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Completion {
+    /// Char index where the completed word starts
+    start: usize,
+    /// What the player had typed before the first <Tab>
+    original: String,
+    candidates: Vec<String>,
+    /// Selected candidate; `candidates.len()` means the original text
+    index: usize,
+}
+
+/// In-progress history recall
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HistoryRecall {
+    /// Text typed before browsing; only entries starting with it match
+    prefix: String,
+    /// Index into the history; `len()` means back at the typed text
+    index: usize,
+}
+
+/// The text being typed after `:` and the cursor within it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandLine {
+    text: String,
+    /// Cursor position in chars
+    cursor: usize,
+    completion: Option<Completion>,
+    recall: Option<HistoryRecall>,
+}
+
+impl CommandLine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A command line holding `text` with the cursor at the end
+    pub fn with_text(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            cursor: text.chars().count(),
+            ..Self::default()
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Cursor position in chars
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(char_index)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    fn reset_state(&mut self) {
+        self.completion = None;
+        self.recall = None;
+    }
+
+    /// Insert a character at the cursor
+    pub fn insert(&mut self, c: char) {
+        self.reset_state();
+        let idx = self.byte_index(self.cursor);
+        self.text.insert(idx, c);
+        self.cursor += 1;
+    }
+
+    /// Delete the character before the cursor
+    pub fn backspace(&mut self) {
+        self.reset_state();
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let idx = self.byte_index(self.cursor);
+            self.text.remove(idx);
+        }
+    }
+
+    /// Apply an editing operation
+    pub fn edit(&mut self, edit: LineEdit, context: &LineContext) {
+        match edit {
+            LineEdit::HistoryPrev => return self.recall(context.history, true),
+            LineEdit::HistoryNext => return self.recall(context.history, false),
+            LineEdit::Complete => return self.complete(true, context),
+            LineEdit::CompletePrev => return self.complete(false, context),
+            _ => self.reset_state(),
+        }
+
+        let len = self.text.chars().count();
+        match edit {
+            LineEdit::Left => self.cursor = self.cursor.saturating_sub(1),
+            LineEdit::Right => self.cursor = (self.cursor + 1).min(len),
+            LineEdit::Home => self.cursor = 0,
+            LineEdit::End => self.cursor = len,
+            LineEdit::Delete if self.cursor < len => {
+                let idx = self.byte_index(self.cursor);
+                self.text.remove(idx);
+            }
+            LineEdit::DeleteWord => {
+                let chars: Vec<char> = self.text.chars().collect();
+                let mut start = self.cursor;
+                while start > 0 && chars[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                if start > 0 {
+                    let word_char = is_word_char(chars[start - 1]);
+                    while start > 0
+                        && !chars[start - 1].is_whitespace()
+                        && is_word_char(chars[start - 1]) == word_char
+                    {
+                        start -= 1;
+                    }
+                }
+                self.delete_range(start, self.cursor);
+            }
+            LineEdit::DeleteToStart => self.delete_range(0, self.cursor),
+            _ => {}
+        }
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) {
+        let (from, to) = (self.byte_index(start), self.byte_index(end));
+        self.text.replace_range(from..to, "");
+        self.cursor = start;
+    }
+
+    /// Replace the line with an older or newer history entry
+    fn recall(&mut self, history: &CommandHistory, older: bool) {
+        self.completion = None;
+        let entries = history.entries();
+        let recall = self.recall.get_or_insert_with(|| HistoryRecall {
+            prefix: self.text.clone(),
+            index: entries.len(),
+        });
+
+        let found = if older {
+            (0..recall.index.min(entries.len()))
+                .rev()
+                .find(|&i| entries[i].starts_with(&recall.prefix))
+        } else {
+            (recall.index + 1..entries.len()).find(|&i| entries[i].starts_with(&recall.prefix))
+        };
+
+        let text = match found {
+            Some(i) => {
+                recall.index = i;
+                entries[i].clone()
+            }
+            None if older => return, // Already at the oldest match
+            None => {
+                recall.index = entries.len();
+                recall.prefix.clone()
+            }
+        };
+        self.cursor = text.chars().count();
+        self.text = text;
+    }
+
+    /// Complete the word before the cursor, cycling through candidates
+    fn complete(&mut self, forward: bool, context: &LineContext) {
+        self.recall = None;
+        if self.completion.is_none() {
+            let before: String = self.text.chars().take(self.cursor).collect();
+            let (start, candidates) = completion_candidates(&before, context);
+            if candidates.is_empty() {
+                return;
+            }
+            let original = before.chars().skip(start).collect();
+            self.completion = Some(Completion {
+                start,
+                original,
+                index: candidates.len(),
+                candidates,
+            });
+        }
+
+        let Some(completion) = self.completion.as_mut() else {
+            return;
+        };
+        let slots = completion.candidates.len() + 1;
+        completion.index = if forward {
+            (completion.index + 1) % slots
+        } else {
+            (completion.index + slots - 1) % slots
+        };
+        let replacement = completion
+            .candidates
+            .get(completion.index)
+            .unwrap_or(&completion.original)
+            .clone();
+        let start = completion.start;
+
+        let (from, to) = (self.byte_index(start), self.byte_index(self.cursor));
+        self.text.replace_range(from..to, &replacement);
+        self.cursor = start + replacement.chars().count();
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Completion candidates for the text before the cursor
+///
+/// Returns the char index where the completed word starts and the sorted
+/// candidates. The first word completes to command names; arguments of
+/// `:set` complete to option names, and to values for enum options;
+/// `:help` completes to help tags, `:edit` and `:write` to save names, and
+/// `:travel` and `:delmarks` to the marks set.
+pub fn completion_candidates(before_cursor: &str, context: &LineContext) -> (usize, Vec<String>) {
+    let trimmed = before_cursor.trim_start_matches([' ', ':']);
+    let leading = before_cursor.chars().count() - trimmed.chars().count();
+
+    let Some((split, space)) = trimmed.char_indices().rfind(|(_, c)| c.is_whitespace()) else {
+        // Still typing the command name
+        let mut names: Vec<String> = EX_COMMANDS
+            .iter()
+            .map(|def| def.name.to_string())
+            .filter(|name| name.starts_with(trimmed))
+            .collect();
+        names.sort();
+        return (leading, names);
+    };
+
+    let command = trimmed.split_whitespace().next().unwrap_or("");
+    let word = &trimmed[split + space.len_utf8()..];
+    let word_start = leading + trimmed[..split].chars().count() + 1;
+
+    match command {
+        "se" | "set" => {
+            if let Some((name, value)) = word.split_once('=') {
+                let values = OPTIONS
+                    .iter()
+                    .find(|def| def.name == name || def.abbrev == Some(name))
+                    .map(|def| match def.kind {
+                        OptionKind::Enum(choices) => choices
+                            .iter()
+                            .filter(|choice| choice.starts_with(value))
+                            .map(|choice| choice.to_string())
+                            .collect(),
+                        _ if def.name == "colorscheme" => colorscheme_names(context.colors_dir, value),
+                        _ => Vec::new(),
+                    })
+                    .unwrap_or_default();
+                let value_start = word_start + name.chars().count() + 1;
+                return (value_start, values);
+            }
+
+            let mut names: Vec<String> = OPTIONS
+                .iter()
+                .flat_map(|def| {
+                    let mut forms = vec![def.name.to_string()];
+                    if def.kind == OptionKind::Bool {
+                        forms.push(format!("no{}", def.name));
+                        forms.push(format!("inv{}", def.name));
+                    }
+                    forms
+                })
+                .chain(["all".to_string()])
+                .filter(|name| name.starts_with(word))
+                .collect();
+            names.sort();
+            (word_start, names)
+        }
+        "h" | "help" => {
+            let tags = context
+                .help
+                .tags()
+                .filter(|tag| tag.starts_with(word))
+                .map(str::to_string)
+                .collect();
+            (word_start, tags)
+        }
+        "e" | "edit" | "w" | "write" | "sav" | "save" | "saveas" => {
+            let saves = seamstress::list_saves(context.save_dir)
+                .into_iter()
+                .filter(|name| name.starts_with(word))
+                .collect();
+            (word_start, saves)
+        }
+        "colo" | "colorscheme" => (word_start, colorscheme_names(context.colors_dir, word)),
+        "tr" | "travel" => {
+            let marks = context
+                .marks
+                .iter()
+                .map(|(key, _)| key.to_string())
+                .filter(|key| key.starts_with(word))
+                .collect();
+            (word_start, marks)
+        }
+        // Marks run together: complete to those typed plus one more
+        "delm" | "delmarks" => {
+            let marks = context
+                .marks
+                .iter()
+                .filter(|(key, _)| !word.contains(*key))
+                .map(|(key, _)| format!("{}{}", word, key))
+                .collect();
+            (word_start, marks)
+        }
+        _ => (word_start, Vec::new()),
+    }
+}
+
+/// Colour schemes in `colors_dir` or built in whose names start with
+/// `prefix`
+fn colorscheme_names(colors_dir: &Path, prefix: &str) -> Vec<String> {
+    Theme::available(colors_dir)
+        .into_iter()
+        .filter(|name| name.starts_with(prefix))
+        .collect()
+}
+
+/// Previously executed command lines, oldest first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandHistory {
+    entries: Vec<String>,
+    max: usize,
+}
+
+impl CommandHistory {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            max: DEFAULT_HISTORY_SIZE,
+        }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Change how many entries are remembered, forgetting the oldest
+    pub fn set_max(&mut self, max: usize) {
+        self.max = max;
+        self.truncate();
+    }
+
+    /// Remember an executed line; a repeated line moves to the end
+    pub fn add(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != line);
+        self.entries.push(line.to_string());
+        self.truncate();
+    }
+
+    fn truncate(&mut self) {
+        let excess = self.entries.len().saturating_sub(self.max);
+        self.entries.drain(..excess);
+    }
+
+    /// Load history saved by a previous session, replacing current entries
+    pub fn load(&mut self, path: &Path) -> io::Result<()> {
+        let contents = fs::read_to_string(path)?;
+        self.entries.clear();
+        for line in contents.lines() {
+            self.add(line);
+        }
+        Ok(())
+    }
+
+    /// Save history for the next session
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut contents = self.entries.join("\n");
+        contents.push('\n');
+        fs::write(path, contents)
+    }
+}
+
+impl Default for CommandHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::LazyLock;
+
+    fn typed(text: &str) -> CommandLine {
+        let mut line = CommandLine::new();
+        for c in text.chars() {
+            line.insert(c);
+        }
+        line
+    }
+
+    static NO_MARKS: Marks = Marks::new();
+
+    static HELP: LazyLock<HelpIndex> = LazyLock::new(HelpIndex::new);
+
+    /// Editing with `history`, every help tag, no marks and no files
+    fn context(history: &CommandHistory) -> LineContext<'_> {
+        LineContext {
+            history,
+            help: &HELP,
+            marks: &NO_MARKS,
+            save_dir: Path::new(""),
+            colors_dir: Path::new(""),
+        }
+    }
+
+    fn history(entries: &[&str]) -> CommandHistory {
+        let mut history = CommandHistory::new();
+        for entry in entries {
+            history.add(entry);
+        }
+        history
+    }
+
+    #[test]
+    fn cursor_movement_and_insertion() {
+        let empty = CommandHistory::new();
+        let mut line = typed("st");
+        line.edit(LineEdit::Left, &context(&empty));
+        line.insert('e');
+        assert_eq!(line.text(), "set");
+        line.edit(LineEdit::Home, &context(&empty));
+        line.insert(':');
+        line.edit(LineEdit::Delete, &context(&empty));
+        assert_eq!(line.text(), ":et");
+        line.edit(LineEdit::End, &context(&empty));
+        assert_eq!(line.cursor(), 3);
+    }
+
+    #[test]
+    fn ctrl_w_deletes_word_and_ctrl_u_deletes_to_start() {
+        let empty = CommandHistory::new();
+        let mut line = typed("set cmdheight=2");
+        line.edit(LineEdit::DeleteWord, &context(&empty));
+        assert_eq!(line.text(), "set cmdheight=");
+        line.edit(LineEdit::DeleteWord, &context(&empty));
+        assert_eq!(line.text(), "set cmdheight");
+        line.edit(LineEdit::DeleteWord, &context(&empty));
+        assert_eq!(line.text(), "set ");
+        line.insert('q');
+        line.edit(LineEdit::DeleteToStart, &context(&empty));
+        assert_eq!(line.text(), "");
+    }
+
+    #[test]
+    fn history_recall_matches_typed_prefix() {
+        let history = history(&["set quiet", "map L 5l", "set ch=2"]);
+        let mut line = typed("set");
+        line.edit(LineEdit::HistoryPrev, &context(&history));
+        assert_eq!(line.text(), "set ch=2");
+        line.edit(LineEdit::HistoryPrev, &context(&history));
+        assert_eq!(line.text(), "set quiet");
+        line.edit(LineEdit::HistoryPrev, &context(&history));
+        assert_eq!(line.text(), "set quiet");
+        line.edit(LineEdit::HistoryNext, &context(&history));
+        assert_eq!(line.text(), "set ch=2");
+        line.edit(LineEdit::HistoryNext, &context(&history));
+        assert_eq!(line.text(), "set");
+    }
+
+    #[test]
+    fn history_deduplicates_and_respects_size() {
+        let mut history = history(&["q", "set quiet", "q"]);
+        assert_eq!(history.entries(), ["set quiet", "q"]);
+        history.set_max(1);
+        assert_eq!(history.entries(), ["q"]);
+    }
+
+    #[test]
+    fn tab_completes_command_names_and_cycles() {
+        let empty = CommandHistory::new();
+        let mut line = typed("s");
+        line.edit(LineEdit::Complete, &context(&empty));
+        assert_eq!(line.text(), "save");
+        line.edit(LineEdit::Complete, &context(&empty));
+        assert_eq!(line.text(), "set");
+        line.edit(LineEdit::Complete, &context(&empty));
+        assert_eq!(line.text(), "source");
+        line.edit(LineEdit::Complete, &context(&empty));
+        assert_eq!(line.text(), "story");
+        line.edit(LineEdit::Complete, &context(&empty));
+        assert_eq!(line.text(), "s");
+        line.edit(LineEdit::CompletePrev, &context(&empty));
+        assert_eq!(line.text(), "story");
+    }
+
+    #[test]
+    fn tab_completes_set_options_and_enum_values() {
+        let empty = CommandHistory::new();
+        let context = context(&empty);
+        assert_eq!(
+            completion_candidates("set noq", &context).1,
+            vec!["noquiet".to_string()]
+        );
+        assert_eq!(completion_candidates("set cmdh", &context), (4, vec!["cmdheight".to_string()]));
+        assert_eq!(
+            completion_candidates("set colorscheme=m", &context),
+            (16, vec!["mono".to_string()])
+        );
+        assert_eq!(completion_candidates("h gram", &context), (2, vec!["grammar".to_string()]));
+    }
+
+    #[test]
+    fn completion_splits_on_wide_whitespace() {
+        let empty = CommandHistory::new();
+        for space in ['\u{a0}', '\u{3000}'] {
+            let line = format!("set{}q", space);
+            assert_eq!(completion_candidates(&line, &context(&empty)), (4, vec!["quiet".to_string()]));
+        }
+    }
+
+    #[test]
+    fn travel_and_delmarks_complete_to_the_marks_set() {
+        use super::super::marks::Mark;
+        use crate::foundation::Position;
+
+        let empty = CommandHistory::new();
+        let mut marks = Marks::new();
+        for key in ['-', '\''] {
+            let mark = Mark {
+                position: Position::new(0, 0),
+                area: None,
+            };
+            marks.set(key, mark);
+        }
+        assert!(completion_candidates("travel ", &context(&empty)).1.is_empty());
+        let context = LineContext {
+            marks: &marks,
+            ..context(&empty)
+        };
+        let strings = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+        assert_eq!(completion_candidates("travel ", &context), (7, strings(&["'", "-"])));
+        assert_eq!(completion_candidates("tr -", &context), (3, strings(&["-"])));
+        assert_eq!(completion_candidates("delm -", &context), (5, strings(&["-'"])));
+    }
+
+    #[test]
+    fn history_round_trips_through_a_file() {
+        let path = std::env::temp_dir().join(format!("vitalis-history-{}", std::process::id()));
+        history(&["set quiet", "q"]).save(&path).unwrap();
+        let mut loaded = CommandHistory::new();
+        loaded.load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.entries(), ["set quiet", "q"]);
+    }
+}
@@ -3,15 +3,25 @@
 //! This module handles parsing player input into game actions following
 //! the universal action grammar: [count][which][type][target]
 
+use super::camera::Align;
+use super::cmdline::LineEdit;
+use super::help::HelpAction;
+use super::keys;
+use super::marks::is_mark;
+use super::selection::VisualKind;
 use crate::foundation::Position;
 
-/// Directions for movement and targeting (vi-style hjkl)
+/// Directions for movement and targeting (vi-style hjkl, roguelike yubn)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
-    Left,   // h
-    Down,   // j
-    Up,     // k
-    Right,  // l
+    Left,      // h
+    Down,      // j
+    Up,        // k
+    Right,     // l
+    UpLeft,    // y
+    UpRight,   // u
+    DownLeft,  // b
+    DownRight, // n
 }
 
 impl Direction {
@@ -22,6 +32,10 @@ impl Direction {
             Direction::Down => (0, 1),
             Direction::Up => (0, -1),
             Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (1, -1),
+            Direction::DownLeft => (-1, 1),
+            Direction::DownRight => (1, 1),
         }
     }
 
@@ -37,6 +51,12 @@ impl Direction {
 pub enum Command {
     /// Move in a direction
     Move(Direction),
+    /// Run in a direction until something interesting appears (HJKL)
+    Run(Direction),
+    /// Strike at whoever stands in a direction, friend or foe (Fl)
+    Attack(Direction),
+    /// Move by a larger motion over the map (w, B, 0, $, gg, G)
+    Motion(Motion),
     /// Enter Ex command mode
     EnterExMode,
     /// Execute an Ex command
@@ -45,21 +65,405 @@ pub enum Command {
     CancelEx,
     /// Add character to Ex buffer
     ExInput(char),
-    /// Remove last character from Ex buffer (Backspace)
+    /// Remove character before the cursor from Ex buffer (Backspace)
     ExBackspace,
+    /// Edit the Ex buffer (cursor movement, deletion, history, completion)
+    ExEdit(LineEdit),
+    /// Open the command-line window (q: or Ctrl-f in Ex mode)
+    OpenCmdWindow,
+    /// Act in the command-line window
+    CmdWindow(CmdWindowAction),
+    /// Navigate the help viewer
+    Help(HelpAction),
+    /// Start selecting an area (v, Ctrl-v)
+    EnterVisual(VisualKind),
+    /// Act in Visual mode
+    Visual(VisualAction),
+    /// Select the last visual area again (gv)
+    Reselect,
+    /// Show an older message on the message line (g<)
+    OlderMessage,
+    /// Show a newer message on the message line (g>)
+    NewerMessage,
+    /// Set a mark at the player's position (mx)
+    SetMark(char),
+    /// Go to a mark (gx)
+    GotoMark(char),
+    /// Move the view without moving the player
+    View(ViewAction),
+    /// Act in the atlas (:atlas)
+    Atlas(AtlasAction),
+    /// Start moving the look cursor (x)
+    EnterLook,
+    /// Act in Look mode
+    Look(LookAction),
+    /// Start typing a name or note in Insert mode
+    EnterInsert(InsertPurpose),
+    /// Edit, commit or cancel the text typed in Insert mode
+    Insert(InsertAction),
     /// Add digit to count buffer in Normal mode
     CountInput(char),
     /// Unknown/invalid command
     Unknown,
 }
 
+/// Motions over whole regions and the map, vi word and line motions
+/// carried over to the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    /// To the first tile of the next region to the right (w, W)
+    NextBoundary,
+    /// To the last tile of the previous region to the left (B)
+    PrevBoundary,
+    /// To the left edge of the region underfoot (0)
+    RegionStart,
+    /// To the right edge of the region underfoot ($)
+    RegionEnd,
+    /// To the top row of the known map, or row [count] (gg)
+    MapTop,
+    /// To the bottom row of the known map, or row [count] (G)
+    MapBottom,
+}
+
+/// Actions in the command-line window (q:)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmdWindowAction {
+    /// Select the next (newer) history line (j)
+    Down,
+    /// Select the previous (older) history line (k)
+    Up,
+    /// Select the oldest line (gg)
+    First,
+    /// Select the newest line (G)
+    Last,
+    /// Execute the selected line (Enter)
+    Execute,
+    /// Copy the selected line to the command line for editing (i, a, e)
+    Edit,
+    /// Close the window (Esc, q, Ctrl-c)
+    Close,
+}
+
+/// Actions in Visual mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualAction {
+    /// Extend the selection (hjkl)
+    Move(Direction),
+    /// Move to the other end of the selection (o)
+    SwapEnds,
+    /// Switch to another kind of selection, or leave if already in it (v, Ctrl-v)
+    Switch(VisualKind),
+    /// Leave Visual mode (Esc)
+    Exit,
+    /// Apply an operator to the selection and leave Visual mode
+    Operator(VisualOperator),
+}
+
+/// Actions in Look mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookAction {
+    /// Move the look cursor (hjkl)
+    Move(Direction),
+    /// Leave Look mode (Esc, q, K)
+    Exit,
+}
+
+/// Actions in the atlas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtlasAction {
+    /// Move the atlas cursor a cell (hjkl)
+    Move(Direction),
+    /// Travel to the cell under the cursor (Enter)
+    SetDestination,
+    /// Show more or fewer tiles per cell (zo, zi)
+    Zoom(i32),
+    /// Close the atlas (Esc, q)
+    Exit,
+}
+
+/// Camera commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewAction {
+    /// Put the cursor at the middle, top or bottom of the map (zz, zt, zb)
+    Align(Align),
+    /// Scroll the map by a row or column (CTRL-E, CTRL-Y, zl, zh)
+    Scroll { dx: i32, dy: i32 },
+    /// Fold more tiles into each map cell, or fewer (zo, zi)
+    Zoom(i32),
+}
+
+/// Operators applied to a visual selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualOperator {
+    /// Describe what is in the area (K)
+    Look,
+    /// Remember the area under a mark (mx)
+    Mark(char),
+}
+
+/// What Insert mode is entered for, chosen by the Normal mode key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertPurpose {
+    /// Rename the Thread under the cursor (c)
+    Rename,
+    /// Add a note to the tile under the cursor (i)
+    Annotate,
+    /// Append an entry to the player's journal (A)
+    Journal,
+}
+
+/// Keys typed in Insert mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertAction {
+    /// Insert a character at the cursor
+    Char(char),
+    /// Delete the character before the cursor (Backspace)
+    Backspace,
+    /// Move the cursor or delete (Ctrl-w, Ctrl-u, arrows)
+    Edit(LineEdit),
+    /// Store the text (Esc, Enter)
+    Commit,
+    /// Discard the text (Ctrl-c)
+    Cancel,
+}
+
 /// Ex commands (colon commands)
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExCommand {
     /// Quit the game (:q or :quit)
     Quit,
+    /// Change options (:set args)
+    Set(String),
+    /// Define a key mapping (:map lhs rhs, :noremap lhs rhs)
+    Map {
+        lhs: String,
+        rhs: String,
+        noremap: bool,
+    },
+    /// List all key mappings (:map with no arguments)
+    ListMaps,
+    /// Remove a key mapping (:unmap lhs)
+    Unmap(String),
+    /// Assign a variable (:let name = value)
+    Let { name: String, value: String },
+    /// Execute ex commands from a file (:source file, :so file)
+    Source(String),
+    /// Open help on a topic (:help, :h grammar)
+    Help(String),
+    /// Search all help pages (:helpgrep text)
+    HelpGrep(String),
+    /// Save the game, optionally under a new name (:w, :w name, :save name)
+    Write(Option<String>),
+    /// Load a saved game (:e name)
+    Edit(String),
+    /// List journal entries (:journal)
+    Journal,
+    /// List marks (:marks)
+    Marks,
+    /// Delete marks (:delmarks ;, :delmarks!)
+    DelMarks(String),
+    /// Load a colour scheme, or show the current one (:colorscheme mono)
+    Colorscheme(Option<String>),
+    /// Change how the camera follows, or show it (:camera lock ')
+    Camera(Option<String>),
+    /// Change the zoom level, or show it (:zoom 4)
+    Zoom(Option<String>),
+    /// Open the overview of the discovered world (:atlas)
+    Atlas,
+    /// Show the world date and time (:calendar)
+    Calendar,
+    /// Show every story's progress (:story)
+    Story,
+    /// List the land each story claimed (:claims)
+    Claims,
+    /// Give up on the character, leaving its stories to resolve (:abandon)
+    Abandon,
+    /// Retire the character, handing its stories on (:retire)
+    Retire,
+    /// Show what the world remembers (:chronicle)
+    Chronicle,
+    /// Surrender to the foes beside the player (:yield)
+    Yield,
+    /// Travel to the destination, or to a mark (:travel, :travel x)
+    Travel(Option<String>),
 }
 
+/// Declaration of an ex command, used by completion and help
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExCommandDef {
+    pub name: &'static str,
+    pub abbrev: Option<&'static str>,
+    /// Argument synopsis, e.g. `{lhs} {rhs}`
+    pub args: &'static str,
+    pub description: &'static str,
+}
+
+/// Every ex command understood by [`parse_ex_command`]
+pub const EX_COMMANDS: &[ExCommandDef] = &[
+    ExCommandDef {
+        name: "abandon",
+        abbrev: None,
+        args: "",
+        description: "Give up on your character, leaving its stories; see resolution",
+    },
+    ExCommandDef {
+        name: "atlas",
+        abbrev: Some("atl"),
+        args: "",
+        description: "Open the overview of the discovered world; see atlas",
+    },
+    ExCommandDef {
+        name: "calendar",
+        abbrev: Some("cal"),
+        args: "",
+        description: "Show the date and time of the world; see calendar",
+    },
+    ExCommandDef {
+        name: "camera",
+        abbrev: Some("cam"),
+        args: "[{mode}]",
+        description: "Follow, center on, or lock the map to a mark; see camera",
+    },
+    ExCommandDef {
+        name: "chronicle",
+        abbrev: Some("chr"),
+        args: "",
+        description: "Show what the world remembers; see chronicle",
+    },
+    ExCommandDef {
+        name: "claims",
+        abbrev: Some("cla"),
+        args: "",
+        description: "List the land each story claimed; see weaving",
+    },
+    ExCommandDef {
+        name: "colorscheme",
+        abbrev: Some("colo"),
+        args: "[{name}]",
+        description: "Load a colour scheme; without a name, show the current one",
+    },
+    ExCommandDef {
+        name: "delmarks",
+        abbrev: Some("delm"),
+        args: "{marks}",
+        description: "Delete the given marks; :delmarks! deletes all",
+    },
+    ExCommandDef {
+        name: "edit",
+        abbrev: Some("e"),
+        args: "{name}",
+        description: "Load the saved game called name",
+    },
+    ExCommandDef {
+        name: "help",
+        abbrev: Some("h"),
+        args: "[{topic}]",
+        description: "Open help on a topic, or the help index",
+    },
+    ExCommandDef {
+        name: "helpgrep",
+        abbrev: Some("helpg"),
+        args: "{text}",
+        description: "Search every help page; n and N step through matches",
+    },
+    ExCommandDef {
+        name: "journal",
+        abbrev: None,
+        args: "",
+        description: "List the entries in your journal",
+    },
+    ExCommandDef {
+        name: "let",
+        abbrev: None,
+        args: "{var} = {value}",
+        description: "Assign a variable, such as mapleader",
+    },
+    ExCommandDef {
+        name: "map",
+        abbrev: None,
+        args: "[{lhs} {rhs}]",
+        description: "Map keys in Normal mode; without arguments, list mappings",
+    },
+    ExCommandDef {
+        name: "marks",
+        abbrev: None,
+        args: "",
+        description: "List marks and the areas they hold",
+    },
+    ExCommandDef {
+        name: "noremap",
+        abbrev: Some("no"),
+        args: "[{lhs} {rhs}]",
+        description: "Map keys without remapping the right-hand side",
+    },
+    ExCommandDef {
+        name: "quit",
+        abbrev: Some("q"),
+        args: "",
+        description: "Quit the game",
+    },
+    ExCommandDef {
+        name: "retire",
+        abbrev: Some("ret"),
+        args: "",
+        description: "Retire your character, handing its stories on; see resolution",
+    },
+    ExCommandDef {
+        name: "save",
+        abbrev: Some("sav"),
+        args: "[{name}]",
+        description: "Save the game, like :write",
+    },
+    ExCommandDef {
+        name: "set",
+        abbrev: Some("se"),
+        args: "[{option}...]",
+        description: "Show or change options",
+    },
+    ExCommandDef {
+        name: "source",
+        abbrev: Some("so"),
+        args: "{file}",
+        description: "Execute ex commands from a file",
+    },
+    ExCommandDef {
+        name: "story",
+        abbrev: Some("sto"),
+        args: "",
+        description: "Show how far each story has come; see stories",
+    },
+    ExCommandDef {
+        name: "travel",
+        abbrev: Some("tr"),
+        args: "[{mark}]",
+        description: "Travel to the atlas destination or a mark; see travel",
+    },
+    ExCommandDef {
+        name: "unmap",
+        abbrev: Some("unm"),
+        args: "{lhs}",
+        description: "Remove a key mapping",
+    },
+    ExCommandDef {
+        name: "write",
+        abbrev: Some("w"),
+        args: "[{name}]",
+        description: "Save the game; asks for a name the first time",
+    },
+    ExCommandDef {
+        name: "yield",
+        abbrev: Some("yi"),
+        args: "",
+        description: "Surrender to the foes beside you; see combat",
+    },
+    ExCommandDef {
+        name: "zoom",
+        abbrev: None,
+        args: "[{level}]",
+        description: "Show 1, 2, 4 or 8 tiles per map cell; without a level, show it",
+    },
+];
+
 /// Parse a character into a command in Normal mode
 pub fn parse_normal_command(c: char) -> Command {
     match c {
@@ -67,29 +471,337 @@ pub fn parse_normal_command(c: char) -> Command {
         'j' => Command::Move(Direction::Down),
         'k' => Command::Move(Direction::Up),
         'l' => Command::Move(Direction::Right),
+        'y' => Command::Move(Direction::UpLeft),
+        'u' => Command::Move(Direction::UpRight),
+        'b' => Command::Move(Direction::DownLeft),
+        'n' => Command::Move(Direction::DownRight),
+        'H' => Command::Run(Direction::Left),
+        'J' => Command::Run(Direction::Down),
+        'K' => Command::Run(Direction::Up),
+        'L' => Command::Run(Direction::Right),
+        'w' | 'W' => Command::Motion(Motion::NextBoundary),
+        'B' => Command::Motion(Motion::PrevBoundary),
+        '$' => Command::Motion(Motion::RegionEnd),
+        'G' => Command::Motion(Motion::MapBottom),
         ':' => Command::EnterExMode,
+        'x' => Command::EnterLook,
+        'v' => Command::EnterVisual(VisualKind::Char),
+        '\x16' => Command::EnterVisual(VisualKind::Block), // Ctrl-v
+        '\x05' => Command::View(ViewAction::Scroll { dx: 0, dy: 1 }), // Ctrl-e
+        '\x19' => Command::View(ViewAction::Scroll { dx: 0, dy: -1 }), // Ctrl-y
+        'c' => Command::EnterInsert(InsertPurpose::Rename),
+        'i' => Command::EnterInsert(InsertPurpose::Annotate),
+        'A' => Command::EnterInsert(InsertPurpose::Journal),
         '0'..='9' => Command::CountInput(c),
         _ => Command::Unknown,
     }
 }
 
+/// Parse a sequence of keys typed in Normal mode (after any count)
+///
+/// Returns None while the keys are the start of a longer command.
+pub fn parse_normal_keys(keys: &str) -> Option<Command> {
+    let mut chars = keys.chars();
+    let first = chars.next()?;
+    let rest = chars.as_str();
+
+    let mut rest_chars = rest.chars();
+    let second = rest_chars.next();
+    if second.is_some() && !rest_chars.as_str().is_empty() {
+        return Some(Command::Unknown);
+    }
+
+    match (first, second) {
+        ('q' | 'm' | 'g' | 'z' | 'F', None) => None,
+        ('q', Some(':')) => Some(Command::OpenCmdWindow),
+        ('m', Some(mark)) if is_mark(mark) => Some(Command::SetMark(mark)),
+        ('g', Some(mark)) if is_mark(mark) => Some(Command::GotoMark(mark)),
+        ('g', Some('v')) => Some(Command::Reselect),
+        ('g', Some('<')) => Some(Command::OlderMessage),
+        ('g', Some('>')) => Some(Command::NewerMessage),
+        ('g', Some('g')) => Some(Command::Motion(Motion::MapTop)),
+        ('F', Some(key)) => Some(match parse_normal_command(key) {
+            Command::Move(direction) => Command::Attack(direction),
+            _ => Command::Unknown,
+        }),
+        ('z', Some(key)) => Some(match key {
+            'z' => Command::View(ViewAction::Align(Align::Center)),
+            't' => Command::View(ViewAction::Align(Align::Top)),
+            'b' => Command::View(ViewAction::Align(Align::Bottom)),
+            'h' => Command::View(ViewAction::Scroll { dx: -1, dy: 0 }),
+            'l' => Command::View(ViewAction::Scroll { dx: 1, dy: 0 }),
+            'o' => Command::View(ViewAction::Zoom(1)),
+            'i' => Command::View(ViewAction::Zoom(-1)),
+            _ => Command::Unknown,
+        }),
+        (_, None) => Some(parse_normal_command(first)),
+        _ => Some(Command::Unknown),
+    }
+}
+
+/// Parse a sequence of keys typed in Look mode (after any count)
+///
+/// Keys other than movement and leaving behave as in Normal mode, acting
+/// at the look cursor: marks, Visual mode, naming and notes.
+pub fn parse_look_keys(keys: &str) -> Option<Command> {
+    let mut chars = keys.chars();
+    let single = match (chars.next(), chars.next()) {
+        (Some(key), None) => Some(key),
+        _ => None,
+    };
+    let direction = match single {
+        Some('q' | 'x' | '\x1b' | '\x03') => return Some(Command::Look(LookAction::Exit)),
+        Some('A') => return Some(Command::Unknown), // The journal is the player's, not the tile's
+        Some(keys::LEFT) => Direction::Left,
+        Some(keys::DOWN) => Direction::Down,
+        Some(keys::UP) => Direction::Up,
+        Some(keys::RIGHT) => Direction::Right,
+        _ => {
+            return parse_normal_keys(keys).map(|command| match command {
+                Command::Move(direction) => Command::Look(LookAction::Move(direction)),
+                // Runs, motions and blows are yours, not the look cursor's
+                Command::Run(_) | Command::Motion(_) | Command::Attack(_) => Command::Unknown,
+                command => command,
+            })
+        }
+    };
+    Some(Command::Look(LookAction::Move(direction)))
+}
+
+/// Parse a sequence of keys typed in the atlas (after any count)
+///
+/// Movement, marks and zoom work as on the map; other map commands do not.
+pub fn parse_atlas_keys(keys: &str) -> Option<Command> {
+    let mut chars = keys.chars();
+    let single = match (chars.next(), chars.next()) {
+        (Some(key), None) => Some(key),
+        _ => None,
+    };
+    let action = match single {
+        Some('q' | '\x1b' | '\x03') => AtlasAction::Exit,
+        Some(keys::ENTER) => AtlasAction::SetDestination,
+        Some(keys::LEFT) => AtlasAction::Move(Direction::Left),
+        Some(keys::DOWN) => AtlasAction::Move(Direction::Down),
+        Some(keys::UP) => AtlasAction::Move(Direction::Up),
+        Some(keys::RIGHT) => AtlasAction::Move(Direction::Right),
+        _ => {
+            return parse_normal_keys(keys).map(|command| match command {
+                Command::Move(direction) => Command::Atlas(AtlasAction::Move(direction)),
+                Command::View(ViewAction::Zoom(steps)) => Command::Atlas(AtlasAction::Zoom(steps)),
+                Command::SetMark(_) | Command::GotoMark(_) | Command::CountInput(_) | Command::EnterExMode => {
+                    command
+                }
+                _ => Command::Unknown,
+            })
+        }
+    };
+    Some(Command::Atlas(action))
+}
+
+/// Parse a sequence of keys typed in Visual mode (after any count)
+///
+/// Returns None while the keys are the start of a longer command.
+pub fn parse_visual_keys(keys: &str) -> Option<Command> {
+    let mut chars = keys.chars();
+    let first = chars.next()?;
+    let second = chars.next();
+
+    let action = match (first, second) {
+        ('m', None) => return None,
+        ('m', Some(mark)) if is_mark(mark) && chars.next().is_none() => {
+            VisualAction::Operator(VisualOperator::Mark(mark))
+        }
+        (_, Some(_)) => return Some(Command::Unknown),
+        ('h' | keys::LEFT, None) => VisualAction::Move(Direction::Left),
+        ('j' | keys::DOWN, None) => VisualAction::Move(Direction::Down),
+        ('k' | keys::UP, None) => VisualAction::Move(Direction::Up),
+        ('l' | keys::RIGHT, None) => VisualAction::Move(Direction::Right),
+        ('y', None) => VisualAction::Move(Direction::UpLeft),
+        ('u', None) => VisualAction::Move(Direction::UpRight),
+        ('b', None) => VisualAction::Move(Direction::DownLeft),
+        ('n', None) => VisualAction::Move(Direction::DownRight),
+        ('o', None) => VisualAction::SwapEnds,
+        ('v', None) => VisualAction::Switch(VisualKind::Char),
+        ('\x16', None) => VisualAction::Switch(VisualKind::Block), // Ctrl-v
+        ('\x1b' | '\x03', None) => VisualAction::Exit,
+        ('K', None) => VisualAction::Operator(VisualOperator::Look),
+        ('0'..='9', None) => return Some(Command::CountInput(first)),
+        _ => return Some(Command::Unknown),
+    };
+    Some(Command::Visual(action))
+}
+
 /// Parse a character in Ex mode
 pub fn parse_ex_input(c: char) -> Command {
     match c {
         '\n' | '\r' => Command::Unknown, // Handled specially (execute command)
         '\x1b' => Command::CancelEx, // ESC to cancel
+        '\x7f' | '\x08' => Command::ExBackspace,
+        keys::LEFT => Command::ExEdit(LineEdit::Left),
+        keys::RIGHT => Command::ExEdit(LineEdit::Right),
+        keys::HOME | '\x02' => Command::ExEdit(LineEdit::Home), // Ctrl-b
+        keys::END | '\x05' => Command::ExEdit(LineEdit::End), // Ctrl-e
+        keys::DELETE => Command::ExEdit(LineEdit::Delete),
+        '\x17' => Command::ExEdit(LineEdit::DeleteWord), // Ctrl-w
+        '\x15' => Command::ExEdit(LineEdit::DeleteToStart), // Ctrl-u
+        keys::UP | '\x10' => Command::ExEdit(LineEdit::HistoryPrev), // Ctrl-p
+        keys::DOWN | '\x0e' => Command::ExEdit(LineEdit::HistoryNext), // Ctrl-n
+        keys::TAB => Command::ExEdit(LineEdit::Complete),
+        keys::BACKTAB => Command::ExEdit(LineEdit::CompletePrev),
+        '\x06' => Command::OpenCmdWindow, // Ctrl-f
+        '\x03' => Command::CancelEx, // Ctrl-c
+        c if c.is_control() || keys::is_special(c) => Command::Unknown,
         _ => Command::ExInput(c),
     }
 }
 
+/// Parse a character in Insert mode
+pub fn parse_insert_input(c: char) -> Command {
+    let action = match c {
+        '\x1b' | '\r' | '\n' => InsertAction::Commit,
+        '\x03' => InsertAction::Cancel, // Ctrl-c
+        '\x7f' | '\x08' => InsertAction::Backspace,
+        '\x17' => InsertAction::Edit(LineEdit::DeleteWord), // Ctrl-w
+        '\x15' => InsertAction::Edit(LineEdit::DeleteToStart), // Ctrl-u
+        keys::LEFT => InsertAction::Edit(LineEdit::Left),
+        keys::RIGHT => InsertAction::Edit(LineEdit::Right),
+        keys::HOME => InsertAction::Edit(LineEdit::Home),
+        keys::END => InsertAction::Edit(LineEdit::End),
+        keys::DELETE => InsertAction::Edit(LineEdit::Delete),
+        c if c.is_control() || keys::is_special(c) => return Command::Unknown,
+        _ => InsertAction::Char(c),
+    };
+    Command::Insert(action)
+}
+
+/// Parse a character in the command-line window
+pub fn parse_cmdwin_input(c: char) -> Command {
+    let action = match c {
+        'j' | keys::DOWN => CmdWindowAction::Down,
+        'k' | keys::UP => CmdWindowAction::Up,
+        'g' | keys::HOME => CmdWindowAction::First,
+        'G' | keys::END => CmdWindowAction::Last,
+        '\r' | '\n' => CmdWindowAction::Execute,
+        'i' | 'a' | 'e' => CmdWindowAction::Edit,
+        '\x1b' | 'q' | '\x03' => CmdWindowAction::Close,
+        _ => return Command::Unknown,
+    };
+    Command::CmdWindow(action)
+}
+
+/// Parse a sequence of keys typed in the help viewer (after any count)
+///
+/// Returns None while the keys are the start of a longer command.
+pub fn parse_help_keys(keys: &str) -> Option<Command> {
+    let mut chars = keys.chars();
+    let first = chars.next()?;
+    let rest = chars.as_str();
+
+    let action = match (first, rest) {
+        ('g', "") => return None,
+        ('g', "g") => HelpAction::Top,
+        (_, "") => match first {
+            'j' | keys::DOWN | '\x0e' | '\x05' => HelpAction::Down, // Ctrl-n, Ctrl-e
+            'k' | keys::UP | '\x10' | '\x19' => HelpAction::Up, // Ctrl-p, Ctrl-y
+            keys::PAGE_DOWN | '\x04' => HelpAction::HalfPageDown, // Ctrl-d
+            keys::PAGE_UP | '\x15' => HelpAction::HalfPageUp, // Ctrl-u
+            'G' | keys::END => HelpAction::Bottom,
+            keys::HOME => HelpAction::Top,
+            keys::TAB => HelpAction::NextLink,
+            keys::BACKTAB => HelpAction::PrevLink,
+            '\x1d' | '\r' | '\n' => HelpAction::FollowLink, // Ctrl-]
+            '\x14' | '\x0f' => HelpAction::Back, // Ctrl-t, Ctrl-o
+            'n' => HelpAction::NextMatch,
+            'N' => HelpAction::PrevMatch,
+            'q' | '\x1b' | '\x03' => HelpAction::Close,
+            ':' => return Some(Command::EnterExMode),
+            '0'..='9' => return Some(Command::CountInput(first)),
+            _ => return Some(Command::Unknown),
+        },
+        _ => return Some(Command::Unknown),
+    };
+    Some(Command::Help(action))
+}
+
 /// Parse an Ex command string
 pub fn parse_ex_command(cmd: &str) -> Option<ExCommand> {
-    match cmd.trim() {
-        "q" | "quit" => Some(ExCommand::Quit),
+    let cmd = cmd.trim();
+    let (name, args) = match cmd.find(char::is_whitespace) {
+        Some(idx) => (&cmd[..idx], cmd[idx..].trim()),
+        None => (cmd, ""),
+    };
+
+    match name {
+        "q" | "quit" if args.is_empty() => Some(ExCommand::Quit),
+        "se" | "set" => Some(ExCommand::Set(args.to_string())),
+        "map" | "nmap" | "nm" => parse_map_args(args, false),
+        "noremap" | "no" | "nnoremap" | "nn" => parse_map_args(args, true),
+        "unmap" | "unm" | "nunmap" | "nun" if !args.is_empty() => {
+            Some(ExCommand::Unmap(args.to_string()))
+        }
+        "let" => {
+            let (var, value) = args.split_once('=')?;
+            let var = var.trim();
+            if var.is_empty() {
+                return None;
+            }
+            Some(ExCommand::Let {
+                name: var.to_string(),
+                value: value.trim().to_string(),
+            })
+        }
+        "so" | "source" if !args.is_empty() => Some(ExCommand::Source(args.to_string())),
+        "h" | "he" | "hel" | "help" => Some(ExCommand::Help(args.to_string())),
+        "helpg" | "helpgr" | "helpgre" | "helpgrep" if !args.is_empty() => {
+            Some(ExCommand::HelpGrep(args.to_string()))
+        }
+        "w" | "write" | "sav" | "save" | "saveas" => {
+            Some(ExCommand::Write((!args.is_empty()).then(|| args.to_string())))
+        }
+        "e" | "edit" if !args.is_empty() => Some(ExCommand::Edit(args.to_string())),
+        "colo" | "colorscheme" => {
+            Some(ExCommand::Colorscheme((!args.is_empty()).then(|| args.to_string())))
+        }
+        "cam" | "came" | "camer" | "camera" => {
+            Some(ExCommand::Camera((!args.is_empty()).then(|| args.to_string())))
+        }
+        "zoom" => Some(ExCommand::Zoom((!args.is_empty()).then(|| args.to_string()))),
+        "atl" | "atla" | "atlas" if args.is_empty() => Some(ExCommand::Atlas),
+        "cal" | "cale" | "calen" | "calend" | "calenda" | "calendar" if args.is_empty() => {
+            Some(ExCommand::Calendar)
+        }
+        "sto" | "stor" | "story" if args.is_empty() => Some(ExCommand::Story),
+        "cla" | "clai" | "claim" | "claims" if args.is_empty() => Some(ExCommand::Claims),
+        "abandon" if args.is_empty() => Some(ExCommand::Abandon),
+        "yi" | "yie" | "yiel" | "yield" if args.is_empty() => Some(ExCommand::Yield),
+        "ret" | "reti" | "retir" | "retire" if args.is_empty() => Some(ExCommand::Retire),
+        "chr" | "chro" | "chron" | "chroni" | "chronic" | "chronicl" | "chronicle" if args.is_empty() => {
+            Some(ExCommand::Chronicle)
+        }
+        "tr" | "tra" | "trav" | "trave" | "travel" => {
+            Some(ExCommand::Travel((!args.is_empty()).then(|| args.to_string())))
+        }
+        "journal" => Some(ExCommand::Journal),
+        "marks" => Some(ExCommand::Marks),
+        "delm!" | "delmarks!" => Some(ExCommand::DelMarks("!".to_string())),
+        "delm" | "delma" | "delmar" | "delmark" | "delmarks" if !args.is_empty() => {
+            Some(ExCommand::DelMarks(args.to_string()))
+        }
         _ => None,
     }
 }
 
+fn parse_map_args(args: &str, noremap: bool) -> Option<ExCommand> {
+    match args.split_once(char::is_whitespace) {
+        Some((lhs, rhs)) if !rhs.trim().is_empty() => Some(ExCommand::Map {
+            lhs: lhs.to_string(),
+            rhs: rhs.trim_start().to_string(),
+            noremap,
+        }),
+        _ => Some(ExCommand::ListMaps),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,10 +827,228 @@ mod tests {
         assert_eq!(parse_ex_command("unknown"), None);
     }
 
+    #[test]
+    fn parse_configuration_commands() {
+        assert_eq!(
+            parse_ex_command("set cmdheight=2"),
+            Some(ExCommand::Set("cmdheight=2".to_string()))
+        );
+        assert_eq!(
+            parse_ex_command("map lh 'wh<CR>"),
+            Some(ExCommand::Map {
+                lhs: "lh".to_string(),
+                rhs: "'wh<CR>".to_string(),
+                noremap: false,
+            })
+        );
+        assert_eq!(
+            parse_ex_command("noremap ;; ;miw<CR>"),
+            Some(ExCommand::Map {
+                lhs: ";;".to_string(),
+                rhs: ";miw<CR>".to_string(),
+                noremap: true,
+            })
+        );
+        assert_eq!(parse_ex_command("map"), Some(ExCommand::ListMaps));
+        assert_eq!(
+            parse_ex_command("let mapleader = \"\\\\\""),
+            Some(ExCommand::Let {
+                name: "mapleader".to_string(),
+                value: "\"\\\\\"".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_ex_command("so ~/keys.vim"),
+            Some(ExCommand::Source("~/keys.vim".to_string()))
+        );
+        assert_eq!(parse_ex_command("source"), None);
+    }
+
+    #[test]
+    fn parse_multi_key_normal_commands() {
+        assert_eq!(parse_normal_keys("q"), None);
+        assert_eq!(parse_normal_keys("q:"), Some(Command::OpenCmdWindow));
+        assert_eq!(parse_normal_keys("qx"), Some(Command::Unknown));
+        assert_eq!(parse_normal_keys("l"), Some(Command::Move(Direction::Right)));
+        assert_eq!(parse_normal_keys("F"), None);
+        assert_eq!(parse_normal_keys("Fn"), Some(Command::Attack(Direction::DownRight)));
+        assert_eq!(parse_normal_keys("Fx"), Some(Command::Unknown));
+    }
+
+    #[test]
+    fn parse_atlas_keys_allows_only_map_navigation() {
+        assert_eq!(
+            parse_atlas_keys("l"),
+            Some(Command::Atlas(AtlasAction::Move(Direction::Right)))
+        );
+        assert_eq!(parse_atlas_keys("\r"), Some(Command::Atlas(AtlasAction::SetDestination)));
+        assert_eq!(parse_atlas_keys("zo"), Some(Command::Atlas(AtlasAction::Zoom(1))));
+        assert_eq!(parse_atlas_keys("g"), None);
+        assert_eq!(parse_atlas_keys("g;"), Some(Command::GotoMark(';')));
+        assert_eq!(parse_atlas_keys("v"), Some(Command::Unknown));
+        assert_eq!(parse_ex_command("atlas"), Some(ExCommand::Atlas));
+        assert_eq!(parse_ex_command("cal"), Some(ExCommand::Calendar));
+        assert_eq!(parse_ex_command("sto"), Some(ExCommand::Story));
+        assert_eq!(parse_ex_command("st"), None);
+        assert_eq!(parse_ex_command("cla"), Some(ExCommand::Claims));
+        assert_eq!(parse_ex_command("cl"), None);
+        assert_eq!(parse_ex_command("abandon"), Some(ExCommand::Abandon));
+        assert_eq!(parse_ex_command("aban"), None);
+        assert_eq!(parse_ex_command("ret"), Some(ExCommand::Retire));
+        assert_eq!(parse_ex_command("chr"), Some(ExCommand::Chronicle));
+        assert_eq!(parse_ex_command("yi"), Some(ExCommand::Yield));
+        assert_eq!(parse_ex_command("yield now"), None);
+        assert_eq!(parse_ex_command("calendar 3"), None);
+        assert_eq!(parse_ex_command("tr"), Some(ExCommand::Travel(None)));
+        assert_eq!(parse_ex_command("travel ;"), Some(ExCommand::Travel(Some(";".to_string()))));
+    }
+
+    #[test]
+    fn parse_view_commands() {
+        assert_eq!(parse_normal_keys("z"), None);
+        assert_eq!(
+            parse_normal_keys("zt"),
+            Some(Command::View(ViewAction::Align(Align::Top)))
+        );
+        assert_eq!(
+            parse_normal_keys("\x05"),
+            Some(Command::View(ViewAction::Scroll { dx: 0, dy: 1 }))
+        );
+        assert_eq!(parse_normal_keys("zo"), Some(Command::View(ViewAction::Zoom(1))));
+        assert_eq!(parse_normal_keys("zq"), Some(Command::Unknown));
+        assert_eq!(
+            parse_ex_command("camera lock ;"),
+            Some(ExCommand::Camera(Some("lock ;".to_string())))
+        );
+        assert_eq!(parse_ex_command("zoom"), Some(ExCommand::Zoom(None)));
+    }
+
+    #[test]
+    fn parse_help_viewer_keys() {
+        assert_eq!(parse_help_keys("g"), None);
+        assert_eq!(parse_help_keys("gg"), Some(Command::Help(HelpAction::Top)));
+        assert_eq!(parse_help_keys("\x1d"), Some(Command::Help(HelpAction::FollowLink)));
+        assert_eq!(parse_help_keys("\x14"), Some(Command::Help(HelpAction::Back)));
+        assert_eq!(parse_help_keys("3"), Some(Command::CountInput('3')));
+        assert_eq!(parse_help_keys("gx"), Some(Command::Unknown));
+        assert_eq!(
+            parse_ex_command("h marks"),
+            Some(ExCommand::Help("marks".to_string()))
+        );
+        assert_eq!(parse_ex_command("helpgrep"), None);
+    }
+
+    #[test]
+    fn parse_insert_mode_keys() {
+        assert_eq!(parse_normal_command('c'), Command::EnterInsert(InsertPurpose::Rename));
+        assert_eq!(parse_insert_input('x'), Command::Insert(InsertAction::Char('x')));
+        assert_eq!(parse_insert_input('\x1b'), Command::Insert(InsertAction::Commit));
+        assert_eq!(parse_insert_input('\x03'), Command::Insert(InsertAction::Cancel));
+        assert_eq!(
+            parse_insert_input('\x17'),
+            Command::Insert(InsertAction::Edit(LineEdit::DeleteWord))
+        );
+        assert_eq!(parse_ex_command("w"), Some(ExCommand::Write(None)));
+        assert_eq!(
+            parse_ex_command("w keep"),
+            Some(ExCommand::Write(Some("keep".to_string())))
+        );
+        assert_eq!(parse_ex_command("e"), None);
+    }
+
+    #[test]
+    fn parse_visual_mode_keys() {
+        assert_eq!(parse_normal_keys("v"), Some(Command::EnterVisual(VisualKind::Char)));
+        assert_eq!(parse_normal_keys("g"), None);
+        assert_eq!(parse_normal_keys("gv"), Some(Command::Reselect));
+        assert_eq!(parse_normal_keys("g<"), Some(Command::OlderMessage));
+        assert_eq!(parse_normal_keys("g>"), Some(Command::NewerMessage));
+        assert_eq!(parse_normal_keys("m;"), Some(Command::SetMark(';')));
+        assert_eq!(parse_normal_keys("g;"), Some(Command::GotoMark(';')));
+        assert_eq!(parse_normal_keys("mx"), Some(Command::Unknown));
+        assert_eq!(
+            parse_visual_keys("l"),
+            Some(Command::Visual(VisualAction::Move(Direction::Right)))
+        );
+        assert_eq!(parse_visual_keys("m"), None);
+        assert_eq!(
+            parse_visual_keys("m'"),
+            Some(Command::Visual(VisualAction::Operator(VisualOperator::Mark('\''))))
+        );
+        // Casting and ordering followers are not yet available
+        assert_eq!(parse_visual_keys("c"), Some(Command::Unknown));
+        assert_eq!(parse_visual_keys("F"), Some(Command::Unknown));
+        assert_eq!(parse_ex_command("delm!"), Some(ExCommand::DelMarks("!".to_string())));
+    }
+
+    #[test]
+    fn parse_look_mode_keys() {
+        assert_eq!(parse_normal_keys("x"), Some(Command::EnterLook));
+        assert_eq!(parse_look_keys("x"), Some(Command::Look(LookAction::Exit)));
+        assert_eq!(parse_look_keys("L"), Some(Command::Unknown));
+        assert_eq!(
+            parse_look_keys("h"),
+            Some(Command::Look(LookAction::Move(Direction::Left)))
+        );
+        assert_eq!(parse_look_keys("q"), Some(Command::Look(LookAction::Exit)));
+        assert_eq!(parse_look_keys("g"), None);
+        assert_eq!(parse_look_keys("g;"), Some(Command::GotoMark(';')));
+        assert_eq!(parse_look_keys("5"), Some(Command::CountInput('5')));
+    }
+
+    #[test]
+    fn parse_ex_line_editing_keys() {
+        assert_eq!(parse_ex_input('\x17'), Command::ExEdit(LineEdit::DeleteWord));
+        assert_eq!(parse_ex_input(keys::LEFT), Command::ExEdit(LineEdit::Left));
+        assert_eq!(parse_ex_input('\t'), Command::ExEdit(LineEdit::Complete));
+        assert_eq!(parse_ex_input(keys::function(1).unwrap()), Command::Unknown);
+        assert_eq!(parse_ex_input('x'), Command::ExInput('x'));
+    }
+
+    #[test]
+    fn every_registered_ex_command_parses() {
+        for def in EX_COMMANDS {
+            let args = match def.name {
+                "let" => " mapleader = ','",
+                "unmap" | "source" | "helpgrep" | "edit" | "delmarks" => " x",
+                _ => "",
+            };
+            for name in std::iter::once(def.name).chain(def.abbrev) {
+                assert!(
+                    parse_ex_command(&format!("{}{}", name, args)).is_some(),
+                    ":{} should parse",
+                    name
+                );
+            }
+        }
+    }
+
     #[test]
     fn direction_to_delta() {
         assert_eq!(Direction::Left.to_delta(), (-1, 0));
         assert_eq!(Direction::Right.to_delta(), (1, 0));
+        assert_eq!(Direction::DownLeft.to_delta(), (-1, 1));
+    }
+
+    #[test]
+    fn parse_diagonals_runs_and_motions() {
+        assert_eq!(parse_normal_command('y'), Command::Move(Direction::UpLeft));
+        assert_eq!(parse_normal_command('n'), Command::Move(Direction::DownRight));
+        assert_eq!(parse_normal_command('K'), Command::Run(Direction::Up));
+        assert_eq!(parse_normal_command('w'), Command::Motion(Motion::NextBoundary));
+        assert_eq!(parse_normal_command('$'), Command::Motion(Motion::RegionEnd));
+        assert_eq!(parse_normal_keys("g"), None);
+        assert_eq!(parse_normal_keys("gg"), Some(Command::Motion(Motion::MapTop)));
+        assert_eq!(parse_normal_keys("G"), Some(Command::Motion(Motion::MapBottom)));
+        assert_eq!(
+            parse_visual_keys("u"),
+            Some(Command::Visual(VisualAction::Move(Direction::UpRight)))
+        );
+        assert_eq!(
+            parse_atlas_keys("b"),
+            Some(Command::Atlas(AtlasAction::Move(Direction::DownLeft)))
+        );
+        assert_eq!(parse_atlas_keys("w"), Some(Command::Unknown));
     }
 
     #[test]
@@ -0,0 +1,226 @@
+//! Startup configuration - the vitalisrc file
+//!
+//! Like `.vimrc`, the vitalisrc is a list of ex commands (`:set`, `:map`,
+//! `:let`) executed at startup. Lines starting with `"` are comments.
+//! Command-line history is kept separately, like vim's viminfo.
+
+use std::env;
+use std::path::PathBuf;
+
+/// Environment variable that overrides the vitalisrc location
+pub const RC_ENV_VAR: &str = "VITALIS_RC";
+
+/// Which startup configuration to load
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RcSource {
+    /// `$VITALIS_RC` or `~/.config/vitalis/vitalisrc`
+    Default,
+    /// `-u file`
+    File(PathBuf),
+    /// `-u NONE` skips configuration entirely
+    None,
+}
+
+/// Where command-line history is kept between sessions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistorySource {
+    /// `$XDG_STATE_HOME/vitalis/history` or `~/.local/state/vitalis/history`
+    Default,
+    /// `-i file`
+    File(PathBuf),
+    /// `-i NONE` neither reads nor writes history
+    None,
+}
+
+/// Options given on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartupOptions {
+    pub rc: RcSource,
+    pub history: HistorySource,
+    /// `-s file`: type the keys in a script before reading the keyboard
+    pub script_in: Option<PathBuf>,
+    /// `-w file`: append every typed key to a script
+    pub script_out: Option<PathBuf>,
+}
+
+impl StartupOptions {
+    /// Parse command line arguments (without the program name)
+    pub fn from_args<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut rc = RcSource::Default;
+        let mut history = HistorySource::Default;
+        let mut script_in = None;
+        let mut script_out = None;
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-u" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "Argument missing after: \"-u\"".to_string())?;
+                    rc = if value == "NONE" {
+                        RcSource::None
+                    } else {
+                        RcSource::File(expand_home(&value))
+                    };
+                }
+                "-i" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "Argument missing after: \"-i\"".to_string())?;
+                    history = if value == "NONE" {
+                        HistorySource::None
+                    } else {
+                        HistorySource::File(expand_home(&value))
+                    };
+                }
+                "-s" | "-w" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("Argument missing after: \"{}\"", arg))?;
+                    let path = Some(expand_home(&value));
+                    if arg == "-s" {
+                        script_in = path;
+                    } else {
+                        script_out = path;
+                    }
+                }
+                _ => return Err(format!("Unknown option argument: \"{}\"", arg)),
+            }
+        }
+
+        Ok(Self {
+            rc,
+            history,
+            script_in,
+            script_out,
+        })
+    }
+
+    /// The vitalisrc file to execute at startup, if any
+    pub fn rc_path(&self) -> Option<PathBuf> {
+        match &self.rc {
+            RcSource::Default => default_rc_path(),
+            RcSource::File(path) => Some(path.clone()),
+            RcSource::None => None,
+        }
+    }
+
+    /// The command-line history file to read at startup and write on exit
+    pub fn history_path(&self) -> Option<PathBuf> {
+        match &self.history {
+            HistorySource::Default => default_state_dir().map(|dir| dir.join("history")),
+            HistorySource::File(path) => Some(path.clone()),
+            HistorySource::None => None,
+        }
+    }
+}
+
+/// `$XDG_STATE_HOME/vitalis`, else `~/.local/state/vitalis`
+pub fn default_state_dir() -> Option<PathBuf> {
+    let state_dir = env::var_os("XDG_STATE_HOME")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(state_dir.join("vitalis"))
+}
+
+/// `$XDG_DATA_HOME/vitalis/saves`, else `~/.local/share/vitalis/saves`
+pub fn default_save_dir() -> Option<PathBuf> {
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })?;
+    Some(data_dir.join("vitalis").join("saves"))
+}
+
+/// `$VITALIS_RC`, else `$XDG_CONFIG_HOME/vitalis/vitalisrc`, else
+/// `~/.config/vitalis/vitalisrc`
+pub fn default_rc_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(RC_ENV_VAR).filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("vitalis").join("vitalisrc"))
+}
+
+/// `$XDG_CONFIG_HOME/vitalis/colors`, else `~/.config/vitalis/colors`
+pub fn default_colors_dir() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("vitalis").join("colors"))
+}
+
+/// Expand a leading `~` to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix('~') {
+        if rest.is_empty() || rest.starts_with('/') {
+            if let Some(home) = env::var_os("HOME") {
+                return PathBuf::from(home).join(rest.trim_start_matches('/'));
+            }
+        }
+    }
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn no_arguments_uses_default_rc() {
+        let options = StartupOptions::from_args(args(&[])).unwrap();
+        assert_eq!(options.rc, RcSource::Default);
+    }
+
+    #[test]
+    fn u_none_skips_configuration() {
+        let options = StartupOptions::from_args(args(&["-u", "NONE"])).unwrap();
+        assert_eq!(options.rc, RcSource::None);
+        assert_eq!(options.rc_path(), None);
+    }
+
+    #[test]
+    fn u_file_selects_configuration() {
+        let options = StartupOptions::from_args(args(&["-u", "team.vitalisrc"])).unwrap();
+        assert_eq!(options.rc_path(), Some(PathBuf::from("team.vitalisrc")));
+    }
+
+    #[test]
+    fn i_none_skips_history() {
+        let options = StartupOptions::from_args(args(&["-u", "NONE", "-i", "NONE"])).unwrap();
+        assert_eq!(options.history, HistorySource::None);
+        assert_eq!(options.history_path(), None);
+    }
+
+    #[test]
+    fn s_and_w_name_scripts() {
+        let options = StartupOptions::from_args(args(&["-s", "in.keys", "-w", "out.keys"])).unwrap();
+        assert_eq!(options.script_in, Some(PathBuf::from("in.keys")));
+        assert_eq!(options.script_out, Some(PathBuf::from("out.keys")));
+    }
+
+    #[test]
+    fn u_without_value_is_an_error() {
+        assert!(StartupOptions::from_args(args(&["-u"])).is_err());
+        assert!(StartupOptions::from_args(args(&["-i"])).is_err());
+        assert!(StartupOptions::from_args(args(&["-s"])).is_err());
+        assert!(StartupOptions::from_args(args(&["--bogus"])).is_err());
+    }
+}
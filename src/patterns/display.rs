@@ -3,26 +3,35 @@
 //! This module isolates all external UI dependencies (ratatui, crossterm)
 //! keeping them out of the domain core.
 
-use crate::foundation::Position;
+use crate::loom::Loom;
 use crate::patterns::modes::Mode;
-use crate::tapestry::Tapestry;
-use crate::threads::ThreadKind;
+use crate::patterns::theme::Palette;
+use crate::patterns::widgets;
 use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
-    backend::CrosstermBackend,
+    backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
-    Terminal,
+    widgets::{Block, Borders, Clear},
+    Frame, Terminal,
 };
 use std::io;
 
 pub type TerminalType = Terminal<CrosstermBackend<io::Stdout>>;
 
+/// Rows the frame needs: borders, status, message, header, one map row,
+/// separators and mode line
+const FRAME_ROWS: u16 = 9;
+
+/// Width of the Look mode side panel, borders included
+const LOOK_PANEL_WIDTH: u16 = 36;
+
+/// Size of the minimap, borders included
+const MINIMAP_WIDTH: u16 = 22;
+const MINIMAP_HEIGHT: u16 = 8;
+
 /// Initialize terminal for rendering
 pub fn init_terminal() -> io::Result<TerminalType> {
     enable_raw_mode()?;
@@ -41,119 +50,118 @@ pub fn restore_terminal(terminal: &mut TerminalType) -> io::Result<()> {
 }
 
 /// Render the current game state
-pub fn render(
-    terminal: &mut TerminalType,
-    tapestry: &Tapestry,
-    player_id: crate::threads::ThreadId,
-    mode: &Mode,
-) -> io::Result<()> {
-    terminal.draw(|f| {
-        // Determine if we need a command line row (Ex mode only)
-        let has_command_line = mode.is_ex();
-
-        let mut constraints = vec![
-            Constraint::Length(3), // Status bar
-            Constraint::Min(1),    // Game view
-            Constraint::Length(3), // Mode indicator bar
-        ];
-
-        if has_command_line {
-            constraints.push(Constraint::Length(1)); // Command line (no border)
-        }
-
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(constraints)
-            .split(f.area());
-
-        // Status bar
-        let player = tapestry.get_thread(player_id);
-        let status_text = if let Some(thread) = player {
-            if let ThreadKind::Player { name } = &thread.kind {
-                format!(" {} | Pos: {:?}", name, thread.position)
-            } else {
-                " Unknown".to_string()
-            }
-        } else {
-            " No player".to_string()
-        };
-
-        let status = Paragraph::new(status_text)
-            .block(Block::default().borders(Borders::ALL).title("Vitalis"));
-        f.render_widget(status, chunks[0]);
-
-        // Game view
-        let game_view = render_game_view(tapestry, player_id, chunks[1]);
-        f.render_widget(game_view, chunks[1]);
-
-        // Mode indicator bar (mode name on left, pending keys on right)
-        let mode_name = mode.mode_name();
-        let pending_keys = mode.pending_keys();
-        let mode_line = if !pending_keys.is_empty() {
-            // Calculate spacing to right-align pending keys
-            let bar_width = chunks[2].width.saturating_sub(4) as usize; // Account for borders
-            let mode_len = mode_name.len();
-            let keys_len = pending_keys.len();
-            let spacing = bar_width.saturating_sub(mode_len + keys_len);
-            format!("{}{:width$}{}", mode_name, "", pending_keys, width = spacing)
-        } else {
-            mode_name.to_string()
-        };
-
-        let mode_bar = Paragraph::new(mode_line)
-            .block(Block::default().borders(Borders::ALL));
-        f.render_widget(mode_bar, chunks[2]);
-
-        // Command line (Ex mode only, no border)
-        if let Some(cmd_line) = mode.command_line() {
-            let command_paragraph = Paragraph::new(cmd_line);
-            f.render_widget(command_paragraph, chunks[3]);
-        }
-    })?;
+///
+/// The screen follows the design document: inside one frame sit the status
+/// bar, the message line, the map under a header naming it, and the mode
+/// line. Below the frame, 'cmdheight' rows hold earlier messages and the
+/// command line.
+///
+/// Any ratatui backend will do: the game draws to the terminal through
+/// crossterm, and tests draw to a `TestBackend` (see `headless`).
+pub fn render<B: Backend>(terminal: &mut Terminal<B>, loom: &Loom) -> io::Result<()> {
+    terminal.draw(|f| draw(f, loom))?;
     Ok(())
 }
 
-/// Render the game world view
-fn render_game_view(
-    tapestry: &Tapestry,
-    player_id: crate::threads::ThreadId,
-    area: Rect,
-) -> Paragraph<'static> {
-    let player = tapestry.get_thread(player_id);
-    let player_pos = player.and_then(|t| t.position).unwrap_or(Position::new(0, 0));
-
-    // Create a simple view centered on player
-    let view_width = (area.width - 2) as i32; // Account for borders
-    let view_height = (area.height - 2) as i32;
-
-    let start_x = player_pos.x - view_width / 2;
-    let start_y = player_pos.y - view_height / 2;
-
-    let mut lines = Vec::new();
-    for y in 0..view_height {
-        let mut line_spans = Vec::new();
-        for x in 0..view_width {
-            let world_pos = Position::new(start_x + x, start_y + y);
-
-            let symbol = if world_pos == player_pos {
-                '@'
-            } else if let Some(thread) = tapestry.get_thread_at(world_pos) {
-                match thread.kind {
-                    ThreadKind::Region { .. } => '·',
-                    ThreadKind::Npc { .. } => 'N',
-                    ThreadKind::Player { .. } => '@',
-                }
-            } else {
-                ' '
-            };
-
-            line_spans.push(Span::styled(
-                symbol.to_string(),
-                Style::default().fg(Color::White),
-            ));
-        }
-        lines.push(Line::from(line_spans));
+fn draw(f: &mut Frame, loom: &Loom) {
+    let palette = Palette {
+        theme: &loom.theme,
+        truecolor: loom.options.get_bool("termguicolors"),
+    };
+    let cmdheight = loom.options.get_number("cmdheight").max(1) as u16;
+
+    let outer = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(FRAME_ROWS), Constraint::Length(cmdheight)])
+        .split(f.area());
+    let (screen, command_area) = (outer[0], outer[1]);
+
+    let frame = Block::default().borders(Borders::ALL);
+    let inner = frame.inner(screen);
+    f.render_widget(frame, screen);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Status bar
+            Constraint::Length(1), // Separator
+            Constraint::Length(1), // Message line
+            Constraint::Length(1), // Map-name header
+            Constraint::Min(1),    // Map
+            Constraint::Length(1), // Separator
+            Constraint::Length(1), // Mode line
+        ])
+        .split(inner);
+    // Rules span the whole frame so they join its borders
+    let rule = |row: Rect| Rect::new(screen.x, row.y, screen.width, 1);
+
+    f.render_widget(widgets::status_bar(&loom.status_view(), inner.width, &palette), rows[0]);
+    f.render_widget(widgets::separator(None, screen.width), rule(rows[1]));
+    f.render_widget(widgets::message_line(&loom.message_line_view(), inner.width, &palette), rows[2]);
+    f.render_widget(widgets::map_header(&loom.map_header_view(), screen.width), rule(rows[3]));
+    draw_game_area(f, loom, rows[4], &palette);
+    f.render_widget(widgets::separator(None, screen.width), rule(rows[5]));
+    f.render_widget(widgets::mode_line(&loom.mode_line_view(), inner.width), rows[6]);
+
+    let command = loom.command_area_view(usize::from(command_area.height));
+    f.render_widget(widgets::command_area(&command, &palette), command_area);
+
+    // Show the terminal cursor where the next typed character will go
+    if let Some(column) = command.cursor {
+        f.set_cursor_position((
+            command_area.x + column as u16,
+            command_area.y + command_area.height.saturating_sub(1),
+        ));
     }
+}
 
-    Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("The Tapestry"))
+/// The map, sharing its space with help, the command-line window (q:) and
+/// the look panel
+fn draw_game_area(f: &mut Frame, loom: &Loom, area: Rect, palette: &Palette) {
+    let area = if let Some(view) = &loom.help_view {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(70), Constraint::Min(3)])
+            .split(area);
+        f.render_widget(widgets::help(&loom.help, view, split[0], palette), split[0]);
+        split[1]
+    } else {
+        area
+    };
+    let area = if let Mode::CmdWindow { selected } = loom.mode {
+        // Three rows if it can have them, but never more than half the area
+        let window_height = (loom.history.len() as u16 + 2).max(3).min(area.height / 2 + 1);
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(window_height)])
+            .split(area);
+        f.render_widget(widgets::cmd_window(&loom.history, selected, split[1]), split[1]);
+        split[0]
+    } else {
+        area
+    };
+    // Look mode: a side panel describes the tile under the look cursor
+    let area = if let Some(cursor) = loom.look_cursor {
+        let split = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(1), Constraint::Length(LOOK_PANEL_WIDTH)])
+            .split(area);
+        f.render_widget(widgets::look_panel(&loom.inspect(cursor), cursor), split[1]);
+        split[0]
+    } else {
+        area
+    };
+    if let Some(atlas) = loom.atlas_view(area.width, area.height) {
+        f.render_widget(widgets::atlas(&atlas, palette), area);
+        return;
+    }
+    f.render_widget(widgets::map(&loom.map_view(area.width, area.height), palette), area);
+
+    // The minimap sits in the top-right corner when there is room for both
+    if loom.options.get_bool("minimap") && area.width >= MINIMAP_WIDTH * 2 && area.height >= MINIMAP_HEIGHT + 2 {
+        let corner = Rect::new(area.right() - MINIMAP_WIDTH, area.y, MINIMAP_WIDTH, MINIMAP_HEIGHT);
+        let view = loom.minimap_view(MINIMAP_WIDTH - 2, MINIMAP_HEIGHT - 2);
+        f.render_widget(Clear, corner);
+        f.render_widget(widgets::minimap(&view, palette), corner);
+    }
 }
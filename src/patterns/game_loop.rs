@@ -0,0 +1,37 @@
+//! The game loop - draw, wait for input, dispatch, repeat
+//!
+//! Frontends pick a backend to draw to and a source of input; everything
+//! else is the same whether the game runs in a terminal, in a test or from
+//! a replay.
+
+use super::display::render;
+use super::input::InputSource;
+use crate::loom::Loom;
+use ratatui::{backend::Backend, Terminal};
+use std::io;
+use std::time::Duration;
+
+/// How long to wait for input before drawing again
+pub const TICK: Duration = Duration::from_millis(100);
+
+/// Run until the player quits or the input runs out
+pub fn run<B: Backend>(
+    terminal: &mut Terminal<B>,
+    loom: &mut Loom,
+    input: &mut dyn InputSource,
+) -> io::Result<()> {
+    while loom.is_running() {
+        render(terminal, loom)?;
+        if input.is_exhausted() {
+            break;
+        }
+        if let Some(event) = input.poll(TICK)? {
+            loom.handle_event(event);
+        }
+    }
+    // Show the final state, e.g. what a script left on screen
+    if loom.is_running() {
+        render(terminal, loom)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! Headless rendering - play the game without a terminal
+//!
+//! [`Headless`] drives a Loom with keys and draws it to ratatui's
+//! `TestBackend`, so screen tests run in-process with no PTY and no sleeps.
+//! [`assert_snapshot`] compares a rendered screen with a file under
+//! `tests/snapshots`, failing if it differs or is missing; run with
+//! `VITALIS_UPDATE_SNAPSHOTS=1` to record new screens and accept changes.
+
+use super::display::render;
+use super::game_loop;
+use super::input::InputSource;
+use super::keymap::parse_keys;
+use crate::loom::Loom;
+use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Screen size used unless another is asked for
+pub const DEFAULT_WIDTH: u16 = 80;
+pub const DEFAULT_HEIGHT: u16 = 24;
+
+/// Set to a non-empty value to write snapshots instead of comparing them
+pub const UPDATE_SNAPSHOTS_VAR: &str = "VITALIS_UPDATE_SNAPSHOTS";
+
+/// Extension of snapshot files
+const SNAPSHOT_EXTENSION: &str = "snap";
+
+/// A game session drawn to an in-memory screen
+pub struct Headless {
+    pub loom: Loom,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Headless {
+    /// Drive `loom` on an 80x24 screen
+    pub fn new(loom: Loom) -> Self {
+        Self::with_size(loom, DEFAULT_WIDTH, DEFAULT_HEIGHT)
+    }
+
+    pub fn with_size(loom: Loom, width: u16, height: u16) -> Self {
+        let terminal = Terminal::new(TestBackend::new(width, height))
+            .expect("the test backend cannot fail to start");
+        Self { loom, terminal }
+    }
+
+    /// Change the screen size, as when the terminal window is resized
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.terminal.backend_mut().resize(width, height);
+    }
+
+    /// Type keys written in vim notation: `3l`, `:q<CR>`, `<C-v>jl<Esc>`
+    pub fn keys(&mut self, notation: &str) -> &mut Self {
+        let keys = parse_keys(notation, self.loom.leader());
+        for key in keys {
+            self.loom.press(key);
+        }
+        self
+    }
+
+    /// Run the game loop on this screen until the input runs out or the
+    /// player quits
+    pub fn play(&mut self, input: &mut dyn InputSource) -> io::Result<()> {
+        game_loop::run(&mut self.terminal, &mut self.loom, input)
+    }
+
+    /// Draw the current state and return the screen
+    pub fn render(&mut self) -> &Buffer {
+        render(&mut self.terminal, &self.loom).expect("the test backend cannot fail to draw");
+        self.terminal.backend().buffer()
+    }
+
+    /// The screen as text, one line per row, trailing blanks trimmed
+    pub fn screen(&mut self) -> String {
+        screen_text(self.render())
+    }
+
+    /// The text of one screen row
+    pub fn row(&mut self, y: u16) -> String {
+        self.screen().lines().nth(usize::from(y)).unwrap_or_default().to_string()
+    }
+
+    /// Column and row where `text` first appears on screen
+    pub fn find(&mut self, text: &str) -> Option<(u16, u16)> {
+        self.screen().lines().enumerate().find_map(|(y, line)| {
+            let byte = line.find(text)?;
+            Some((line[..byte].chars().count() as u16, y as u16))
+        })
+    }
+
+    /// Compare the screen with the snapshot called `name`
+    pub fn assert_snapshot(&mut self, name: &str) {
+        let screen = self.screen();
+        assert_snapshot(name, &screen);
+    }
+}
+
+/// A buffer as text, one line per row, trailing blanks trimmed
+pub fn screen_text(buffer: &Buffer) -> String {
+    let width = usize::from(buffer.area.width);
+    let symbols: Vec<&str> = buffer.content().iter().map(|cell| cell.symbol()).collect();
+    let mut text: String = symbols
+        .chunks(width.max(1))
+        .map(|row| row.concat().trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    text.push('\n');
+    text
+}
+
+/// Directory holding snapshot files
+pub fn snapshot_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("snapshots")
+}
+
+/// Compare `actual` with the snapshot called `name`, failing with a diff
+///
+/// A missing snapshot fails too. With `VITALIS_UPDATE_SNAPSHOTS` set the
+/// snapshot is written instead, so a new test records its first run for
+/// review.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = snapshot_dir().join(format!("{}.{}", name, SNAPSHOT_EXTENSION));
+    if env::var_os(UPDATE_SNAPSHOTS_VAR).is_some_and(|value| !value.is_empty()) {
+        fs::create_dir_all(snapshot_dir()).expect("cannot create the snapshot directory");
+        fs::write(&path, actual).expect("cannot write the snapshot");
+        return;
+    }
+    let Ok(expected) = fs::read_to_string(&path) else {
+        panic!(
+            "snapshot '{}' is missing ({}).\nRun with {}=1 to record it.",
+            name,
+            path.display(),
+            UPDATE_SNAPSHOTS_VAR
+        );
+    };
+    if expected == actual {
+        return;
+    }
+
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let mut diff = String::new();
+    for row in 0..expected_lines.len().max(actual_lines.len()) {
+        let (old, new) = (expected_lines.get(row), actual_lines.get(row));
+        if old != new {
+            diff.push_str(&format!("{:>3} - {}\n", row, old.unwrap_or(&"")));
+            diff.push_str(&format!("{:>3} + {}\n", row, new.unwrap_or(&"")));
+        }
+    }
+    panic!(
+        "snapshot '{}' does not match ({}):\n{}\nRun with {}=1 to accept the new screen.",
+        name,
+        path.display(),
+        diff,
+        UPDATE_SNAPSHOTS_VAR
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every test's world is woven from the same seed
+    const SEED: u64 = 1;
+
+    #[test]
+    fn keys_drive_the_rendered_screen() {
+        let mut game = Headless::with_size(Loom::new_game_with_seed(SEED), 40, 12);
+        assert!(game.find("@").is_some());
+        assert!(game.screen().contains("[0,0]"));
+
+        game.keys("3lj");
+        assert!(game.screen().contains("[3,1]"));
+
+        // The player stays in the middle of the map as it scrolls
+        let centre = game.find("@").unwrap();
+        game.keys("l");
+        assert_eq!(game.find("@"), Some(centre));
+    }
+
+    #[test]
+    fn key_notation_reaches_the_command_line() {
+        let mut game = Headless::new(Loom::new_game_with_seed(SEED));
+        game.keys(":frobnicate<CR>");
+        assert!(game.screen().contains("E492: Not an editor command: frobnicate"));
+    }
+
+    #[test]
+    fn game_loop_plays_a_script() {
+        use crate::patterns::input::ScriptedInput;
+
+        let mut game = Headless::new(Loom::new_game_with_seed(SEED));
+        game.play(&mut ScriptedInput::from_keys("2lk")).unwrap();
+        assert!(game.loom.is_running());
+        assert!(game.screen().contains("[2,-1]"));
+
+        game.play(&mut ScriptedInput::from_keys(":q<CR>")).unwrap();
+        assert!(!game.loom.is_running());
+    }
+
+    #[test]
+    fn tiny_screens_do_not_panic() {
+        let mut game = Headless::with_size(Loom::new_game_with_seed(SEED), 3, 2);
+        game.screen();
+        game.resize(0, 0);
+        game.screen();
+    }
+
+    #[test]
+    fn command_window_fits_short_screens() {
+        let mut game = Headless::with_size(Loom::new_game_with_seed(SEED), 80, 10);
+        game.keys(":calendar<CR>q:");
+        for height in [10, 11, 12, 4] {
+            game.resize(80, height);
+            game.screen();
+        }
+    }
+}
@@ -0,0 +1,1091 @@
+//! In-game help - topic-addressable pages with vim-style tags
+//!
+//! Pages use vim's help markup: `*tag*` defines a jump target and `|tag|`
+//! links to one. The command and option pages are generated from
+//! [`EX_COMMANDS`] and [`OPTIONS`] so they cannot drift from the code.
+
+use super::commands::EX_COMMANDS;
+use super::options::{OptionKind, OPTIONS};
+use std::collections::BTreeMap;
+
+/// Lines moved by Ctrl-d and Ctrl-u
+pub const HALF_PAGE: usize = 10;
+
+const INDEX: &str = "\
+*help* *vitalis*              Vitalis in-game help
+
+Move with j and k, follow a link with CTRL-] or <Enter>, and jump back
+with CTRL-T. <Tab> moves to the next link. Close help with q or :q.
+
+Search every page with :helpgrep {text}; n and N step through matches.
+
+Topics
+  |grammar|       The universal action grammar
+  |modes|         Normal, Insert, Visual, Ex and command-line window
+  |marks|         The universal mark system
+  |camera|        Scrolling, zooming and locking the map view
+  |atlas|         The overview of the discovered world and the minimap
+  |contests|      How Threads contest through The Weaver
+  |stories|       Stories, their stages and goals
+  |properties|    The ten Thread properties
+  |states|        The ten Thread states
+  |cmdline|       Editing the command line, history and completion
+  |vitalisrc|     Startup configuration and key mappings
+  |colors|        Colour schemes and map textures
+  |ex-commands|   Every ex command
+  |options|       Every option
+";
+
+const GRAMMAR: &str = "\
+*grammar*                     The universal action grammar
+
+Every action follows the same pattern:
+
+    [count][which][type][target]
+
+*count*
+A number typed before a command repeats it: 5l moves five tiles right.
+Counts are capped by the |'maxcount'| option.
+
+*movement* *hjkl* *yubn*
+    h    move left          j    move down
+    k    move up            l    move right
+    y    move up-left       u    move up-right
+    b    move down-left     n    move down-right
+Stepping into an enemy strikes at it; stepping into anyone else trades
+places with them. See |combat|.
+
+*run* *HJKL*
+H, J, K and L run left, down, up and right until something interesting
+appears: the land changes, someone comes into sight, the way is blocked
+or a warning is given. A count runs that many times.
+
+*motions* *w* *B* *0* *$* *gg* *G*
+Larger motions travel over ground you remember, see |travel|:
+    w, W  to the first tile of the next region to the right
+    B     to the last tile of the region to the left
+    0     to the left edge of the region you stand in
+    $     to its right edge; 3$ to the right edge of the third region
+    gg    to the top row of the explored map, 5gg to its fifth row
+    G     to the bottom row, or like gg with a count
+Counts repeat w and B. b is taken by the down-left diagonal, as in other
+roguelikes, so B moves back by regions.
+
+*which*
+A punctuation mark naming a specific item: '  \"  ;  ,  /  \\  [  ]  -  =
+
+*type*
+    w    weapons            s    scrolls
+    k    skills             m    memorized spells
+    c    consumables        e    equipment
+
+*target*
+    hjkl  a direction
+    iw    self (inner word)
+    ip    self and the 8 adjacent tiles (inner paragraph)
+    ap    the 8 adjacent tiles only (a paragraph)
+
+See also |marks| and |modes|.
+";
+
+const MODES: &str = "\
+*modes*                       Modal interface
+
+*normal-mode*
+The default mode. Movement and actions follow the |grammar|.
+
+*g<* *g>* *message-line*
+The message line shows the newest message and its place in the log,
+[n/N]. g< steps back to an older message and g> on to a newer one, with
+counts; any other key returns to the newest.
+
+*ex-mode* *:*
+Typing : opens the command line. <Enter> executes the command, <Esc>
+cancels. See |cmdline| and |ex-commands|.
+
+*q:* *cmdwin*
+q: opens the command-line window listing past commands. Move with j and
+k, execute with <Enter>, edit a line with i, close with q or <Esc>.
+CTRL-F on the command line opens the same window.
+
+*visual-mode* *v* *CTRL-V*
+v selects an area of the map starting where you stand; CTRL-V selects a
+rectangular block. hjkl and yubn with counts extend the selection and o
+moves to its other end. Operators act on the area and return to Normal
+mode:
+    K       look: list what is in the area
+    mx      set mark x to the area, see |marks|
+Casting spells and ordering followers over an area are part of the
+design and not yet available.
+*gv*
+gv selects the last area again. <Esc> leaves Visual mode.
+
+*look-mode* *x*
+x moves a look cursor over the map without moving you. hjkl with counts
+move it and gx jumps it to mark x. A panel lists every Thread on the
+tile under the cursor. Its kind, name and obvious states are always
+seen; its stats and subtler states only if your Wisdom beats its
+Dexterity in a |contests| roll, made afresh each time you start looking.
+v, c and i act at the look cursor. <Esc>, q or x leave Look mode.
+
+*insert-mode*
+Insert mode names things and takes notes. The text is typed on the
+command line; <Esc> or <Enter> stores it and CTRL-C discards it.
+    c       rename the Thread under the cursor
+    i       add a note to the tile under the cursor
+    A       add an entry to your journal, see |:journal|
+CTRL-W deletes the word before the cursor and CTRL-U the whole line.
+Names and notes are kept in saved games, see |:write|.
+
+*help-mode*
+This viewer. See |help|.
+";
+
+const MARKS: &str = "\
+*marks*                       The universal mark system
+
+Marks give instant access to locations and areas.
+
+*m* *g'*
+    mx    set mark x at your position
+    gx    travel to mark x, see |travel|
+In Visual mode mx remembers the selected area as well, see |visual-mode|.
+
+*mark-pool*
+Every mark category uses the same ten punctuation marks:
+    '  \"  ;  ,  /  \\  [  ]  -  =
+
+List marks with |:marks| and delete them with |:delmarks|.
+
+Item, spell and skill marks are part of the design and not yet available.
+";
+
+const CAMERA: &str = "\
+*camera* *scrolling*          Scrolling, zooming and locking the map view
+
+The map follows the cursor: you, or the look cursor in |look-mode|. It
+scrolls once the cursor comes within |'scrolloff'| rows or
+|'sidescrolloff'| columns of an edge. A margin of half the map or more,
+as by default, keeps the cursor in the middle.
+
+*:camera*
+    :camera follow      scroll near the edges (the default)
+    :camera center      keep the cursor in the middle, ignoring margins
+    :camera lock {x}    keep mark x in the middle, see |marks|
+
+*zz* *zt* *zb*
+    zz      redraw with the cursor in the middle of the map
+    zt      redraw with the cursor at the top
+    zb      redraw with the cursor at the bottom
+
+*CTRL-E* *CTRL-Y* *zh* *zl*
+    [count]CTRL-E   scroll the map [count] rows down, without moving you
+    [count]CTRL-Y   scroll the map [count] rows up
+    [count]zl       scroll the map [count] columns right
+    [count]zh       scroll the map [count] columns left
+The view stays where it was put until the cursor moves.
+
+*zoom* *zo* *zi*
+zo zooms out and zi zooms back in; :zoom {level} picks 1, 2, 4 or 8
+tiles per cell. Zoomed out, a cell shows you or an NPC if one is there,
+otherwise the most common biome, and the map header gives the scale.
+";
+
+const ATLAS: &str = "\
+*atlas* *overview*            The overview of the discovered world
+
+:atlas shows everything you have discovered, each cell a square of
+tiles in the colour of their most common biome. Ground you have never
+seen stays blank. Markers show where things are:
+    @       you
+    X       your travel destination
+    ;  ,    a mark, see |marks|
+    N       an NPC you know of
+    *       land claimed by a story still unfolding, see |stories|
+    +       where someone died, see |chronicle|
+
+hjkl, yubn and the arrow keys move the atlas cursor a cell, with
+counts. gx jumps it to mark x and mx sets mark x under it. zo and zi
+show more or fewer tiles per cell, as with |zoom|.
+
+*travel-destination*
+<Enter> chooses the cell under the cursor as your travel destination and
+closes the atlas. Only discovered ground can be chosen. <Esc> or q close
+the atlas without choosing.
+
+*travel* *:travel* *:tr*
+:tr[avel]               travel to your destination
+:tr[avel] {mark}        travel to a mark; gx does the same
+You take the cheapest way over ground you have seen, a step a turn,
+diagonals included.
+Plains are quickest; forests, deserts and tundra take twice as long,
+swamps three times and mountains four. You stop when a hostile comes
+into view or anything else warns you; travel again to carry on.
+
+*minimap*
+With |'minimap'| set, a small atlas around you sits in the corner of the
+map. Its title gives the scale.
+
+*sight* *field-of-view*
+You see as far as your Wisdom allows: four tiles, and one more for
+every two points of Wisdom, less at |night| and in bad |weather|.
+Mountains and forests block sight; you see them but not what lies
+behind. The map draws what you see now, dims
+ground you remember, and leaves ground you have never seen blank. Only
+what you have seen is discovered.
+
+*explored* *fog-of-war*
+Your lineage remembers the land as it last saw it. Out of sight, the
+map and the atlas show that memory, which may have changed since; look
+again to bring it up to date. Other Threads are only drawn while in
+sight. The status bar gives the share of the map's regions explored
+(◐ 47% Explored) and the map header the share of the region underfoot.
+Memory is saved with the game. When you fall, your heir remembers the
+regions your lineage explored at least half of, and nothing else.
+";
+
+const CONTESTS: &str = "\
+*contests*                    Contest resolution
+
+Every interaction between Threads is a contest. Both sides roll:
+
+    d20 + stat + luck modifier + state modifier
+
+The higher total wins; ties go to the defender.
+
+*luck-modifier*
+Luck adds 1 for every 5 points, so Luck 10 adds 2.
+
+*stat-pairs*
+The stats contested depend on the action:
+    Combat              Strength vs Constitution, or Dexterity vs
+                        Dexterity
+    Social              Charisma vs Wisdom
+    Story progression   Intelligence vs story complexity
+
+See |properties| for the stats and |states| for modifiers.
+
+*relationships* *trust* *fear*
+Threads remember one another. Each pair has a relationship both ways:
+how far one trusts the other, fears it, knows it and owes it. Contests
+between the pair change them. Coming alongside a peaceful NPC you greet
+it, Charisma against its Wisdom: it warms to you if you win and cools
+if you lose, you come to know each other, and a warm welcome leaves you
+owing a little. Seeing through a Thread in |look-mode| makes it more
+familiar. Relationships weigh on contests between the pair: knowing the
+other adds up to 2, fearing it takes up to 4, and its trust or distrust
+in you adds or takes up to 3. The look panel shows how a Thread
+regards you once it shows: friendly, familiar, afraid or hostile.
+Feelings fade day by day, debts do not. Relationships are saved with the
+game, and a lineage's heirs inherit its enemies, its debts and half its
+goodwill.
+
+*combat* *fighting* *F*
+Step into an enemy to strike at it, or strike at anyone with F and a
+direction (Fl); a count strikes again. Every blow is a contest, and
+|'fightstyle'| picks the stats: forceful sets your Strength against
+its Constitution, deft your Dexterity against its Dexterity. NPCs
+fight in whichever suits them. You fight with the first weapon you
+carry, or your fists; a staff or club adds 2 to forceful blows, a blade
+or knife to deft ones, and both hit harder:
+    fists   1      staff   3      blade   4
+plus a fifth of the stat you strike with. Harm is kept as the Damaged
+state: you have 5 hit points, 2 more for every point of Constitution
+and 3 for every |level| past the first, less the share of them Damaged,
+and fall with none left.
+A blow struck makes an enemy of the one struck; foes beside you strike
+back every turn while awake, and corrupted NPCs need no reason. Every
+exchange is told in the log, and blows taken are warnings.
+
+*morale* *fleeing* *surrender*
+Blows taken and blows missed make a fighter Stressed, and Stress takes
+up to 4 from every roll. An NPC whose morale breaks, at half Stressed
+or a quarter of its hit points, flees if it beats your Dexterity and
+has somewhere to go, and yields if not; one that yields fears you and
+owes you its life. To flee yourself, step away: each foe you leave
+behind gets a parting blow unless you beat its Dexterity. :yield
+surrenders to the foes beside you. The corrupted take no prisoners.
+
+*death* *heirs*
+The fallen leave the map and the |chronicle| remembers them; what they
+carried lies where they fell. When you fall your heir (Wanderer II)
+takes up the lineage at its home in the heart of the world, with your
+stats, your journal and what you carried, the land your lineage knew
+well (|explored|) and what the world made of you (|relationships|).
+
+*vitals* *level* *fatigue* *hunger* *supplies*
+The status bar shows your hit points (|combat|) and level, and how you
+fare when you are Tired, Exhausted, Hungry or Starving. A hundredth of
+the Experienced state is a point of experience, and every 10 points a
+level; slaying a foe is worth 3. Each step onto land harder to cross
+than plains tires you by the difference (|travel|), and you can bear
+10 fatigue and 1 more for every point of Constitution: past half of
+it you are Tired, and strain beyond it comes out of your hit points.
+Every turn you do not strain yourself eases a point. You eat every 8
+hours from your supplies, which hold as many meals as your Resources;
+an NPC who greets you warmly may share their food. Out of supplies you
+go hungry, and after two days without food you starve, losing a hit
+point an hour. Each hour you are fed and not Tired you heal 1 hit
+point, and 1 more for every 10 Constitution, and your nerve settles;
+NPCs heal too. The log warns you as your hit points fall to half and
+a quarter, as you tire and as you hunger, and tells you when you
+recover. Starving and exhaustion kill, and your heir takes over.
+
+*weather*
+Weather systems are Threads too. Every twenty turns a tile of land may
+breed one, if the system wins its stat against the land's Constitution.
+The |seasons| favour some over others:
+    Thunderstorm     plains, mountains, deserts   Charisma +2, Dexterity
+                                                  and Wisdom -2
+    Gentle Rain      plains, forests, swamps      Charisma, Connections +2
+    Blizzard         mountains, tundra            Constitution +2,
+                                                  Dexterity, Intelligence
+                                                  and Wisdom -3
+    Magical Aurora   tundra                       Intelligence, Wisdom +2,
+                                                  Luck +3
+A system drifts a tile every other turn, contesting its Constitution
+against the land it crosses and wearing out when it loses; wilderness
+starves it. Everyone inside rolls with its modifiers and sees less far,
+except under an aurora. The map is tinted where weather lies in |sight|,
+and the log tells you when it forms, reaches you or dies away. Being
+caught in a thunderstorm or blizzard is a warning and stops |travel|.
+
+*calendar* *time*
+The world keeps time in turns: ten turns make an hour, and a new world
+begins at 08:00 on day 1 of Spring, year 1. The status bar shows the
+day and the time (Spring 1, Year 1   08:00 Day ☀); :calendar spells it
+out.
+
+*night* *day*
+    Dawn    ◓   05:00-07:00   sight three quarters as far
+    Day     ☀   07:00-18:00   sight as far as Wisdom allows
+    Dusk    ◒   18:00-20:00   sight three quarters as far
+    Night   ☾   20:00-05:00   sight half as far
+NPCs keep hours. Most work by day and sleep from 22:00 to 06:00;
+hostile ones sleep through the day and prowl after dusk. The look panel
+says what an NPC is about. A sleeping NPC is easy to study, a prowler
+harder to make out.
+
+*seasons* *years*
+Spring, Summer, Autumn and Winter last thirty days each, so a year is
+120 days. Summer breeds thunderstorms and never blizzards; Winter
+breeds blizzards and auroras and never thunderstorms; Spring and Autumn
+favour gentle rain. Lineages live for decades of years. The date is
+saved with the game.
+";
+
+const STORIES: &str = "\
+*stories*                     Stories, their stages and goals
+
+Stories are Threads with stats of their own, and they want to reach
+their end. Every game begins with one. A story has no place on the map;
+:story shows how far each has come.
+
+*stages*
+A story runs through two to seven stages, the beats of its arc, one at
+a time. Intelligence and Constitution make it long; Intelligence makes
+each stage ask more, with one to three goals. A stage is over when all
+its goals are met, and the next begins.
+
+*goals*
+Each goal is tied to a biome the world holds and asks for a number of
+different Threads there:
+    knowledge       \"Study the Forest\": see all there is to a Thread
+                    there when you |look-mode| at it
+    relationship    \"Meet the folk of the Plains\": stand beside a
+                    peaceful NPC there
+    resource        \"Find what the Swamp hides\": take an item there
+    challenge       \"Brave the Mountains\": cross swamps or mountains
+                    there, or be caught in a storm or blizzard
+                    (|weather|) over them
+The kinds a story sets follow its stats: Intelligence and Wisdom favour
+knowledge, Charisma and Connections relationships, Resources and Luck
+resources, Strength and Constitution challenges. The same stats decide
+how many Threads each goal needs. Challenges are set on the hardest
+ground there is. The log tells you when a goal is met and a stage is
+over.
+
+*story-view*
+    The Gilded Oath: stage 2 of 4, The Threshold
+      ✓ Study the Plains 2/2
+      · Meet the folk of the Plains 0/1
+Stories are saved with the game.
+
+*weaving* *claims*
+When the world is woven each story lays claim to land for its goals.
+Stage by stage, every goal contests the stat behind it against the
+Constitution of free regions of its biome, and keeps those it wins, as
+many as it needs Threads. A claimed region grows in that stat, turns
+Prestigious and takes on the goal's |states|: Experienced for
+knowledge, Connected for relationships, Blessed for resources and
+Stressed for challenges. Relationship goals bring an NPC to live on
+each claim, resource goals leave an item (!) there to find; walk onto
+it to take it. The same world seed weaves the same claims.
+:claims lists which story claimed which land:
+    The Gilded Oath, The Call: Whispering Plains at 3,-2 (resource)
+
+*resolution*
+Stories do not wait for a hero. When you :abandon your character or
+:retire them, or a save lies unloaded for |'absence'| years of world
+time (a year for every |'worldyear'| hours away, a week unless set),
+every story under way resolves itself in a contest of the story against
+the world:
+    Constitution + Intelligence + Connections + d20
+    vs Stability + Complexity + Inertia + d20
+Stability and Complexity are the Constitution and Intelligence of the
+land the story claimed; Inertia grows with every goal still unmet. A
+retiring hero hands their work on in good order and adds 3. Winning by
+more than 5 the story ends well, leaving its land Enhanced and its folk
+Connected; losing by more it falls apart, leaving them Damaged and
+Stressed. Anything closer and nothing comes of it: the story becomes a
+Neglected legend, and its land and folk grow Neglected.
+
+*chronicle*
+The world remembers what became of its stories. :chronicle lists it:
+    Spring 1, Year 4: The Gilded Oath ended well when its hero retired
+The chronicle is saved with the game.
+";
+
+const PROPERTIES: &str = "\
+*properties*                  The ten Thread properties
+
+Every Thread (players, NPCs, regions, items, weather) has the same ten
+properties on a 0-20 scale.
+
+*physical*
+    Strength        raw power
+    Dexterity       speed and precision
+    Constitution    endurance
+
+*mental*
+    Intelligence    reasoning and complexity
+    Wisdom          perception and judgement
+    Charisma        presence and persuasion
+
+*social*
+    Connections     who the Thread knows
+    Resources       what the Thread has
+    Reputation      what others think
+
+*mystical*
+    Luck            fortune; see |luck-modifier|
+";
+
+const STATES: &str = "\
+*states*                      The ten Thread states
+
+States change how Threads weave over time. Each runs from 0.0 to 1.0.
+
+*negative-states*
+    Damaged         physical harm; battlefields, broken gear
+    Corrupted       dark influence spreading to nearby Threads
+    Stressed        fear and pressure affecting decisions
+    Neglected       abandoned tasks and forgotten places
+
+*positive-states*
+    Enhanced        improved beyond its nature
+    Experienced     remembers what worked before
+    Connected       bonds to other Threads
+    Prestigious     renown that precedes it
+    Blessed         favoured by fortune
+    Adapted         at home in its surroundings
+
+States flow between Threads: a Corrupted region corrupts items found
+there. See |contests|.
+";
+
+const CMDLINE: &str = "\
+*cmdline*                     Command-line editing
+
+*c_<Left>* *c_<Right>*
+    <Left> <Right>      move the cursor
+*c_CTRL-B* *c_CTRL-E*
+    CTRL-B <Home>       cursor to start of line
+    CTRL-E <End>        cursor to end of line
+*c_CTRL-W* *c_CTRL-U*
+    CTRL-W              delete the word before the cursor
+    CTRL-U              delete everything before the cursor
+*c_<Up>* *history*
+    <Up> <Down>         recall older or newer commands starting with
+                        what has been typed; see |'history'|
+*c_<Tab>* *completion*
+    <Tab> <S-Tab>       complete command names, options, help topics,
+                        saves, colour schemes and marks
+*c_CTRL-F*
+    CTRL-F              open the command-line window, see |q:|
+
+Command history is saved between sessions. Start with -i NONE to skip it.
+";
+
+const VITALISRC: &str = "\
+*vitalisrc* *config*          Startup configuration
+
+At startup Vitalis executes ex commands from $VITALIS_RC, or from
+~/.config/vitalis/vitalisrc. Lines starting with \" are comments.
+Errors are reported in the message log with file and line number.
+
+Start with -u NONE to skip the file, or -u {file} to use another.
+
+*-s* *-w* *replay*
+    -w {file}           record every key typed to {file}
+    -s {file}           type the keys in {file} first, then carry on
+                        from the keyboard; keys are written like 3l:w<CR>
+
+*mapping* *leader*
+    :map lh 'wh<CR>             map lh to attack left
+    :let mapleader = \",\"        set the <leader> key
+    :map <leader>h :help<CR>    ,h opens help
+
+See |:map|, |:let|, |:source| and |options|.
+";
+
+const COLORS: &str = "\
+*colors* *colour-schemes*     Colour schemes and map textures
+
+:colorscheme {name} loads {name}.theme from ~/.config/vitalis/colors,
+falling back to the built-in schemes: default and mono. Without a name
+it shows the current scheme. Setting NO_COLOR starts Vitalis in mono.
+
+*theme-files*
+A scheme is a list of highlight groups, one per line:
+    highlight Forest glyph=▒♣▒♠ ctermfg=28 guifg=#228b22
+    highlight Damaged glyph=%▒% ctermfg=124 attr=bold
+
+glyph=      glyphs to draw; several make a material pattern
+ctermfg=    256-colour palette index (ctermbg= for the background)
+guifg=      #rrggbb colour, used with |'termguicolors'| (guibg= too)
+attr=       bold, dim, italic, underline, reverse, comma separated
+
+*highlight-groups*
+A map tile combines its Thread kind (Player, Npc, Item, Region), its biome
+(Plains, Mountains, Forest, Desert, Swamp, Tundra), its strongest
+obvious |states| (Damaged, Blessed, ...), Annotated for noted tiles,
+the |weather| over it (Thunderstorm, GentleRain, Blizzard, Aurora) and
+Remembered for tiles out of |sight|, later groups overriding earlier
+ones. Visual marks selections;
+ErrorMsg, WarningMsg, MoreMsg and Comment colour messages; HelpTag and
+HelpLink colour help; StatusHP, StatusLevel, StatusCondition and Hostile
+the status bar; AtlasMark, AtlasDestination, AtlasStory and AtlasGrave
+the markers on the |atlas|.
+";
+
+/// One segment of a help line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HelpSegment {
+    Text(String),
+    /// `*tag*` - a jump target
+    Tag(String),
+    /// `|tag|` - a link to a jump target
+    Link(String),
+}
+
+/// A page of help text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpPage {
+    /// Topic name shown in the pane title
+    pub topic: String,
+    pub lines: Vec<String>,
+}
+
+/// Where a tag is defined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TagLocation {
+    pub page: usize,
+    pub line: usize,
+}
+
+/// All help pages and the tags defined in them
+#[derive(Debug, Clone)]
+pub struct HelpIndex {
+    pages: Vec<HelpPage>,
+    tags: BTreeMap<String, TagLocation>,
+}
+
+impl HelpIndex {
+    pub fn new() -> Self {
+        let mut pages: Vec<HelpPage> = [
+            ("help", INDEX),
+            ("grammar", GRAMMAR),
+            ("modes", MODES),
+            ("marks", MARKS),
+            ("camera", CAMERA),
+            ("atlas", ATLAS),
+            ("contests", CONTESTS),
+            ("stories", STORIES),
+            ("properties", PROPERTIES),
+            ("states", STATES),
+            ("cmdline", CMDLINE),
+            ("vitalisrc", VITALISRC),
+            ("colors", COLORS),
+        ]
+        .into_iter()
+        .map(|(topic, text)| HelpPage {
+            topic: topic.to_string(),
+            lines: text.lines().map(str::to_string).collect(),
+        })
+        .collect();
+        pages.push(ex_commands_page());
+        pages.push(options_page());
+
+        let mut tags = BTreeMap::new();
+        for (page_index, page) in pages.iter().enumerate() {
+            for (line_index, line) in page.lines.iter().enumerate() {
+                for segment in parse_line(line) {
+                    if let HelpSegment::Tag(tag) = segment {
+                        tags.entry(tag).or_insert(TagLocation {
+                            page: page_index,
+                            line: line_index,
+                        });
+                    }
+                }
+            }
+        }
+
+        Self { pages, tags }
+    }
+
+    pub fn page(&self, index: usize) -> Option<&HelpPage> {
+        self.pages.get(index)
+    }
+
+    pub fn pages(&self) -> &[HelpPage] {
+        &self.pages
+    }
+
+    /// All tag names, sorted
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.keys().map(String::as_str)
+    }
+
+    /// Find the best tag for a `:help` argument
+    ///
+    /// Tries the exact tag, then `:topic` and `'topic'`, then the shortest
+    /// tag starting with the topic, then any tag containing it.
+    pub fn find(&self, topic: &str) -> Option<TagLocation> {
+        let topic = topic.trim();
+        let exact = [
+            topic.to_string(),
+            format!(":{}", topic),
+            format!("'{}'", topic),
+        ];
+        if let Some(location) = exact.iter().find_map(|tag| self.tags.get(tag)) {
+            return Some(*location);
+        }
+
+        let best = |matches: &dyn Fn(&str) -> bool| {
+            self.tags
+                .iter()
+                .filter(|(tag, _)| matches(tag))
+                .min_by_key(|(tag, _)| tag.len())
+                .map(|(_, location)| *location)
+        };
+        let lower = topic.to_lowercase();
+        best(&|tag| tag.to_lowercase().starts_with(&lower))
+            .or_else(|| best(&|tag| tag.to_lowercase().contains(&lower)))
+    }
+
+    /// Every line containing `pattern` (case-insensitive), for `:helpgrep`
+    pub fn grep(&self, pattern: &str) -> Vec<TagLocation> {
+        let pattern = pattern.to_lowercase();
+        self.pages
+            .iter()
+            .enumerate()
+            .flat_map(|(page, p)| {
+                p.lines
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, line)| line.to_lowercase().contains(&pattern))
+                    .map(move |(line, _)| TagLocation { page, line })
+            })
+            .collect()
+    }
+}
+
+impl Default for HelpIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Split a help line into text, tag definitions and links
+pub fn parse_line(line: &str) -> Vec<HelpSegment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '*' || c == '|' {
+            let body = &rest[1..];
+            if let Some(end) = body.find(c) {
+                let name = &body[..end];
+                if !name.is_empty() && !name.contains(char::is_whitespace) {
+                    if !text.is_empty() {
+                        segments.push(HelpSegment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(if c == '*' {
+                        HelpSegment::Tag(name.to_string())
+                    } else {
+                        HelpSegment::Link(name.to_string())
+                    });
+                    rest = &body[end + 1..];
+                    continue;
+                }
+            }
+        }
+        text.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    if !text.is_empty() {
+        segments.push(HelpSegment::Text(text));
+    }
+    segments
+}
+
+/// Links on a help line, in order
+pub fn links(line: &str) -> Vec<String> {
+    parse_line(line)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            HelpSegment::Link(tag) => Some(tag),
+            _ => None,
+        })
+        .collect()
+}
+
+fn ex_commands_page() -> HelpPage {
+    let mut lines = vec![
+        "*ex-commands*                 Ex command index".to_string(),
+        String::new(),
+        "Type : in Normal mode to enter a command. See |cmdline|.".to_string(),
+    ];
+    for def in EX_COMMANDS {
+        lines.push(String::new());
+        let mut tags = format!("*:{}*", def.name);
+        if let Some(abbrev) = def.abbrev {
+            tags.push_str(&format!(" *:{}*", abbrev));
+        }
+        lines.push(tags);
+        let name = match def.abbrev {
+            Some(abbrev) => format!(":{}[{}]", abbrev, &def.name[abbrev.len()..]),
+            None => format!(":{}", def.name),
+        };
+        lines.push(format!("{} {}", name, def.args).trim_end().to_string());
+        lines.push(format!("    {}", def.description));
+    }
+    HelpPage {
+        topic: "ex-commands".to_string(),
+        lines,
+    }
+}
+
+fn options_page() -> HelpPage {
+    let mut lines = vec![
+        "*options*                     Option index".to_string(),
+        String::new(),
+        "Change options with |:set|:".to_string(),
+        "    :set {option}       switch a flag on (or show a value)".to_string(),
+        "    :set no{option}     switch a flag off".to_string(),
+        "    :set {option}={val} set a value".to_string(),
+        "    :set {option}?      show the value".to_string(),
+        "    :set {option}&      reset to the default".to_string(),
+        "    :set all            show every option".to_string(),
+    ];
+    for def in OPTIONS {
+        lines.push(String::new());
+        let mut tags = format!("*'{}'*", def.name);
+        if let Some(abbrev) = def.abbrev {
+            tags.push_str(&format!(" *'{}'*", abbrev));
+        }
+        lines.push(tags);
+        let kind = match def.kind {
+            OptionKind::Bool => "boolean".to_string(),
+            OptionKind::Number { min, max } => format!("number {}-{}", min, max),
+            OptionKind::String => "string".to_string(),
+            OptionKind::Enum(choices) => choices.join(", "),
+        };
+        let default = match def.kind {
+            OptionKind::Bool if def.default == "false" => format!("no{}", def.name),
+            OptionKind::Bool => def.name.to_string(),
+            _ => format!("{}={}", def.name, def.default),
+        };
+        lines.push(format!("'{}'  ({}, default {})", def.name, kind, default));
+        lines.push(format!("    {}", def.description));
+    }
+    HelpPage {
+        topic: "options".to_string(),
+        lines,
+    }
+}
+
+/// Navigation in the help viewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpAction {
+    /// Cursor down (j)
+    Down,
+    /// Cursor up (k)
+    Up,
+    /// Half a page down (Ctrl-d)
+    HalfPageDown,
+    /// Half a page up (Ctrl-u)
+    HalfPageUp,
+    /// First line (gg)
+    Top,
+    /// Last line (G)
+    Bottom,
+    /// Next link on the page (Tab)
+    NextLink,
+    /// Previous link on the page (S-Tab)
+    PrevLink,
+    /// Follow the link on the cursor line (Ctrl-], Enter)
+    FollowLink,
+    /// Return to where the last link was followed from (Ctrl-t)
+    Back,
+    /// Next :helpgrep match (n)
+    NextMatch,
+    /// Previous :helpgrep match (N)
+    PrevMatch,
+    /// Close help (q, Esc)
+    Close,
+}
+
+/// State of the open help viewer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpView {
+    pub page: usize,
+    pub cursor: usize,
+    /// Index of the selected link on the cursor line
+    pub link: usize,
+    /// Tag stack for Ctrl-t
+    stack: Vec<TagLocation>,
+    /// Results of the last :helpgrep
+    matches: Vec<TagLocation>,
+    match_index: usize,
+}
+
+impl HelpView {
+    /// Open the viewer at a location
+    pub fn at(location: TagLocation) -> Self {
+        Self {
+            page: location.page,
+            cursor: location.line,
+            link: 0,
+            stack: Vec::new(),
+            matches: Vec::new(),
+            match_index: 0,
+        }
+    }
+
+    fn location(&self) -> TagLocation {
+        TagLocation {
+            page: self.page,
+            line: self.cursor,
+        }
+    }
+
+    /// Jump to a location, remembering where we came from
+    pub fn jump(&mut self, location: TagLocation) {
+        if location != self.location() {
+            self.stack.push(self.location());
+        }
+        self.go(location);
+    }
+
+    fn go(&mut self, location: TagLocation) {
+        self.page = location.page;
+        self.cursor = location.line;
+        self.link = 0;
+    }
+
+    /// Replace the :helpgrep results and jump to the first
+    pub fn set_matches(&mut self, matches: Vec<TagLocation>) {
+        self.matches = matches;
+        self.match_index = 0;
+        if let Some(first) = self.matches.first().copied() {
+            self.jump(first);
+        }
+    }
+
+    /// Current match number and total, for "(1 of 5)" reports
+    pub fn match_position(&self) -> Option<(usize, usize)> {
+        (!self.matches.is_empty()).then(|| (self.match_index + 1, self.matches.len()))
+    }
+
+    /// Apply a navigation action `count` times
+    ///
+    /// Returns an error message when the action cannot be performed.
+    pub fn apply(&mut self, action: HelpAction, count: usize, index: &HelpIndex) -> Result<(), String> {
+        let Some(page) = index.page(self.page) else {
+            return Ok(());
+        };
+        let last_line = page.lines.len().saturating_sub(1);
+
+        match action {
+            HelpAction::Down => self.move_to((self.cursor + count).min(last_line)),
+            HelpAction::Up => self.move_to(self.cursor.saturating_sub(count)),
+            HelpAction::HalfPageDown => self.move_to((self.cursor + HALF_PAGE * count).min(last_line)),
+            HelpAction::HalfPageUp => self.move_to(self.cursor.saturating_sub(HALF_PAGE * count)),
+            HelpAction::Top => self.move_to(0),
+            HelpAction::Bottom => self.move_to(last_line),
+            HelpAction::NextLink | HelpAction::PrevLink => {
+                let forward = action == HelpAction::NextLink;
+                for _ in 0..count {
+                    self.step_link(page, forward);
+                }
+            }
+            HelpAction::FollowLink => {
+                let line = &page.lines[self.cursor.min(last_line)];
+                let tag = links(line)
+                    .into_iter()
+                    .nth(self.link)
+                    .ok_or_else(|| "E426: No link under cursor".to_string())?;
+                let location = index
+                    .find(&tag)
+                    .ok_or_else(|| format!("E426: Tag not found: {}", tag))?;
+                self.jump(location);
+            }
+            HelpAction::Back => {
+                for _ in 0..count {
+                    let previous = self
+                        .stack
+                        .pop()
+                        .ok_or_else(|| "E73: Tag stack empty".to_string())?;
+                    self.go(previous);
+                }
+            }
+            HelpAction::NextMatch | HelpAction::PrevMatch => {
+                if self.matches.is_empty() {
+                    return Err("E42: No helpgrep matches".to_string());
+                }
+                let len = self.matches.len();
+                let step = count % len;
+                self.match_index = if action == HelpAction::NextMatch {
+                    (self.match_index + step) % len
+                } else {
+                    (self.match_index + len - step) % len
+                };
+                self.go(self.matches[self.match_index]);
+            }
+            HelpAction::Close => {}
+        }
+        Ok(())
+    }
+
+    fn move_to(&mut self, line: usize) {
+        if line != self.cursor {
+            self.cursor = line;
+            self.link = 0;
+        }
+    }
+
+    /// Select the next or previous link, moving across lines as needed
+    fn step_link(&mut self, page: &HelpPage, forward: bool) {
+        let on_line = links(&page.lines[self.cursor]).len();
+        if forward && self.link + 1 < on_line {
+            self.link += 1;
+            return;
+        }
+        if !forward && self.link > 0 && on_line > 0 {
+            self.link -= 1;
+            return;
+        }
+
+        let count = page.lines.len();
+        for offset in 1..=count {
+            let line = if forward {
+                (self.cursor + offset) % count
+            } else {
+                (self.cursor + count - offset % count) % count
+            };
+            let found = links(&page.lines[line]).len();
+            if found > 0 {
+                self.cursor = line;
+                self.link = if forward { 0 } else { found - 1 };
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_line_splits_tags_links_and_text() {
+        assert_eq!(
+            parse_line("*marks* see |grammar| and 3 * 4"),
+            vec![
+                HelpSegment::Tag("marks".to_string()),
+                HelpSegment::Text(" see ".to_string()),
+                HelpSegment::Link("grammar".to_string()),
+                HelpSegment::Text(" and 3 * 4".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn requested_topics_exist() {
+        let index = HelpIndex::new();
+        for topic in ["grammar", "marks", "contests", "states", "help"] {
+            let location = index.find(topic).expect(topic);
+            assert_eq!(index.page(location.page).unwrap().topic, topic);
+        }
+        assert!(index.find("no such topic").is_none());
+    }
+
+    #[test]
+    fn every_command_and_option_has_help() {
+        let index = HelpIndex::new();
+        for def in EX_COMMANDS {
+            assert!(index.tags().any(|t| t == format!(":{}", def.name)), ":{}", def.name);
+        }
+        for def in OPTIONS {
+            assert!(index.tags().any(|t| t == format!("'{}'", def.name)), "'{}'", def.name);
+        }
+        // `:h set` finds the command, `:h ch` the option
+        let set = index.find("set").unwrap();
+        assert_eq!(index.page(set.page).unwrap().topic, "ex-commands");
+        let ch = index.find("ch").unwrap();
+        assert_eq!(index.page(ch.page).unwrap().topic, "options");
+    }
+
+    #[test]
+    fn every_link_resolves() {
+        let index = HelpIndex::new();
+        for page in index.pages() {
+            for line in &page.lines {
+                for link in links(line) {
+                    assert!(index.find(&link).is_some(), "dangling link |{}|", link);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn follow_link_and_back() {
+        let index = HelpIndex::new();
+        let mut view = HelpView::at(index.find("help").unwrap());
+        view.apply(HelpAction::NextLink, 1, &index).unwrap();
+        view.apply(HelpAction::FollowLink, 1, &index).unwrap();
+        assert_eq!(index.page(view.page).unwrap().topic, "grammar");
+
+        view.apply(HelpAction::Back, 1, &index).unwrap();
+        assert_eq!(index.page(view.page).unwrap().topic, "help");
+        assert!(view.apply(HelpAction::Back, 1, &index).is_err());
+    }
+
+    #[test]
+    fn helpgrep_matches_cycle() {
+        let index = HelpIndex::new();
+        let mut view = HelpView::at(index.find("help").unwrap());
+        let matches = index.grep("strength");
+        assert!(matches.len() >= 2);
+        view.set_matches(matches.clone());
+        assert_eq!(view.match_position(), Some((1, matches.len())));
+        view.apply(HelpAction::NextMatch, 1, &index).unwrap();
+        assert_eq!(view.match_position(), Some((2, matches.len())));
+        view.apply(HelpAction::PrevMatch, 2, &index).unwrap();
+        assert_eq!(view.match_position(), Some((matches.len(), matches.len())));
+    }
+}
@@ -0,0 +1,356 @@
+//! Input events - where keys come from
+//!
+//! Frontends turn whatever they receive into [`InputEvent`]s, and the game
+//! loop hands every event to the Loom through one dispatch path. Sources
+//! implement [`InputSource`]: the terminal (crossterm), a fixed script for
+//! tests, and a replay of keys recorded with `-w` (`vitalis -s file`).
+//!
+//! Script and recording files use vim key notation (`3l:w<CR>`); line
+//! breaks in them are ignored, so `<CR>` spells out Enter.
+
+use super::keymap::{format_keys, parse_keys};
+use super::keys;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// A key pressed by the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    /// A letter or one of `\ ] ^ _` with Ctrl held
+    Ctrl(char),
+    Enter,
+    Esc,
+    Backspace,
+    Tab,
+    BackTab,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Delete,
+    PageUp,
+    PageDown,
+    /// Function key 1 to 12
+    F(u8),
+}
+
+impl Key {
+    /// The key as it travels through mappings and parsers (see `keys`)
+    ///
+    /// None for keys the game has no code for, such as `<F13>`.
+    pub fn to_char(self) -> Option<char> {
+        Some(match self {
+            // Ctrl-J and a raw newline are Enter, as on the command line in vim
+            Key::Char('\n' | '\r') | Key::Enter => keys::ENTER,
+            Key::Char(c) => c,
+            Key::Ctrl(c @ ('a'..='z' | 'A'..='Z' | '\\' | ']' | '^' | '_')) => keys::ctrl(c),
+            Key::Ctrl(_) => return None,
+            Key::Esc => keys::ESC,
+            Key::Backspace => keys::BACKSPACE,
+            Key::Tab => keys::TAB,
+            Key::BackTab => keys::BACKTAB,
+            Key::Left => keys::LEFT,
+            Key::Right => keys::RIGHT,
+            Key::Up => keys::UP,
+            Key::Down => keys::DOWN,
+            Key::Home => keys::HOME,
+            Key::End => keys::END,
+            Key::Delete => keys::DELETE,
+            Key::PageUp => keys::PAGE_UP,
+            Key::PageDown => keys::PAGE_DOWN,
+            Key::F(n) => return keys::function(n),
+        })
+    }
+
+    /// The key for a char in the internal encoding
+    pub fn from_char(c: char) -> Key {
+        match c {
+            keys::ENTER | '\n' => Key::Enter,
+            keys::ESC => Key::Esc,
+            keys::BACKSPACE => Key::Backspace,
+            keys::TAB => Key::Tab,
+            keys::BACKTAB => Key::BackTab,
+            keys::LEFT => Key::Left,
+            keys::RIGHT => Key::Right,
+            keys::UP => Key::Up,
+            keys::DOWN => Key::Down,
+            keys::HOME => Key::Home,
+            keys::END => Key::End,
+            keys::DELETE => Key::Delete,
+            keys::PAGE_UP => Key::PageUp,
+            keys::PAGE_DOWN => Key::PageDown,
+            '\x01'..='\x1a' => Key::Ctrl((c as u8 - 1 + b'a') as char),
+            '\x1c'..='\x1f' => Key::Ctrl((c as u8 + b'@') as char),
+            _ => match (1..=12).find(|n| keys::function(*n) == Some(c)) {
+                Some(n) => Key::F(n),
+                None => Key::Char(c),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    /// Vim key notation: `l`, `<CR>`, `<C-v>`, `<F1>`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_char() {
+            Some(c) => write!(f, "{}", format_keys(&[c])),
+            None => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// Something that happened at the frontend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    Key(Key),
+    /// The screen changed size; the next frame is drawn to fit
+    Resize { width: u16, height: u16 },
+}
+
+/// A stream of input events
+pub trait InputSource {
+    /// The next event, waiting up to `timeout`; `Ok(None)` if none came
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<InputEvent>>;
+
+    /// True once the source will never produce another event
+    fn is_exhausted(&self) -> bool {
+        false
+    }
+}
+
+impl<S: InputSource + ?Sized> InputSource for Box<S> {
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<InputEvent>> {
+        (**self).poll(timeout)
+    }
+
+    fn is_exhausted(&self) -> bool {
+        (**self).is_exhausted()
+    }
+}
+
+/// Keys read from the terminal through crossterm
+#[derive(Debug, Default)]
+pub struct CrosstermInput;
+
+impl InputSource for CrosstermInput {
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<InputEvent>> {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+        Ok(match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key_from_crossterm(key).map(InputEvent::Key),
+            Event::Resize(width, height) => Some(InputEvent::Resize { width, height }),
+            _ => None,
+        })
+    }
+}
+
+/// Translate a crossterm key event, or None for keys the game ignores
+pub fn key_from_crossterm(key: KeyEvent) -> Option<Key> {
+    Some(match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => match c {
+            'a'..='z' | 'A'..='Z' | '\\' | ']' | '^' | '_' => Key::Ctrl(c.to_ascii_lowercase()),
+            // Some terminals report Ctrl-\ through Ctrl-_ as Ctrl-4..7
+            '4'..='7' => Key::Ctrl(['\\', ']', '^', '_'][(c as u8 - b'4') as usize]),
+            _ => return None,
+        },
+        KeyCode::Char(c) => Key::Char(c),
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Esc => Key::Esc,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::BackTab => Key::BackTab,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::F(n) if (1..=12).contains(&n) => Key::F(n),
+        _ => return None,
+    })
+}
+
+/// A fixed list of events, for tests and scripted runs
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedInput {
+    events: VecDeque<InputEvent>,
+}
+
+impl ScriptedInput {
+    pub fn new(events: impl IntoIterator<Item = InputEvent>) -> Self {
+        Self {
+            events: events.into_iter().collect(),
+        }
+    }
+
+    /// Keys written in vim notation, e.g. `3l:q<CR>`
+    pub fn from_keys(notation: &str) -> Self {
+        Self::new(parse_script(notation))
+    }
+
+    /// Queue more events after the current ones
+    pub fn push(&mut self, event: InputEvent) {
+        self.events.push_back(event);
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn poll(&mut self, _timeout: Duration) -> io::Result<Option<InputEvent>> {
+        Ok(self.events.pop_front())
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+/// Keys replayed from a script file, then handed over to a live source
+///
+/// Like vim's `-s {scriptin}`: the recorded keys are typed first, and the
+/// game carries on from wherever they leave it.
+#[derive(Debug)]
+pub struct ReplayInput<S> {
+    script: ScriptedInput,
+    live: S,
+}
+
+impl<S: InputSource> ReplayInput<S> {
+    pub fn open(path: &Path, live: S) -> io::Result<Self> {
+        let notation = fs::read_to_string(path)?;
+        Ok(Self {
+            script: ScriptedInput::from_keys(&notation),
+            live,
+        })
+    }
+}
+
+impl<S: InputSource> InputSource for ReplayInput<S> {
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<InputEvent>> {
+        if !self.script.is_exhausted() {
+            return self.script.poll(timeout);
+        }
+        self.live.poll(timeout)
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.script.is_exhausted() && self.live.is_exhausted()
+    }
+}
+
+/// Passes events through, appending every key to a script file
+///
+/// Like vim's `-w {scriptout}`; the file replays with `-s`.
+#[derive(Debug)]
+pub struct RecordingInput<S> {
+    inner: S,
+    file: File,
+}
+
+impl<S: InputSource> RecordingInput<S> {
+    pub fn create(path: &Path, inner: S) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { inner, file })
+    }
+}
+
+impl<S: InputSource> InputSource for RecordingInput<S> {
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<InputEvent>> {
+        let event = self.inner.poll(timeout)?;
+        if let Some(InputEvent::Key(key)) = event {
+            // One line per command line keeps recordings readable
+            let line_break = if key == Key::Enter { "\n" } else { "" };
+            write!(self.file, "{}{}", key, line_break)?;
+        }
+        Ok(event)
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.inner.is_exhausted()
+    }
+}
+
+/// Key events for a script in vim notation, ignoring line breaks
+pub fn parse_script(notation: &str) -> Vec<InputEvent> {
+    let notation: String = notation.lines().collect();
+    parse_keys(&notation, "")
+        .into_iter()
+        .map(|c| InputEvent::Key(Key::from_char(c)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_round_trip_through_the_internal_encoding() {
+        let all = [
+            Key::Char('x'),
+            Key::Ctrl('v'),
+            Key::Ctrl(']'),
+            Key::Enter,
+            Key::Esc,
+            Key::BackTab,
+            Key::PageDown,
+            Key::F(12),
+        ];
+        for key in all {
+            assert_eq!(Key::from_char(key.to_char().unwrap()), key, "{:?}", key);
+        }
+        assert_eq!(Key::Char('\n').to_char(), Some(keys::ENTER));
+        assert_eq!(Key::F(13).to_char(), None);
+    }
+
+    #[test]
+    fn crossterm_keys_are_translated() {
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        assert_eq!(key_from_crossterm(ctrl('W')), Some(Key::Ctrl('w')));
+        assert_eq!(key_from_crossterm(ctrl('5')), Some(Key::Ctrl(']')));
+        assert_eq!(key_from_crossterm(ctrl('1')), None);
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(key_from_crossterm(enter), Some(Key::Enter));
+    }
+
+    #[test]
+    fn scripts_ignore_line_breaks() {
+        let events = parse_script("2l\n:q<CR>\n");
+        let keys: Vec<String> = events
+            .iter()
+            .map(|event| match event {
+                InputEvent::Key(key) => key.to_string(),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(keys, ["2", "l", ":", "q", "<CR>"]);
+    }
+
+    #[test]
+    fn recordings_replay_the_same_keys() {
+        let path = std::env::temp_dir().join(format!("vitalis-scriptout-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let typed = ScriptedInput::from_keys(":set ch=2<CR><C-v>jl<Esc>");
+        let mut recorder = RecordingInput::create(&path, typed.clone()).unwrap();
+        while recorder.poll(Duration::ZERO).unwrap().is_some() {}
+
+        let mut replay = ReplayInput::open(&path, ScriptedInput::default()).unwrap();
+        let mut replayed = Vec::new();
+        while let Some(event) = replay.poll(Duration::ZERO).unwrap() {
+            replayed.push(event);
+        }
+        assert_eq!(replayed, typed.events.into_iter().collect::<Vec<_>>());
+        assert!(replay.is_exhausted());
+        fs::remove_file(&path).unwrap();
+    }
+}
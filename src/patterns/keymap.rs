@@ -0,0 +1,150 @@
+//! Key mappings - vi-style `:map` and `:noremap` support
+//!
+//! Mappings translate a sequence of typed keys into another sequence of keys
+//! before they reach the command parser.
+
+use super::keys;
+use std::collections::BTreeMap;
+
+/// Default leader key when `mapleader` is not set
+pub const DEFAULT_LEADER: &str = "\\";
+
+/// The replacement side of a mapping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mapping {
+    pub rhs: Vec<char>,
+    /// If true, the replacement keys are not themselves remapped
+    pub noremap: bool,
+}
+
+/// Result of looking up a typed key sequence
+#[derive(Debug, PartialEq, Eq)]
+pub struct Lookup<'a> {
+    /// Mapping whose left-hand side equals the sequence exactly
+    pub exact: Option<&'a Mapping>,
+    /// True if a longer mapping starts with the sequence
+    pub is_prefix: bool,
+}
+
+/// Table of Normal mode key mappings
+#[derive(Debug, Clone, Default)]
+pub struct KeyMap {
+    mappings: BTreeMap<Vec<char>, Mapping>,
+}
+
+impl KeyMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define or replace a mapping
+    pub fn insert(&mut self, lhs: Vec<char>, rhs: Vec<char>, noremap: bool) {
+        self.mappings.insert(lhs, Mapping { rhs, noremap });
+    }
+
+    /// Remove a mapping, returning true if it existed
+    pub fn remove(&mut self, lhs: &[char]) -> bool {
+        self.mappings.remove(lhs).is_some()
+    }
+
+    /// Look up a typed key sequence
+    pub fn lookup(&self, keys: &[char]) -> Lookup<'_> {
+        let exact = self.mappings.get(keys);
+        let is_prefix = self
+            .mappings
+            .range(keys.to_vec()..)
+            .take_while(|(lhs, _)| lhs.starts_with(keys))
+            .any(|(lhs, _)| lhs.len() > keys.len());
+        Lookup { exact, is_prefix }
+    }
+
+    /// All mappings ordered by left-hand side
+    pub fn iter(&self) -> impl Iterator<Item = (&Vec<char>, &Mapping)> {
+        self.mappings.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+}
+
+/// Parse vim key notation (`<CR>`, `<Esc>`, `<leader>`, `<C-w>`, `<F1>`) into keys
+///
+/// Unrecognised `<...>` sequences are taken literally, as vim does.
+pub fn parse_keys(notation: &str, leader: &str) -> Vec<char> {
+    let mut keys = Vec::new();
+    let mut rest = notation;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            if let Some(end) = rest.find('>') {
+                let name = &rest[1..end];
+                if let Some(expanded) = special_key(name, leader) {
+                    keys.extend(expanded);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        keys.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    keys
+}
+
+fn special_key(name: &str, leader: &str) -> Option<Vec<char>> {
+    if name.eq_ignore_ascii_case("leader") {
+        return Some(leader.chars().collect());
+    }
+    keys::from_name(name).map(|key| vec![key])
+}
+
+/// Format keys back into vim key notation for display
+pub fn format_keys(keys: &[char]) -> String {
+    keys.iter()
+        .map(|&c| match keys::name(c) {
+            Some(name) => format!("<{}>", name),
+            None => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keys_expands_special_notation() {
+        assert_eq!(parse_keys(":help<CR>", "\\"), vec![':', 'h', 'e', 'l', 'p', '\r']);
+        assert_eq!(parse_keys("<leader>h", ","), vec![',', 'h']);
+        assert_eq!(parse_keys("<C-w>", "\\"), vec!['\x17']);
+        assert_eq!(parse_keys("<F1>", "\\"), vec![keys::function(1).unwrap()]);
+        assert_eq!(parse_keys("<F99>", "\\"), "<F99>".chars().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn format_keys_round_trips() {
+        let keys = parse_keys("'wh<CR><Esc><C-u>", "\\");
+        assert_eq!(format_keys(&keys), "'wh<CR><Esc><C-u>");
+    }
+
+    #[test]
+    fn lookup_reports_exact_and_prefix_matches() {
+        let mut keymap = KeyMap::new();
+        keymap.insert(vec!['g'], vec!['l'], false);
+        keymap.insert(vec!['g', 'g'], vec!['k'], false);
+
+        let lookup = keymap.lookup(&['g']);
+        assert!(lookup.exact.is_some());
+        assert!(lookup.is_prefix);
+
+        let lookup = keymap.lookup(&['g', 'g']);
+        assert!(lookup.exact.is_some());
+        assert!(!lookup.is_prefix);
+
+        let lookup = keymap.lookup(&['x']);
+        assert!(lookup.exact.is_none());
+        assert!(!lookup.is_prefix);
+    }
+}
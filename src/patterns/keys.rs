@@ -0,0 +1,124 @@
+//! Key codes - special keys encoded as chars
+//!
+//! Typed keys travel through mappings and parsers as `char`s. Keys without
+//! a character of their own (arrows, function keys) are encoded in the
+//! Unicode private use area, much like vim's internal special-key codes.
+
+pub const ENTER: char = '\r';
+pub const ESC: char = '\x1b';
+pub const BACKSPACE: char = '\x7f';
+pub const TAB: char = '\t';
+
+pub const LEFT: char = '\u{E000}';
+pub const RIGHT: char = '\u{E001}';
+pub const UP: char = '\u{E002}';
+pub const DOWN: char = '\u{E003}';
+pub const HOME: char = '\u{E004}';
+pub const END: char = '\u{E005}';
+pub const DELETE: char = '\u{E006}';
+pub const BACKTAB: char = '\u{E007}';
+pub const PAGE_UP: char = '\u{E008}';
+pub const PAGE_DOWN: char = '\u{E009}';
+
+/// First function key; `<F1>` through `<F12>` follow consecutively
+const F1: u32 = 0xE011;
+
+/// True for keys encoded in the private use area (arrows, function keys)
+pub fn is_special(key: char) -> bool {
+    ('\u{E000}'..='\u{F8FF}').contains(&key)
+}
+
+/// Control-modified key (`ctrl('w')` is Ctrl-W, `ctrl(']')` is Ctrl-])
+pub const fn ctrl(c: char) -> char {
+    ((c as u8).to_ascii_uppercase() & 0x1f) as char
+}
+
+/// Function key `<Fn>` for n in 1..=12
+pub fn function(n: u8) -> Option<char> {
+    if (1..=12).contains(&n) {
+        char::from_u32(F1 + n as u32 - 1)
+    } else {
+        None
+    }
+}
+
+/// Name of a special key in vim notation, without the angle brackets
+pub fn name(key: char) -> Option<String> {
+    let name = match key {
+        '\r' | '\n' => "CR",
+        ESC => "Esc",
+        BACKSPACE => "BS",
+        TAB => "Tab",
+        ' ' => "Space",
+        '<' => "lt",
+        LEFT => "Left",
+        RIGHT => "Right",
+        UP => "Up",
+        DOWN => "Down",
+        HOME => "Home",
+        END => "End",
+        DELETE => "Del",
+        BACKTAB => "S-Tab",
+        PAGE_UP => "PageUp",
+        PAGE_DOWN => "PageDown",
+        '\x01'..='\x1a' => return Some(format!("C-{}", (key as u8 - 1 + b'a') as char)),
+        '\x1c'..='\x1f' => return Some(format!("C-{}", (key as u8 + b'@') as char)),
+        _ => {
+            let n = (key as u32).checked_sub(F1).filter(|n| *n < 12)?;
+            return Some(format!("F{}", n + 1));
+        }
+    };
+    Some(name.to_string())
+}
+
+/// Key for a vim notation name such as `CR`, `Left`, `C-w` or `F1`
+pub fn from_name(name: &str) -> Option<char> {
+    let lower = name.to_ascii_lowercase();
+    let key = match lower.as_str() {
+        "cr" | "enter" | "return" => ENTER,
+        "esc" => ESC,
+        "bs" => BACKSPACE,
+        "tab" => TAB,
+        "space" => ' ',
+        "lt" => '<',
+        "bar" => '|',
+        "bslash" => '\\',
+        "left" => LEFT,
+        "right" => RIGHT,
+        "up" => UP,
+        "down" => DOWN,
+        "home" => HOME,
+        "end" => END,
+        "del" => DELETE,
+        "s-tab" => BACKTAB,
+        "pageup" => PAGE_UP,
+        "pagedown" => PAGE_DOWN,
+        _ => {
+            if let Some(letter) = lower.strip_prefix("c-") {
+                let mut chars = letter.chars();
+                return match (chars.next(), chars.next()) {
+                    (Some(c @ ('a'..='z' | '\\' | ']' | '^' | '_')), None) => Some(ctrl(c)),
+                    _ => None,
+                };
+            }
+            let n = lower.strip_prefix('f')?.parse().ok()?;
+            return function(n);
+        }
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for key in [ENTER, ESC, LEFT, BACKTAB, ctrl('w'), ctrl(']'), function(1).unwrap()] {
+            let name = name(key).unwrap();
+            assert_eq!(from_name(&name), Some(key), "{}", name);
+        }
+        assert_eq!(from_name("F13"), None);
+        assert_eq!(name('x'), None);
+    }
+}
@@ -0,0 +1,64 @@
+//! Marks - named locations and areas (mx, gx)
+//!
+//! Every mark category shares the same ten punctuation keys. Location
+//! marks remember a position; marks set in Visual mode also remember the
+//! selected area.
+
+use super::selection::Selection;
+use crate::foundation::Position;
+use std::collections::BTreeMap;
+
+/// The mark pool: `'  "  ;  ,  /  \  [  ]  -  =`
+pub const MARK_KEYS: [char; 10] = ['\'', '"', ';', ',', '/', '\\', '[', ']', '-', '='];
+
+/// True for keys that name a mark
+pub fn is_mark(key: char) -> bool {
+    MARK_KEYS.contains(&key)
+}
+
+/// A marked location, and area if set from Visual mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mark {
+    pub position: Position,
+    pub area: Option<Selection>,
+}
+
+/// Marks set by the player
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Marks {
+    marks: BTreeMap<char, Mark>,
+}
+
+impl Marks {
+    pub const fn new() -> Self {
+        Self { marks: BTreeMap::new() }
+    }
+
+    pub fn set(&mut self, key: char, mark: Mark) {
+        self.marks.insert(key, mark);
+    }
+
+    pub fn get(&self, key: char) -> Option<&Mark> {
+        self.marks.get(&key)
+    }
+
+    /// Remove a mark, returning whether it existed
+    pub fn remove(&mut self, key: char) -> bool {
+        self.marks.remove(&key).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.marks.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+
+    /// Marks in pool order
+    pub fn iter(&self) -> impl Iterator<Item = (char, &Mark)> {
+        MARK_KEYS
+            .iter()
+            .filter_map(|key| self.marks.get(key).map(|mark| (*key, mark)))
+    }
+}
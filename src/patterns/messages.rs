@@ -0,0 +1,156 @@
+//! Message log - Neovim-style notifications with persistent history
+//!
+//! Messages are never cleared; they become part of the narrative log.
+
+/// How a message should be presented to the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+    Success,
+    /// Diagnostic detail, only recorded when 'verbose' is high enough
+    Debug,
+}
+
+impl Severity {
+    /// Icon shown in front of the message text
+    pub fn icon(self) -> char {
+        match self {
+            Severity::Info => 'ℹ',
+            Severity::Warning => '⚠',
+            Severity::Error => '✗',
+            Severity::Success => '✓',
+            Severity::Debug => '·',
+        }
+    }
+}
+
+/// A single entry in the message log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub severity: Severity,
+    pub text: String,
+}
+
+/// Persistent history of every message shown to the player
+#[derive(Debug, Clone, Default)]
+pub struct MessageLog {
+    messages: Vec<Message>,
+    /// Mirrors the 'quiet' option: drop informational messages
+    quiet: bool,
+    /// Mirrors the 'verbose' option: highest debug level recorded
+    verbose: u8,
+}
+
+impl MessageLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the filters from the 'quiet' and 'verbose' options
+    pub fn set_filters(&mut self, quiet: bool, verbose: u8) {
+        self.quiet = quiet;
+        self.verbose = verbose;
+    }
+
+    /// Append a message to the log, bypassing the filters
+    pub fn push(&mut self, severity: Severity, text: impl Into<String>) {
+        self.messages.push(Message {
+            severity,
+            text: text.into(),
+        });
+    }
+
+    /// Informational narration, suppressed by 'quiet'
+    pub fn info(&mut self, text: impl Into<String>) {
+        if !self.quiet {
+            self.push(Severity::Info, text);
+        }
+    }
+
+    /// Output the player explicitly asked for (`:set opt?`), never suppressed
+    pub fn echo(&mut self, text: impl Into<String>) {
+        self.push(Severity::Info, text);
+    }
+
+    /// Debug detail, recorded only when 'verbose' is at least `level`
+    pub fn debug(&mut self, level: u8, text: impl Into<String>) {
+        if self.verbose >= level {
+            self.push(Severity::Debug, text);
+        }
+    }
+
+    pub fn warn(&mut self, text: impl Into<String>) {
+        self.push(Severity::Warning, text);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Severity::Error, text);
+    }
+
+    pub fn success(&mut self, text: impl Into<String>) {
+        self.push(Severity::Success, text);
+    }
+
+    /// Most recent message, if any
+    pub fn latest(&self) -> Option<&Message> {
+        self.messages.last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// The most recent `count` messages, oldest first
+    pub fn recent(&self, count: usize) -> &[Message] {
+        &self.messages[self.messages.len().saturating_sub(count)..]
+    }
+
+    /// All messages, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &Message> {
+        self.messages.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_suppresses_info_but_not_echo_or_errors() {
+        let mut log = MessageLog::new();
+        log.set_filters(true, 0);
+        log.info("You hear faint muttering");
+        log.echo("quiet");
+        log.error("E492: Not an editor command: x");
+        let texts: Vec<_> = log.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["quiet", "E492: Not an editor command: x"]);
+    }
+
+    #[test]
+    fn verbose_controls_debug_messages() {
+        let mut log = MessageLog::new();
+        log.debug(1, "hidden");
+        log.set_filters(false, 2);
+        log.debug(1, "shown");
+        log.debug(3, "too detailed");
+        assert_eq!(log.len(), 1);
+        assert_eq!(log.latest().unwrap().text, "shown");
+    }
+
+    #[test]
+    fn recent_returns_last_messages_in_order() {
+        let mut log = MessageLog::new();
+        log.info("one");
+        log.info("two");
+        log.info("three");
+        let recent: Vec<_> = log.recent(2).iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(recent, vec!["two", "three"]);
+        assert_eq!(log.recent(10).len(), 3);
+    }
+}
@@ -9,5 +9,56 @@ pub mod commands;
 /// Terminal rendering using ratatui (infrastructure concern)
 pub mod display;
 
+/// Headless game driver and screen snapshots for tests
+pub mod headless;
+
+/// View-models the screen components are drawn from
+pub mod view;
+
+/// The atlas: an overview of the discovered world (:atlas, 'minimap')
+pub mod atlas;
+
+/// The camera: which part of the world the map shows (zz, CTRL-E, :zoom)
+pub mod camera;
+
+/// Colour schemes (:colorscheme) and map textures
+pub mod theme;
+
+/// Screen components: status bar, message line, map, mode line
+pub mod widgets;
+
+/// Input events and where they come from (terminal, scripts, replays)
+pub mod input;
+
+/// The game loop: draw, read input, dispatch
+pub mod game_loop;
+
 /// Modal state machine (Normal, Insert, Ex modes)
 pub mod modes;
+
+/// Special key codes (arrows, function keys)
+pub mod keys;
+
+/// Ex command-line editing, history and completion
+pub mod cmdline;
+
+/// Message log with persistent history
+pub mod messages;
+
+/// Visual mode selections over the map
+pub mod selection;
+
+/// Marks for locations and areas (mx, gx)
+pub mod marks;
+
+/// Key mappings (:map, :noremap)
+pub mod keymap;
+
+/// Configuration options (:set)
+pub mod options;
+
+/// In-game help pages (:help, :helpgrep)
+pub mod help;
+
+/// Startup configuration file (vitalisrc) and command line flags
+pub mod config;
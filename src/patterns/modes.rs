@@ -1,52 +1,197 @@
 //! Modal state machine for vi-style interaction
 //!
-//! Handles switching between Normal, Insert, and Ex modes following vi conventions.
+//! Handles switching between Normal, Insert, Visual, Look, Atlas, Ex and Help modes following vi conventions.
+
+use super::cmdline::CommandLine;
+use super::selection::{Selection, VisualKind};
+use crate::threads::ThreadId;
 
 /// The current mode of the interface
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Mode {
     /// Normal mode - movement and commands (default)
-    Normal { count_buffer: String },
-    /// Insert mode - text entry (future)
-    Insert,
-    /// Ex command mode - colon commands with buffer
-    Ex { command_buffer: String },
+    Normal {
+        count_buffer: String,
+        /// Keys of a multi-key command typed so far (e.g. `q` of `q:`)
+        pending: String,
+    },
+    /// Insert mode - naming and note-taking with an editable line
+    Insert { target: InsertTarget, line: CommandLine },
+    /// Visual mode - select an area of the map for an operator
+    Visual {
+        selection: Selection,
+        count_buffer: String,
+        /// Keys of a multi-key command typed so far (e.g. `m` of `m'`)
+        pending: String,
+    },
+    /// Look mode - move a cursor over the map to inspect tiles
+    Look {
+        count_buffer: String,
+        /// Keys of a multi-key command typed so far (e.g. `g` of `g'`)
+        pending: String,
+    },
+    /// Atlas mode - move a cursor over the overview of the world
+    Atlas {
+        count_buffer: String,
+        /// Keys of a multi-key command typed so far (e.g. `g` of `g'`)
+        pending: String,
+    },
+    /// Ex command mode - colon commands with an editable line
+    Ex { line: CommandLine },
+    /// Command-line window (q:) - browse and edit command history
+    CmdWindow { selected: usize },
+    /// Help viewer (:help) - scroll pages and follow links
+    Help {
+        count_buffer: String,
+        /// Keys of a multi-key command typed so far (e.g. `g` of `gg`)
+        pending: String,
+    },
+}
+
+/// What the text typed in Insert mode will become
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertTarget {
+    /// The player's name for a Thread
+    Rename(ThreadId),
+    /// A note on a tile (region Thread)
+    Annotate(ThreadId),
+    /// A journal entry on the player Thread
+    Journal(ThreadId),
+    /// The name of a save file
+    SaveName,
+}
+
+impl InsertTarget {
+    /// Prompt shown before the text being typed
+    pub fn prompt(self) -> &'static str {
+        match self {
+            InsertTarget::Rename(_) => "Name: ",
+            InsertTarget::Annotate(_) => "Note: ",
+            InsertTarget::Journal(_) => "Journal: ",
+            InsertTarget::SaveName => "Save as: ",
+        }
+    }
 }
 
 impl Default for Mode {
     fn default() -> Self {
         Mode::Normal {
             count_buffer: String::new(),
+            pending: String::new(),
         }
     }
 }
 
 impl Mode {
+    /// Help viewer mode with no keys pending
+    pub fn help() -> Self {
+        Mode::Help {
+            count_buffer: String::new(),
+            pending: String::new(),
+        }
+    }
+
+    /// Visual mode over `selection` with no keys pending
+    pub fn visual(selection: Selection) -> Self {
+        Mode::Visual {
+            selection,
+            count_buffer: String::new(),
+            pending: String::new(),
+        }
+    }
+
+    /// Look mode with no keys pending
+    pub fn look() -> Self {
+        Mode::Look {
+            count_buffer: String::new(),
+            pending: String::new(),
+        }
+    }
+
+    /// Atlas mode with no keys pending
+    pub fn atlas() -> Self {
+        Mode::Atlas {
+            count_buffer: String::new(),
+            pending: String::new(),
+        }
+    }
+
     /// Get display string for mode indicator
     pub fn mode_name(&self) -> &str {
         match self {
             Mode::Normal { .. } => "-- NORMAL --",
-            Mode::Insert => "-- INSERT --",
+            Mode::Insert { .. } => "-- INSERT --",
+            Mode::Visual { selection, .. } => match selection.kind {
+                VisualKind::Char => "-- VISUAL --",
+                VisualKind::Block => "-- VISUAL BLOCK --",
+            },
             Mode::Ex { .. } => "-- COMMAND --",
+            Mode::Look { .. } => "-- LOOK --",
+            Mode::Atlas { .. } => "-- ATLAS --",
+            Mode::CmdWindow { .. } => "-- COMMAND WINDOW --",
+            Mode::Help { .. } => "-- HELP --",
         }
     }
 
-    /// Get pending keys to display (count buffer in Normal mode, empty otherwise)
-    pub fn pending_keys(&self) -> &str {
+    /// Get pending keys to display (count and partial command)
+    pub fn pending_keys(&self) -> String {
         match self {
-            Mode::Normal { count_buffer } => count_buffer,
-            _ => "",
+            Mode::Normal {
+                count_buffer,
+                pending,
+            }
+            | Mode::Look {
+                count_buffer,
+                pending,
+            }
+            | Mode::Atlas {
+                count_buffer,
+                pending,
+            }
+            | Mode::Help {
+                count_buffer,
+                pending,
+            } => format!("{}{}", count_buffer, pending),
+            Mode::Visual {
+                selection,
+                count_buffer,
+                pending,
+            } => {
+                if count_buffer.is_empty() && pending.is_empty() {
+                    // Like vim's showcmd, report the size of the selection
+                    let (width, height) = selection.size();
+                    format!("{}x{}", width, height)
+                } else {
+                    format!("{}{}", count_buffer, pending)
+                }
+            }
+            _ => String::new(),
         }
     }
 
-    /// Get command line text (for Ex mode, shown below mode indicator without border)
+    /// Get command line text (for Ex and Insert modes, shown below mode indicator without border)
     pub fn command_line(&self) -> Option<String> {
         match self {
-            Mode::Ex { command_buffer } => Some(format!(":{}", command_buffer)),
+            Mode::Ex { line } => Some(format!(":{}", line.text())),
+            Mode::Insert { target, line } => Some(format!("{}{}", target.prompt(), line.text())),
+            _ => None,
+        }
+    }
+
+    /// Column of the text cursor on the command line, if one is being edited
+    pub fn command_line_cursor(&self) -> Option<usize> {
+        match self {
+            Mode::Ex { line } => Some(1 + line.cursor()),
+            Mode::Insert { target, line } => Some(target.prompt().chars().count() + line.cursor()),
             _ => None,
         }
     }
 
+    /// Check if in Normal mode
+    pub fn is_normal(&self) -> bool {
+        matches!(self, Mode::Normal { .. })
+    }
+
     /// Check if in Ex mode
     pub fn is_ex(&self) -> bool {
         matches!(self, Mode::Ex { .. })
@@ -55,15 +200,39 @@ impl Mode {
     /// Get command buffer if in Ex mode
     pub fn command_buffer(&self) -> Option<&str> {
         match self {
-            Mode::Ex { command_buffer } => Some(command_buffer),
+            Mode::Ex { line } => Some(line.text()),
             _ => None,
         }
     }
 
-    /// Get count buffer if in Normal mode
+    /// Get count buffer if in a mode that takes counts
     pub fn count_buffer(&self) -> Option<&str> {
         match self {
-            Mode::Normal { count_buffer } => Some(count_buffer),
+            Mode::Normal { count_buffer, .. }
+            | Mode::Visual { count_buffer, .. }
+            | Mode::Look { count_buffer, .. }
+            | Mode::Atlas { count_buffer, .. }
+            | Mode::Help { count_buffer, .. } => Some(count_buffer),
+            _ => None,
+        }
+    }
+
+    /// Mutable count buffer if in a mode that takes counts
+    pub fn count_buffer_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Mode::Normal { count_buffer, .. }
+            | Mode::Visual { count_buffer, .. }
+            | Mode::Look { count_buffer, .. }
+            | Mode::Atlas { count_buffer, .. }
+            | Mode::Help { count_buffer, .. } => Some(count_buffer),
+            _ => None,
+        }
+    }
+
+    /// The area selected in Visual mode
+    pub fn selection(&self) -> Option<&Selection> {
+        match self {
+            Mode::Visual { selection, .. } => Some(selection),
             _ => None,
         }
     }
@@ -0,0 +1,446 @@
+//! Options - typed configuration values changed with `:set`
+//!
+//! Every option is declared once in [`OPTIONS`] with its type, default and
+//! validation rules. The renderer, message log and input layer read the
+//! current values at runtime.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The type of an option and its validation rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionKind {
+    /// On/off flag (`:set quiet`, `:set noquiet`)
+    Bool,
+    /// Integer within an inclusive range
+    Number { min: i64, max: i64 },
+    /// Free-form text
+    String,
+    /// One of a fixed set of words
+    Enum(&'static [&'static str]),
+}
+
+/// The current value of an option
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    Bool(bool),
+    Number(i64),
+    String(String),
+}
+
+impl fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionValue::Bool(value) => write!(f, "{}", value),
+            OptionValue::Number(value) => write!(f, "{}", value),
+            OptionValue::String(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Declaration of a single option
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptionDef {
+    pub name: &'static str,
+    /// Short name accepted by `:set` (like vim's `ch` for `cmdheight`)
+    pub abbrev: Option<&'static str>,
+    pub kind: OptionKind,
+    /// Default value in `:set` syntax
+    pub default: &'static str,
+    pub description: &'static str,
+}
+
+impl OptionDef {
+    /// Parse and validate a value given in `:set opt=val` syntax
+    pub fn parse(&self, text: &str) -> Result<OptionValue, String> {
+        match self.kind {
+            OptionKind::Bool => match text {
+                "true" | "1" => Ok(OptionValue::Bool(true)),
+                "false" | "0" => Ok(OptionValue::Bool(false)),
+                _ => Err(format!("E474: Invalid argument: {}={}", self.name, text)),
+            },
+            OptionKind::Number { min, max } => {
+                let value: i64 = text
+                    .parse()
+                    .map_err(|_| format!("E521: Number required after =: {}={}", self.name, text))?;
+                if value < min || value > max {
+                    return Err(format!(
+                        "E474: {} must be between {} and {}: {}",
+                        self.name, min, max, value
+                    ));
+                }
+                Ok(OptionValue::Number(value))
+            }
+            OptionKind::String => Ok(OptionValue::String(text.to_string())),
+            OptionKind::Enum(choices) => {
+                if choices.contains(&text) {
+                    Ok(OptionValue::String(text.to_string()))
+                } else {
+                    Err(format!(
+                        "E474: Invalid argument: {}={} (expected one of: {})",
+                        self.name,
+                        text,
+                        choices.join(", ")
+                    ))
+                }
+            }
+        }
+    }
+
+    /// The default value
+    pub fn default_value(&self) -> OptionValue {
+        self.parse(self.default)
+            .expect("option defaults must satisfy their own validation")
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.name == name || self.abbrev == Some(name)
+    }
+}
+
+/// Every option understood by `:set`
+pub const OPTIONS: &[OptionDef] = &[
+    OptionDef {
+        name: "absence",
+        abbrev: Some("abs"),
+        kind: OptionKind::Number { min: 1, max: 1000 },
+        default: "3",
+        description: "World years a save may lie unloaded before its stories resolve themselves",
+    },
+    OptionDef {
+        name: "cmdheight",
+        abbrev: Some("ch"),
+        kind: OptionKind::Number { min: 1, max: 10 },
+        default: "1",
+        description: "Number of screen lines used for messages and the command line",
+    },
+    OptionDef {
+        name: "colorscheme",
+        abbrev: Some("colo"),
+        kind: OptionKind::String,
+        default: "default",
+        description: "Colour scheme used to draw the screen, as loaded by :colorscheme",
+    },
+    OptionDef {
+        name: "fightstyle",
+        abbrev: Some("fs"),
+        kind: OptionKind::Enum(&["forceful", "deft"]),
+        default: "forceful",
+        description: "How you fight: forceful (Strength vs Constitution) or deft (Dexterity vs Dexterity)",
+    },
+    OptionDef {
+        name: "history",
+        abbrev: Some("hi"),
+        kind: OptionKind::Number { min: 0, max: 10000 },
+        default: "200",
+        description: "Number of command lines remembered between sessions",
+    },
+    OptionDef {
+        name: "maxcount",
+        abbrev: Some("mco"),
+        kind: OptionKind::Number { min: 1, max: 9999 },
+        default: "999",
+        description: "Largest count prefix accepted before a command",
+    },
+    OptionDef {
+        name: "minimap",
+        abbrev: Some("mmap"),
+        kind: OptionKind::Bool,
+        default: "false",
+        description: "Show a minimap of the discovered world in the corner of the map",
+    },
+    OptionDef {
+        name: "quiet",
+        abbrev: None,
+        kind: OptionKind::Bool,
+        default: "false",
+        description: "Suppress informational messages",
+    },
+    OptionDef {
+        name: "scrolloff",
+        abbrev: Some("so"),
+        kind: OptionKind::Number { min: 0, max: 999 },
+        default: "999",
+        description: "Rows kept between the player and the top or bottom edge of the map",
+    },
+    OptionDef {
+        name: "sidescrolloff",
+        abbrev: Some("siso"),
+        kind: OptionKind::Number { min: 0, max: 999 },
+        default: "999",
+        description: "Columns kept between the player and the left or right edge of the map",
+    },
+    OptionDef {
+        name: "termguicolors",
+        abbrev: Some("tgc"),
+        kind: OptionKind::Bool,
+        default: "false",
+        description: "Use 24-bit colour schemes instead of the 256-colour palette",
+    },
+    OptionDef {
+        name: "verbose",
+        abbrev: Some("vbs"),
+        kind: OptionKind::Number { min: 0, max: 9 },
+        default: "0",
+        description: "Show debug messages up to this level of detail",
+    },
+    OptionDef {
+        name: "worldyear",
+        abbrev: Some("wy"),
+        kind: OptionKind::Number { min: 0, max: 8760 },
+        default: "168",
+        description: "Real hours a save lies unloaded for each year of world time; 0 stops the world",
+    },
+];
+
+/// Look up an option by full or abbreviated name
+pub fn find_option(name: &str) -> Option<&'static OptionDef> {
+    OPTIONS.iter().find(|def| def.matches(name))
+}
+
+/// Current values of every option
+#[derive(Debug, Clone)]
+pub struct Options {
+    values: BTreeMap<&'static str, OptionValue>,
+}
+
+impl Options {
+    /// All options at their defaults
+    pub fn new() -> Self {
+        Self {
+            values: OPTIONS
+                .iter()
+                .map(|def| (def.name, def.default_value()))
+                .collect(),
+        }
+    }
+
+    /// Current value of an option, by full or abbreviated name
+    pub fn get(&self, name: &str) -> Option<&OptionValue> {
+        find_option(name).and_then(|def| self.values.get(def.name))
+    }
+
+    /// Current value of a boolean option (false if unknown)
+    pub fn get_bool(&self, name: &str) -> bool {
+        matches!(self.get(name), Some(OptionValue::Bool(true)))
+    }
+
+    /// Current value of a number option (0 if unknown)
+    pub fn get_number(&self, name: &str) -> i64 {
+        match self.get(name) {
+            Some(OptionValue::Number(value)) => *value,
+            _ => 0,
+        }
+    }
+
+    /// Current value of a string or enum option (empty if unknown)
+    pub fn get_str(&self, name: &str) -> &str {
+        match self.get(name) {
+            Some(OptionValue::String(value)) => value,
+            _ => "",
+        }
+    }
+
+    /// Set an option to a validated value
+    pub fn set_value(&mut self, name: &str, value: OptionValue) -> Result<(), String> {
+        let def = find_option(name).ok_or_else(|| format!("E518: Unknown option: {}", name))?;
+        // Round-trip through the parser so every value is validated the same way
+        let value = def.parse(&value.to_string())?;
+        self.values.insert(def.name, value);
+        Ok(())
+    }
+
+    /// Apply one `:set` argument
+    ///
+    /// Supports `opt`, `noopt`, `invopt`, `opt!`, `opt=val`, `opt+=n`,
+    /// `opt-=n`, `opt?` and `opt&`. Returns text to show the player when the
+    /// argument is a query.
+    pub fn apply(&mut self, arg: &str) -> Result<Option<String>, String> {
+        if let Some(name) = arg.strip_suffix('?') {
+            let def = lookup(name)?;
+            return Ok(Some(self.format(def)));
+        }
+
+        if let Some(name) = arg.strip_suffix('&') {
+            let def = lookup(name)?;
+            self.values.insert(def.name, def.default_value());
+            return Ok(None);
+        }
+
+        if let Some(idx) = arg.find(['=', ':']) {
+            let (name, op, value) = match &arg[..idx] {
+                n if n.ends_with('+') => (&n[..n.len() - 1], '+', &arg[idx + 1..]),
+                n if n.ends_with('-') => (&n[..n.len() - 1], '-', &arg[idx + 1..]),
+                n => (n, '=', &arg[idx + 1..]),
+            };
+            let def = lookup(name)?;
+            if def.kind == OptionKind::Bool {
+                return Err(format!("E474: Invalid argument: {}", arg));
+            }
+            let value = match (op, self.values.get(def.name)) {
+                ('=', _) => def.parse(value)?,
+                (op, Some(OptionValue::Number(current))) => {
+                    let delta: i64 = value
+                        .parse()
+                        .map_err(|_| format!("E521: Number required after =: {}", arg))?;
+                    let result = if op == '+' {
+                        current.checked_add(delta)
+                    } else {
+                        current.checked_sub(delta)
+                    };
+                    let result = result.ok_or_else(|| format!("E474: Invalid argument: {}", arg))?;
+                    def.parse(&result.to_string())?
+                }
+                _ => return Err(format!("E474: Invalid argument: {}", arg)),
+            };
+            self.values.insert(def.name, value);
+            return Ok(None);
+        }
+
+        // Boolean forms: opt, noopt, invopt, opt!
+        let (name, change): (&str, fn(bool) -> bool) = if let Some(name) = arg.strip_suffix('!') {
+            (name, |v| !v)
+        } else if let Some(name) = arg.strip_prefix("inv").filter(|n| find_option(n).is_some()) {
+            (name, |v| !v)
+        } else if let Some(name) = arg.strip_prefix("no").filter(|n| find_option(n).is_some()) {
+            (name, |_| false)
+        } else {
+            (arg, |_| true)
+        };
+
+        let def = lookup(name)?;
+        match self.values.get(def.name) {
+            Some(OptionValue::Bool(current)) => {
+                let value = change(*current);
+                self.values.insert(def.name, OptionValue::Bool(value));
+                Ok(None)
+            }
+            // `:set cmdheight` shows a non-boolean option like `:set cmdheight?`
+            Some(_) if name == arg => Ok(Some(self.format(def))),
+            _ => Err(format!("E474: Invalid argument: {}", arg)),
+        }
+    }
+
+    /// Format an option the way `:set opt?` shows it
+    pub fn format(&self, def: &OptionDef) -> String {
+        match self.values.get(def.name) {
+            Some(OptionValue::Bool(true)) => def.name.to_string(),
+            Some(OptionValue::Bool(false)) => format!("no{}", def.name),
+            Some(value) => format!("{}={}", def.name, value),
+            None => def.name.to_string(),
+        }
+    }
+
+    /// Options that differ from their defaults, as shown by `:set`
+    pub fn changed(&self) -> Vec<String> {
+        OPTIONS
+            .iter()
+            .filter(|def| self.values.get(def.name) != Some(&def.default_value()))
+            .map(|def| self.format(def))
+            .collect()
+    }
+
+    /// Every option, as shown by `:set all`
+    pub fn all(&self) -> Vec<String> {
+        OPTIONS.iter().map(|def| self.format(def)).collect()
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn lookup(name: &str) -> Result<&'static OptionDef, String> {
+    find_option(name).ok_or_else(|| format!("E518: Unknown option: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_satisfy_validation() {
+        let options = Options::new();
+        assert_eq!(options.get_number("cmdheight"), 1);
+        assert!(!options.get_bool("quiet"));
+        assert_eq!(options.get_str("colorscheme"), "default");
+    }
+
+    #[test]
+    fn boolean_forms() {
+        let mut options = Options::new();
+        options.apply("quiet").unwrap();
+        assert!(options.get_bool("quiet"));
+        options.apply("noquiet").unwrap();
+        assert!(!options.get_bool("quiet"));
+        options.apply("invquiet").unwrap();
+        assert!(options.get_bool("quiet"));
+        options.apply("quiet!").unwrap();
+        assert!(!options.get_bool("quiet"));
+    }
+
+    #[test]
+    fn number_forms_are_validated() {
+        let mut options = Options::new();
+        options.apply("ch=2").unwrap();
+        assert_eq!(options.get_number("cmdheight"), 2);
+        options.apply("cmdheight+=3").unwrap();
+        assert_eq!(options.get_number("cmdheight"), 5);
+        options.apply("cmdheight-=1").unwrap();
+        assert_eq!(options.get_number("cmdheight"), 4);
+
+        assert!(options.apply("cmdheight=0").is_err());
+        assert!(options.apply("cmdheight=tall").is_err());
+        assert!(options.apply("nocmdheight").is_err());
+        assert_eq!(options.get_number("cmdheight"), 4);
+    }
+
+    #[test]
+    fn overflowing_arithmetic_is_an_invalid_argument() {
+        let mut options = Options::new();
+        let maxcount = options.get_number("maxcount");
+        let err = options.apply("maxcount+=9223372036854775807").unwrap_err();
+        assert!(err.starts_with("E474"), "{}", err);
+        let err = options.apply("maxcount-=-9223372036854775807").unwrap_err();
+        assert!(err.starts_with("E474"), "{}", err);
+        assert_eq!(options.get_number("maxcount"), maxcount);
+    }
+
+    #[test]
+    fn enum_values_are_validated() {
+        let def = OptionDef {
+            name: "fillchar",
+            abbrev: None,
+            kind: OptionKind::Enum(&["dot", "space"]),
+            default: "dot",
+            description: "",
+        };
+        assert_eq!(def.parse("space"), Ok(OptionValue::String("space".to_string())));
+        assert!(def.parse("plaid").is_err());
+    }
+
+    #[test]
+    fn query_and_reset() {
+        let mut options = Options::new();
+        options.apply("verbose=3").unwrap();
+        assert_eq!(options.apply("verbose?").unwrap(), Some("verbose=3".to_string()));
+        assert_eq!(options.apply("quiet?").unwrap(), Some("noquiet".to_string()));
+        assert_eq!(options.changed(), vec!["verbose=3".to_string()]);
+
+        options.apply("verbose&").unwrap();
+        assert_eq!(options.get_number("verbose"), 0);
+        assert!(options.changed().is_empty());
+        assert_eq!(options.all().len(), OPTIONS.len());
+    }
+
+    #[test]
+    fn unknown_options_are_rejected() {
+        let mut options = Options::new();
+        assert_eq!(
+            options.apply("frobnicate"),
+            Err("E518: Unknown option: frobnicate".to_string())
+        );
+    }
+}
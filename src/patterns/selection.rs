@@ -0,0 +1,125 @@
+//! Visual selections - arbitrary areas of the map
+//!
+//! A selection runs from an anchor to a cursor, like vim's Visual mode.
+//! Characterwise selections read like text: the first row from the start
+//! rightwards, whole rows in between, and the last row up to the end.
+//! Block selections are the rectangle between the two corners.
+
+use crate::foundation::Position;
+use std::cmp::Ordering;
+
+/// Shape of a visual selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualKind {
+    /// Characterwise (v)
+    Char,
+    /// Rectangular (Ctrl-v)
+    Block,
+}
+
+/// An area selected in Visual mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selection {
+    pub kind: VisualKind,
+    /// Where the selection started
+    pub anchor: Position,
+    /// The end moved by hjkl
+    pub cursor: Position,
+}
+
+impl Selection {
+    /// A one-tile selection at `anchor`
+    pub fn new(kind: VisualKind, anchor: Position) -> Self {
+        Self {
+            kind,
+            anchor,
+            cursor: anchor,
+        }
+    }
+
+    /// Top-left and bottom-right corners of the bounding box
+    pub fn bounds(&self) -> (Position, Position) {
+        (
+            Position::new(self.anchor.x.min(self.cursor.x), self.anchor.y.min(self.cursor.y)),
+            Position::new(self.anchor.x.max(self.cursor.x), self.anchor.y.max(self.cursor.y)),
+        )
+    }
+
+    /// Width and height of the bounding box
+    pub fn size(&self) -> (u32, u32) {
+        let (min, max) = self.bounds();
+        (min.x.abs_diff(max.x) + 1, min.y.abs_diff(max.y) + 1)
+    }
+
+    /// Swap the anchor and cursor (o)
+    pub fn swap_ends(&mut self) {
+        std::mem::swap(&mut self.anchor, &mut self.cursor);
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        let (min, max) = self.bounds();
+        if position.y < min.y || position.y > max.y || position.x < min.x || position.x > max.x {
+            return false;
+        }
+        if self.kind == VisualKind::Block || min.y == max.y {
+            return true;
+        }
+
+        // Characterwise: the ends are ordered as in reading order
+        let (start, end) = match reading_order(self.anchor, self.cursor) {
+            Ordering::Greater => (self.cursor, self.anchor),
+            _ => (self.anchor, self.cursor),
+        };
+        if position.y == start.y {
+            position.x >= start.x
+        } else if position.y == end.y {
+            position.x <= end.x
+        } else {
+            true
+        }
+    }
+
+    /// Every selected position, row by row
+    pub fn positions(&self) -> Vec<Position> {
+        let (min, max) = self.bounds();
+        (min.y..=max.y)
+            .flat_map(|y| (min.x..=max.x).map(move |x| Position::new(x, y)))
+            .filter(|position| self.contains(*position))
+            .collect()
+    }
+}
+
+fn reading_order(a: Position, b: Position) -> Ordering {
+    (a.y, a.x).cmp(&(b.y, b.x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_selection_is_a_rectangle() {
+        let mut selection = Selection::new(VisualKind::Block, Position::new(2, 1));
+        selection.cursor = Position::new(0, 2);
+        assert_eq!(selection.size(), (3, 2));
+        assert_eq!(selection.positions().len(), 6);
+        assert!(selection.contains(Position::new(2, 2)));
+    }
+
+    #[test]
+    fn char_selection_reads_like_text() {
+        let mut selection = Selection::new(VisualKind::Char, Position::new(2, 0));
+        selection.cursor = Position::new(1, 2);
+        // Row 0 from x=2, row 1 whole, row 2 up to x=1
+        assert!(!selection.contains(Position::new(1, 0)));
+        assert!(selection.contains(Position::new(2, 0)));
+        assert!(selection.contains(Position::new(1, 1)));
+        assert!(selection.contains(Position::new(1, 2)));
+        assert!(!selection.contains(Position::new(2, 2)));
+
+        // Swapping the ends selects the same area
+        let before = selection.positions();
+        selection.swap_ends();
+        assert_eq!(selection.positions(), before);
+    }
+}
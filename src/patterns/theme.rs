@@ -0,0 +1,318 @@
+//! Colour schemes - glyphs and styles for everything drawn on screen
+//!
+//! A theme is a set of named highlight groups, loaded from a data file of
+//! `highlight` lines (see `data/colors/default.theme`). Each group may give
+//! glyphs, 256-colour and truecolour foreground and background, and text
+//! attributes. Map tiles combine several groups, later ones overriding.
+
+use ratatui::style::{Color, Modifier, Style};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Extension of colour scheme files
+pub const THEME_EXTENSION: &str = "theme";
+
+/// Colour schemes shipped with the game
+const BUILTIN_THEMES: &[(&str, &str)] = &[
+    ("default", include_str!("../../data/colors/default.theme")),
+    ("mono", include_str!("../../data/colors/mono.theme")),
+];
+
+/// How one highlight group draws
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Highlight {
+    /// Glyphs to draw; several form a material pattern
+    pub glyphs: Vec<char>,
+    pub ctermfg: Option<u8>,
+    pub ctermbg: Option<u8>,
+    pub guifg: Option<(u8, u8, u8)>,
+    pub guibg: Option<(u8, u8, u8)>,
+    pub modifiers: Modifier,
+}
+
+impl Highlight {
+    /// Apply `other` on top: whatever it sets wins, attributes add up
+    pub fn overlay(&mut self, other: &Highlight) {
+        if !other.glyphs.is_empty() {
+            self.glyphs = other.glyphs.clone();
+        }
+        self.ctermfg = other.ctermfg.or(self.ctermfg);
+        self.ctermbg = other.ctermbg.or(self.ctermbg);
+        self.guifg = other.guifg.or(self.guifg);
+        self.guibg = other.guibg.or(self.guibg);
+        self.modifiers |= other.modifiers;
+    }
+
+    /// The glyph for a tile at (`x`, `y`), varying across a pattern
+    pub fn glyph(&self, x: i32, y: i32, fallback: char) -> char {
+        match self.glyphs.len() {
+            0 => fallback,
+            1 => self.glyphs[0],
+            len => {
+                let hash = (x as u32).wrapping_mul(73_856_093) ^ (y as u32).wrapping_mul(19_349_663);
+                self.glyphs[(hash % len as u32) as usize]
+            }
+        }
+    }
+
+    /// Terminal style, using truecolour if the terminal supports it
+    pub fn style(&self, truecolor: bool) -> Style {
+        let color = |cterm: Option<u8>, gui: Option<(u8, u8, u8)>| match (truecolor, gui, cterm) {
+            (true, Some((r, g, b)), _) => Some(Color::Rgb(r, g, b)),
+            (_, _, Some(index)) => Some(Color::Indexed(index)),
+            (false, Some(rgb), None) => Some(Color::Indexed(rgb_to_ansi256(rgb))),
+            _ => None,
+        };
+        let mut style = Style::default().add_modifier(self.modifiers);
+        if let Some(fg) = color(self.ctermfg, self.guifg) {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = color(self.ctermbg, self.guibg) {
+            style = style.bg(bg);
+        }
+        style
+    }
+}
+
+/// Nearest colour in the xterm 256-colour palette
+pub fn rgb_to_ansi256((r, g, b): (u8, u8, u8)) -> u8 {
+    if r == g && g == b {
+        // The grey ramp runs from 8 to 238 in steps of 10
+        return match r {
+            0..=3 => 16,
+            248..=255 => 231,
+            v => 232 + ((v.saturating_sub(8)) / 10).min(23),
+        };
+    }
+    let level = |c: u8| ((u16::from(c) * 5 + 127) / 255) as u8;
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// A named colour scheme
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    groups: BTreeMap<String, Highlight>,
+}
+
+impl Theme {
+    /// Parse a colour scheme file
+    pub fn parse(name: &str, text: &str) -> Result<Theme, String> {
+        let mut groups = BTreeMap::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('"') {
+                continue;
+            }
+            let (group, highlight) =
+                parse_highlight(line).map_err(|err| format!("line {}: {}", index + 1, err))?;
+            groups.insert(group, highlight);
+        }
+        Ok(Theme {
+            name: name.to_string(),
+            groups,
+        })
+    }
+
+    /// A colour scheme shipped with the game
+    pub fn builtin(name: &str) -> Option<Theme> {
+        let (name, text) = BUILTIN_THEMES.iter().find(|(builtin, _)| *builtin == name)?;
+        Some(Theme::parse(name, text).expect("built-in colour schemes must parse"))
+    }
+
+    /// Load a colour scheme from `dir`, falling back to the built-in ones
+    pub fn load(name: &str, dir: &Path) -> Result<Theme, String> {
+        let path = dir.join(format!("{}.{}", name, THEME_EXTENSION));
+        match fs::read_to_string(&path) {
+            Ok(text) => Theme::parse(name, &text).map_err(|err| format!("{}: {}", path.display(), err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Theme::builtin(name).ok_or_else(|| format!("E185: Cannot find color scheme '{}'", name))
+            }
+            Err(err) => Err(format!("E484: Can't open file {}: {}", path.display(), err)),
+        }
+    }
+
+    /// Names of the built-in colour schemes and those in `dir`, sorted
+    pub fn available(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN_THEMES.iter().map(|(name, _)| name.to_string()).collect();
+        if let Ok(entries) = fs::read_dir(dir) {
+            names.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == THEME_EXTENSION))
+                    .filter_map(|path| path.file_stem()?.to_str().map(str::to_string)),
+            );
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn highlight(&self, group: &str) -> Option<&Highlight> {
+        self.groups.get(group)
+    }
+
+    /// The groups combined in order, later ones overriding earlier ones
+    pub fn combine(&self, groups: &[&str]) -> Highlight {
+        let mut combined = Highlight::default();
+        for group in groups {
+            if let Some(highlight) = self.groups.get(*group) {
+                combined.overlay(highlight);
+            }
+        }
+        combined
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::builtin("default").expect("the default colour scheme is built in")
+    }
+}
+
+/// The theme and what the terminal can show, handed to screen components
+#[derive(Debug, Clone, Copy)]
+pub struct Palette<'a> {
+    pub theme: &'a Theme,
+    /// Use 24-bit colour ('termguicolors')
+    pub truecolor: bool,
+}
+
+impl Palette<'_> {
+    /// Style of a single group (plain if the theme lacks it)
+    pub fn style(&self, group: &str) -> Style {
+        self.theme
+            .highlight(group)
+            .map(|highlight| highlight.style(self.truecolor))
+            .unwrap_or_default()
+    }
+}
+
+/// Parse `highlight Group key=value...`
+fn parse_highlight(line: &str) -> Result<(String, Highlight), String> {
+    let mut words = line.split_whitespace();
+    match words.next() {
+        Some("hi" | "highlight") => {}
+        _ => return Err(format!("E492: Not a highlight command: {}", line)),
+    }
+    let group = words
+        .next()
+        .ok_or_else(|| "E416: Missing highlight group".to_string())?;
+
+    let mut highlight = Highlight::default();
+    for word in words {
+        let (key, value) = word
+            .split_once('=')
+            .ok_or_else(|| format!("E416: Missing equal sign: {}", word))?;
+        match key {
+            "glyph" => highlight.glyphs = value.chars().collect(),
+            "ctermfg" => highlight.ctermfg = Some(parse_cterm(value)?),
+            "ctermbg" => highlight.ctermbg = Some(parse_cterm(value)?),
+            "guifg" => highlight.guifg = Some(parse_gui(value)?),
+            "guibg" => highlight.guibg = Some(parse_gui(value)?),
+            "attr" => {
+                for attr in value.split(',') {
+                    highlight.modifiers |= match attr {
+                        "bold" => Modifier::BOLD,
+                        "dim" => Modifier::DIM,
+                        "italic" => Modifier::ITALIC,
+                        "underline" => Modifier::UNDERLINED,
+                        "reverse" => Modifier::REVERSED,
+                        "NONE" => Modifier::empty(),
+                        _ => return Err(format!("E418: Illegal value: {}", attr)),
+                    };
+                }
+            }
+            _ => return Err(format!("E423: Illegal argument: {}", word)),
+        }
+    }
+    Ok((group.to_string(), highlight))
+}
+
+fn parse_cterm(value: &str) -> Result<u8, String> {
+    value
+        .parse()
+        .map_err(|_| format!("E421: Color name or number not recognized: {}", value))
+}
+
+fn parse_gui(value: &str) -> Result<(u8, u8, u8), String> {
+    let hex = value
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6 && hex.is_ascii())
+        .ok_or_else(|| format!("E254: Cannot allocate color {}", value))?;
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| format!("E254: Cannot allocate color {}", value))
+    };
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_themes_parse() {
+        for (name, _) in BUILTIN_THEMES {
+            assert!(Theme::builtin(name).is_some());
+        }
+        assert!(Theme::builtin("plaid").is_none());
+    }
+
+    #[test]
+    fn later_groups_override_earlier_ones() {
+        let theme = Theme::parse(
+            "test",
+            "hi Region glyph=· ctermfg=250 attr=bold\n\
+             hi Forest glyph=▒♣ ctermfg=28\n\
+             hi Damaged ctermfg=124 attr=dim\n",
+        )
+        .unwrap();
+        let forest = theme.combine(&["Region", "Forest"]);
+        assert_eq!(forest.glyphs, vec!['▒', '♣']);
+        let damaged = theme.combine(&["Region", "Forest", "Damaged"]);
+        assert_eq!(damaged.glyphs, forest.glyphs);
+        assert_eq!(damaged.ctermfg, Some(124));
+        assert_eq!(damaged.modifiers, Modifier::BOLD | Modifier::DIM);
+    }
+
+    #[test]
+    fn colour_depth_picks_the_palette() {
+        let theme = Theme::parse("test", "hi A ctermfg=28 guifg=#228b22\nhi B guifg=#ff0000").unwrap();
+        let a = theme.highlight("A").unwrap();
+        assert_eq!(a.style(true).fg, Some(Color::Rgb(0x22, 0x8b, 0x22)));
+        assert_eq!(a.style(false).fg, Some(Color::Indexed(28)));
+        // Without a cterm colour the truecolour one is approximated
+        assert_eq!(theme.highlight("B").unwrap().style(false).fg, Some(Color::Indexed(196)));
+    }
+
+    #[test]
+    fn mono_theme_has_no_colours() {
+        let mono = Theme::builtin("mono").unwrap();
+        for group in ["Player", "Forest", "Damaged", "ErrorMsg"] {
+            let style = mono.combine(&[group]).style(true);
+            assert_eq!((style.fg, style.bg), (None, None), "{} is coloured", group);
+        }
+    }
+
+    #[test]
+    fn patterns_vary_but_are_stable() {
+        let highlight = Highlight {
+            glyphs: vec!['▒', '♣'],
+            ..Highlight::default()
+        };
+        let glyphs: Vec<char> = (0..8).map(|x| highlight.glyph(x, 3, ' ')).collect();
+        assert!(glyphs.contains(&'▒') && glyphs.contains(&'♣'));
+        assert_eq!(highlight.glyph(5, -2, ' '), highlight.glyph(5, -2, ' '));
+    }
+
+    #[test]
+    fn bad_lines_report_their_number() {
+        let err = Theme::parse("bad", "\" comment\nhi Player ctermfg=red").unwrap_err();
+        assert_eq!(err, "line 2: E421: Color name or number not recognized: red");
+        assert!(Theme::parse("bad", "hi Player guifg=#12").is_err());
+        assert!(Theme::parse("bad", "hi Player shape=round").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! View-models - what each part of the screen shows
+//!
+//! The Loom builds these from the live session; the screen components in
+//! `widgets` draw them without ever touching the Tapestry. Fields the game
+//! does not track yet are `None` and left off the screen.
+
+use super::messages::Message;
+use crate::foundation::Position;
+use crate::threads::{Biome, WeatherSystem};
+
+/// The status bar across the top of the screen
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatusView {
+    pub name: String,
+    /// Current and maximum hit points
+    pub hp: Option<(u32, u32)>,
+    pub level: Option<u32>,
+    /// How the player's vitals have run down ("Tired", "Hungry")
+    pub conditions: Vec<&'static str>,
+    /// Whether the player holds a weapon
+    pub armed: Option<bool>,
+    /// Whether anything nearby is hostile
+    pub hostile: Option<bool>,
+    /// Percentage of the map explored
+    pub explored: Option<u8>,
+    /// Day, season and year ("Spring 1, Year 1")
+    pub date: Option<String>,
+    /// Clock and name of the time of day ("22:00 Night ☾")
+    pub time_of_day: Option<String>,
+}
+
+/// The message line under the status bar
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageLineView {
+    pub message: Option<Message>,
+    /// 1-based position of the message in the log
+    pub index: usize,
+    pub total: usize,
+}
+
+/// The map-name header above the map (`dungeon.lvl3`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapHeaderView {
+    pub name: String,
+    /// Tiles per map cell along each side
+    pub zoom: u8,
+    /// Percentage of the region underfoot explored
+    pub explored: Option<u8>,
+}
+
+/// The kind of Thread occupying one map cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Empty,
+    Player,
+    Region,
+    /// A region the player wrote notes about
+    AnnotatedRegion,
+    Npc,
+    /// Something lying on the ground to be taken
+    Item,
+}
+
+/// How much the player knows of a map cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// In sight now
+    Visible,
+    /// Seen before; only the land is shown
+    Remembered,
+    /// Never seen
+    Unknown,
+}
+
+/// One map cell: what is there, what it looks like, and whether it is
+/// selected or under the look cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapCell {
+    pub position: Position,
+    pub tile: Tile,
+    pub biome: Option<Biome>,
+    /// The strongest state obvious at a glance
+    pub state: Option<&'static str>,
+    pub visibility: Visibility,
+    pub highlighted: bool,
+    /// Weather seen over the cell, drawn as a tint
+    pub weather: Option<WeatherSystem>,
+}
+
+/// The visible part of the map, row by row
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MapView {
+    pub rows: Vec<Vec<MapCell>>,
+}
+
+/// The mode line under the map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModeLineView {
+    pub mode: String,
+    pub pending: String,
+    /// World coordinates of the cursor
+    pub position: Option<Position>,
+}
+
+/// Messages and the command line below the frame
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandAreaView {
+    /// Earlier messages, oldest first
+    pub messages: Vec<Message>,
+    pub command_line: Option<String>,
+    /// Column of the terminal cursor on the command line
+    pub cursor: Option<usize>,
+}
+
+/// Something marked on the atlas and minimap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    Player,
+    /// The travel destination chosen on the atlas
+    Destination,
+    /// A location mark (`m;`)
+    Mark(char),
+    /// An NPC standing on discovered ground
+    Npc,
+    /// Where a story still unfolding has claimed the land
+    Story,
+    /// Where someone the chronicle remembers died
+    Grave,
+}
+
+/// One atlas cell: a square of tiles collapsed to its biome
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasCell {
+    /// Top-left tile of the square
+    pub position: Position,
+    /// False until any tile in the square has been seen
+    pub discovered: bool,
+    /// Most common biome among the discovered regions
+    pub biome: Option<Biome>,
+    pub marker: Option<Marker>,
+    /// Under the atlas cursor
+    pub highlighted: bool,
+}
+
+/// The atlas or minimap, row by row
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AtlasView {
+    pub rows: Vec<Vec<AtlasCell>>,
+    /// Tiles per cell along each side
+    pub scale: u8,
+}
@@ -0,0 +1,398 @@
+//! Screen components
+//!
+//! Each function draws one part of the screen from its view-model. None of
+//! them look at the Tapestry; `display` lays them out and feeds them.
+
+use super::cmdline::CommandHistory;
+use super::help::{self, HelpIndex, HelpSegment, HelpView};
+use super::messages::{Message, Severity};
+use super::theme::Palette;
+use super::view::{
+    AtlasCell, AtlasView, CommandAreaView, MapCell, MapHeaderView, MapView, Marker,
+    MessageLineView, ModeLineView, StatusView, Tile, Visibility,
+};
+use crate::foundation::Position;
+use crate::loom::ThreadReport;
+use crate::threads::WeatherSystem;
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+/// One row with `left` flush left and `right` flush right
+fn spread(mut left: Vec<Span<'static>>, right: Vec<Span<'static>>, width: u16) -> Line<'static> {
+    let used: usize = left.iter().chain(&right).map(Span::width).sum();
+    let gap = usize::from(width).saturating_sub(used);
+    left.push(Span::raw(" ".repeat(gap)));
+    left.extend(right);
+    Line::from(left)
+}
+
+/// Horizontal rule across the frame, with an optional title (`├─ name ──┤`)
+pub fn separator(title: Option<&str>, width: u16) -> Paragraph<'static> {
+    let inner = usize::from(width.saturating_sub(2));
+    let mut rule = match title {
+        Some(title) => format!("─ {} ", title),
+        None => String::new(),
+    };
+    let used = rule.chars().count();
+    rule.push_str(&"─".repeat(inner.saturating_sub(used)));
+    let rule: String = rule.chars().take(inner).collect();
+    Paragraph::new(format!("├{}┤", rule))
+}
+
+/// Name, vitals and surroundings of the player
+pub fn status_bar(view: &StatusView, width: u16, palette: &Palette) -> Paragraph<'static> {
+    let mut left = vec![
+        Span::raw(" ▌ "),
+        Span::styled(view.name.clone(), Style::default().add_modifier(Modifier::BOLD)),
+    ];
+    if let Some((hp, max)) = view.hp {
+        left.push(Span::styled(format!("    ♥ {:>3}/{}", hp, max), palette.style("StatusHP")));
+    }
+    if let Some(level) = view.level {
+        left.push(Span::styled(format!("  ⚡ Lv{}", level), palette.style("StatusLevel")));
+    }
+    for condition in &view.conditions {
+        left.push(Span::styled(format!("  {}", condition), palette.style("StatusCondition")));
+    }
+    if let Some(armed) = view.armed {
+        left.push(Span::raw(if armed { "  ⚔ Armed" } else { "  ⚔ Unarmed" }));
+    }
+
+    // Each detail with how long it holds on as the bar narrows: the date
+    // goes first, then the exploration, then the time of day
+    let mut right: Vec<(u8, Span<'static>)> = Vec::new();
+    if view.hostile == Some(true) {
+        right.push((3, Span::styled("⚠ Hostile   ", palette.style("Hostile"))));
+    }
+    if let Some(explored) = view.explored {
+        right.push((1, Span::raw(format!("◐ {}% Explored   ", explored))));
+    }
+    if let Some(date) = &view.date {
+        right.push((0, Span::raw(format!("{}   ", date))));
+    }
+    if let Some(time) = &view.time_of_day {
+        right.push((2, Span::raw(format!("{}   ", time))));
+    }
+    // On narrow screens the details go, least important first, keeping
+    // clear of the left side, then the vitals, leaving the name
+    let used = |spans: &[Span]| spans.iter().map(Span::width).sum::<usize>();
+    let edge = Span::raw("▌");
+    let details = |right: &[(u8, Span)]| right.iter().map(|(_, span)| span.width()).sum::<usize>();
+    while !right.is_empty() && used(&left) + details(&right) + edge.width() + 3 > usize::from(width) {
+        let least = (0..right.len()).min_by_key(|&i| right[i].0).unwrap_or(0);
+        right.remove(least);
+    }
+    let mut right: Vec<Span<'static>> = right.into_iter().map(|(_, span)| span).collect();
+    right.push(edge);
+    while left.len() > 2 && used(&left) + used(&right) > usize::from(width) {
+        left.pop();
+    }
+
+    Paragraph::new(spread(left, right, width))
+}
+
+fn severity_style(severity: Severity, palette: &Palette) -> Style {
+    match severity {
+        Severity::Info => Style::default(),
+        Severity::Warning => palette.style("WarningMsg"),
+        Severity::Error => palette.style("ErrorMsg"),
+        Severity::Success => palette.style("MoreMsg"),
+        Severity::Debug => palette.style("Comment"),
+    }
+}
+
+/// The newest message and its `[n/N]` counter
+pub fn message_line(view: &MessageLineView, width: u16, palette: &Palette) -> Paragraph<'static> {
+    let Some(message) = &view.message else {
+        return Paragraph::new("");
+    };
+    let counter = format!(" [{}/{}] ", view.index, view.total);
+    let room = usize::from(width).saturating_sub(counter.chars().count() + 3);
+    let text: String = message.text.chars().take(room).collect();
+    let left = vec![
+        Span::raw(" » "),
+        Span::styled(text, severity_style(message.severity, palette)),
+    ];
+    Paragraph::new(spread(left, vec![Span::raw(counter)], width))
+}
+
+/// Header rule naming the current map, the scale when zoomed out, and how
+/// much of the region has been explored
+pub fn map_header(view: &MapHeaderView, width: u16) -> Paragraph<'static> {
+    let mut title = view.name.clone();
+    if view.zoom > 1 {
+        title.push_str(&format!(" 1:{}", view.zoom));
+    }
+    if let Some(explored) = view.explored {
+        title.push_str(&format!(" ◐ {}%", explored));
+    }
+    separator(Some(&title), width)
+}
+
+/// Highlight groups for a map cell, in the order they are combined: the
+/// Thread kind, its biome, its strongest obvious state, annotations, the
+/// weather over it, then Remembered for land out of sight
+fn cell_groups(cell: &MapCell) -> Vec<&'static str> {
+    if cell.visibility == Visibility::Unknown {
+        return Vec::new();
+    }
+    let weather = cell.weather.map(weather_group);
+    let mut groups = vec![match cell.tile {
+        Tile::Empty => return weather.into_iter().collect(),
+        Tile::Player => return ["Player"].into_iter().chain(weather).collect(),
+        Tile::Npc => "Npc",
+        Tile::Item => "Item",
+        Tile::Region | Tile::AnnotatedRegion => "Region",
+    }];
+    groups.extend(cell.biome.map(|biome| biome.name()));
+    groups.extend(cell.state);
+    if cell.tile == Tile::AnnotatedRegion {
+        groups.push("Annotated");
+    }
+    groups.extend(weather);
+    if cell.visibility == Visibility::Remembered {
+        groups.push("Remembered");
+    }
+    groups
+}
+
+/// Highlight group tinting the cells under a weather system
+fn weather_group(system: WeatherSystem) -> &'static str {
+    match system {
+        WeatherSystem::Thunderstorm => "Thunderstorm",
+        WeatherSystem::GentleRain => "GentleRain",
+        WeatherSystem::Blizzard => "Blizzard",
+        WeatherSystem::MagicalAurora => "Aurora",
+    }
+}
+
+/// The map, textured by the colour scheme, with selected cells and the
+/// look cursor in the Visual style
+pub fn map(view: &MapView, palette: &Palette) -> Paragraph<'static> {
+    let lines: Vec<Line> = view
+        .rows
+        .iter()
+        .map(|row| {
+            let spans: Vec<Span> = row
+                .iter()
+                .map(|cell| {
+                    let mut groups = cell_groups(cell);
+                    if cell.highlighted {
+                        groups.push("Visual");
+                    }
+                    let highlight = palette.theme.combine(&groups);
+                    let glyph = highlight.glyph(cell.position.x, cell.position.y, ' ');
+                    Span::styled(glyph.to_string(), highlight.style(palette.truecolor))
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+    Paragraph::new(lines)
+}
+
+/// Highlight groups for an atlas cell: its biome, then any marker on it
+fn atlas_groups(cell: &AtlasCell) -> Vec<&'static str> {
+    let mut groups = Vec::new();
+    if let Some(biome) = cell.biome {
+        groups.extend(["Region", biome.name()]);
+    }
+    groups.extend(cell.marker.map(|marker| match marker {
+        Marker::Player => "Player",
+        Marker::Destination => "AtlasDestination",
+        Marker::Mark(_) => "AtlasMark",
+        Marker::Npc => "Npc",
+        Marker::Story => "AtlasStory",
+        Marker::Grave => "AtlasGrave",
+    }));
+    groups
+}
+
+/// The atlas: discovered ground in biome colours, with markers
+pub fn atlas(view: &AtlasView, palette: &Palette) -> Paragraph<'static> {
+    let lines: Vec<Line> = view
+        .rows
+        .iter()
+        .map(|row| {
+            let spans: Vec<Span> = row
+                .iter()
+                .map(|cell| {
+                    let mut groups = if cell.discovered || cell.marker.is_some() {
+                        atlas_groups(cell)
+                    } else {
+                        Vec::new()
+                    };
+                    if cell.highlighted {
+                        groups.push("Visual");
+                    }
+                    let highlight = palette.theme.combine(&groups);
+                    let glyph = match cell.marker {
+                        Some(Marker::Mark(key)) => key,
+                        _ => highlight.glyph(cell.position.x, cell.position.y, ' '),
+                    };
+                    Span::styled(glyph.to_string(), highlight.style(palette.truecolor))
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+    Paragraph::new(lines)
+}
+
+/// The minimap in a box of its own
+pub fn minimap(view: &AtlasView, palette: &Palette) -> Paragraph<'static> {
+    atlas(view, palette).block(Block::default().borders(Borders::ALL).title(format!("1:{}", view.scale)))
+}
+
+/// Mode name on the left, pending keys and coordinates on the right
+pub fn mode_line(view: &ModeLineView, width: u16) -> Paragraph<'static> {
+    let mut right = view.pending.clone();
+    if let Some(position) = view.position {
+        if !right.is_empty() {
+            right.push_str("  ");
+        }
+        right.push_str(&format!("[{},{}]", position.x, position.y));
+    }
+    right.push(' ');
+    let left = vec![Span::raw(format!(" {}", view.mode))];
+    Paragraph::new(spread(left, vec![Span::raw(right)], width))
+}
+
+/// Earlier messages, then the command line being typed
+pub fn command_area(view: &CommandAreaView, palette: &Palette) -> Paragraph<'static> {
+    let mut lines: Vec<Line> = view
+        .messages
+        .iter()
+        .map(|Message { severity, text }| {
+            Line::from(Span::styled(
+                format!("{} {}", severity.icon(), text),
+                severity_style(*severity, palette),
+            ))
+        })
+        .collect();
+    if let Some(command_line) = &view.command_line {
+        lines.push(Line::from(command_line.clone()));
+    }
+    Paragraph::new(lines)
+}
+
+/// The command-line window listing history, selected line highlighted
+pub fn cmd_window(history: &CommandHistory, selected: usize, area: Rect) -> Paragraph<'static> {
+    let visible = area.height.saturating_sub(2) as usize;
+    let first = (selected + 1).saturating_sub(visible);
+    let lines: Vec<Line> = history
+        .entries()
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(visible)
+        .map(|(index, entry)| {
+            let style = if index == selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            Line::from(Span::styled(format!(":{}", entry), style))
+        })
+        .collect();
+
+    Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("[Command Line]"))
+}
+
+/// The help viewer, keeping the cursor line centred
+///
+/// Tag markers are hidden; tags are highlighted and links underlined, with
+/// the selected link on the cursor line reversed.
+pub fn help(index: &HelpIndex, view: &HelpView, area: Rect, palette: &Palette) -> Paragraph<'static> {
+    let Some(page) = index.page(view.page) else {
+        return Paragraph::new("");
+    };
+    let visible = area.height.saturating_sub(2) as usize;
+    let first = view
+        .cursor
+        .saturating_sub(visible / 2)
+        .min(page.lines.len().saturating_sub(visible));
+
+    let tag_style = palette.style("HelpTag");
+    let link_style = palette.style("HelpLink");
+
+    let lines: Vec<Line> = page
+        .lines
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(visible)
+        .map(|(line_index, text)| {
+            let on_cursor = line_index == view.cursor;
+            let mut link = 0;
+            let mut spans = vec![Span::raw(if on_cursor { ">" } else { " " })];
+            for segment in help::parse_line(text) {
+                spans.push(match segment {
+                    HelpSegment::Text(text) => Span::raw(text),
+                    HelpSegment::Tag(tag) => Span::styled(tag, tag_style),
+                    HelpSegment::Link(tag) => {
+                        let selected = on_cursor && link == view.link;
+                        link += 1;
+                        let style = if selected {
+                            link_style.add_modifier(Modifier::REVERSED)
+                        } else {
+                            link_style
+                        };
+                        Span::styled(tag, style)
+                    }
+                });
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    let title = format!("help: {}", page.topic);
+    Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title))
+}
+
+/// The look panel: every Thread on the tile and what is perceived of it
+pub fn look_panel(reports: &[ThreadReport], cursor: Position) -> Paragraph<'static> {
+    let mut lines = Vec::new();
+    if reports.is_empty() {
+        lines.push(Line::from(" Nothing here"));
+    }
+    for report in reports {
+        let kind = [Some(report.kind), report.activity, report.attitude]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(", ");
+        let kind = format!(" ({})", kind);
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {}", report.name), Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(kind),
+        ]));
+        match &report.stats {
+            Some(stats) => {
+                for row in stats.chunks(3) {
+                    let cells: Vec<String> = row
+                        .iter()
+                        .map(|(stat, value)| format!("{} {:>2}", stat.abbrev(), value))
+                        .collect();
+                    lines.push(Line::from(format!("   {}", cells.join("  "))));
+                }
+            }
+            None => lines.push(Line::from("   You notice nothing more")),
+        }
+        for (state, value) in &report.states {
+            lines.push(Line::from(format!("   {} {:.0}%", state, value * 100.0)));
+        }
+        for note in &report.notes {
+            lines.push(Line::from(format!("   ¶ {}", note)));
+        }
+    }
+
+    let title = format!("Look [{},{}]", cursor.x, cursor.y);
+    Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title(title))
+}
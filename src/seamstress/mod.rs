@@ -3,7 +3,199 @@
 //! This bounded context handles saving and loading game state,
 //! preserving the weaving across character deaths and play sessions.
 //!
-//! For the initial MVP, this is stubbed - we'll focus on in-memory state first.
+//! Save files are JSON documents in the save directory, one per name.
+//!
+//! The world does not stand still while a save lies unloaded: every
+//! 'worldyear' hours away, a week unless set, is a year of world time.
+
+use crate::tapestry::exploration::Exploration;
+use crate::tapestry::Tapestry;
+use crate::threads::ThreadId;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Format version written to every save; older or newer files are refused
+pub const SAVE_VERSION: u32 = 1;
+
+/// Extension of save files in the save directory
+pub const SAVE_EXTENSION: &str = "json";
+
+/// Seconds in an hour of real time
+pub const SECONDS_PER_HOUR: u64 = 60 * 60;
+
+/// Everything needed to resume a game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    pub player_id: ThreadId,
+    pub tapestry: Tapestry,
+    /// What the player's lineage has seen; saves from before it was kept
+    /// remember nothing
+    #[serde(default)]
+    pub exploration: Exploration,
+    /// When it was written, in seconds since the Unix epoch; 0 for saves
+    /// from before it was kept
+    #[serde(default)]
+    pub saved_at: u64,
+}
+
+impl SaveGame {
+    pub fn new(tapestry: Tapestry, player_id: ThreadId, exploration: Exploration) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            player_id,
+            tapestry,
+            exploration,
+            saved_at: now(),
+        }
+    }
+
+    /// Whole years of world time that passed while the save lay unloaded,
+    /// a year for every `hours_per_year` real hours; none if that is 0
+    pub fn years_away(&self, hours_per_year: u64) -> u64 {
+        match (self.saved_at, hours_per_year) {
+            (0, _) | (_, 0) => 0,
+            (saved_at, hours) => now().saturating_sub(saved_at) / (hours * SECONDS_PER_HOUR),
+        }
+    }
+}
+
+/// Seconds since the Unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Path of the save called `name`
+///
+/// Names containing a path separator or the save extension are used as
+/// paths; anything else is a file in `dir`.
+pub fn save_path(dir: &Path, name: &str) -> PathBuf {
+    let suffix = format!(".{}", SAVE_EXTENSION);
+    if name.contains(std::path::MAIN_SEPARATOR) || name.ends_with(&suffix) {
+        PathBuf::from(name)
+    } else {
+        dir.join(format!("{}{}", name, suffix))
+    }
+}
+
+/// Write a save file, creating its directory if needed
+pub fn save(path: &Path, game: &SaveGame) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(game).map_err(io::Error::other)?;
+    fs::write(path, json)
+}
+
+/// Read a save file
+pub fn load(path: &Path) -> io::Result<SaveGame> {
+    let json = fs::read_to_string(path)?;
+    let game: SaveGame =
+        serde_json::from_str(&json).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    if game.version != SAVE_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported save version {}", game.version),
+        ));
+    }
+    Ok(game)
+}
+
+/// Names of the saves in `dir`, sorted
+pub fn list_saves(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == SAVE_EXTENSION))
+        .filter_map(|path| path.file_stem()?.to_str().map(str::to_string))
+        .collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::foundation::Position;
+    use crate::threads::{Thread, ThreadKind};
+    use crate::weaver::{properties::ThreadProperties, states::ThreadStates};
+
+    #[test]
+    fn names_notes_exploration_and_the_date_survive_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("vitalis-saves-{}", std::process::id()));
+        let mut tapestry = Tapestry::new();
+        let player_id = tapestry.next_id();
+        tapestry.add_thread(Thread {
+            id: player_id,
+            kind: ThreadKind::Player {
+                name: "Wanderer".to_string(),
+            },
+            properties: ThreadProperties::default(),
+            states: ThreadStates::default(),
+            position: Some(Position::new(3, -2)),
+            name: Some("Ysolde".to_string()),
+            notes: vec!["Met a hermit".to_string()],
+        });
+
+        let mut exploration = Exploration::new();
+        exploration.record(&tapestry, &[Position::new(3, -2)].into());
+        for _ in 0..125 {
+            tapestry.pass_turn();
+        }
+        let date = tapestry.date();
+
+        let path = save_path(&dir, "first");
+        save(&path, &SaveGame::new(tapestry, player_id, exploration)).unwrap();
+        assert_eq!(list_saves(&dir), vec!["first".to_string()]);
+
+        let game = load(&path).unwrap();
+        let player = game.tapestry.get_thread(game.player_id).unwrap();
+        assert_eq!(player.display_name(), "Ysolde");
+        assert_eq!(player.notes, vec!["Met a hermit".to_string()]);
+        assert_eq!(player.position, Some(Position::new(3, -2)));
+        assert!(game.exploration.is_seen(Position::new(3, -2)));
+        assert_eq!(game.tapestry.date(), date);
+        assert_eq!(date.clock(), "20:30");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn saves_without_exploration_remember_nothing() {
+        let game = SaveGame::new(Tapestry::new(), ThreadId(1), Exploration::new());
+        let mut json: serde_json::Value = serde_json::to_value(&game).unwrap();
+        json.as_object_mut().unwrap().remove("exploration");
+        let game: SaveGame = serde_json::from_value(json).unwrap();
+        assert!(game.exploration.is_empty());
+    }
+
+    #[test]
+    fn time_away_passes_in_world_years() {
+        let mut game = SaveGame::new(Tapestry::new(), ThreadId(1), Exploration::new());
+        assert_eq!(game.years_away(168), 0);
+        // A weekend away is no time at all at a week to the year
+        game.saved_at -= 3 * 24 * SECONDS_PER_HOUR + 60;
+        assert_eq!(game.years_away(168), 0);
+        assert_eq!(game.years_away(24), 3);
+        // At 0 the world waits however long you are gone
+        assert_eq!(game.years_away(0), 0);
+        // Nothing is known of saves from before the time was kept
+        game.saved_at = 0;
+        assert_eq!(game.years_away(24), 0);
+    }
 
-// Future: Save/load implementation using serde
-// Future: Cross-generational memory preservation
+    #[test]
+    fn corrupt_save_is_invalid_data() {
+        let path = std::env::temp_dir().join(format!("vitalis-corrupt-{}.json", std::process::id()));
+        fs::write(&path, "not a save").unwrap();
+        assert_eq!(load(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+//! Calendar - hours, days, seasons and years counted in turns
+//!
+//! The world keeps time by the Tapestry's turn counter: [`TURNS_PER_HOUR`]
+//! turns make an hour, and the world is woven at [`START_HOUR`] on the first
+//! day of Spring in year 1. Years are short, four seasons of
+//! [`DAYS_PER_SEASON`] days, so a lineage sees decades pass.
+//!
+//! The time of day sets how much light there is to see by and what NPCs are
+//! about; the season sets which weather the land breeds.
+
+use super::Tapestry;
+use crate::threads::{Thread, ThreadKind, WeatherSystem};
+use std::fmt;
+
+/// Turns in an hour of world time
+pub const TURNS_PER_HOUR: u64 = 10;
+
+pub const HOURS_PER_DAY: u64 = 24;
+
+pub const DAYS_PER_SEASON: u64 = 30;
+
+pub const DAYS_PER_YEAR: u64 = DAYS_PER_SEASON * Season::ALL.len() as u64;
+
+/// Turns in a year of world time
+pub const TURNS_PER_YEAR: u64 = TURNS_PER_HOUR * HOURS_PER_DAY * DAYS_PER_YEAR;
+
+/// The hour of the first turn
+pub const START_HOUR: u64 = 8;
+
+/// A season of the year
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub const ALL: [Season; 4] = [Season::Spring, Season::Summer, Season::Autumn, Season::Winter];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Season::Spring => "Spring",
+            Season::Summer => "Summer",
+            Season::Autumn => "Autumn",
+            Season::Winter => "Winter",
+        }
+    }
+
+    /// How readily the season breeds `system`, relative to the others it
+    /// breeds; 0 never
+    pub fn weather_weight(self, system: WeatherSystem) -> u32 {
+        use WeatherSystem::*;
+        match (self, system) {
+            (Season::Summer, Thunderstorm) => 3,
+            (Season::Summer, Blizzard) => 0,
+            (Season::Winter, Thunderstorm) => 0,
+            (Season::Winter, Blizzard) => 3,
+            (Season::Winter, MagicalAurora) => 2,
+            (Season::Spring | Season::Autumn, GentleRain) => 3,
+            _ => 1,
+        }
+    }
+}
+
+/// The part of the day, by the light there is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeOfDay {
+    /// 05:00 to 07:00
+    Dawn,
+    /// 07:00 to 18:00
+    Day,
+    /// 18:00 to 20:00
+    Dusk,
+    Night,
+}
+
+impl TimeOfDay {
+    pub fn of_hour(hour: u64) -> Self {
+        match hour {
+            5..=6 => TimeOfDay::Dawn,
+            7..=17 => TimeOfDay::Day,
+            18..=19 => TimeOfDay::Dusk,
+            _ => TimeOfDay::Night,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TimeOfDay::Dawn => "Dawn",
+            TimeOfDay::Day => "Day",
+            TimeOfDay::Dusk => "Dusk",
+            TimeOfDay::Night => "Night",
+        }
+    }
+
+    /// Symbol shown beside the name on the status bar
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TimeOfDay::Dawn => "◓",
+            TimeOfDay::Day => "☀",
+            TimeOfDay::Dusk => "◒",
+            TimeOfDay::Night => "☾",
+        }
+    }
+
+    /// Light to see by, from 0 (pitch dark) to 1 (full day)
+    pub fn daylight(self) -> f32 {
+        match self {
+            TimeOfDay::Day => 1.0,
+            TimeOfDay::Dawn | TimeOfDay::Dusk => 0.75,
+            TimeOfDay::Night => 0.5,
+        }
+    }
+}
+
+/// A moment of world time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    /// Counted from 1
+    pub year: u64,
+    pub season: Season,
+    /// Day of the season, counted from 1
+    pub day: u64,
+    pub hour: u64,
+    pub minute: u64,
+}
+
+impl Date {
+    /// The date `turn` turns after the world was woven
+    pub fn of(turn: u64) -> Self {
+        let hours = turn / TURNS_PER_HOUR + START_HOUR;
+        let days = hours / HOURS_PER_DAY;
+        let day_of_year = days % DAYS_PER_YEAR;
+        Self {
+            year: days / DAYS_PER_YEAR + 1,
+            season: Season::ALL[(day_of_year / DAYS_PER_SEASON) as usize],
+            day: day_of_year % DAYS_PER_SEASON + 1,
+            hour: hours % HOURS_PER_DAY,
+            minute: turn % TURNS_PER_HOUR * (60 / TURNS_PER_HOUR),
+        }
+    }
+
+    pub fn time_of_day(&self) -> TimeOfDay {
+        TimeOfDay::of_hour(self.hour)
+    }
+
+    /// The hour and minute, `08:00`
+    pub fn clock(&self) -> String {
+        format!("{:02}:{:02}", self.hour, self.minute)
+    }
+
+    /// The day, season and year, `Spring 1, Year 1`
+    pub fn day_name(&self) -> String {
+        format!("{} {}, Year {}", self.season.name(), self.day, self.year)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} on day {} of {}, year {}", self.clock(), self.day, self.season.name(), self.year)
+    }
+}
+
+/// What an NPC is about at a time of day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Working,
+    Wandering,
+    Sleeping,
+    /// Abroad in the dark, looking for trouble
+    Prowling,
+}
+
+impl Activity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Activity::Working => "working",
+            Activity::Wandering => "wandering",
+            Activity::Sleeping => "asleep",
+            Activity::Prowling => "prowling",
+        }
+    }
+
+    /// What the activity adds to the NPC's Dexterity against being
+    /// noticed: the sleeping hide nothing, prowlers keep to the shadows
+    pub fn concealment(self) -> i32 {
+        match self {
+            Activity::Sleeping => -4,
+            Activity::Prowling => 2,
+            Activity::Working | Activity::Wandering => 0,
+        }
+    }
+}
+
+/// An NPC's schedule: honest folk work by day and sleep at night; hostile
+/// ones sleep the day away and prowl after dark
+pub fn schedule(hour: u64, hostile: bool) -> Activity {
+    match (hostile, TimeOfDay::of_hour(hour)) {
+        (false, _) if !(6..22).contains(&hour) => Activity::Sleeping,
+        (false, _) if (8..18).contains(&hour) => Activity::Working,
+        (false, _) => Activity::Wandering,
+        (true, TimeOfDay::Day) => Activity::Sleeping,
+        (true, TimeOfDay::Dusk | TimeOfDay::Night) => Activity::Prowling,
+        (true, TimeOfDay::Dawn) => Activity::Wandering,
+    }
+}
+
+/// What `thread` is about now, if it is an NPC
+pub fn activity(tapestry: &Tapestry, thread: &Thread) -> Option<Activity> {
+    matches!(thread.kind, ThreadKind::Npc { .. }).then(|| schedule(tapestry.date().hour, thread.is_hostile()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TURNS_PER_DAY: u64 = TURNS_PER_HOUR * HOURS_PER_DAY;
+
+    #[test]
+    fn turns_count_hours_days_seasons_and_years() {
+        let start = Date::of(0);
+        assert_eq!((start.year, start.season, start.day), (1, Season::Spring, 1));
+        assert_eq!(start.clock(), "08:00");
+        assert_eq!(start.to_string(), "08:00 on day 1 of Spring, year 1");
+        assert_eq!(Date::of(TURNS_PER_HOUR + 5).clock(), "09:30");
+
+        let midnight = Date::of((HOURS_PER_DAY - START_HOUR) * TURNS_PER_HOUR);
+        assert_eq!((midnight.day, midnight.clock()), (2, "00:00".to_string()));
+        let summer = Date::of(DAYS_PER_SEASON * TURNS_PER_DAY);
+        assert_eq!(summer.day_name(), "Summer 1, Year 1");
+        let next_year = Date::of(TURNS_PER_YEAR + 3 * DAYS_PER_SEASON * TURNS_PER_DAY);
+        assert_eq!(next_year.day_name(), "Winter 1, Year 2");
+    }
+
+    #[test]
+    fn darkness_falls_at_night() {
+        let hours: Vec<TimeOfDay> = [4, 5, 12, 19, 23].into_iter().map(TimeOfDay::of_hour).collect();
+        assert_eq!(
+            hours,
+            [TimeOfDay::Night, TimeOfDay::Dawn, TimeOfDay::Day, TimeOfDay::Dusk, TimeOfDay::Night]
+        );
+        assert!(TimeOfDay::Night.daylight() < TimeOfDay::Dusk.daylight());
+        assert!(TimeOfDay::Dusk.daylight() < TimeOfDay::Day.daylight());
+    }
+
+    #[test]
+    fn hostiles_keep_the_opposite_hours() {
+        assert_eq!(schedule(12, false), Activity::Working);
+        assert_eq!(schedule(2, false), Activity::Sleeping);
+        assert_eq!(schedule(20, false), Activity::Wandering);
+        assert_eq!(schedule(12, true), Activity::Sleeping);
+        assert_eq!(schedule(2, true), Activity::Prowling);
+    }
+
+    #[test]
+    fn seasons_favour_their_weather() {
+        assert_eq!(Season::Summer.weather_weight(WeatherSystem::Blizzard), 0);
+        assert!(
+            Season::Winter.weather_weight(WeatherSystem::Blizzard)
+                > Season::Winter.weather_weight(WeatherSystem::GentleRain)
+        );
+        assert!(
+            Season::Summer.weather_weight(WeatherSystem::Thunderstorm)
+                > Season::Spring.weather_weight(WeatherSystem::Thunderstorm)
+        );
+    }
+}
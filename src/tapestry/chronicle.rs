@@ -0,0 +1,26 @@
+//! Chronicle - what the world remembers happening
+//!
+//! The Tapestry keeps a chronicle of the events that outlive the characters
+//! who saw them, each dated by the turn it was written. It is saved with
+//! the world, so a lineage can read what befell it.
+
+use super::calendar::Date;
+use crate::foundation::Position;
+use serde::{Deserialize, Serialize};
+
+/// One event the world remembers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// The turn it happened on
+    pub turn: u64,
+    pub text: String,
+    /// Where it happened, if anywhere in particular
+    #[serde(default)]
+    pub place: Option<Position>,
+}
+
+impl Entry {
+    pub fn date(&self) -> Date {
+        Date::of(self.turn)
+    }
+}
@@ -0,0 +1,582 @@
+//! Combat - Threads fighting it out through The Weaver
+//!
+//! Every blow is a contest. The style of fighting picks the stats: a
+//! forceful blow sets Strength against the defender's Constitution, a deft
+//! one Dexterity against their Dexterity. The weapon carried adds to the
+//! roll when it suits the style, and to the harm done. Relationships weigh
+//! in as in any contest, and so does morale: a Stressed fighter rolls
+//! worse.
+//!
+//! Harm is kept as the Damaged state. A Thread's hit points are the health
+//! its Constitution and level give it less the share of it Damaged, and a
+//! Thread with none left falls. Blows taken and blows missed wear down
+//! morale; an NPC whose morale breaks flees if it can get away and yields
+//! if it cannot. A blow struck makes an enemy of the one struck.
+
+use super::calendar::{self, Activity};
+use super::pathfinding::{distances, Terrain};
+use super::relationships::{Stance, HOSTILE_TRUST, STRONG};
+use super::vitals::{self, Death};
+use super::Tapestry;
+use crate::foundation::Position;
+use crate::threads::{Thread, ThreadId, ThreadKind};
+use crate::weaver::contests::{contest, ContestResult, Contestant};
+use crate::weaver::properties::{Stat, ThreadProperties};
+use crate::weaver::states::ThreadStates;
+use rand::Rng;
+
+/// Hit points every Thread has, before its Constitution
+pub const HP_BASE: u32 = 5;
+
+/// Hit points for each point of Constitution
+pub const HP_PER_CONSTITUTION: u32 = 2;
+
+/// Hit points for each level past the first
+pub const HP_PER_LEVEL: u32 = 3;
+
+/// Stressed added by a blow taken
+pub const SHOCK: f32 = 0.1;
+
+/// Stressed added by a blow missed
+pub const FALTER: f32 = 0.05;
+
+/// Stressed at which morale breaks
+pub const BREAKING: f32 = 0.5;
+
+/// What full Stress takes from a fighter's roll
+pub const STRESS_PENALTY: f32 = 4.0;
+
+/// Morale also breaks once hit points fall to a share this small
+pub const ROUT: f32 = 0.25;
+
+/// What a Thread spared owes its victor
+pub const SPARED_DEBT: f32 = 0.5;
+
+/// How far below hostile a blow leaves the trust of the one struck, so
+/// the grudge outlasts a month of feelings fading
+pub const GRUDGE: f32 = 0.25;
+
+/// How dear a way a fleeing Thread weighs ahead of its foe; anywhere
+/// dearer is as far away as can be
+pub const FLIGHT_REACH: u32 = 32;
+
+/// What a blow is struck with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weapon {
+    Fists,
+    /// Staves, clubs and cudgels, made for forceful blows
+    Staff,
+    /// Swords, knives and daggers, made for deft ones
+    Blade,
+}
+
+impl Weapon {
+    pub fn name(self) -> &'static str {
+        match self {
+            Weapon::Fists => "fists",
+            Weapon::Staff => "staff",
+            Weapon::Blade => "blade",
+        }
+    }
+
+    /// The weapon an item's name describes ("Walking Staff"), if any
+    pub fn of(name: &str) -> Option<Weapon> {
+        let name = name.to_lowercase();
+        let names = |words: &[&str]| words.iter().any(|word| name.contains(word));
+        if names(&["staff", "club", "cudgel"]) {
+            Some(Weapon::Staff)
+        } else if names(&["blade", "sword", "knife", "dagger"]) {
+            Some(Weapon::Blade)
+        } else {
+            None
+        }
+    }
+
+    /// Harm a blow does before the striker's stat adds to it
+    pub fn damage(self) -> u32 {
+        match self {
+            Weapon::Fists => 1,
+            Weapon::Staff => 3,
+            Weapon::Blade => 4,
+        }
+    }
+
+    /// What the weapon adds to a roll in `style`
+    pub fn modifier(self, style: Style) -> i32 {
+        match (self, style) {
+            (Weapon::Staff, Style::Forceful) | (Weapon::Blade, Style::Deft) => 2,
+            _ => 0,
+        }
+    }
+
+    /// How a blow with it is told: "strike" and "strikes"
+    pub fn verbs(self) -> (&'static str, &'static str) {
+        match self {
+            Weapon::Fists => ("punch", "punches"),
+            Weapon::Staff => ("strike", "strikes"),
+            Weapon::Blade => ("cut", "cuts"),
+        }
+    }
+}
+
+/// How a Thread fights, deciding the stats contested
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Style {
+    /// Strength against Constitution
+    #[default]
+    Forceful,
+    /// Dexterity against Dexterity
+    Deft,
+}
+
+impl Style {
+    pub const ALL: [Style; 2] = [Style::Forceful, Style::Deft];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Style::Forceful => "forceful",
+            Style::Deft => "deft",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Style> {
+        Style::ALL.into_iter().find(|style| style.name() == name)
+    }
+
+    /// The striker's stat and the defender's
+    pub fn stats(self) -> (Stat, Stat) {
+        match self {
+            Style::Forceful => (Stat::Strength, Stat::Constitution),
+            Style::Deft => (Stat::Dexterity, Stat::Dexterity),
+        }
+    }
+
+    /// The style a Thread with `properties` fights in by nature
+    pub fn suited(properties: &ThreadProperties) -> Style {
+        if properties.dexterity > properties.strength {
+            Style::Deft
+        } else {
+            Style::Forceful
+        }
+    }
+}
+
+/// What became of the defender after an exchange
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fate {
+    /// It has no hit points left
+    Slain,
+    /// Its morale broke and it got away to the position
+    Fled(Position),
+    /// Its morale broke and it could not get away
+    Surrendered,
+}
+
+/// One blow and what came of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exchange {
+    pub attacker: ThreadId,
+    pub defender: ThreadId,
+    pub attacker_name: String,
+    pub defender_name: String,
+    pub weapon: Weapon,
+    /// The harm done, or None if the blow missed
+    pub damage: Option<u32>,
+    pub fate: Option<Fate>,
+}
+
+/// Hit points of a Thread unharmed
+pub fn max_hp(thread: &Thread) -> u32 {
+    HP_BASE
+        + HP_PER_CONSTITUTION * u32::from(thread.properties.constitution)
+        + HP_PER_LEVEL * (vitals::level(&thread.states) - 1)
+}
+
+/// Hit points a Thread has left after the harm it has taken
+pub fn hp(thread: &Thread) -> u32 {
+    (max_hp(thread) as f32 * (1.0 - thread.states.damaged)).round() as u32
+}
+
+pub fn is_dead(thread: &Thread) -> bool {
+    hp(thread) == 0
+}
+
+/// What Stress takes from a fighter's roll
+pub fn morale_penalty(states: &ThreadStates) -> i32 {
+    (states.stressed * STRESS_PENALTY).round() as i32
+}
+
+/// The weapon a Thread fights with: the first it carries, or its fists
+pub fn weapon(tapestry: &Tapestry, id: ThreadId) -> Weapon {
+    carried(tapestry, id)
+        .into_iter()
+        .filter_map(|item| Weapon::of(tapestry.get_thread(item)?.display_name()))
+        .next()
+        .unwrap_or(Weapon::Fists)
+}
+
+/// Every item `id` carries, oldest first
+pub fn carried(tapestry: &Tapestry, id: ThreadId) -> Vec<ThreadId> {
+    let mut items: Vec<ThreadId> = tapestry
+        .threads()
+        .filter(|thread| matches!(thread.kind, ThreadKind::Item { carrier: Some(carrier), .. } if carrier == id))
+        .map(|thread| thread.id)
+        .collect();
+    items.sort_by_key(|id| id.0);
+    items
+}
+
+/// Whether `thread` and `other` are at odds: either is hostile to the
+/// other, or `thread` is a corrupted NPC
+pub fn are_enemies(tapestry: &Tapestry, thread: &Thread, other: ThreadId) -> bool {
+    let graph = tapestry.relationships();
+    thread.is_hostile() || graph.get(thread.id, other).is_hostile() || graph.get(other, thread.id).is_hostile()
+}
+
+/// Whether `npc` is awake and set on fighting `target`: corrupted, or
+/// hostile to it, and not afraid of it
+pub fn is_foe(tapestry: &Tapestry, npc: &Thread, target: ThreadId) -> bool {
+    let regard = tapestry.relationships().get(npc.id, target);
+    matches!(npc.kind, ThreadKind::Npc { .. })
+        && calendar::activity(tapestry, npc) != Some(Activity::Sleeping)
+        && (npc.is_hostile() || regard.is_hostile())
+        && regard.fear < STRONG
+}
+
+/// The foes standing beside `target`, oldest first
+pub fn foes_beside(tapestry: &Tapestry, target: ThreadId) -> Vec<ThreadId> {
+    let Some(position) = tapestry.get_thread(target).and_then(|thread| thread.position) else {
+        return Vec::new();
+    };
+    let mut foes: Vec<ThreadId> = tapestry
+        .threads()
+        .filter(|thread| thread.position.is_some_and(|at| at != position && is_beside(at, position)))
+        .filter(|thread| is_foe(tapestry, thread, target))
+        .map(|thread| thread.id)
+        .collect();
+    foes.sort_by_key(|id| id.0);
+    foes
+}
+
+/// Whether two positions touch, diagonals included
+pub fn is_beside(a: Position, b: Position) -> bool {
+    (a.x - b.x).abs() <= 1 && (a.y - b.y).abs() <= 1
+}
+
+/// Let `attacker` strike at `defender` in `style`, harming it, shaking
+/// both, and breaking its morale if it is an NPC and has had enough
+///
+/// A Thread that falls is left for [`fall`]; combat does not decide what
+/// becomes of the dead.
+pub fn attack(
+    tapestry: &mut Tapestry,
+    rng: &mut impl Rng,
+    attacker: ThreadId,
+    defender: ThreadId,
+    style: Style,
+) -> Option<Exchange> {
+    let weapon = weapon(tapestry, attacker);
+    let (striker, struck) = (tapestry.get_thread(attacker)?, tapestry.get_thread(defender)?);
+    let (stat, against) = style.stats();
+    let graph = tapestry.relationships();
+    let blow = Contestant::new(&striker.properties, &striker.states, stat).with_modifier(
+        weapon.modifier(style) + graph.modifier(attacker, defender) - morale_penalty(&striker.states),
+    );
+    let guard = Contestant::new(&struck.properties, &struck.states, against)
+        .with_modifier(graph.modifier(defender, attacker) - morale_penalty(&struck.states));
+    let hit = contest(rng, blow, guard) == ContestResult::Success;
+    let mut exchange = Exchange {
+        attacker,
+        defender,
+        attacker_name: striker.display_name().to_string(),
+        defender_name: struck.display_name().to_string(),
+        weapon,
+        damage: hit.then(|| weapon.damage() + u32::from(striker.properties.get_stat(stat)) / 5),
+        fate: None,
+    };
+
+    let graph = tapestry.relationships_mut();
+    graph.contested(attacker, defender, hit, Stance::Hostile);
+    // A blow struck is not forgiven
+    graph.update(defender, attacker, |edge| edge.trust = edge.trust.min(HOSTILE_TRUST - GRUDGE));
+
+    let Some(damage) = exchange.damage else {
+        if let Some(striker) = tapestry.get_thread_mut(attacker) {
+            striker.states.stressed += FALTER;
+            striker.states.clamp();
+        }
+        return Some(exchange);
+    };
+    let struck = tapestry.get_thread_mut(defender)?;
+    struck.states.damaged += damage as f32 / max_hp(struck) as f32;
+    struck.states.stressed += SHOCK;
+    struck.states.clamp();
+    exchange.fate = if is_dead(struck) {
+        Some(Fate::Slain)
+    } else if matches!(struck.kind, ThreadKind::Npc { .. }) && is_broken(struck) {
+        Some(rout(tapestry, rng, defender, attacker))
+    } else {
+        None
+    };
+    Some(exchange)
+}
+
+/// Whether a Thread's morale has broken
+pub fn is_broken(thread: &Thread) -> bool {
+    thread.states.stressed >= BREAKING || hp(thread) as f32 <= max_hp(thread) as f32 * ROUT
+}
+
+/// Let `fleeing` try to get away from `foe`: its Dexterity against the
+/// foe's, returning whether it did
+pub fn break_away(tapestry: &Tapestry, rng: &mut impl Rng, fleeing: ThreadId, foe: ThreadId) -> bool {
+    let (Some(runner), Some(chaser)) = (tapestry.get_thread(fleeing), tapestry.get_thread(foe)) else {
+        return true;
+    };
+    let runner = Contestant::new(&runner.properties, &runner.states, Stat::Dexterity)
+        .with_modifier(-morale_penalty(&runner.states));
+    let chaser = Contestant::new(&chaser.properties, &chaser.states, Stat::Dexterity);
+    contest(rng, runner, chaser) == ContestResult::Success
+}
+
+/// Let `yielder` surrender to `victor`, returning whether it was accepted
+///
+/// The corrupted take no prisoners. Once accepted, both give up their
+/// hostility; the one spared fears the victor and owes it.
+pub fn yield_to(tapestry: &mut Tapestry, yielder: ThreadId, victor: ThreadId) -> bool {
+    if tapestry.get_thread(victor).is_none_or(Thread::is_hostile) {
+        return false;
+    }
+    let graph = tapestry.relationships_mut();
+    graph.update(victor, yielder, |edge| edge.trust = edge.trust.max(0.0));
+    graph.update(yielder, victor, |edge| {
+        edge.trust = edge.trust.max(0.0);
+        edge.fear = 1.0;
+        edge.debt += SPARED_DEBT;
+    });
+    true
+}
+
+/// Let a broken NPC flee from `foe` to the free tile beside it furthest
+/// away, or yield if it cannot get away
+fn rout(tapestry: &mut Tapestry, rng: &mut impl Rng, npc: ThreadId, foe: ThreadId) -> Fate {
+    let escape = retreat(tapestry, npc, foe).filter(|_| break_away(tapestry, rng, npc, foe));
+    match escape {
+        Some(position) => {
+            if let Some(thread) = tapestry.get_thread_mut(npc) {
+                thread.position = Some(position);
+            }
+            Fate::Fled(position)
+        }
+        None => {
+            yield_to(tapestry, npc, foe);
+            Fate::Surrendered
+        }
+    }
+}
+
+/// The free land beside `id` that is dearest for `foe` to reach, if any
+/// is dearer than where `id` stands
+///
+/// NPCs keep to woven land; wilderness is no refuge.
+fn retreat(tapestry: &Tapestry, id: ThreadId, foe: ThreadId) -> Option<Position> {
+    let from = tapestry.get_thread(id)?.position?;
+    let away = tapestry.get_thread(foe)?.position?;
+    let mut terrain = Terrain::of(tapestry);
+    terrain.vacate(from);
+    let land = |at: Position| terrain.cost(at).filter(|_| terrain.is_land(at));
+    let reach = distances(away, FLIGHT_REACH, land);
+    let distance = |at: Position| reach.get(&at).copied().unwrap_or(u32::MAX);
+    let mut best: Option<Position> = None;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let at = Position::new(from.x + dx, from.y + dy);
+            if distance(at) > best.map_or(distance(from), distance) && land(at).is_some() {
+                best = Some(at);
+            }
+        }
+    }
+    best
+}
+
+/// Take the fallen Thread `id` off the Tapestry as slain by `victor`,
+/// see [`vitals::perish`]
+pub fn fall(tapestry: &mut Tapestry, id: ThreadId, victor: ThreadId) -> Option<String> {
+    vitals::perish(tapestry, id, Death::Slain(victor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tapestry::relationships;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn add(tapestry: &mut Tapestry, kind: ThreadKind, properties: ThreadProperties, x: i32) -> ThreadId {
+        let id = tapestry.next_id();
+        tapestry.add_thread(Thread {
+            id,
+            kind,
+            properties,
+            states: Default::default(),
+            position: Some(Position::new(x, 0)),
+            name: None,
+            notes: Vec::new(),
+        })
+    }
+
+    fn npc(tapestry: &mut Tapestry, name: &str, properties: ThreadProperties, x: i32) -> ThreadId {
+        add(tapestry, ThreadKind::Npc { name: name.to_string() }, properties, x)
+    }
+
+    /// A strip of plains from x = -3 to 3
+    fn field() -> Tapestry {
+        let mut tapestry = Tapestry::new();
+        for x in -3..=3 {
+            let region = ThreadKind::Region {
+                description: "Whispering Plains".to_string(),
+            };
+            add(&mut tapestry, region, Default::default(), x);
+        }
+        tapestry
+    }
+
+    #[test]
+    fn weapons_and_styles_pick_the_contest() {
+        assert_eq!(Style::Forceful.stats(), (Stat::Strength, Stat::Constitution));
+        assert_eq!(Style::Deft.stats(), (Stat::Dexterity, Stat::Dexterity));
+        assert_eq!(Style::parse("deft"), Some(Style::Deft));
+        assert_eq!(Weapon::of("Walking Staff"), Some(Weapon::Staff));
+        assert_eq!(Weapon::of("Rusty Dagger"), Some(Weapon::Blade));
+        assert_eq!(Weapon::of("Silver Key"), None);
+        assert_eq!(Weapon::Staff.modifier(Style::Forceful), 2);
+        assert_eq!(Weapon::Staff.modifier(Style::Deft), 0);
+
+        let mut tapestry = field();
+        let hero = npc(&mut tapestry, "Hale", Default::default(), 0);
+        assert_eq!(weapon(&tapestry, hero), Weapon::Fists);
+        let key = ThreadKind::Item {
+            name: "Silver Key".to_string(),
+            carrier: Some(hero),
+        };
+        add(&mut tapestry, key, Default::default(), 0);
+        let knife = ThreadKind::Item {
+            name: "Bone Knife".to_string(),
+            carrier: Some(hero),
+        };
+        add(&mut tapestry, knife, Default::default(), 0);
+        assert_eq!(weapon(&tapestry, hero), Weapon::Blade);
+    }
+
+    #[test]
+    fn hit_points_follow_constitution_level_and_harm() {
+        let mut tapestry = field();
+        let id = npc(&mut tapestry, "Hale", ThreadProperties::new(10), 0);
+        let thread = tapestry.get_thread_mut(id).unwrap();
+        assert_eq!(max_hp(thread), 25);
+        assert_eq!(hp(thread), 25);
+        thread.states.damaged = 0.2;
+        assert_eq!(hp(thread), 20);
+        assert!(!is_broken(thread));
+        thread.states.damaged = 0.8;
+        assert!(is_broken(thread));
+        thread.states.damaged = 1.0;
+        assert!(is_dead(thread));
+        thread.states = ThreadStates {
+            experienced: 0.2,
+            ..Default::default()
+        };
+        assert_eq!(max_hp(thread), 25 + 2 * HP_PER_LEVEL);
+    }
+
+    #[test]
+    fn blows_harm_shake_and_make_enemies() {
+        let mut tapestry = field();
+        let brute = npc(&mut tapestry, "Corwin", ThreadProperties::new(20), 0);
+        let frail = ThreadProperties {
+            constitution: 5,
+            ..ThreadProperties::new(0)
+        };
+        let victim = npc(&mut tapestry, "Edda", frail, 1);
+        let mut rng = StdRng::seed_from_u64(5);
+        let exchange = attack(&mut tapestry, &mut rng, brute, victim, Style::Forceful).unwrap();
+        // Strength 20 and Luck 20 against Constitution 5 all but never miss
+        assert_eq!(exchange.damage, Some(Weapon::Fists.damage() + 4));
+        assert_eq!(exchange.defender_name, "Edda");
+        let edda = tapestry.get_thread(victim).unwrap();
+        assert_eq!(hp(edda), max_hp(edda) - 5);
+        assert_eq!(edda.states.stressed, SHOCK);
+        assert!(tapestry.relationships().get(victim, brute).is_hostile());
+        assert!(is_foe(&tapestry, edda, brute));
+        assert!(are_enemies(&tapestry, tapestry.get_thread(brute).unwrap(), victim));
+        assert!(!is_foe(&tapestry, tapestry.get_thread(brute).unwrap(), victim));
+        assert_eq!(foes_beside(&tapestry, brute), vec![victim]);
+
+        // Grudges outlast the days
+        for _ in 0..relationships::DECAY_INTERVAL * 30 {
+            tapestry.pass_turn();
+            relationships::advance(&mut tapestry);
+        }
+        assert!(tapestry.relationships().get(victim, brute).is_hostile());
+    }
+
+    #[test]
+    fn broken_npcs_flee_or_yield() {
+        let mut tapestry = field();
+        let hero = npc(&mut tapestry, "Hale", ThreadProperties::new(0), 0);
+        let quick = npc(&mut tapestry, "Fenn", ThreadProperties::new(0), 1);
+        tapestry.get_thread_mut(quick).unwrap().properties.dexterity = 20;
+        tapestry.get_thread_mut(quick).unwrap().states.stressed = BREAKING;
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(rout(&mut tapestry, &mut rng, quick, hero), Fate::Fled(Position::new(2, 0)));
+        assert_eq!(tapestry.get_thread(quick).unwrap().position, Some(Position::new(2, 0)));
+
+        // Backed against the edge of the land, there is nowhere to run
+        tapestry.get_thread_mut(quick).unwrap().position = Some(Position::new(3, 0));
+        tapestry.get_thread_mut(hero).unwrap().position = Some(Position::new(2, 0));
+        assert_eq!(rout(&mut tapestry, &mut rng, quick, hero), Fate::Surrendered);
+        let regard = tapestry.relationships().get(quick, hero);
+        assert_eq!((regard.fear, regard.debt), (1.0, SPARED_DEBT));
+
+        // The corrupted take no prisoners
+        tapestry.get_thread_mut(hero).unwrap().states.corrupted = 1.0;
+        assert!(!yield_to(&mut tapestry, quick, hero));
+    }
+
+    #[test]
+    fn the_fleeing_take_to_rough_ground() {
+        let mut tapestry = field();
+        let mountains = ThreadKind::Region {
+            description: "Stubborn Mountains".to_string(),
+        };
+        let peak = add(&mut tapestry, mountains, Default::default(), 2);
+        tapestry.get_thread_mut(peak).unwrap().position = Some(Position::new(2, 1));
+        let hero = npc(&mut tapestry, "Hale", ThreadProperties::new(0), 0);
+        let quick = npc(&mut tapestry, "Fenn", ThreadProperties::new(0), 1);
+        // As far as the crow flies, but dearer for the hero to cross
+        assert_eq!(retreat(&tapestry, quick, hero), Some(Position::new(2, 1)));
+        // Nobody runs through someone else
+        npc(&mut tapestry, "Oren", ThreadProperties::new(0), 2);
+        tapestry.get_thread_mut(peak).unwrap().position = Some(Position::new(-2, 1));
+        assert_eq!(retreat(&tapestry, quick, hero), None);
+    }
+
+    #[test]
+    fn the_fallen_leave_the_map_and_the_chronicle_remembers() {
+        let mut tapestry = field();
+        let hero = npc(&mut tapestry, "Hale", Default::default(), 0);
+        let foe = npc(&mut tapestry, "Corwin", Default::default(), 1);
+        let club = ThreadKind::Item {
+            name: "Oak Club".to_string(),
+            carrier: Some(foe),
+        };
+        let club = add(&mut tapestry, club, Default::default(), 0);
+        tapestry.get_thread_mut(club).unwrap().position = None;
+        tapestry.relationships_mut().update(foe, hero, |edge| edge.trust = -1.0);
+
+        assert_eq!(fall(&mut tapestry, foe, hero).as_deref(), Some("Corwin was slain by Hale"));
+        assert!(tapestry.get_thread(foe).is_none());
+        assert_eq!(tapestry.get_thread(club).unwrap().position, Some(Position::new(1, 0)));
+        assert!(tapestry.relationships().is_empty());
+        assert_eq!(tapestry.chronicle()[0].text, "Corwin was slain by Hale");
+    }
+}
@@ -0,0 +1,258 @@
+//! Exploration memory - what a lineage has seen of the world
+//!
+//! Every tile a member of the lineage has set eyes on is remembered along
+//! with the land that was there at the time. The memory is only refreshed
+//! when the tile is seen again, so it goes stale: a region that changes out
+//! of sight is remembered as it was.
+//!
+//! How much has been explored is measured over the region tiles of the
+//! Tapestry, either for one region (every tile sharing its description) or
+//! for the whole map. Wilderness does not count either way.
+
+use super::Tapestry;
+use crate::foundation::Position;
+use crate::threads::ThreadKind;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// How much of a region must have been explored for descendants to
+/// remember it
+pub const INHERITED_SHARE: f32 = 0.5;
+
+/// One remembered tile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Glimpse {
+    pub position: Position,
+    /// Description of the region seen there, or None for wilderness
+    pub land: Option<String>,
+}
+
+/// Every tile a lineage has seen and what it last saw there
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Vec<Glimpse>", into = "Vec<Glimpse>")]
+pub struct Exploration {
+    tiles: BTreeMap<Position, Option<String>>,
+}
+
+impl Exploration {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember the land on each `visible` tile as it is now
+    pub fn record(&mut self, tapestry: &Tapestry, visible: &HashSet<Position>) {
+        let regions = region_tiles(tapestry);
+        for position in visible {
+            let land = regions.get(position).map(|land| land.to_string());
+            self.tiles.insert(*position, land);
+        }
+    }
+
+    /// Whether the tile has ever been seen
+    pub fn is_seen(&self, position: Position) -> bool {
+        self.tiles.contains_key(&position)
+    }
+
+    /// The region last seen on a tile, if it was seen and not wilderness
+    pub fn land(&self, position: Position) -> Option<&str> {
+        self.tiles.get(&position)?.as_deref()
+    }
+
+    /// Every seen tile, in order
+    pub fn tiles(&self) -> impl Iterator<Item = Position> + '_ {
+        self.tiles.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Corners of the rectangle holding every seen tile
+    pub fn bounds(&self) -> Option<(Position, Position)> {
+        let mut tiles = self.tiles();
+        let first = tiles.next()?;
+        Some(tiles.fold((first, first), |(low, high), tile| {
+            (
+                Position::new(low.x.min(tile.x), low.y.min(tile.y)),
+                Position::new(high.x.max(tile.x), high.y.max(tile.y)),
+            )
+        }))
+    }
+
+    /// Percentage of the map's region tiles seen, or None with no regions
+    pub fn explored(&self, tapestry: &Tapestry) -> Option<u8> {
+        let tiles = region_tiles(tapestry);
+        self.percentage(tiles.keys())
+    }
+
+    /// Percentage of the region called `description` seen, or None if
+    /// there is no such region
+    pub fn region_explored(&self, tapestry: &Tapestry, description: &str) -> Option<u8> {
+        let tiles = region_tiles(tapestry);
+        self.percentage(tiles.iter().filter(|(_, land)| **land == description).map(|(position, _)| position))
+    }
+
+    /// What a descendant remembers: the regions this lineage explored at
+    /// least [`INHERITED_SHARE`] of, as they were last seen
+    pub fn inherit(&self, tapestry: &Tapestry) -> Exploration {
+        let tiles = region_tiles(tapestry);
+        let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+        for (position, land) in &tiles {
+            let (seen, total) = counts.entry(land).or_default();
+            *seen += usize::from(self.is_seen(*position));
+            *total += 1;
+        }
+        let known = |land: &str| {
+            counts
+                .get(land)
+                .is_some_and(|(seen, total)| *seen as f32 >= *total as f32 * INHERITED_SHARE)
+        };
+        let tiles = self
+            .tiles
+            .iter()
+            .filter(|(position, _)| tiles.get(position).is_some_and(|land| known(land)))
+            .map(|(position, land)| (*position, land.clone()))
+            .collect();
+        Exploration { tiles }
+    }
+
+    fn percentage<'a>(&self, tiles: impl Iterator<Item = &'a Position>) -> Option<u8> {
+        let (seen, total) = tiles.fold((0, 0), |(seen, total), position| {
+            (seen + usize::from(self.is_seen(*position)), total + 1)
+        });
+        // Rounded down, so 100% means every tile
+        (total > 0).then(|| (seen * 100 / total) as u8)
+    }
+}
+
+impl From<Vec<Glimpse>> for Exploration {
+    fn from(glimpses: Vec<Glimpse>) -> Self {
+        let tiles = glimpses.into_iter().map(|glimpse| (glimpse.position, glimpse.land)).collect();
+        Self { tiles }
+    }
+}
+
+impl From<Exploration> for Vec<Glimpse> {
+    fn from(exploration: Exploration) -> Self {
+        exploration
+            .tiles
+            .into_iter()
+            .map(|(position, land)| Glimpse { position, land })
+            .collect()
+    }
+}
+
+/// Each tile holding a region, with the description of its oldest region,
+/// which names the tile as on the map
+fn region_tiles(tapestry: &Tapestry) -> HashMap<Position, &str> {
+    let mut tiles: HashMap<Position, (u64, &str)> = HashMap::new();
+    for thread in tapestry.threads() {
+        let (Some(position), ThreadKind::Region { description }) = (thread.position, &thread.kind) else {
+            continue;
+        };
+        let oldest = tiles.entry(position).or_insert((thread.id.0, description));
+        if thread.id.0 < oldest.0 {
+            *oldest = (thread.id.0, description);
+        }
+    }
+    tiles.into_iter().map(|(position, (_, land))| (position, land)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::threads::Thread;
+
+    fn add_region(tapestry: &mut Tapestry, x: i32, description: &str) {
+        let id = tapestry.next_id();
+        tapestry.add_thread(Thread {
+            id,
+            kind: ThreadKind::Region {
+                description: description.to_string(),
+            },
+            properties: Default::default(),
+            states: Default::default(),
+            position: Some(Position::new(x, 0)),
+            name: None,
+            notes: Vec::new(),
+        });
+    }
+
+    /// Plains from x=0 to 3, a forest from 4 to 5
+    fn tapestry() -> Tapestry {
+        let mut tapestry = Tapestry::new();
+        for x in 0..4 {
+            add_region(&mut tapestry, x, "Whispering Plains");
+        }
+        for x in 4..6 {
+            add_region(&mut tapestry, x, "Scheming Forests");
+        }
+        tapestry
+    }
+
+    fn seen(xs: impl IntoIterator<Item = i32>) -> HashSet<Position> {
+        xs.into_iter().map(|x| Position::new(x, 0)).collect()
+    }
+
+    #[test]
+    fn remembers_the_land_as_it_was_last_seen() {
+        let mut tapestry = tapestry();
+        let mut exploration = Exploration::new();
+        exploration.record(&tapestry, &seen([-1, 0]));
+        assert!(exploration.is_seen(Position::new(-1, 0)));
+        assert_eq!(exploration.land(Position::new(-1, 0)), None);
+        assert_eq!(exploration.land(Position::new(0, 0)), Some("Whispering Plains"));
+
+        // The plains burn out of sight; memory keeps them until seen again
+        let burnt = tapestry.get_thread_at(Position::new(0, 0)).unwrap().id;
+        tapestry.get_thread_mut(burnt).unwrap().kind = ThreadKind::Region {
+            description: "Ashen Desert".to_string(),
+        };
+        assert_eq!(exploration.land(Position::new(0, 0)), Some("Whispering Plains"));
+        exploration.record(&tapestry, &seen([0]));
+        assert_eq!(exploration.land(Position::new(0, 0)), Some("Ashen Desert"));
+    }
+
+    #[test]
+    fn explored_counts_region_tiles_per_region_and_map() {
+        let tapestry = tapestry();
+        let mut exploration = Exploration::new();
+        assert_eq!(exploration.explored(&tapestry), Some(0));
+        exploration.record(&tapestry, &seen([-3, -2, -1, 0, 1, 4]));
+        assert_eq!(exploration.explored(&tapestry), Some(50));
+        assert_eq!(exploration.region_explored(&tapestry, "Whispering Plains"), Some(50));
+        assert_eq!(exploration.region_explored(&tapestry, "Scheming Forests"), Some(50));
+        assert_eq!(exploration.region_explored(&tapestry, "Lost Isles"), None);
+        assert_eq!(exploration.explored(&Tapestry::new()), None);
+        assert_eq!(exploration.bounds(), Some((Position::new(-3, 0), Position::new(4, 0))));
+    }
+
+    #[test]
+    fn descendants_inherit_well_explored_regions() {
+        let tapestry = tapestry();
+        let mut exploration = Exploration::new();
+        exploration.record(&tapestry, &seen([-1, 0, 1, 2, 4]));
+        let inherited = exploration.inherit(&tapestry);
+        assert!(inherited.is_seen(Position::new(2, 0)));
+        assert!(inherited.is_seen(Position::new(4, 0)));
+        // Wilderness is forgotten
+        assert!(!inherited.is_seen(Position::new(-1, 0)));
+
+        let mut glimpsed = Exploration::new();
+        glimpsed.record(&tapestry, &seen([0]));
+        assert!(glimpsed.inherit(&tapestry).is_empty());
+    }
+
+    #[test]
+    fn survives_serialisation() {
+        let tapestry = tapestry();
+        let mut exploration = Exploration::new();
+        exploration.record(&tapestry, &seen([-1, 0]));
+        let json = serde_json::to_string(&exploration).unwrap();
+        assert_eq!(serde_json::from_str::<Exploration>(&json).unwrap(), exploration);
+    }
+}
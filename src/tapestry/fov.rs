@@ -0,0 +1,284 @@
+//! Field of view - which tiles a Thread can see from where it stands
+//!
+//! Sight is cast with symmetric shadowcasting: if one tile can see another,
+//! the other can see it back, and walls cast no gaps or artefacts. Regions
+//! whose biome blocks sight (mountains, forests) are walls; everything else,
+//! including unwoven wilderness, is clear. A wall is seen itself but hides
+//! what lies behind it.
+
+use super::Tapestry;
+use crate::foundation::Position;
+use std::collections::HashSet;
+
+/// How far a Thread with no Wisdom sees in daylight and clear weather
+pub const BASE_RADIUS: i32 = 4;
+
+/// How far a Thread can see
+///
+/// Wisdom widens sight; `daylight` and `clarity` run from 0 (pitch dark,
+/// thick fog) to 1 (noon, clear skies) and narrow it. A Thread always sees
+/// the tiles next to it.
+pub fn vision_radius(wisdom: u8, daylight: f32, clarity: f32) -> i32 {
+    let radius = (BASE_RADIUS as f32 + f32::from(wisdom) / 2.0)
+        * daylight.clamp(0.0, 1.0)
+        * clarity.clamp(0.0, 1.0);
+    (radius.round() as i32).max(1)
+}
+
+/// Tiles visible from `origin` within `radius` in the Tapestry
+pub fn field_of_view(tapestry: &Tapestry, origin: Position, radius: i32) -> HashSet<Position> {
+    let walls: HashSet<Position> = tapestry
+        .threads()
+        .filter(|thread| thread.blocks_sight())
+        .filter_map(|thread| thread.position)
+        .collect();
+    shadowcast(origin, radius, |position| walls.contains(&position))
+}
+
+/// Tiles visible from `origin` within `radius`, given which tiles are walls
+pub fn shadowcast(origin: Position, radius: i32, is_wall: impl Fn(Position) -> bool) -> HashSet<Position> {
+    let mut visible = HashSet::from([origin]);
+    for quadrant in Quadrant::ALL {
+        let mut scan = Scan {
+            origin,
+            quadrant,
+            radius,
+            is_wall: &is_wall,
+            visible: &mut visible,
+        };
+        scan.row(1, Slope::new(-1, 1), Slope::new(1, 1));
+    }
+    visible
+}
+
+/// One of the four cones around the origin, scanned row by row outwards
+#[derive(Debug, Clone, Copy)]
+enum Quadrant {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Quadrant {
+    const ALL: [Quadrant; 4] = [Quadrant::North, Quadrant::East, Quadrant::South, Quadrant::West];
+
+    /// The world position `depth` rows out and `column` across
+    fn tile(self, origin: Position, depth: i32, column: i32) -> Position {
+        match self {
+            Quadrant::North => Position::new(origin.x + column, origin.y - depth),
+            Quadrant::South => Position::new(origin.x + column, origin.y + depth),
+            Quadrant::East => Position::new(origin.x + depth, origin.y + column),
+            Quadrant::West => Position::new(origin.x - depth, origin.y + column),
+        }
+    }
+}
+
+/// An exact slope, `num / den` with a positive denominator
+#[derive(Debug, Clone, Copy)]
+struct Slope {
+    num: i32,
+    den: i32,
+}
+
+impl Slope {
+    fn new(num: i32, den: i32) -> Self {
+        Self { num, den }
+    }
+
+    /// Slope through the near edge of the tile at `column` in row `depth`
+    fn of_tile(depth: i32, column: i32) -> Self {
+        Self::new(2 * column - 1, 2 * depth)
+    }
+
+    /// `depth * slope` rounded to the nearest column, halves rounded up
+    fn round_up(self, depth: i32) -> i32 {
+        (2 * depth * self.num + self.den).div_euclid(2 * self.den)
+    }
+
+    /// `depth * slope` rounded to the nearest column, halves rounded down
+    fn round_down(self, depth: i32) -> i32 {
+        -(self.den - 2 * depth * self.num).div_euclid(2 * self.den)
+    }
+}
+
+struct Scan<'a, F> {
+    origin: Position,
+    quadrant: Quadrant,
+    radius: i32,
+    is_wall: &'a F,
+    visible: &'a mut HashSet<Position>,
+}
+
+impl<F: Fn(Position) -> bool> Scan<'_, F> {
+    /// Scan the row `depth` out between two slopes, then the rows behind it
+    fn row(&mut self, depth: i32, mut start: Slope, end: Slope) {
+        if depth > self.radius {
+            return;
+        }
+        let mut previous_wall = None;
+        for column in start.round_up(depth)..=end.round_down(depth) {
+            let tile = self.quadrant.tile(self.origin, depth, column);
+            let wall = (self.is_wall)(tile);
+            // Floors are seen only from inside the cone, which keeps sight symmetric
+            let symmetric = column * start.den >= depth * start.num && column * end.den <= depth * end.num;
+            if (wall || symmetric) && self.within_radius(depth, column) {
+                self.visible.insert(tile);
+            }
+            match (previous_wall, wall) {
+                (Some(true), false) => start = Slope::of_tile(depth, column),
+                (Some(false), true) => self.row(depth + 1, start, Slope::of_tile(depth, column)),
+                _ => {}
+            }
+            previous_wall = Some(wall);
+        }
+        if previous_wall == Some(false) {
+            self.row(depth + 1, start, end);
+        }
+    }
+
+    fn within_radius(&self, depth: i32, column: i32) -> bool {
+        depth * depth + column * column <= self.radius * (self.radius + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::threads::{Thread, ThreadKind};
+
+    /// Walls in a picture, where `#` is a wall and `@` the origin
+    fn parse(picture: &str) -> (Position, HashSet<Position>) {
+        let mut origin = Position::new(0, 0);
+        let mut walls = HashSet::new();
+        for (y, line) in picture.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let position = Position::new(x as i32, y as i32);
+                match c {
+                    '#' => {
+                        walls.insert(position);
+                    }
+                    '@' => origin = position,
+                    _ => {}
+                }
+            }
+        }
+        (origin, walls)
+    }
+
+    /// The picture redrawn with unseen tiles blank, line by line
+    fn sight(picture: &str, radius: i32) -> Vec<String> {
+        let (origin, walls) = parse(picture);
+        let visible = shadowcast(origin, radius, |position| walls.contains(&position));
+        picture
+            .lines()
+            .enumerate()
+            .map(|(y, line)| {
+                let seen: String = line
+                    .chars()
+                    .enumerate()
+                    .map(|(x, c)| match visible.contains(&Position::new(x as i32, y as i32)) {
+                        true => c,
+                        false => ' ',
+                    })
+                    .collect();
+                seen.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn open_ground_is_a_disc() {
+        let picture = "\
+.......
+.......
+.......
+...@...
+.......
+.......
+.......";
+        let expected = ["", "  ...", " .....", " ..@..", " .....", "  ...", ""];
+        assert_eq!(sight(picture, 2), expected);
+    }
+
+    #[test]
+    fn a_pillar_casts_a_shadow() {
+        let picture = "\
+.......
+.......
+...#...
+...@...";
+        let expected = ["..   ..", "... ...", "...#...", "...@..."];
+        assert_eq!(sight(picture, 9), expected);
+    }
+
+    #[test]
+    fn walls_are_seen_but_hide_the_room_behind() {
+        let picture = "\
+.....
+#####
+..@..
+#####
+.....";
+        let expected = ["", "#####", "..@..", "#####", ""];
+        assert_eq!(sight(picture, 9), expected);
+    }
+
+    #[test]
+    fn sight_is_symmetric() {
+        let picture = "\
+..#.....#.
+....#.....
+.#....##..
+......#...
+..#.......
+.....#..#.";
+        let (_, walls) = parse(picture);
+        let floors: Vec<Position> = (0..10)
+            .flat_map(|x| (0..6).map(move |y| Position::new(x, y)))
+            .filter(|position| !walls.contains(position))
+            .collect();
+        let seen: Vec<HashSet<Position>> = floors
+            .iter()
+            .map(|from| shadowcast(*from, 20, |position| walls.contains(&position)))
+            .collect();
+        for (a, from) in floors.iter().enumerate() {
+            for (b, to) in floors.iter().enumerate() {
+                assert_eq!(seen[a].contains(to), seen[b].contains(from), "{:?} and {:?}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn forests_and_mountains_block_sight_in_the_tapestry() {
+        let mut tapestry = Tapestry::new();
+        for (x, description) in [(1, "Scheming Forests"), (-1, "Whispering Plains")] {
+            let id = tapestry.next_id();
+            tapestry.add_thread(Thread {
+                id,
+                kind: ThreadKind::Region {
+                    description: description.to_string(),
+                },
+                properties: Default::default(),
+                states: Default::default(),
+                position: Some(Position::new(x, 0)),
+                name: None,
+                notes: Vec::new(),
+            });
+        }
+        let visible = field_of_view(&tapestry, Position::new(0, 0), 5);
+        assert!(visible.contains(&Position::new(1, 0)));
+        assert!(!visible.contains(&Position::new(2, 0)));
+        assert!(visible.contains(&Position::new(-2, 0)));
+        assert!(!visible.contains(&Position::new(-6, 0)));
+    }
+
+    #[test]
+    fn wisdom_widens_and_darkness_narrows_sight() {
+        assert_eq!(vision_radius(10, 1.0, 1.0), 9);
+        assert!(vision_radius(20, 1.0, 1.0) > vision_radius(10, 1.0, 1.0));
+        assert!(vision_radius(10, 0.3, 1.0) < vision_radius(10, 1.0, 1.0));
+        assert!(vision_radius(10, 1.0, 0.5) < vision_radius(10, 1.0, 1.0));
+        assert_eq!(vision_radius(0, 0.0, 0.0), 1);
+    }
+}
@@ -5,22 +5,152 @@
 
 use crate::foundation::Position;
 use crate::threads::{Thread, ThreadId};
-use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Calendar: world time in hours, days, seasons and years
+pub mod calendar;
+/// Combat: blows, harm, morale and the fallen
+pub mod combat;
+/// Chronicle: the events the world remembers
+pub mod chronicle;
+/// Exploration memory: what a lineage has seen, and how much
+pub mod exploration;
+/// Field of view: which tiles can be seen from where
+pub mod fov;
+/// Pathfinding: the cheapest routes across the land
+pub mod pathfinding;
+/// Relationships: what Threads make of one another
+pub mod relationships;
+/// Autonomous resolution: stories that go on without their hero
+pub mod resolution;
+/// Story generation: stories shaped by their stats, and their progress
+pub mod story;
+/// Weather: systems that form, drift and die out over the land
+pub mod weather;
+/// Vitals: hit points, fatigue, hunger and death
+pub mod vitals;
+/// Story weaving: stories claiming and shaping the land they are set in
+pub mod weaving;
 
 /// The Tapestry holds all Threads and coordinates their interactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tapestry {
     threads: HashMap<ThreadId, Thread>,
     next_id: u64,
+    /// Turns passed since the world was woven
+    #[serde(default)]
+    turn: u64,
+    /// Seed of the world's own chance, e.g. the weather
+    #[serde(default)]
+    seed: u64,
+    /// What the world remembers, oldest first
+    #[serde(default)]
+    chronicle: Vec<chronicle::Entry>,
+    /// What the Threads make of one another
+    #[serde(default)]
+    relationships: relationships::Relationships,
+    /// What each player has left in them
+    #[serde(default)]
+    vitals: BTreeMap<ThreadId, vitals::Vitals>,
 }
 
 impl Tapestry {
     pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    /// An empty Tapestry whose chance follows `seed`
+    pub fn with_seed(seed: u64) -> Self {
         Self {
             threads: HashMap::new(),
             next_id: 1,
+            turn: 0,
+            seed,
+            chronicle: Vec::new(),
+            relationships: relationships::Relationships::new(),
+            vitals: BTreeMap::new(),
         }
     }
 
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn turn(&self) -> u64 {
+        self.turn
+    }
+
+    /// The world time now
+    pub fn date(&self) -> calendar::Date {
+        calendar::Date::of(self.turn)
+    }
+
+    /// Let a turn pass
+    pub fn pass_turn(&mut self) {
+        self.turn += 1;
+    }
+
+    /// Let `turns` turns pass at once, with nothing happening in them
+    pub fn pass_time(&mut self, turns: u64) {
+        self.turn += turns;
+    }
+
+    pub fn relationships(&self) -> &relationships::Relationships {
+        &self.relationships
+    }
+
+    pub fn relationships_mut(&mut self) -> &mut relationships::Relationships {
+        &mut self.relationships
+    }
+
+    /// The vitals of the player `id`, if it keeps them
+    pub fn vitals(&self, id: ThreadId) -> Option<&vitals::Vitals> {
+        self.vitals.get(&id)
+    }
+
+    pub fn vitals_mut(&mut self, id: ThreadId) -> Option<&mut vitals::Vitals> {
+        self.vitals.get_mut(&id)
+    }
+
+    /// Start keeping `vitals` for `id`
+    pub fn keep_vitals(&mut self, id: ThreadId, vitals: vitals::Vitals) {
+        self.vitals.insert(id, vitals);
+    }
+
+    pub fn forget_vitals(&mut self, id: ThreadId) {
+        self.vitals.remove(&id);
+    }
+
+    /// Every event the world remembers, oldest first
+    pub fn chronicle(&self) -> &[chronicle::Entry] {
+        &self.chronicle
+    }
+
+    /// Add `text` to the chronicle, dated now
+    pub fn write_chronicle(&mut self, text: impl Into<String>) {
+        self.write_chronicle_at(text, None);
+    }
+
+    /// Add `text` to the chronicle, dated now and placed at `place`
+    pub fn write_chronicle_at(&mut self, text: impl Into<String>, place: Option<Position>) {
+        self.chronicle.push(chronicle::Entry {
+            turn: self.turn,
+            text: text.into(),
+            place,
+        });
+    }
+
+    /// Chance for this turn, the same every time for the same seed and turn
+    ///
+    /// Each part of the world draws with its own `salt`, so that nothing
+    /// rolls the same dice as anything else in the same turn.
+    pub fn turn_rng(&self, salt: u64) -> StdRng {
+        StdRng::seed_from_u64(self.seed ^ salt ^ self.turn.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
     pub fn add_thread(&mut self, thread: Thread) -> ThreadId {
         let id = thread.id;
         self.threads.insert(id, thread);
@@ -41,9 +171,39 @@ impl Tapestry {
         self.threads.get_mut(&id)
     }
 
+    pub fn remove_thread(&mut self, id: ThreadId) -> Option<Thread> {
+        self.threads.remove(&id)
+    }
+
+    /// Every Thread, in no particular order
+    pub fn threads(&self) -> impl Iterator<Item = &Thread> {
+        self.threads.values()
+    }
+
     pub fn get_thread_at(&self, position: Position) -> Option<&Thread> {
         self.threads.values().find(|t| t.position == Some(position))
     }
+
+    /// Every Thread standing at a position, in creation order
+    ///
+    /// Weather does not stand anywhere; see [`Tapestry::weather_at`].
+    pub fn threads_at(&self, position: Position) -> Vec<&Thread> {
+        let mut threads: Vec<&Thread> = self
+            .threads
+            .values()
+            .filter(|t| t.position == Some(position) && t.weather().is_none())
+            .collect();
+        threads.sort_by_key(|t| t.id.0);
+        threads
+    }
+
+    /// The weather over a position: the oldest system covering it
+    pub fn weather_at(&self, position: Position) -> Option<&Thread> {
+        self.threads
+            .values()
+            .filter(|t| t.covers(position))
+            .min_by_key(|t| t.id.0)
+    }
 }
 
 impl Default for Tapestry {
@@ -51,3 +211,21 @@ impl Default for Tapestry {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn each_salt_draws_its_own_dice() {
+        let mut tapestry = Tapestry::with_seed(7);
+        tapestry.pass_turn();
+        let draws = |salt: u64| -> Vec<u32> {
+            let mut rng = tapestry.turn_rng(salt);
+            (0..8).map(|_| rng.gen_range(1..=20)).collect()
+        };
+        assert_eq!(draws(1), draws(1));
+        assert_ne!(draws(1), draws(2));
+    }
+}
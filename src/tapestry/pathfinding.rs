@@ -0,0 +1,274 @@
+//! Pathfinding - routes across the Tapestry
+//!
+//! Entering a tile costs what its land costs to cross: open plains are
+//! cheap, mountains dear. Costs come from a function so each traveller can
+//! judge the ground by what it knows; [`Terrain`] judges it by the Tapestry
+//! as it is, for Threads that know the land. A tile with no cost cannot be
+//! entered.
+//!
+//! [`find_path`] is A* from one tile to another: the player's travel.
+//! [`distances`] is Dijkstra outwards from a tile, for AI that weighs many
+//! destinations at once: a fleeing NPC steps wherever is dearest for its
+//! foe to reach.
+
+use super::Tapestry;
+use crate::foundation::Position;
+use crate::threads::{Biome, ThreadKind};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Cost of entering a tile where no region has been woven
+pub const WILDERNESS_COST: u32 = 1;
+
+/// Most tiles a search looks at before giving up
+pub const SEARCH_LIMIT: usize = 20_000;
+
+/// Moves from a tile to its neighbours, diagonals included
+const STEPS: [(i32, i32); 8] = [(0, -1), (1, 0), (0, 1), (-1, 0), (1, -1), (1, 1), (-1, 1), (-1, -1)];
+
+fn neighbours(position: Position) -> impl Iterator<Item = Position> {
+    STEPS
+        .iter()
+        .map(move |(dx, dy)| Position::new(position.x + dx, position.y + dy))
+}
+
+/// Cost of entering a tile whose land is `biome`, None for wilderness
+pub fn land_cost(biome: Option<Biome>) -> u32 {
+    biome.map_or(WILDERNESS_COST, Biome::move_cost)
+}
+
+/// The Tapestry's ground as a traveller sees it: what each tile costs to
+/// enter, and where other Threads stand in the way
+#[derive(Debug, Clone, Default)]
+pub struct Terrain {
+    costs: HashMap<Position, u32>,
+    occupied: HashSet<Position>,
+}
+
+impl Terrain {
+    /// The ground of the Tapestry now
+    pub fn of(tapestry: &Tapestry) -> Self {
+        // The oldest region on a tile is its land, as on the map
+        let mut lands: HashMap<Position, (u64, Option<Biome>)> = HashMap::new();
+        let mut occupied = HashSet::new();
+        for thread in tapestry.threads() {
+            let Some(position) = thread.position else {
+                continue;
+            };
+            match thread.kind {
+                ThreadKind::Region { .. } => {
+                    let land = lands.entry(position).or_insert((thread.id.0, thread.biome()));
+                    if thread.id.0 < land.0 {
+                        *land = (thread.id.0, thread.biome());
+                    }
+                }
+                ThreadKind::Player { .. } | ThreadKind::Npc { .. } => {
+                    occupied.insert(position);
+                }
+                // Weather slows no one and items block nothing; stories are
+                // nowhere
+                ThreadKind::Weather { .. } | ThreadKind::Item { .. } | ThreadKind::Story(_) => {}
+            }
+        }
+        let costs = lands
+            .into_iter()
+            .map(|(position, (_, biome))| (position, land_cost(biome)))
+            .collect();
+        Self { costs, occupied }
+    }
+
+    /// Cost of entering a tile, or None if someone stands there
+    pub fn cost(&self, position: Position) -> Option<u32> {
+        if self.occupied.contains(&position) {
+            return None;
+        }
+        Some(self.costs.get(&position).copied().unwrap_or(WILDERNESS_COST))
+    }
+
+    /// Whether a region has been woven on a tile
+    pub fn is_land(&self, position: Position) -> bool {
+        self.costs.contains_key(&position)
+    }
+
+    /// Treat a tile as free to enter, e.g. where the traveller itself stands
+    pub fn vacate(&mut self, position: Position) {
+        self.occupied.remove(&position);
+    }
+}
+
+/// The cheapest route from `from` to `to`, not counting `from`, or None if
+/// there is none within [`SEARCH_LIMIT`] tiles
+pub fn find_path(from: Position, to: Position, cost: impl Fn(Position) -> Option<u32>) -> Option<Vec<Position>> {
+    if from == to {
+        return Some(Vec::new());
+    }
+    cost(to)?;
+    // Every step costs at least one and diagonals are single steps, so the
+    // larger of the two distances never overestimates
+    let estimate = |position: Position| (position.x - to.x).unsigned_abs().max((position.y - to.y).unsigned_abs());
+    // Among equally good tiles, the one fewest orthogonal steps away keeps
+    // routes straight
+    let nearness = |position: Position| (position.x - to.x).unsigned_abs() + (position.y - to.y).unsigned_abs();
+
+    let mut spent: HashMap<Position, u32> = HashMap::from([(from, 0)]);
+    let mut came_from: HashMap<Position, Position> = HashMap::new();
+    // Cheapest total first; ties go to the tile nearer the goal
+    let mut open = BinaryHeap::from([Reverse((estimate(from), nearness(from), from))]);
+    let mut closed = HashSet::new();
+    while let Some(Reverse((_, _, position))) = open.pop() {
+        if position == to {
+            let mut route = vec![to];
+            let mut step = to;
+            while let Some(previous) = came_from.get(&step).filter(|previous| **previous != from) {
+                route.push(*previous);
+                step = *previous;
+            }
+            route.reverse();
+            return Some(route);
+        }
+        if !closed.insert(position) {
+            continue;
+        }
+        if closed.len() > SEARCH_LIMIT {
+            return None;
+        }
+        for next in neighbours(position) {
+            let Some(step_cost) = cost(next) else {
+                continue;
+            };
+            let total = spent[&position] + step_cost.max(1);
+            if spent.get(&next).is_some_and(|known| *known <= total) {
+                continue;
+            }
+            spent.insert(next, total);
+            came_from.insert(next, position);
+            open.push(Reverse((total + estimate(next), nearness(next), next)));
+        }
+    }
+    None
+}
+
+/// The cheapest cost of reaching every tile within `max_cost` of `from`
+pub fn distances(from: Position, max_cost: u32, cost: impl Fn(Position) -> Option<u32>) -> HashMap<Position, u32> {
+    let mut spent: HashMap<Position, u32> = HashMap::from([(from, 0)]);
+    let mut open = BinaryHeap::from([Reverse((0, from))]);
+    while let Some(Reverse((total, position))) = open.pop() {
+        if spent.get(&position).is_some_and(|known| *known < total) || spent.len() > SEARCH_LIMIT {
+            continue;
+        }
+        for next in neighbours(position) {
+            let Some(step_cost) = cost(next) else {
+                continue;
+            };
+            let total = total + step_cost.max(1);
+            if total > max_cost || spent.get(&next).is_some_and(|known| *known <= total) {
+                continue;
+            }
+            spent.insert(next, total);
+            open.push(Reverse((total, next)));
+        }
+    }
+    spent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::threads::Thread;
+
+    /// Costs drawn as a picture: digits are costs, `#` cannot be entered
+    fn grid(picture: &'static str) -> impl Fn(Position) -> Option<u32> {
+        move |position: Position| {
+            let line = picture.lines().nth(usize::try_from(position.y).ok()?)?;
+            match line.chars().nth(usize::try_from(position.x).ok()?)? {
+                '#' => None,
+                c => c.to_digit(10),
+            }
+        }
+    }
+
+    #[test]
+    fn routes_around_walls() {
+        let cost = grid(
+            "\
+1111
+1##1
+1#11",
+        );
+        let route = find_path(Position::new(0, 2), Position::new(2, 2), cost).unwrap();
+        assert_eq!(route.len(), 5);
+        assert_eq!(route.first(), Some(&Position::new(0, 1)));
+        assert_eq!(route.last(), Some(&Position::new(2, 2)));
+    }
+
+    #[test]
+    fn prefers_cheap_ground() {
+        let cost = grid(
+            "\
+11111
+19991
+11111",
+        );
+        // Straight through the mountains costs 28; around them, 4
+        let route = find_path(Position::new(0, 1), Position::new(4, 1), &cost).unwrap();
+        assert!(route.iter().all(|step| cost(*step) == Some(1)));
+        assert_eq!(route.len(), 4);
+    }
+
+    #[test]
+    fn no_route_to_a_walled_off_tile() {
+        let cost = grid(
+            "\
+1#1
+1#1",
+        );
+        assert_eq!(find_path(Position::new(0, 0), Position::new(2, 0), &cost), None);
+        assert_eq!(find_path(Position::new(0, 0), Position::new(1, 0), &cost), None);
+        assert_eq!(find_path(Position::new(0, 0), Position::new(0, 0), &cost), Some(Vec::new()));
+    }
+
+    #[test]
+    fn distances_spread_by_cost() {
+        let cost = grid(
+            "\
+131
+111",
+        );
+        let reached = distances(Position::new(0, 0), 2, cost);
+        assert_eq!(reached[&Position::new(1, 1)], 1);
+        assert_eq!(reached[&Position::new(2, 0)], 2);
+        // Three either way
+        assert!(!reached.contains_key(&Position::new(1, 0)));
+        assert_eq!(reached.len(), 5);
+    }
+
+    #[test]
+    fn terrain_costs_biomes_and_blocks_occupied_tiles() {
+        let mut tapestry = Tapestry::new();
+        for (x, kind) in [
+            (1, ThreadKind::Region {
+                description: "Stubborn Mountains".to_string(),
+            }),
+            (2, ThreadKind::Npc {
+                name: "Mira".to_string(),
+            }),
+        ] {
+            let id = tapestry.next_id();
+            tapestry.add_thread(Thread {
+                id,
+                kind,
+                properties: Default::default(),
+                states: Default::default(),
+                position: Some(Position::new(x, 0)),
+                name: None,
+                notes: Vec::new(),
+            });
+        }
+        let mut terrain = Terrain::of(&tapestry);
+        assert_eq!(terrain.cost(Position::new(0, 0)), Some(WILDERNESS_COST));
+        assert_eq!(terrain.cost(Position::new(1, 0)), Some(Biome::Mountains.move_cost()));
+        assert_eq!(terrain.cost(Position::new(2, 0)), None);
+        terrain.vacate(Position::new(2, 0));
+        assert_eq!(terrain.cost(Position::new(2, 0)), Some(WILDERNESS_COST));
+    }
+}
@@ -0,0 +1,378 @@
+//! Relationships - what Threads make of one another
+//!
+//! Every pair of Threads that has dealt with each other has an edge in each
+//! direction holding how much one trusts, fears, knows and owes the other.
+//! Edges change through contests between the pair, and contests read them
+//! back as a modifier: knowing someone helps in dealing with them, fearing
+//! them hinders, and being trusted opens doors.
+//!
+//! Feelings fade: every [`DECAY_INTERVAL`] turns trust, fear and
+//! familiarity lose a share of their strength, and edges with nothing left
+//! are forgotten. Debts stand until they are paid. When a lineage passes
+//! to an heir, the world's feelings pass with it, see
+//! [`Relationships::inherit`].
+
+use super::calendar::{HOURS_PER_DAY, TURNS_PER_HOUR};
+use super::Tapestry;
+use crate::threads::ThreadId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Trust at or below which a Thread is hostile to the other
+pub const HOSTILE_TRUST: f32 = -0.5;
+
+/// Strength at which a feeling shows in how a Thread carries itself
+pub const STRONG: f32 = 0.5;
+
+/// How much each contest between the pair changes their edges
+pub const FAMILIARITY_STEP: f32 = 0.1;
+
+pub const TRUST_STEP: f32 = 0.1;
+
+pub const FEAR_STEP: f32 = 0.15;
+
+pub const DEBT_STEP: f32 = 0.1;
+
+/// Contest modifiers at full strength of each feeling
+pub const FAMILIARITY_BONUS: f32 = 2.0;
+
+pub const FEAR_PENALTY: f32 = 4.0;
+
+pub const TRUST_BONUS: f32 = 3.0;
+
+/// Turns between each fading of feelings: a day
+pub const DECAY_INTERVAL: u64 = TURNS_PER_HOUR * HOURS_PER_DAY;
+
+/// Share of trust, fear and familiarity lost each interval
+pub const DECAY: f32 = 0.01;
+
+/// Below this a feeling is forgotten
+const FORGOTTEN: f32 = 0.01;
+
+/// Share of the trust an ancestor earned that their heir starts with
+pub const INHERITED_TRUST: f32 = 0.5;
+
+/// How one Thread regards another
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Relationship {
+    /// From -1 (utter distrust) to 1 (complete trust)
+    pub trust: f32,
+    /// From 0 to 1
+    pub fear: f32,
+    /// How well it knows the other, from 0 to 1
+    pub familiarity: f32,
+    /// What it owes the other, from 0 to 1
+    pub debt: f32,
+}
+
+impl Relationship {
+    pub fn is_hostile(&self) -> bool {
+        self.trust <= HOSTILE_TRUST
+    }
+
+    /// The strongest feeling that shows, e.g. "afraid"
+    pub fn attitude(&self) -> Option<&'static str> {
+        if self.is_hostile() {
+            Some("hostile")
+        } else if self.fear >= STRONG {
+            Some("afraid")
+        } else if self.trust >= STRONG {
+            Some("friendly")
+        } else if self.familiarity >= STRONG {
+            Some("familiar")
+        } else {
+            None
+        }
+    }
+
+    fn clamp(&mut self) {
+        self.trust = self.trust.clamp(-1.0, 1.0);
+        self.fear = self.fear.clamp(0.0, 1.0);
+        self.familiarity = self.familiarity.clamp(0.0, 1.0);
+        self.debt = self.debt.clamp(0.0, 1.0);
+    }
+
+    fn is_forgotten(&self) -> bool {
+        [self.trust.abs(), self.fear, self.familiarity, self.debt]
+            .iter()
+            .all(|value| *value < FORGOTTEN)
+    }
+}
+
+/// How a contest between two Threads was meant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stance {
+    /// One sizing the other up; only the watcher learns anything
+    Watching,
+    /// One asking something of the other in good faith
+    Friendly,
+    /// One setting itself against the other
+    Hostile,
+}
+
+/// One directed edge, as saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Edge {
+    pub from: ThreadId,
+    pub to: ThreadId,
+    pub relationship: Relationship,
+}
+
+/// Every relationship between the Tapestry's Threads
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Vec<Edge>", into = "Vec<Edge>")]
+pub struct Relationships {
+    edges: BTreeMap<(ThreadId, ThreadId), Relationship>,
+}
+
+impl Relationships {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How `from` regards `to`; strangers feel nothing
+    pub fn get(&self, from: ThreadId, to: ThreadId) -> Relationship {
+        self.edges.get(&(from, to)).copied().unwrap_or_default()
+    }
+
+    /// Change how `from` regards `to`
+    pub fn update(&mut self, from: ThreadId, to: ThreadId, change: impl FnOnce(&mut Relationship)) {
+        let edge = self.edges.entry((from, to)).or_default();
+        change(edge);
+        edge.clamp();
+        if edge.is_forgotten() {
+            self.edges.remove(&(from, to));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// Every Thread with feelings about `to`, and what they are
+    pub fn toward(&self, to: ThreadId) -> impl Iterator<Item = (ThreadId, &Relationship)> {
+        self.edges
+            .iter()
+            .filter(move |((_, target), _)| *target == to)
+            .map(|((from, _), relationship)| (*from, relationship))
+    }
+
+    /// Every Thread hostile to `to`, oldest first
+    pub fn hostile_to(&self, to: ThreadId) -> Vec<ThreadId> {
+        let mut hostile: Vec<ThreadId> = self
+            .toward(to)
+            .filter(|(_, relationship)| relationship.is_hostile())
+            .map(|(from, _)| from)
+            .collect();
+        hostile.sort_by_key(|id| id.0);
+        hostile
+    }
+
+    /// What the relationship adds to `from`'s roll in a contest with `to`:
+    /// knowing them helps, fearing them hinders, and their trust (or
+    /// distrust) in `from` counts too
+    pub fn modifier(&self, from: ThreadId, to: ThreadId) -> i32 {
+        let view = self.get(from, to);
+        let trust = self.get(to, from).trust;
+        (view.familiarity * FAMILIARITY_BONUS - view.fear * FEAR_PENALTY + trust * TRUST_BONUS).round() as i32
+    }
+
+    /// Let a contest `initiator` started against `defender` with `stance`
+    /// change how they regard each other
+    pub fn contested(&mut self, initiator: ThreadId, defender: ThreadId, won: bool, stance: Stance) {
+        match stance {
+            Stance::Watching => {
+                if won {
+                    self.update(initiator, defender, |edge| edge.familiarity += FAMILIARITY_STEP);
+                }
+                return;
+            }
+            Stance::Friendly if won => {
+                // A favour granted is a favour owed
+                self.update(defender, initiator, |edge| edge.trust += TRUST_STEP);
+                self.update(initiator, defender, |edge| edge.debt += DEBT_STEP);
+            }
+            Stance::Friendly => self.update(defender, initiator, |edge| edge.trust -= TRUST_STEP / 2.0),
+            Stance::Hostile if won => self.update(defender, initiator, |edge| {
+                edge.fear += FEAR_STEP;
+                edge.trust -= TRUST_STEP;
+            }),
+            Stance::Hostile => {
+                self.update(defender, initiator, |edge| edge.trust -= TRUST_STEP);
+                self.update(initiator, defender, |edge| edge.fear += FEAR_STEP / 2.0);
+            }
+        }
+        self.update(initiator, defender, |edge| edge.familiarity += FAMILIARITY_STEP);
+        self.update(defender, initiator, |edge| edge.familiarity += FAMILIARITY_STEP);
+    }
+
+    /// Let `intervals` decay intervals pass over every feeling but debt
+    pub fn decay(&mut self, intervals: u64) {
+        let kept = (1.0 - DECAY).powi(intervals.min(i32::MAX as u64) as i32);
+        let fade = |value: f32| if (value * kept).abs() < FORGOTTEN { 0.0 } else { value * kept };
+        for edge in self.edges.values_mut() {
+            edge.trust = fade(edge.trust);
+            edge.fear = fade(edge.fear);
+            edge.familiarity = fade(edge.familiarity);
+        }
+        self.edges.retain(|_, edge| !edge.is_forgotten());
+    }
+
+    /// Pass what the world feels about `ancestor` on to `heir`: enemies
+    /// and fears are inherited whole, goodwill by half, and debts both
+    /// ways; nobody knows the heir yet
+    pub fn inherit(&mut self, ancestor: ThreadId, heir: ThreadId) {
+        let toward: Vec<(ThreadId, Relationship)> = self
+            .toward(ancestor)
+            .filter(|(from, _)| *from != heir)
+            .map(|(from, relationship)| (from, *relationship))
+            .collect();
+        for (from, relationship) in toward {
+            let trust = if relationship.trust > 0.0 {
+                relationship.trust * INHERITED_TRUST
+            } else {
+                relationship.trust
+            };
+            self.update(from, heir, |edge| {
+                edge.trust += trust;
+                edge.fear += relationship.fear;
+                edge.debt += relationship.debt;
+            });
+        }
+        let owed: Vec<(ThreadId, f32)> = self
+            .edges
+            .iter()
+            .filter(|((from, to), _)| *from == ancestor && *to != heir)
+            .map(|((_, to), relationship)| (*to, relationship.debt))
+            .collect();
+        for (to, debt) in owed {
+            self.update(heir, to, |edge| edge.debt += debt);
+        }
+    }
+
+    /// Forget every relationship `id` had, e.g. once it is gone
+    pub fn forget(&mut self, id: ThreadId) {
+        self.edges.retain(|(from, to), _| *from != id && *to != id);
+    }
+}
+
+impl From<Vec<Edge>> for Relationships {
+    fn from(edges: Vec<Edge>) -> Self {
+        Self {
+            edges: edges
+                .into_iter()
+                .map(|edge| ((edge.from, edge.to), edge.relationship))
+                .collect(),
+        }
+    }
+}
+
+impl From<Relationships> for Vec<Edge> {
+    fn from(relationships: Relationships) -> Self {
+        relationships
+            .edges
+            .into_iter()
+            .map(|((from, to), relationship)| Edge { from, to, relationship })
+            .collect()
+    }
+}
+
+/// Let feelings fade for the Tapestry's current turn
+pub fn advance(tapestry: &mut Tapestry) {
+    if tapestry.turn().is_multiple_of(DECAY_INTERVAL) {
+        tapestry.relationships_mut().decay(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: ThreadId = ThreadId(1);
+    const B: ThreadId = ThreadId(2);
+    const C: ThreadId = ThreadId(3);
+
+    #[test]
+    fn contests_build_trust_fear_familiarity_and_debt() {
+        let mut graph = Relationships::new();
+        graph.contested(A, B, false, Stance::Watching);
+        assert!(graph.is_empty());
+        graph.contested(A, B, true, Stance::Watching);
+        assert_eq!(graph.get(A, B).familiarity, FAMILIARITY_STEP);
+        assert_eq!(graph.get(B, A), Relationship::default());
+
+        graph.contested(A, B, true, Stance::Friendly);
+        assert_eq!(graph.get(B, A).trust, TRUST_STEP);
+        assert_eq!(graph.get(A, B).debt, DEBT_STEP);
+        assert_eq!(graph.get(B, A).familiarity, FAMILIARITY_STEP);
+
+        for _ in 0..7 {
+            graph.contested(C, A, true, Stance::Hostile);
+        }
+        let edge = graph.get(A, C);
+        assert!(edge.fear >= STRONG && edge.is_hostile());
+        assert_eq!(edge.attitude(), Some("hostile"));
+        assert_eq!(graph.hostile_to(C), vec![A]);
+        assert!(graph.hostile_to(A).is_empty());
+    }
+
+    #[test]
+    fn relationships_weigh_on_contests() {
+        let mut graph = Relationships::new();
+        assert_eq!(graph.modifier(A, B), 0);
+        graph.update(A, B, |edge| edge.familiarity = 1.0);
+        graph.update(B, A, |edge| edge.trust = 1.0);
+        assert_eq!(graph.modifier(A, B), 5);
+        graph.update(A, B, |edge| edge.fear = 1.0);
+        assert_eq!(graph.modifier(A, B), 1);
+        // Distrust counts against whoever is distrusted
+        graph.update(A, B, |edge| edge.trust = -1.0);
+        assert_eq!(graph.modifier(B, A), -3);
+    }
+
+    #[test]
+    fn feelings_fade_but_debts_stand() {
+        let mut graph = Relationships::new();
+        graph.update(A, B, |edge| {
+            edge.trust = 0.8;
+            edge.debt = 0.3;
+        });
+        graph.update(B, C, |edge| edge.fear = 0.5);
+        graph.decay(1);
+        assert!(graph.get(A, B).trust < 0.8);
+        graph.decay(1000);
+        assert_eq!(graph.get(A, B).trust, 0.0);
+        assert_eq!(graph.get(A, B).debt, 0.3);
+        // Nothing left, nothing remembered
+        assert_eq!(graph.len(), 1);
+    }
+
+    #[test]
+    fn heirs_inherit_enemies_debts_and_half_the_goodwill() {
+        let mut graph = Relationships::new();
+        graph.update(B, A, |edge| {
+            edge.trust = 0.8;
+            edge.familiarity = 1.0;
+        });
+        graph.update(C, A, |edge| {
+            edge.trust = -0.9;
+            edge.fear = 0.4;
+        });
+        graph.update(A, B, |edge| edge.debt = 0.5);
+        graph.inherit(A, ThreadId(4));
+        let heir = ThreadId(4);
+        assert_eq!(graph.get(B, heir).trust, 0.8 * INHERITED_TRUST);
+        assert_eq!(graph.get(B, heir).familiarity, 0.0);
+        assert_eq!(graph.hostile_to(heir), vec![C]);
+        assert_eq!(graph.get(C, heir).fear, 0.4);
+        assert_eq!(graph.get(heir, B).debt, 0.5);
+
+        // The graph is saved as a list of edges
+        let saved: Relationships = serde_json::from_str(&serde_json::to_string(&graph).unwrap()).unwrap();
+        assert_eq!(saved, graph);
+    }
+}
@@ -0,0 +1,320 @@
+//! Autonomous resolution - stories that go on without their hero
+//!
+//! When the player abandons or retires their character, or leaves the world
+//! unvisited for years, every story still under way resolves itself by a
+//! contest of the story against the world:
+//!
+//! Story Constitution + Intelligence + Connections + d20 + luck modifier
+//! vs World Stability + Complexity + Inertia + d20
+//!
+//! The world's Stability is the mean Constitution of the land the story
+//! claimed and its Complexity their mean Intelligence, or of all the land
+//! if it claimed none. Inertia grows with the goals still unmet, so a story
+//! near its end is hard to stop. A retiring hero hands their work on in
+//! good order, which helps. Winning by more than [`MARGIN`] the story
+//! succeeds, losing by more it fails; anything closer and nothing comes of
+//! it, and it lives on as a Neglected legend.
+//!
+//! The outcome marks the claimed land and the NPCs living there for good,
+//! and is written in the chronicle.
+
+use super::Tapestry;
+use crate::threads::story::{Outcome, Story};
+use crate::threads::{Thread, ThreadId, ThreadKind};
+use rand::Rng;
+
+/// How far one side must win by for the story to end either way
+pub const MARGIN: i32 = 5;
+
+/// Inertia for each goal still unmet, up to [`MAX_INERTIA`]
+pub const INERTIA_PER_GOAL: u32 = 2;
+
+pub const MAX_INERTIA: u32 = 20;
+
+/// Added to the story's roll when its hero retires
+pub const RETIREMENT_BONUS: i32 = 3;
+
+/// How much a success or failure marks the land and its people
+pub const LEGACY: f32 = 0.3;
+
+/// How much Neglected a stagnant story leaves behind
+pub const NEGLECT: f32 = 0.4;
+
+/// Keeps resolution's chance apart from the rest of the turn's
+const SALT: u64 = 0x5245_534f_4c56_4553;
+
+/// Why the stories had to go on alone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cause {
+    /// The player gave up on their character
+    Abandoned,
+    /// The character laid down their stories
+    Retired,
+    /// Nobody visited the world for `years` years
+    Absence { years: u64 },
+}
+
+impl Cause {
+    /// When the story went on alone, e.g. "when its hero retired"
+    pub fn describe(self) -> String {
+        match self {
+            Cause::Abandoned => "when its hero abandoned it".to_string(),
+            Cause::Retired => "when its hero retired".to_string(),
+            Cause::Absence { years: 1 } => "after a year untold".to_string(),
+            Cause::Absence { years } => format!("after {} years untold", years),
+        }
+    }
+}
+
+/// How one story resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolution {
+    pub story: ThreadId,
+    pub outcome: Outcome,
+    /// What was written in the chronicle
+    pub entry: String,
+}
+
+/// The outcome of a contest the story won by `margin` (lost, if negative)
+pub fn outcome(margin: i32) -> Outcome {
+    if margin > MARGIN {
+        Outcome::Success
+    } else if margin < -MARGIN {
+        Outcome::Failure
+    } else {
+        Outcome::Stagnation
+    }
+}
+
+/// The world's Stability, Complexity and Inertia against `story`
+pub fn world(tapestry: &Tapestry, story: &Story) -> (u32, u32, u32) {
+    let claimed = land(tapestry, story);
+    let land: Vec<&Thread> = if claimed.is_empty() {
+        tapestry
+            .threads()
+            .filter(|thread| matches!(thread.kind, ThreadKind::Region { .. }))
+            .collect()
+    } else {
+        claimed
+    };
+    let mean = |stat: fn(&Thread) -> u8| match land.len() as u32 {
+        0 => 10,
+        count => land.iter().map(|thread| u32::from(stat(thread))).sum::<u32>() / count,
+    };
+    let unmet = story.stages[story.stage.min(story.stages.len())..]
+        .iter()
+        .flat_map(|stage| &stage.goals)
+        .filter(|goal| !goal.is_met())
+        .count() as u32;
+    (
+        mean(|thread| thread.properties.constitution),
+        mean(|thread| thread.properties.intelligence),
+        (unmet * INERTIA_PER_GOAL).min(MAX_INERTIA),
+    )
+}
+
+/// Resolve every story still under way, oldest first, marking the world
+/// and the chronicle with what became of each
+pub fn resolve(tapestry: &mut Tapestry, cause: Cause) -> Vec<Resolution> {
+    let mut rng = tapestry.turn_rng(SALT);
+    let mut resolutions = Vec::new();
+    for id in super::story::stories(tapestry) {
+        let Some(thread) = tapestry.get_thread(id) else {
+            continue;
+        };
+        let Some(story) = thread.story().filter(|story| !story.is_over()) else {
+            continue;
+        };
+        let properties = &thread.properties;
+        let bonus = if cause == Cause::Retired { RETIREMENT_BONUS } else { 0 };
+        let story_total = rng.gen_range(1..=20)
+            + i32::from(properties.constitution)
+            + i32::from(properties.intelligence)
+            + i32::from(properties.connections)
+            + i32::from(properties.luck) / 5
+            + bonus;
+        let (stability, complexity, inertia) = world(tapestry, story);
+        let world_total = rng.gen_range(1..=20) + (stability + complexity + inertia) as i32;
+        let outcome = outcome(story_total - world_total);
+
+        let entry = format!("{} {} {}", story.title, outcome.describe(), cause.describe());
+        let land: Vec<ThreadId> = land(tapestry, story).iter().map(|thread| thread.id).collect();
+        mark(tapestry, id, &land, outcome);
+        tapestry.write_chronicle(entry.clone());
+        resolutions.push(Resolution { story: id, outcome, entry });
+    }
+    resolutions
+}
+
+/// The regions `story` claimed that are still there
+fn land<'a>(tapestry: &'a Tapestry, story: &Story) -> Vec<&'a Thread> {
+    story
+        .stages
+        .iter()
+        .flat_map(|stage| &stage.goals)
+        .flat_map(|goal| &goal.regions)
+        .filter_map(|id| tapestry.get_thread(*id))
+        .collect()
+}
+
+/// Settle the story `id` with `outcome` and leave its mark on `land` and
+/// the NPCs living there
+fn mark(tapestry: &mut Tapestry, id: ThreadId, land: &[ThreadId], outcome: Outcome) {
+    if let Some(thread) = tapestry.get_thread_mut(id) {
+        if outcome == Outcome::Stagnation {
+            thread.states.neglected = 1.0;
+        }
+        if let ThreadKind::Story(story) = &mut thread.kind {
+            story.outcome = Some(outcome);
+            if outcome == Outcome::Success {
+                story.stage = story.stages.len();
+            }
+        }
+    }
+
+    let positions: Vec<_> = land
+        .iter()
+        .filter_map(|id| tapestry.get_thread(*id)?.position)
+        .collect();
+    let folk: Vec<ThreadId> = tapestry
+        .threads()
+        .filter(|thread| matches!(thread.kind, ThreadKind::Npc { .. }))
+        .filter(|thread| thread.position.is_some_and(|position| positions.contains(&position)))
+        .map(|thread| thread.id)
+        .collect();
+    for region in land {
+        let Some(states) = tapestry.get_thread_mut(*region).map(|thread| &mut thread.states) else {
+            continue;
+        };
+        match outcome {
+            Outcome::Success => {
+                states.enhanced += LEGACY;
+                states.neglected = 0.0;
+            }
+            Outcome::Failure => states.damaged += LEGACY,
+            Outcome::Stagnation => states.neglected += NEGLECT,
+        }
+        states.clamp();
+    }
+    for npc in folk {
+        let Some(states) = tapestry.get_thread_mut(npc).map(|thread| &mut thread.states) else {
+            continue;
+        };
+        match outcome {
+            Outcome::Success => states.connected += LEGACY,
+            Outcome::Failure => states.stressed += LEGACY,
+            Outcome::Stagnation => states.neglected += NEGLECT,
+        }
+        states.clamp();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::foundation::Position;
+    use crate::threads::story::{Goal, GoalKind, Stage};
+    use crate::threads::Biome;
+    use crate::weaver::properties::ThreadProperties;
+
+    fn add(tapestry: &mut Tapestry, kind: ThreadKind, properties: ThreadProperties, x: i32) -> ThreadId {
+        let id = tapestry.next_id();
+        tapestry.add_thread(Thread {
+            id,
+            kind,
+            properties,
+            states: Default::default(),
+            position: Some(Position::new(x, 0)),
+            name: None,
+            notes: Vec::new(),
+        })
+    }
+
+    /// A story with `stats` that claimed a region of `land` stats, an NPC
+    /// living there
+    fn world_with(stats: u8, land: u8) -> (Tapestry, ThreadId, ThreadId, ThreadId) {
+        let mut tapestry = Tapestry::with_seed(9);
+        let region = ThreadKind::Region {
+            description: "Whispering Plains".to_string(),
+        };
+        let region = add(&mut tapestry, region, ThreadProperties::new(land), 1);
+        let npc = ThreadKind::Npc {
+            name: "Mira".to_string(),
+        };
+        let npc = add(&mut tapestry, npc, Default::default(), 1);
+        let mut goal = Goal::new(GoalKind::Relationship, Biome::Plains, 1);
+        goal.regions.push(region);
+        let story = ThreadKind::Story(Story {
+            title: "The Woven Oath".to_string(),
+            stages: vec![Stage {
+                name: "The Call".to_string(),
+                goals: vec![goal],
+            }],
+            stage: 0,
+            woven: true,
+            outcome: None,
+        });
+        let story = add(&mut tapestry, story, ThreadProperties::new(stats), 0);
+        tapestry.get_thread_mut(story).unwrap().position = None;
+        (tapestry, story, region, npc)
+    }
+
+    #[test]
+    fn margins_decide_the_outcome() {
+        assert_eq!(outcome(MARGIN + 1), Outcome::Success);
+        assert_eq!(outcome(MARGIN), Outcome::Stagnation);
+        assert_eq!(outcome(0), Outcome::Stagnation);
+        assert_eq!(outcome(-MARGIN - 1), Outcome::Failure);
+        assert_eq!(Cause::Absence { years: 4 }.describe(), "after 4 years untold");
+    }
+
+    #[test]
+    fn the_world_weighs_claimed_land_and_unmet_goals() {
+        let (tapestry, story, _, _) = world_with(10, 6);
+        let story = tapestry.get_thread(story).unwrap().story().unwrap();
+        assert_eq!(world(&tapestry, story), (6, 6, INERTIA_PER_GOAL));
+    }
+
+    #[test]
+    fn strong_stories_end_well_and_weak_ones_fall_apart() {
+        let (mut tapestry, story, region, npc) = world_with(20, 0);
+        let resolutions = resolve(&mut tapestry, Cause::Retired);
+        assert_eq!(resolutions.len(), 1);
+        assert_eq!(resolutions[0].outcome, Outcome::Success);
+        assert_eq!(resolutions[0].entry, "The Woven Oath ended well when its hero retired");
+        let resolved = tapestry.get_thread(story).unwrap().story().unwrap();
+        assert!(resolved.is_complete());
+        assert_eq!(tapestry.get_thread(region).unwrap().states.enhanced, LEGACY);
+        assert_eq!(tapestry.get_thread(npc).unwrap().states.connected, LEGACY);
+        assert_eq!(tapestry.chronicle().len(), 1);
+        assert_eq!(tapestry.chronicle()[0].text, resolutions[0].entry);
+        // A story resolves once
+        assert!(resolve(&mut tapestry, Cause::Abandoned).is_empty());
+
+        let (mut tapestry, story, region, npc) = world_with(0, 20);
+        let resolutions = resolve(&mut tapestry, Cause::Abandoned);
+        assert_eq!(resolutions[0].outcome, Outcome::Failure);
+        assert_eq!(resolutions[0].entry, "The Woven Oath fell apart when its hero abandoned it");
+        let resolved = tapestry.get_thread(story).unwrap().story().unwrap();
+        assert!(resolved.is_over() && !resolved.is_complete());
+        assert_eq!(tapestry.get_thread(region).unwrap().states.damaged, LEGACY);
+        assert_eq!(tapestry.get_thread(npc).unwrap().states.stressed, LEGACY);
+    }
+
+    #[test]
+    fn stagnant_stories_leave_neglect_behind() {
+        let (mut tapestry, story, region, npc) = world_with(10, 10);
+        mark(&mut tapestry, story, &[region], Outcome::Stagnation);
+        let thread = tapestry.get_thread(story).unwrap().clone();
+        assert_eq!(thread.states.neglected, 1.0);
+        assert_eq!(thread.story().unwrap().outcome, Some(Outcome::Stagnation));
+        assert_eq!(tapestry.get_thread(region).unwrap().states.neglected, NEGLECT);
+        assert_eq!(tapestry.get_thread(npc).unwrap().states.neglected, NEGLECT);
+
+        // Resolved stories are saved with the world, and the chronicle too
+        tapestry.write_chronicle("The Woven Oath faded into a Neglected legend after a year untold");
+        let saved: Tapestry = serde_json::from_str(&serde_json::to_string(&tapestry).unwrap()).unwrap();
+        assert_eq!(saved.get_thread(story).unwrap().story(), thread.story());
+        assert_eq!(saved.chronicle(), tapestry.chronicle());
+    }
+}
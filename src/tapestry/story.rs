@@ -0,0 +1,309 @@
+//! Story generation - stories woven into the Tapestry, and their progress
+//!
+//! A story's stats are its personality and decide its shape. Intelligence
+//! and Constitution make it long: two stages for the simplest, seven for an
+//! epic. Intelligence alone makes it complex, with up to three goals a
+//! stage. Each goal's kind is drawn by the stats that favour it, its biome
+//! from the land the Tapestry holds, and how much it asks by the stat
+//! behind it.
+//!
+//! World events are recorded against every story still under way.
+
+use super::Tapestry;
+use crate::threads::story::{Advance, Goal, GoalKind, Stage, Story, WorldEvent};
+use crate::threads::{Biome, Thread, ThreadId, ThreadKind};
+use crate::weaver::properties::{Stat, ThreadProperties};
+use crate::weaver::states::ThreadStates;
+use rand::Rng;
+
+pub const MIN_STAGES: usize = 2;
+
+pub const MAX_STAGES: usize = 7;
+
+/// Most goals a stage sets
+pub const MAX_GOALS: usize = 3;
+
+/// The beats of the longest story, shortened from the middle for others
+const BEATS: [&str; MAX_STAGES] = [
+    "The Call",
+    "The Threshold",
+    "The Gathering",
+    "The Trial",
+    "The Descent",
+    "The Return",
+    "The Reckoning",
+];
+
+/// The first word of a title, by the story's strongest stat
+const TEMPERS: [(Stat, &str); 10] = [
+    (Stat::Strength, "Iron"),
+    (Stat::Dexterity, "Quicksilver"),
+    (Stat::Constitution, "Enduring"),
+    (Stat::Intelligence, "Tangled"),
+    (Stat::Wisdom, "Quiet"),
+    (Stat::Charisma, "Silver"),
+    (Stat::Connections, "Woven"),
+    (Stat::Resources, "Gilded"),
+    (Stat::Reputation, "Storied"),
+    (Stat::Luck, "Fickle"),
+];
+
+const SUBJECTS: [&str; 8] = ["Oath", "Crown", "Road", "Song", "Debt", "Flame", "Bargain", "Exile"];
+
+/// How many stages a story with `properties` runs to
+pub fn stage_count(properties: &ThreadProperties) -> usize {
+    let weight = usize::from(properties.intelligence) + usize::from(properties.constitution);
+    (MIN_STAGES + weight * (MAX_STAGES - MIN_STAGES) / 40).clamp(MIN_STAGES, MAX_STAGES)
+}
+
+/// How many goals each stage of a story with `properties` sets
+pub fn goal_count(properties: &ThreadProperties) -> usize {
+    (1 + usize::from(properties.intelligence) / 8).min(MAX_GOALS)
+}
+
+/// Weave a new story with random stats into the Tapestry
+pub fn generate(tapestry: &mut Tapestry, rng: &mut impl Rng) -> ThreadId {
+    let mut properties = ThreadProperties::default();
+    for stat in Stat::ALL {
+        properties.set_stat(stat, rng.gen_range(0..=20));
+    }
+    let story = shape(&properties, &biomes(tapestry), rng);
+    let id = tapestry.next_id();
+    tapestry.add_thread(Thread {
+        id,
+        kind: ThreadKind::Story(story),
+        properties,
+        states: ThreadStates::default(),
+        position: None,
+        name: None,
+        notes: Vec::new(),
+    })
+}
+
+/// Every story, oldest first
+pub fn stories(tapestry: &Tapestry) -> Vec<ThreadId> {
+    let mut stories: Vec<ThreadId> = tapestry
+        .threads()
+        .filter(|thread| thread.story().is_some())
+        .map(|thread| thread.id)
+        .collect();
+    stories.sort_by_key(|id| id.0);
+    stories
+}
+
+/// Record `event` against every story under way, oldest first
+pub fn record(tapestry: &mut Tapestry, event: &WorldEvent) -> Vec<(ThreadId, Advance)> {
+    let mut advances = Vec::new();
+    for id in stories(tapestry) {
+        if let Some(ThreadKind::Story(story)) = tapestry.get_thread_mut(id).map(|thread| &mut thread.kind) {
+            advances.extend(story.record(event).into_iter().map(|advance| (id, advance)));
+        }
+    }
+    advances
+}
+
+/// The biomes of the Tapestry's land, or every biome before any is woven
+fn biomes(tapestry: &Tapestry) -> Vec<Biome> {
+    let present: Vec<Biome> = Biome::ALL
+        .into_iter()
+        .filter(|biome| tapestry.threads().any(|thread| thread.biome() == Some(*biome)))
+        .collect();
+    if present.is_empty() {
+        Biome::ALL.to_vec()
+    } else {
+        present
+    }
+}
+
+/// The title and stages of a story with `properties`, set in `biomes`
+fn shape(properties: &ThreadProperties, biomes: &[Biome], rng: &mut impl Rng) -> Story {
+    let temper = TEMPERS
+        .iter()
+        .rev()
+        .max_by_key(|(stat, _)| properties.get_stat(*stat))
+        .map_or("Quiet", |(_, word)| word);
+    let title = format!("The {} {}", temper, SUBJECTS[rng.gen_range(0..SUBJECTS.len())]);
+
+    let count = stage_count(properties);
+    let stages = (0..count)
+        .map(|index| {
+            // Spread the beats so every story opens with the call and ends
+            // with a reckoning
+            let beat = index * (MAX_STAGES - 1) / (count - 1);
+            let mut kinds = GoalKind::ALL.to_vec();
+            let goals = (0..goal_count(properties))
+                .map(|_| {
+                    let kind = pick_kind(&mut kinds, properties, rng);
+                    Goal::new(kind, pick_biome(kind, biomes, rng), needed(kind, properties))
+                })
+                .collect();
+            Stage {
+                name: BEATS[beat].to_string(),
+                goals,
+            }
+        })
+        .collect();
+    Story {
+        title,
+        stages,
+        stage: 0,
+        woven: false,
+        outcome: None,
+    }
+}
+
+/// Take one of `kinds` at random, weighted by the stats that drive it
+fn pick_kind(kinds: &mut Vec<GoalKind>, properties: &ThreadProperties, rng: &mut impl Rng) -> GoalKind {
+    let weight = |kind: GoalKind| 1 + kind.drives().iter().map(|stat| u32::from(properties.get_stat(*stat))).sum::<u32>();
+    let mut pick = rng.gen_range(0..kinds.iter().map(|kind| weight(*kind)).sum::<u32>());
+    let index = kinds
+        .iter()
+        .position(|kind| {
+            if pick < weight(*kind) {
+                return true;
+            }
+            pick -= weight(*kind);
+            false
+        })
+        .unwrap_or(0);
+    kinds.remove(index)
+}
+
+/// Where a goal of `kind` is set: challenges go to the hardest ground there
+/// is, everything else anywhere
+fn pick_biome(kind: GoalKind, biomes: &[Biome], rng: &mut impl Rng) -> Biome {
+    let hardest = biomes.iter().map(|biome| biome.move_cost()).max().unwrap_or(0);
+    let choices: Vec<Biome> = match kind {
+        GoalKind::Challenge => biomes.iter().copied().filter(|biome| biome.move_cost() == hardest).collect(),
+        _ => biomes.to_vec(),
+    };
+    choices[rng.gen_range(0..choices.len())]
+}
+
+/// How many Threads a goal of `kind` needs, by the stat behind it
+fn needed(kind: GoalKind, properties: &ThreadProperties) -> u32 {
+    1 + u32::from(properties.get_stat(kind.drives()[0])) / 7
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::foundation::Position;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashSet;
+
+    fn add_region(tapestry: &mut Tapestry, x: i32, description: &str) -> ThreadId {
+        let id = tapestry.next_id();
+        tapestry.add_thread(Thread {
+            id,
+            kind: ThreadKind::Region {
+                description: description.to_string(),
+            },
+            properties: Default::default(),
+            states: Default::default(),
+            position: Some(Position::new(x, 0)),
+            name: None,
+            notes: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn stats_decide_length_and_complexity() {
+        let simple = ThreadProperties::new(0);
+        assert_eq!((stage_count(&simple), goal_count(&simple)), (MIN_STAGES, 1));
+        let standard = ThreadProperties::new(10);
+        assert_eq!((stage_count(&standard), goal_count(&standard)), (4, 2));
+        let epic = ThreadProperties::new(20);
+        assert_eq!((stage_count(&epic), goal_count(&epic)), (MAX_STAGES, MAX_GOALS));
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let story = shape(&epic, &[Biome::Plains, Biome::Swamp], &mut rng);
+        let names: Vec<&str> = story.stages.iter().map(|stage| stage.name.as_str()).collect();
+        assert_eq!(names, BEATS);
+        let story = shape(&simple, &[Biome::Plains], &mut rng);
+        let names: Vec<&str> = story.stages.iter().map(|stage| stage.name.as_str()).collect();
+        assert_eq!(names, ["The Call", "The Reckoning"]);
+        // Ties go to the first stat
+        assert!(story.title.starts_with("The Iron "));
+    }
+
+    #[test]
+    fn goals_are_set_in_the_land_there_is() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let epic = ThreadProperties::new(20);
+        let story = shape(&epic, &[Biome::Plains, Biome::Mountains], &mut rng);
+        for stage in &story.stages {
+            let kinds: HashSet<GoalKind> = stage.goals.iter().map(|goal| goal.kind).collect();
+            assert_eq!(kinds.len(), MAX_GOALS);
+            for goal in &stage.goals {
+                assert!([Biome::Plains, Biome::Mountains].contains(&goal.biome));
+                if goal.kind == GoalKind::Challenge {
+                    assert_eq!(goal.biome, Biome::Mountains);
+                }
+                assert_eq!(goal.needed, 3);
+            }
+        }
+    }
+
+    #[test]
+    fn generation_is_the_same_for_the_same_seed() {
+        let world = |seed| {
+            let mut tapestry = Tapestry::new();
+            add_region(&mut tapestry, 0, "Scheming Forests");
+            let id = generate(&mut tapestry, &mut StdRng::seed_from_u64(seed));
+            let thread = tapestry.get_thread(id).unwrap();
+            assert_eq!(thread.kind.label(), "story");
+            assert!(thread.position.is_none());
+            // Stories are saved with the Tapestry
+            let saved: Tapestry = serde_json::from_str(&serde_json::to_string(&tapestry).unwrap()).unwrap();
+            assert_eq!(saved.get_thread(id).unwrap().story(), thread.story());
+            (thread.story().cloned(), thread.properties)
+        };
+        assert_eq!(world(5), world(5));
+        let (story, _) = world(5);
+        let story = story.unwrap();
+        assert!((MIN_STAGES..=MAX_STAGES).contains(&story.stages.len()));
+        assert!(story.stages.iter().flat_map(|stage| &stage.goals).all(|goal| goal.biome == Biome::Forest));
+    }
+
+    #[test]
+    fn events_advance_every_story_under_way() {
+        let mut tapestry = Tapestry::new();
+        let forest = add_region(&mut tapestry, 0, "Scheming Forests");
+        let mut stories = Vec::new();
+        for _ in 0..2 {
+            let id = tapestry.next_id();
+            let story = Story {
+                title: "The Quiet Road".to_string(),
+                stages: vec![Stage {
+                    name: "The Call".to_string(),
+                    goals: vec![Goal::new(GoalKind::Knowledge, Biome::Forest, 1)],
+                }],
+                stage: 0,
+                woven: false,
+                outcome: None,
+            };
+            stories.push(tapestry.add_thread(Thread {
+                id,
+                kind: ThreadKind::Story(story),
+                properties: Default::default(),
+                states: Default::default(),
+                position: None,
+                name: None,
+                notes: Vec::new(),
+            }));
+        }
+        let event = WorldEvent::Studied {
+            subject: forest,
+            biome: Some(Biome::Forest),
+        };
+        let advances = record(&mut tapestry, &event);
+        assert_eq!(advances.len(), 6);
+        assert_eq!(advances[2], (stories[0], Advance::Completed));
+        assert_eq!(advances[5], (stories[1], Advance::Completed));
+        assert!(record(&mut tapestry, &event).is_empty());
+        // Stories stay off the map
+        assert!(tapestry.threads_at(Position::new(0, 0)).iter().all(|thread| thread.story().is_none()));
+    }
+}
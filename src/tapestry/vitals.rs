@@ -0,0 +1,493 @@
+//! Vitals - hit points, fatigue, hunger and death
+//!
+//! A Thread's hit points come from its Constitution and its level, and its
+//! level from how Experienced it is: a hundredth of the state is a point of
+//! experience, and every [`XP_PER_LEVEL`] points a level. Harm is kept as
+//! the Damaged state, see [`combat`](super::combat).
+//!
+//! Players also tire and go hungry. Each step onto land harder to cross
+//! than plains tires them by the difference, and strain past their stamina
+//! comes out of their hit points. Hunger grows by the turn; a hungry player
+//! eats from their supplies, as many meals as their Resources stock.
+//!
+//! The world tends to everyone's vitals each turn, see [`advance`]:
+//! fatigue eases every turn a player does not strain themselves, and each
+//! hour wounds heal and nerves settle for those fed and rested. A player
+//! starving loses hit points by the hour instead, and dies of it.
+
+use super::calendar::{HOURS_PER_DAY, TURNS_PER_HOUR};
+use super::combat;
+use super::pathfinding;
+use super::Tapestry;
+use crate::threads::{Biome, Thread, ThreadId, ThreadKind};
+use crate::weaver::properties::ThreadProperties;
+use crate::weaver::states::ThreadStates;
+use serde::{Deserialize, Serialize};
+
+/// Points of experience to each level; full Experienced is 100 points
+pub const XP_PER_LEVEL: u32 = 10;
+
+/// Experienced gained by slaying a foe
+pub const VICTORY: f32 = 0.03;
+
+/// Stamina every player has, before their Constitution
+pub const STAMINA_BASE: u32 = 10;
+
+/// Turns without food after which a player is hungry, and eats if they can:
+/// three meals a day
+pub const HUNGRY: u64 = 8 * TURNS_PER_HOUR;
+
+/// Turns without food after which a player is starving
+pub const STARVING: u64 = 2 * HOURS_PER_DAY * TURNS_PER_HOUR;
+
+/// Hit points a starving player loses each hour
+pub const STARVATION: u32 = 1;
+
+/// Stressed eased each hour
+pub const CALM: f32 = 0.05;
+
+/// What a player has left in them besides their hit points
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Vitals {
+    /// Strain from hard going, from 0 up to their stamina
+    pub fatigue: u32,
+    /// Turns since they last ate
+    pub hunger: u64,
+    /// Meals carried
+    pub supplies: u32,
+    /// Whether they strained themselves this turn, and so get no rest
+    pub strained: bool,
+}
+
+impl Vitals {
+    /// Fresh vitals for a Thread with `properties`, its supplies full
+    pub fn provisioned(properties: &ThreadProperties) -> Self {
+        Self {
+            supplies: max_supplies(properties),
+            ..Self::default()
+        }
+    }
+}
+
+/// One of a Thread's vitals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vital {
+    Health,
+    Stamina,
+    Nourishment,
+}
+
+impl Vital {
+    pub const ALL: [Vital; 3] = [Vital::Health, Vital::Stamina, Vital::Nourishment];
+
+    /// What the player is told as the vital runs down to `strain`, or,
+    /// if `worse` is false, recovers to it
+    pub fn report(self, strain: Strain, worse: bool) -> &'static str {
+        match (self, strain, worse) {
+            (Vital::Health, Strain::Critical, _) => "You are near death",
+            (Vital::Health, Strain::Low, true) => "You are badly hurt",
+            (Vital::Health, Strain::Low, false) => "You are out of danger",
+            (Vital::Health, Strain::Fine, _) => "Your wounds are mending",
+            (Vital::Stamina, Strain::Critical, _) => "You are exhausted",
+            (Vital::Stamina, Strain::Low, true) => "You are growing tired",
+            (Vital::Stamina, Strain::Low, false) => "You are no longer exhausted",
+            (Vital::Stamina, Strain::Fine, _) => "You have your breath back",
+            (Vital::Nourishment, Strain::Critical, _) => "You are starving",
+            (Vital::Nourishment, Strain::Low, true) => "You are hungry",
+            (Vital::Nourishment, Strain::Low, false) => "You are no longer starving",
+            (Vital::Nourishment, Strain::Fine, _) => "You are fed",
+        }
+    }
+
+    /// How the vital at `strain` shows on the status bar, if it does
+    pub fn condition(self, strain: Strain) -> Option<&'static str> {
+        match (self, strain) {
+            (_, Strain::Fine) | (Vital::Health, _) => None,
+            (Vital::Stamina, Strain::Low) => Some("Tired"),
+            (Vital::Stamina, Strain::Critical) => Some("Exhausted"),
+            (Vital::Nourishment, Strain::Low) => Some("Hungry"),
+            (Vital::Nourishment, Strain::Critical) => Some("Starving"),
+        }
+    }
+}
+
+/// How far one of a Thread's vitals has run down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strain {
+    Fine,
+    /// Hit points down to half, fatigue up to half the stamina, or hungry
+    Low,
+    /// Hit points down to a quarter, no stamina left, or starving
+    Critical,
+}
+
+/// How a Thread died
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Death {
+    /// Struck down by the Thread
+    Slain(ThreadId),
+    Starved,
+    Exhausted,
+}
+
+/// Something the player should hear of from [`advance`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VitalsEvent {
+    /// The player ate, with so many meals left
+    Ate { thread: ThreadId, supplies: u32 },
+    /// The player did not live through the turn
+    Perished { thread: ThreadId, death: Death },
+}
+
+/// Points of experience in a Thread's Experienced state
+pub fn experience(states: &ThreadStates) -> u32 {
+    (states.experienced * 100.0).round() as u32
+}
+
+pub fn level(states: &ThreadStates) -> u32 {
+    1 + experience(states) / XP_PER_LEVEL
+}
+
+/// Fatigue a player bears before it harms them
+pub fn max_stamina(properties: &ThreadProperties) -> u32 {
+    STAMINA_BASE + u32::from(properties.constitution)
+}
+
+/// Meals a player can carry: their Resources
+pub fn max_supplies(properties: &ThreadProperties) -> u32 {
+    u32::from(properties.resources)
+}
+
+/// Hit points healed each hour of rest
+pub fn healing(properties: &ThreadProperties) -> u32 {
+    1 + u32::from(properties.constitution) / 10
+}
+
+/// Fatigue from a step onto land of `biome`: what it costs to cross
+/// beyond plains
+pub fn toll(biome: Option<Biome>) -> u32 {
+    pathfinding::land_cost(biome).saturating_sub(Biome::Plains.move_cost())
+}
+
+/// How far `vital` of `thread` has run down; Threads that keep no vitals
+/// never tire or go hungry
+pub fn strain(tapestry: &Tapestry, thread: &Thread, vital: Vital) -> Strain {
+    let vitals = tapestry.vitals(thread.id).copied().unwrap_or_default();
+    match vital {
+        Vital::Health => {
+            let (hp, max) = (combat::hp(thread), combat::max_hp(thread));
+            if hp * 4 <= max {
+                Strain::Critical
+            } else if hp * 2 <= max {
+                Strain::Low
+            } else {
+                Strain::Fine
+            }
+        }
+        Vital::Stamina => {
+            let stamina = max_stamina(&thread.properties);
+            if vitals.fatigue >= stamina {
+                Strain::Critical
+            } else if vitals.fatigue * 2 >= stamina {
+                Strain::Low
+            } else {
+                Strain::Fine
+            }
+        }
+        Vital::Nourishment if vitals.hunger >= STARVING => Strain::Critical,
+        Vital::Nourishment if vitals.hunger >= HUNGRY => Strain::Low,
+        Vital::Nourishment => Strain::Fine,
+    }
+}
+
+/// Every vital of `id` and how far it has run down, in [`Vital::ALL`] order
+pub fn strains(tapestry: &Tapestry, id: ThreadId) -> Option<[Strain; 3]> {
+    let thread = tapestry.get_thread(id)?;
+    Some(Vital::ALL.map(|vital| strain(tapestry, thread, vital)))
+}
+
+/// Start keeping vitals for the player `id`, its supplies full
+pub fn provision(tapestry: &mut Tapestry, id: ThreadId) {
+    if let Some(properties) = tapestry.get_thread(id).map(|thread| thread.properties) {
+        tapestry.keep_vitals(id, Vitals::provisioned(&properties));
+    }
+}
+
+/// The vitals of `id`; players from saves made before vitals were kept
+/// start out provisioned
+fn kept(tapestry: &mut Tapestry, id: ThreadId) -> Option<&mut Vitals> {
+    let player = matches!(tapestry.get_thread(id)?.kind, ThreadKind::Player { .. });
+    if player && tapestry.vitals(id).is_none() {
+        provision(tapestry, id);
+    }
+    tapestry.vitals_mut(id)
+}
+
+/// Add up to `meals` to the supplies of `id`, as far as its Resources
+/// stretch, returning how many it took
+pub fn restock(tapestry: &mut Tapestry, id: ThreadId, meals: u32) -> u32 {
+    let Some(most) = tapestry.get_thread(id).map(|thread| max_supplies(&thread.properties)) else {
+        return 0;
+    };
+    let Some(vitals) = kept(tapestry, id) else {
+        return 0;
+    };
+    let taken = meals.min(most.saturating_sub(vitals.supplies));
+    vitals.supplies += taken;
+    taken
+}
+
+/// Let `id` strain itself by `fatigue`, any past its stamina coming out of
+/// its hit points; returns how it died if that was the end of it
+pub fn exert(tapestry: &mut Tapestry, id: ThreadId, fatigue: u32) -> Option<Death> {
+    let stamina = max_stamina(&tapestry.get_thread(id)?.properties);
+    let vitals = kept(tapestry, id)?;
+    if fatigue == 0 {
+        return None;
+    }
+    let total = vitals.fatigue + fatigue;
+    vitals.fatigue = total.min(stamina);
+    vitals.strained = true;
+    harm(tapestry, id, total.saturating_sub(stamina)).then_some(Death::Exhausted)
+}
+
+/// Take `hp` hit points from `id`, returning whether it has none left
+fn harm(tapestry: &mut Tapestry, id: ThreadId, hp: u32) -> bool {
+    let Some(thread) = tapestry.get_thread_mut(id) else {
+        return false;
+    };
+    thread.states.damaged += hp as f32 / combat::max_hp(thread) as f32;
+    thread.states.clamp();
+    combat::is_dead(thread)
+}
+
+/// Tend to every Thread's vitals for the Tapestry's current turn: players
+/// get hungrier, eat when hungry and catch their breath when not
+/// straining; on the hour everyone fed and rested heals and settles, and
+/// the starving waste away
+pub fn advance(tapestry: &mut Tapestry) -> Vec<VitalsEvent> {
+    let hourly = tapestry.turn().is_multiple_of(TURNS_PER_HOUR);
+    let mut living: Vec<(ThreadId, bool)> = tapestry
+        .threads()
+        .filter_map(|thread| match thread.kind {
+            ThreadKind::Player { .. } => Some((thread.id, true)),
+            ThreadKind::Npc { .. } => Some((thread.id, false)),
+            _ => None,
+        })
+        .collect();
+    living.sort_by_key(|(id, _)| id.0);
+
+    let mut events = Vec::new();
+    for (id, player) in living {
+        let Some(properties) = tapestry.get_thread(id).map(|thread| thread.properties) else {
+            continue;
+        };
+        let (mut starving, mut rested) = (false, true);
+        if player {
+            let Some(vitals) = kept(tapestry, id) else {
+                continue;
+            };
+            vitals.hunger += 1;
+            if !vitals.strained {
+                vitals.fatigue = vitals.fatigue.saturating_sub(1);
+            }
+            vitals.strained = false;
+            if vitals.hunger >= HUNGRY && vitals.supplies > 0 {
+                vitals.supplies -= 1;
+                vitals.hunger = 0;
+                events.push(VitalsEvent::Ate {
+                    thread: id,
+                    supplies: vitals.supplies,
+                });
+            }
+            starving = vitals.hunger >= STARVING;
+            rested = vitals.hunger < HUNGRY && vitals.fatigue * 2 < max_stamina(&properties);
+        }
+        if !hourly {
+            continue;
+        }
+        if starving {
+            if harm(tapestry, id, STARVATION) {
+                events.push(VitalsEvent::Perished {
+                    thread: id,
+                    death: Death::Starved,
+                });
+            }
+        } else if rested {
+            if let Some(thread) = tapestry.get_thread_mut(id) {
+                let max = combat::max_hp(thread) as f32;
+                thread.states.damaged -= healing(&properties) as f32 / max;
+                thread.states.stressed -= CALM;
+                thread.states.clamp();
+            }
+        }
+    }
+    events
+}
+
+/// Take the dead Thread `id` off the Tapestry, dropping what it carried
+/// where it died, and write its end in the chronicle, returning the entry;
+/// whoever slew it grows more Experienced
+pub fn perish(tapestry: &mut Tapestry, id: ThreadId, death: Death) -> Option<String> {
+    let victor = match death {
+        Death::Slain(victor) if victor != id => tapestry.get_thread_mut(victor).map(|thread| {
+            thread.states.experienced += VICTORY;
+            thread.states.clamp();
+            thread.display_name().to_string()
+        }),
+        _ => None,
+    };
+    let fallen = tapestry.remove_thread(id)?;
+    for item in combat::carried(tapestry, id) {
+        if let Some(item) = tapestry.get_thread_mut(item) {
+            if let ThreadKind::Item { carrier, .. } = &mut item.kind {
+                *carrier = None;
+            }
+            item.position = fallen.position;
+        }
+    }
+    tapestry.relationships_mut().forget(id);
+    tapestry.forget_vitals(id);
+    let name = fallen.display_name();
+    let entry = match (death, victor) {
+        (Death::Slain(_), Some(victor)) => format!("{} was slain by {}", name, victor),
+        (Death::Slain(_), None) => format!("{} was slain", name),
+        (Death::Starved, _) => format!("{} starved to death", name),
+        (Death::Exhausted, _) => format!("{} died of exhaustion", name),
+    };
+    tapestry.write_chronicle_at(entry.clone(), fallen.position);
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::foundation::Position;
+
+    fn add(tapestry: &mut Tapestry, kind: ThreadKind) -> ThreadId {
+        let id = tapestry.next_id();
+        tapestry.add_thread(Thread {
+            id,
+            kind,
+            properties: ThreadProperties::new(10),
+            states: Default::default(),
+            position: Some(Position::new(0, 0)),
+            name: None,
+            notes: Vec::new(),
+        })
+    }
+
+    fn player(tapestry: &mut Tapestry) -> ThreadId {
+        let id = add(tapestry, ThreadKind::Player { name: "Tester".to_string() });
+        provision(tapestry, id);
+        id
+    }
+
+    /// Let `turns` turns pass, tending to vitals each
+    fn pass(tapestry: &mut Tapestry, turns: u64) -> Vec<VitalsEvent> {
+        (0..turns)
+            .flat_map(|_| {
+                tapestry.pass_turn();
+                advance(tapestry)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn levels_follow_experience() {
+        let mut states = ThreadStates::default();
+        assert_eq!((experience(&states), level(&states)), (0, 1));
+        states.experienced = 0.29;
+        assert_eq!((experience(&states), level(&states)), (29, 3));
+        states.experienced = 1.0;
+        assert_eq!(level(&states), 11);
+    }
+
+    #[test]
+    fn hard_ground_tires_and_exhaustion_harms() {
+        let mut tapestry = Tapestry::new();
+        let id = player(&mut tapestry);
+        assert_eq!(toll(Some(Biome::Plains)), 0);
+        assert_eq!(toll(None), 0);
+        assert_eq!(toll(Some(Biome::Mountains)), 3);
+
+        for _ in 0..3 {
+            assert_eq!(exert(&mut tapestry, id, 4), None);
+        }
+        assert_eq!(strains(&tapestry, id), Some([Strain::Fine, Strain::Low, Strain::Fine]));
+        // Straining turns give no rest; the others ease a point each
+        advance(&mut tapestry);
+        assert_eq!(tapestry.vitals(id).unwrap().fatigue, 12);
+        advance(&mut tapestry);
+        assert_eq!(tapestry.vitals(id).unwrap().fatigue, 11);
+
+        assert_eq!(exert(&mut tapestry, id, 14), None);
+        let thread = tapestry.get_thread(id).unwrap();
+        assert_eq!(combat::hp(thread), 20);
+        assert_eq!(strain(&tapestry, thread, Vital::Stamina), Strain::Critical);
+        assert_eq!(exert(&mut tapestry, id, 20), Some(Death::Exhausted));
+    }
+
+    #[test]
+    fn the_hungry_eat_their_supplies_and_the_starving_waste_away() {
+        let mut tapestry = Tapestry::new();
+        let id = player(&mut tapestry);
+        assert_eq!(tapestry.vitals(id).unwrap().supplies, 10);
+        let events = pass(&mut tapestry, HUNGRY);
+        assert_eq!(events, vec![VitalsEvent::Ate { thread: id, supplies: 9 }]);
+        assert_eq!(tapestry.vitals(id).unwrap().hunger, 0);
+        assert_eq!(restock(&mut tapestry, id, 5), 1);
+
+        tapestry.vitals_mut(id).unwrap().supplies = 0;
+        pass(&mut tapestry, HUNGRY);
+        assert_eq!(strains(&tapestry, id), Some([Strain::Fine, Strain::Fine, Strain::Low]));
+        tapestry.vitals_mut(id).unwrap().hunger = STARVING;
+        pass(&mut tapestry, TURNS_PER_HOUR);
+        assert_eq!(combat::hp(tapestry.get_thread(id).unwrap()), 25 - STARVATION);
+        let events = pass(&mut tapestry, 25 * TURNS_PER_HOUR);
+        assert!(events.contains(&VitalsEvent::Perished {
+            thread: id,
+            death: Death::Starved
+        }));
+    }
+
+    #[test]
+    fn the_fed_and_rested_heal_by_the_hour() {
+        let mut tapestry = Tapestry::new();
+        let id = player(&mut tapestry);
+        let npc = add(&mut tapestry, ThreadKind::Npc { name: "Fenn".to_string() });
+        for id in [id, npc] {
+            let thread = tapestry.get_thread_mut(id).unwrap();
+            thread.states.damaged = 0.6;
+            thread.states.stressed = 0.5;
+        }
+        assert_eq!(strains(&tapestry, id), Some([Strain::Low, Strain::Fine, Strain::Fine]));
+        pass(&mut tapestry, TURNS_PER_HOUR);
+        for id in [id, npc] {
+            let thread = tapestry.get_thread(id).unwrap();
+            assert_eq!(combat::hp(thread), 10 + healing(&thread.properties));
+            assert!(thread.states.stressed < 0.5);
+        }
+
+        // Not while tired
+        tapestry.vitals_mut(id).unwrap().fatigue = 20;
+        pass(&mut tapestry, TURNS_PER_HOUR);
+        assert_eq!(combat::hp(tapestry.get_thread(id).unwrap()), 12);
+    }
+
+    #[test]
+    fn the_dead_leave_the_tapestry_and_their_slayer_the_wiser() {
+        let mut tapestry = Tapestry::new();
+        let id = player(&mut tapestry);
+        let victor = add(&mut tapestry, ThreadKind::Npc { name: "Corwin".to_string() });
+        let entry = perish(&mut tapestry, id, Death::Slain(victor));
+        assert_eq!(entry.as_deref(), Some("Tester was slain by Corwin"));
+        assert!(tapestry.get_thread(id).is_none() && tapestry.vitals(id).is_none());
+        assert_eq!(experience(&tapestry.get_thread(victor).unwrap().states), 3);
+
+        let id = player(&mut tapestry);
+        assert_eq!(perish(&mut tapestry, id, Death::Starved).as_deref(), Some("Tester starved to death"));
+        assert_eq!(tapestry.chronicle().len(), 2);
+    }
+}
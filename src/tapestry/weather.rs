@@ -0,0 +1,311 @@
+//! Weather - systems that form over the land, drift across it and die out
+//!
+//! Every [`SPAWN_INTERVAL`] turns one region tile, picked by the world's
+//! chance, may breed weather: a system its biome breeds, picked as the
+//! season favours, contests its own stat against the land's Constitution
+//! and forms over the tile if it wins. Out of season there may be none.
+//! Systems drift a tile along their heading every [`DRIFT_INTERVAL`] turns.
+//! Wherever one passes it contests its Constitution against the land's, and
+//! every loss wears it down; wilderness gives it nothing to feed on. Worn
+//! through, it dissipates.
+//!
+//! Threads inside a system contest with its modifiers and see only as far
+//! as its clarity allows.
+
+use super::Tapestry;
+use crate::foundation::Position;
+use crate::threads::{Thread, ThreadId, ThreadKind, WeatherSystem};
+use crate::weaver::contests::{contest, ContestResult, Contestant};
+use crate::weaver::properties::Stat;
+use crate::weaver::states::ThreadStates;
+use rand::Rng;
+
+/// Turns between chances for new weather to form
+pub const SPAWN_INTERVAL: u64 = 20;
+
+/// Turns between each tile a system drifts
+pub const DRIFT_INTERVAL: u64 = 2;
+
+/// Most systems over the world at once
+pub const MAX_SYSTEMS: usize = 3;
+
+/// How much a system wears (Damaged) each time the land beats it
+pub const WEAR: f32 = 0.25;
+
+/// Headings a system can drift along: every neighbouring tile
+/// Keeps the weather's chance apart from the rest of the turn's
+const SALT: u64 = 0x5745_4154_4845_5221;
+
+const HEADINGS: [(i32, i32); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+/// Something the weather did in a turn
+#[derive(Debug, Clone, PartialEq)]
+pub enum WeatherEvent {
+    /// A new system formed
+    Formed(ThreadId),
+    /// A system wore out over `position`
+    Dissipated { system: WeatherSystem, position: Position },
+}
+
+/// Let the weather act for the Tapestry's current turn
+pub fn advance(tapestry: &mut Tapestry) -> Vec<WeatherEvent> {
+    let mut rng = tapestry.turn_rng(SALT);
+    let mut events = Vec::new();
+    if tapestry.turn().is_multiple_of(DRIFT_INTERVAL) {
+        events.extend(drift(tapestry, &mut rng));
+    }
+    if tapestry.turn().is_multiple_of(SPAWN_INTERVAL) {
+        events.extend(spawn(tapestry, &mut rng).map(WeatherEvent::Formed));
+    }
+    events
+}
+
+/// What the weather over `position` adds to a roll with `stat`
+pub fn modifier(tapestry: &Tapestry, position: Position, stat: Stat) -> i32 {
+    tapestry
+        .weather_at(position)
+        .and_then(Thread::weather)
+        .map_or(0, |system| system.modifier(stat))
+}
+
+/// How clear the air over `position` is, 1 with no weather
+pub fn clarity(tapestry: &Tapestry, position: Position) -> f32 {
+    tapestry
+        .weather_at(position)
+        .and_then(Thread::weather)
+        .map_or(1.0, WeatherSystem::clarity)
+}
+
+/// Every weather system, oldest first
+fn systems(tapestry: &Tapestry) -> Vec<ThreadId> {
+    let mut systems: Vec<ThreadId> = tapestry
+        .threads()
+        .filter(|thread| thread.weather().is_some())
+        .map(|thread| thread.id)
+        .collect();
+    systems.sort_by_key(|id| id.0);
+    systems
+}
+
+/// Move every system a tile and let the land below contest it
+fn drift(tapestry: &mut Tapestry, rng: &mut impl Rng) -> Vec<WeatherEvent> {
+    let mut events = Vec::new();
+    for id in systems(tapestry) {
+        let Some(thread) = tapestry.get_thread(id) else {
+            continue;
+        };
+        let (ThreadKind::Weather { system, heading }, Some(position)) = (&thread.kind, thread.position) else {
+            continue;
+        };
+        let (system, position) = (*system, Position::new(position.x + heading.0, position.y + heading.1));
+        let held = match tapestry.threads_at(position).into_iter().find(|t| t.biome().is_some()) {
+            Some(land) => {
+                let attack = Contestant::new(&thread.properties, &thread.states, Stat::Constitution);
+                let defence = Contestant::new(&land.properties, &land.states, Stat::Constitution);
+                contest(rng, attack, defence) == ContestResult::Success
+            }
+            None => false,
+        };
+        let Some(thread) = tapestry.get_thread_mut(id) else {
+            continue;
+        };
+        thread.position = Some(position);
+        if !held {
+            thread.states.damaged = (thread.states.damaged + WEAR).min(1.0);
+        }
+        if thread.states.damaged >= 1.0 {
+            tapestry.remove_thread(id);
+            events.push(WeatherEvent::Dissipated { system, position });
+        }
+    }
+    events
+}
+
+/// Give one region tile the chance to breed weather
+fn spawn(tapestry: &mut Tapestry, rng: &mut impl Rng) -> Option<ThreadId> {
+    if systems(tapestry).len() >= MAX_SYSTEMS {
+        return None;
+    }
+    let mut lands: Vec<&Thread> = tapestry.threads().filter(|thread| thread.biome().is_some()).collect();
+    if lands.is_empty() {
+        return None;
+    }
+    lands.sort_by_key(|thread| thread.id.0);
+    let land = lands[rng.gen_range(0..lands.len())];
+    let position = land.position?;
+    let season = tapestry.date().season;
+    let bred = WeatherSystem::bred_by(land.biome()?);
+    let total: u32 = bred.iter().map(|system| season.weather_weight(*system)).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut pick = rng.gen_range(0..total);
+    let system = bred.iter().copied().find(|system| {
+        let weight = season.weather_weight(*system);
+        if pick < weight {
+            return true;
+        }
+        pick -= weight;
+        false
+    })?;
+
+    let properties = system.properties();
+    let states = ThreadStates::default();
+    let attack = Contestant::new(&properties, &states, system.stat());
+    let defence = Contestant::new(&land.properties, &land.states, Stat::Constitution);
+    if contest(rng, attack, defence) == ContestResult::Failure {
+        return None;
+    }
+    let heading = HEADINGS[rng.gen_range(0..HEADINGS.len())];
+    let id = tapestry.next_id();
+    tapestry.add_thread(Thread {
+        id,
+        kind: ThreadKind::Weather { system, heading },
+        properties,
+        states,
+        position: Some(position),
+        name: None,
+        notes: Vec::new(),
+    });
+    Some(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tapestry::calendar::{Season, DAYS_PER_SEASON, HOURS_PER_DAY, TURNS_PER_HOUR};
+    use crate::weaver::properties::ThreadProperties;
+
+    fn add(tapestry: &mut Tapestry, kind: ThreadKind, properties: ThreadProperties, x: i32) -> ThreadId {
+        let id = tapestry.next_id();
+        tapestry.add_thread(Thread {
+            id,
+            kind,
+            properties,
+            states: ThreadStates::default(),
+            position: Some(Position::new(x, 0)),
+            name: None,
+            notes: Vec::new(),
+        })
+    }
+
+    fn pass(tapestry: &mut Tapestry, turns: u64) -> Vec<WeatherEvent> {
+        (0..turns)
+            .flat_map(|_| {
+                tapestry.pass_turn();
+                advance(tapestry)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn systems_drift_and_wear_out_over_wilderness() {
+        let mut tapestry = Tapestry::new();
+        let kind = ThreadKind::Weather {
+            system: WeatherSystem::Thunderstorm,
+            heading: (1, 0),
+        };
+        let storm = add(&mut tapestry, kind, WeatherSystem::Thunderstorm.properties(), 0);
+        assert!(tapestry.weather_at(Position::new(3, 0)).is_some());
+        assert!(tapestry.weather_at(Position::new(4, 0)).is_none());
+        assert!(tapestry.threads_at(Position::new(0, 0)).is_empty());
+
+        assert!(pass(&mut tapestry, DRIFT_INTERVAL).is_empty());
+        assert_eq!(tapestry.get_thread(storm).unwrap().position, Some(Position::new(1, 0)));
+        assert_eq!(tapestry.get_thread(storm).unwrap().states.damaged, WEAR);
+
+        let events = pass(&mut tapestry, DRIFT_INTERVAL * 3);
+        assert_eq!(
+            events,
+            vec![WeatherEvent::Dissipated {
+                system: WeatherSystem::Thunderstorm,
+                position: Position::new(4, 0),
+            }]
+        );
+        assert!(tapestry.get_thread(storm).is_none());
+    }
+
+    #[test]
+    fn yielding_land_breeds_its_own_weather() {
+        let mut tapestry = Tapestry::with_seed(3);
+        // Nothing in the tundra can hold weather back
+        let yielding = ThreadProperties {
+            constitution: 0,
+            luck: 0,
+            ..Default::default()
+        };
+        let kind = ThreadKind::Region {
+            description: "Bitter Tundra".to_string(),
+        };
+        add(&mut tapestry, kind, yielding, 0);
+
+        let events = pass(&mut tapestry, SPAWN_INTERVAL);
+        let [WeatherEvent::Formed(id)] = events[..] else {
+            panic!("expected weather to form, got {:?}", events);
+        };
+        let system = tapestry.get_thread(id).unwrap().weather().unwrap();
+        assert!(WeatherSystem::bred_by(crate::threads::Biome::Tundra).contains(&system));
+        assert_eq!(tapestry.get_thread(id).unwrap().properties, system.properties());
+    }
+
+    #[test]
+    fn out_of_season_the_land_breeds_nothing() {
+        let mut tapestry = Tapestry::with_seed(3);
+        let yielding = ThreadProperties {
+            constitution: 0,
+            luck: 0,
+            ..Default::default()
+        };
+        let kind = ThreadKind::Region {
+            description: "Scorching Desert".to_string(),
+        };
+        add(&mut tapestry, kind, yielding, 0);
+        // Deserts breed only thunderstorms, which Winter never does
+        let winter = 3 * DAYS_PER_SEASON * HOURS_PER_DAY * TURNS_PER_HOUR;
+        tapestry.turn = winter;
+        assert_eq!(tapestry.date().season, Season::Winter);
+        assert!(pass(&mut tapestry, SPAWN_INTERVAL * 5).is_empty());
+
+        tapestry.turn = 0;
+        assert_eq!(pass(&mut tapestry, SPAWN_INTERVAL).len(), 1);
+    }
+
+    #[test]
+    fn weather_is_the_same_for_the_same_seed() {
+        let world = |seed| {
+            let mut tapestry = Tapestry::with_seed(seed);
+            for x in 0..10 {
+                let kind = ThreadKind::Region {
+                    description: "Stubborn Mountains".to_string(),
+                };
+                add(&mut tapestry, kind, ThreadProperties::new(5), x);
+            }
+            let events = pass(&mut tapestry, SPAWN_INTERVAL * 5);
+            let mut systems: Vec<(Option<Position>, f32)> = tapestry
+                .threads()
+                .filter(|thread| thread.weather().is_some())
+                .map(|thread| (thread.position, thread.states.damaged))
+                .collect();
+            systems.sort_by_key(|(position, _)| position.map(|p| (p.x, p.y)));
+            (events, systems)
+        };
+        assert_eq!(world(11), world(11));
+    }
+
+    #[test]
+    fn weather_modifies_contests_and_sight_inside_it() {
+        let mut tapestry = Tapestry::new();
+        let kind = ThreadKind::Weather {
+            system: WeatherSystem::Blizzard,
+            heading: (0, 1),
+        };
+        add(&mut tapestry, kind, WeatherSystem::Blizzard.properties(), 0);
+        let inside = Position::new(2, 0);
+        assert_eq!(modifier(&tapestry, inside, Stat::Wisdom), -3);
+        assert_eq!(modifier(&tapestry, inside, Stat::Constitution), 2);
+        assert_eq!(clarity(&tapestry, inside), WeatherSystem::Blizzard.clarity());
+        let outside = Position::new(9, 0);
+        assert_eq!(modifier(&tapestry, outside, Stat::Wisdom), 0);
+        assert_eq!(clarity(&tapestry, outside), 1.0);
+    }
+}
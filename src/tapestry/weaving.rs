@@ -0,0 +1,342 @@
+//! Story weaving - stories shaping the land they are set in
+//!
+//! Once the world is generated, every story not yet woven lays claim to the
+//! land its goals are set in. Stage by stage, each goal contests the stat
+//! that drives it against the Constitution of free region tiles of its
+//! biome, in an order the world's seed decides, and claims the winners
+//! until it holds as many as it needs Threads. A claimed region takes on
+//! the story's temper: the stat grows, the land grows Prestigious and takes
+//! the state of the goal. Goals about people seed an NPC on each claim,
+//! goals about finding things an item.
+//!
+//! The claims are kept on the goals, so a saved world remembers which story
+//! shaped which land.
+
+use super::Tapestry;
+use crate::foundation::Position;
+use crate::threads::story::{Goal, GoalKind};
+use crate::threads::{Thread, ThreadId, ThreadKind};
+use crate::weaver::contests::{contest, ContestResult, Contestant};
+use crate::weaver::properties::{Stat, ThreadProperties};
+use crate::weaver::states::ThreadStates;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::HashSet;
+
+/// Tries a goal gets at the land for each Thread it needs
+pub const CONTESTS_PER_CLAIM: u32 = 3;
+
+/// How much a claim raises the region's stat, up to [`MAX_STAT`]
+pub const STAT_GAIN: u8 = 2;
+
+pub const MAX_STAT: u8 = 20;
+
+/// How much Prestigious a claim adds, and the state of its goal
+pub const PRESTIGE: f32 = 0.2;
+
+pub const IMPRINT: f32 = 0.3;
+
+/// Keeps weaving's chance apart from the weather's for the same seed
+const SALT: u64 = 0x5354_4f52_5957_4541;
+
+const FOLK: [&str; 8] = ["Aldric", "Brenna", "Corwin", "Dagny", "Edda", "Fenn", "Gisla", "Hale"];
+
+const TREASURES: [&str; 6] = ["Old Map", "Sealed Letter", "Silver Key", "Bone Flute", "Star Chart", "Iron Seal"];
+
+/// A region a story claimed for one of its goals
+#[derive(Debug, Clone, PartialEq)]
+pub struct Claim {
+    pub story: ThreadId,
+    /// Index of the stage the goal belongs to
+    pub stage: usize,
+    pub goal: GoalKind,
+    pub region: ThreadId,
+    pub position: Position,
+}
+
+/// Let every story not yet woven claim and shape its land, oldest first,
+/// returning the claims made
+pub fn weave(tapestry: &mut Tapestry) -> Vec<Claim> {
+    let mut rng = StdRng::seed_from_u64(tapestry.seed() ^ SALT);
+    let mut claimed: HashSet<ThreadId> = claims(tapestry).iter().map(|claim| claim.region).collect();
+    let mut made = Vec::new();
+    for id in super::story::stories(tapestry) {
+        let Some(thread) = tapestry.get_thread(id) else {
+            continue;
+        };
+        let Some(story) = thread.story().filter(|story| !story.woven).cloned() else {
+            continue;
+        };
+        let properties = thread.properties;
+        let mut stages = story.stages;
+        for (stage, goals) in stages.iter_mut().enumerate() {
+            for goal in &mut goals.goals {
+                for region in claim(tapestry, &properties, goal, &claimed, &mut rng) {
+                    let Some(position) = imprint(tapestry, region, goal.kind, &properties, &mut rng) else {
+                        continue;
+                    };
+                    claimed.insert(region);
+                    goal.regions.push(region);
+                    made.push(Claim {
+                        story: id,
+                        stage,
+                        goal: goal.kind,
+                        region,
+                        position,
+                    });
+                }
+            }
+        }
+        if let Some(ThreadKind::Story(story)) = tapestry.get_thread_mut(id).map(|thread| &mut thread.kind) {
+            story.stages = stages;
+            story.woven = true;
+        }
+    }
+    made
+}
+
+/// Every claim the Tapestry's stories hold, oldest story first
+pub fn claims(tapestry: &Tapestry) -> Vec<Claim> {
+    let mut claims = Vec::new();
+    for id in super::story::stories(tapestry) {
+        let Some(story) = tapestry.get_thread(id).and_then(Thread::story) else {
+            continue;
+        };
+        for (stage, goals) in story.stages.iter().enumerate() {
+            for goal in &goals.goals {
+                claims.extend(goal.regions.iter().filter_map(|region| {
+                    let position = tapestry.get_thread(*region)?.position?;
+                    Some(Claim {
+                        story: id,
+                        stage,
+                        goal: goal.kind,
+                        region: *region,
+                        position,
+                    })
+                }));
+            }
+        }
+    }
+    claims
+}
+
+/// The regions `goal` wins, contesting a story with `properties` against
+/// free land of its biome
+fn claim(
+    tapestry: &Tapestry,
+    properties: &ThreadProperties,
+    goal: &Goal,
+    claimed: &HashSet<ThreadId>,
+    rng: &mut StdRng,
+) -> Vec<ThreadId> {
+    let mut candidates: Vec<&Thread> = tapestry
+        .threads()
+        .filter(|thread| thread.biome() == Some(goal.biome) && !claimed.contains(&thread.id))
+        .filter(|thread| thread.position.is_some_and(|position| is_free(tapestry, position)))
+        .collect();
+    candidates.sort_by_key(|thread| thread.id.0);
+    candidates.shuffle(rng);
+
+    let states = ThreadStates::default();
+    let stat = goal.kind.drives()[0];
+    let mut won = Vec::new();
+    let mut tries = goal.needed * CONTESTS_PER_CLAIM;
+    for region in candidates {
+        if won.len() as u32 >= goal.needed || tries == 0 {
+            break;
+        }
+        tries -= 1;
+        let story = Contestant::new(properties, &states, stat);
+        let land = Contestant::new(&region.properties, &region.states, Stat::Constitution);
+        if contest(rng, story, land) == ContestResult::Success {
+            won.push(region.id);
+        }
+    }
+    won
+}
+
+/// Whether nothing but land stands at `position`
+fn is_free(tapestry: &Tapestry, position: Position) -> bool {
+    tapestry
+        .threads_at(position)
+        .iter()
+        .all(|thread| matches!(thread.kind, ThreadKind::Region { .. }))
+}
+
+/// Shape `region` to a goal of `kind` and seed what the goal needs on it,
+/// returning where it lies
+fn imprint(
+    tapestry: &mut Tapestry,
+    region: ThreadId,
+    kind: GoalKind,
+    properties: &ThreadProperties,
+    rng: &mut StdRng,
+) -> Option<Position> {
+    let thread = tapestry.get_thread_mut(region)?;
+    let position = thread.position?;
+    let stat = kind.drives()[0];
+    let value = thread.properties.get_stat(stat);
+    thread.properties.set_stat(stat, value.saturating_add(STAT_GAIN).min(MAX_STAT));
+    let states = &mut thread.states;
+    states.prestigious += PRESTIGE;
+    match kind {
+        GoalKind::Knowledge => states.experienced += IMPRINT,
+        GoalKind::Relationship => states.connected += IMPRINT,
+        GoalKind::Resource => states.blessed += IMPRINT,
+        GoalKind::Challenge => states.stressed += IMPRINT,
+    }
+    states.clamp();
+
+    let seeded = match kind {
+        GoalKind::Relationship => Some((
+            ThreadKind::Npc {
+                name: FOLK.choose(rng).copied().unwrap_or("Stranger").to_string(),
+            },
+            ThreadProperties {
+                charisma: properties.charisma.max(14),
+                connections: 12,
+                ..Default::default()
+            },
+        )),
+        GoalKind::Resource => Some((
+            ThreadKind::Item {
+                name: TREASURES.choose(rng).copied().unwrap_or("Trinket").to_string(),
+                carrier: None,
+            },
+            ThreadProperties {
+                resources: 14,
+                ..Default::default()
+            },
+        )),
+        GoalKind::Knowledge | GoalKind::Challenge => None,
+    };
+    if let Some((kind, properties)) = seeded {
+        let id = tapestry.next_id();
+        tapestry.add_thread(Thread {
+            id,
+            kind,
+            properties,
+            states: ThreadStates::default(),
+            position: Some(position),
+            name: None,
+            notes: Vec::new(),
+        });
+    }
+    Some(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::threads::story::{Stage, Story};
+    use crate::threads::Biome;
+
+    fn world(seed: u64) -> (Tapestry, ThreadId) {
+        let mut tapestry = Tapestry::with_seed(seed);
+        for x in 0..6 {
+            for (y, description) in ["Scheming Forests", "Whispering Plains"].into_iter().enumerate() {
+                let id = tapestry.next_id();
+                tapestry.add_thread(Thread {
+                    id,
+                    kind: ThreadKind::Region {
+                        description: description.to_string(),
+                    },
+                    properties: Default::default(),
+                    states: Default::default(),
+                    position: Some(Position::new(x, y as i32)),
+                    name: None,
+                    notes: Vec::new(),
+                });
+            }
+        }
+        let id = tapestry.next_id();
+        let story = Story {
+            title: "The Woven Oath".to_string(),
+            stages: vec![
+                Stage {
+                    name: "The Call".to_string(),
+                    goals: vec![
+                        Goal::new(GoalKind::Relationship, Biome::Forest, 2),
+                        Goal::new(GoalKind::Resource, Biome::Plains, 1),
+                    ],
+                },
+                Stage {
+                    name: "The Reckoning".to_string(),
+                    goals: vec![Goal::new(GoalKind::Knowledge, Biome::Forest, 2)],
+                },
+            ],
+            stage: 0,
+            woven: false,
+            outcome: None,
+        };
+        tapestry.add_thread(Thread {
+            id,
+            kind: ThreadKind::Story(story),
+            properties: ThreadProperties::new(20),
+            states: Default::default(),
+            position: None,
+            name: None,
+            notes: Vec::new(),
+        });
+        (tapestry, id)
+    }
+
+    fn seeded(tapestry: &Tapestry, label: &str) -> Vec<Position> {
+        let mut positions: Vec<Position> = tapestry
+            .threads()
+            .filter(|thread| thread.kind.label() == label)
+            .filter_map(|thread| thread.position)
+            .collect();
+        positions.sort_by_key(|position| (position.x, position.y));
+        positions
+    }
+
+    #[test]
+    fn stories_claim_and_shape_land_of_their_biomes() {
+        let (mut tapestry, story) = world(3);
+        let claims = weave(&mut tapestry);
+        assert!(!claims.is_empty());
+        assert_eq!(claims, super::claims(&tapestry));
+        for claim in &claims {
+            assert_eq!(claim.story, story);
+            let region = tapestry.get_thread(claim.region).unwrap();
+            let goal = tapestry.get_thread(story).unwrap().story().unwrap().stages[claim.stage]
+                .goals
+                .iter()
+                .find(|goal| goal.kind == claim.goal)
+                .unwrap();
+            assert_eq!(region.biome(), Some(goal.biome));
+            assert_eq!(region.properties.get_stat(claim.goal.drives()[0]), 10 + STAT_GAIN);
+            assert!(region.states.prestigious > 0.0);
+        }
+        let regions: HashSet<ThreadId> = claims.iter().map(|claim| claim.region).collect();
+        assert_eq!(regions.len(), claims.len());
+
+        // Folk where the story seeks people, treasure where it seeks things
+        let npcs: Vec<Position> = claims
+            .iter()
+            .filter(|claim| claim.goal == GoalKind::Relationship)
+            .map(|claim| claim.position)
+            .collect();
+        assert_eq!(seeded(&tapestry, "NPC").len(), npcs.len());
+        assert!(seeded(&tapestry, "NPC").iter().all(|position| npcs.contains(position)));
+        let items = claims.iter().filter(|claim| claim.goal == GoalKind::Resource).count();
+        assert_eq!(seeded(&tapestry, "item").len(), items);
+
+        // A woven story weaves no more
+        assert!(weave(&mut tapestry).is_empty());
+        assert_eq!(super::claims(&tapestry), claims);
+    }
+
+    #[test]
+    fn weaving_is_the_same_for_the_same_seed() {
+        let woven = |seed| {
+            let (mut tapestry, _) = world(seed);
+            let claims = weave(&mut tapestry);
+            (claims, seeded(&tapestry, "NPC"), seeded(&tapestry, "item"))
+        };
+        assert_eq!(woven(11), woven(11));
+        assert_ne!(woven(11).0, woven(12).0);
+    }
+}
@@ -4,25 +4,301 @@
 //! that interact through The Weaver's mathematical laws.
 
 use crate::foundation::Position;
-use crate::weaver::properties::ThreadProperties;
+use crate::weaver::properties::{Stat, ThreadProperties};
 use crate::weaver::states::ThreadStates;
+use serde::{Deserialize, Serialize};
+
+/// Stories: stages, goals and the world events that meet them
+pub mod story;
+
+use story::Story;
+
+/// Corruption at which an NPC turns hostile
+pub const HOSTILE_CORRUPTION: f32 = 0.5;
 
 /// A Thread is any entity in the game world that can interact with other Threads
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Thread {
     pub id: ThreadId,
     pub kind: ThreadKind,
     pub properties: ThreadProperties,
     pub states: ThreadStates,
     pub position: Option<Position>,
+    /// Name given by the player in Insert mode, overriding the kind's name
+    pub name: Option<String>,
+    /// Player notes: tile annotations, or journal entries on the player
+    pub notes: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+impl Thread {
+    /// The name shown to the player
+    pub fn display_name(&self) -> &str {
+        if let Some(name) = &self.name {
+            return name;
+        }
+        match &self.kind {
+            ThreadKind::Player { name } | ThreadKind::Npc { name } | ThreadKind::Item { name, .. } => name,
+            ThreadKind::Region { description } => description,
+            ThreadKind::Weather { system, .. } => system.name(),
+            ThreadKind::Story(story) => &story.title,
+        }
+    }
+
+    /// The biome of a region Thread
+    pub fn biome(&self) -> Option<Biome> {
+        match &self.kind {
+            ThreadKind::Region { description } => Biome::of(description),
+            _ => None,
+        }
+    }
+
+    /// Whether this Thread is an NPC corrupted enough to turn on others
+    pub fn is_hostile(&self) -> bool {
+        matches!(self.kind, ThreadKind::Npc { .. }) && self.states.corrupted >= HOSTILE_CORRUPTION
+    }
+
+    /// Whether nothing can be seen through this Thread's tile
+    pub fn blocks_sight(&self) -> bool {
+        self.biome().is_some_and(Biome::blocks_sight)
+    }
+
+    /// The system of a weather Thread
+    pub fn weather(&self) -> Option<WeatherSystem> {
+        match &self.kind {
+            ThreadKind::Weather { system, .. } => Some(*system),
+            _ => None,
+        }
+    }
+
+    /// The story of a story Thread
+    pub fn story(&self) -> Option<&Story> {
+        match &self.kind {
+            ThreadKind::Story(story) => Some(story),
+            _ => None,
+        }
+    }
+
+    /// Whether this weather Thread covers `position`
+    ///
+    /// Weather covers a disc around its position; other Threads cover
+    /// nothing.
+    pub fn covers(&self, position: Position) -> bool {
+        let (Some(system), Some(centre)) = (self.weather(), self.position) else {
+            return false;
+        };
+        let (dx, dy) = (position.x - centre.x, position.y - centre.y);
+        let radius = system.radius();
+        dx * dx + dy * dy <= radius * (radius + 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct ThreadId(pub u64);
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ThreadKind {
     Player { name: String },
     Region { description: String },
     Npc { name: String },
+    /// A weather system centred on its position, drifting by `heading`
+    Weather { system: WeatherSystem, heading: (i32, i32) },
+    /// A story working towards its end; it has no place on the map
+    Story(Story),
+    /// Something to be found; once taken it goes with its `carrier`
+    Item {
+        name: String,
+        #[serde(default)]
+        carrier: Option<ThreadId>,
+    },
+}
+
+impl ThreadKind {
+    /// Short name of the kind, e.g. "region"
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThreadKind::Player { .. } => "player",
+            ThreadKind::Region { .. } => "region",
+            ThreadKind::Npc { .. } => "NPC",
+            ThreadKind::Weather { .. } => "weather",
+            ThreadKind::Story(_) => "story",
+            ThreadKind::Item { .. } => "item",
+        }
+    }
+}
+
+/// The kinds of land a region can be, each with its own stat personality
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Biome {
+    Plains,
+    Mountains,
+    Forest,
+    Desert,
+    Swamp,
+    Tundra,
+}
+
+impl Biome {
+    pub const ALL: [Biome; 6] = [
+        Biome::Plains,
+        Biome::Mountains,
+        Biome::Forest,
+        Biome::Desert,
+        Biome::Swamp,
+        Biome::Tundra,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Biome::Plains => "Plains",
+            Biome::Mountains => "Mountains",
+            Biome::Forest => "Forest",
+            Biome::Desert => "Desert",
+            Biome::Swamp => "Swamp",
+            Biome::Tundra => "Tundra",
+        }
+    }
+
+    /// What entering a tile of this land costs a traveller, plains being 1
+    pub fn move_cost(self) -> u32 {
+        match self {
+            Biome::Plains => 1,
+            Biome::Desert | Biome::Tundra | Biome::Forest => 2,
+            Biome::Swamp => 3,
+            Biome::Mountains => 4,
+        }
+    }
+
+    /// Whether the land is too high or too dense to see through
+    pub fn blocks_sight(self) -> bool {
+        matches!(self, Biome::Mountains | Biome::Forest)
+    }
+
+    /// The biome named in a region's description ("Scheming Forests")
+    pub fn of(description: &str) -> Option<Biome> {
+        let description = description.to_lowercase();
+        Biome::ALL.into_iter().find(|biome| {
+            let name = biome.name().to_lowercase();
+            // "Forests" and "Mountain" both name their biome
+            description.contains(name.trim_end_matches('s'))
+        })
+    }
+}
+
+/// The kinds of weather, each with its own stat personality
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WeatherSystem {
+    Thunderstorm,
+    GentleRain,
+    Blizzard,
+    MagicalAurora,
+}
+
+impl WeatherSystem {
+    pub const ALL: [WeatherSystem; 4] = [
+        WeatherSystem::Thunderstorm,
+        WeatherSystem::GentleRain,
+        WeatherSystem::Blizzard,
+        WeatherSystem::MagicalAurora,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WeatherSystem::Thunderstorm => "Thunderstorm",
+            WeatherSystem::GentleRain => "Gentle Rain",
+            WeatherSystem::Blizzard => "Blizzard",
+            WeatherSystem::MagicalAurora => "Magical Aurora",
+        }
+    }
+
+    /// The system's stats, from the design: a thunderstorm is strong and
+    /// awe-inspiring, a blizzard enduring and mindless
+    pub fn properties(self) -> ThreadProperties {
+        match self {
+            WeatherSystem::Thunderstorm => ThreadProperties {
+                strength: 16,
+                charisma: 14,
+                ..Default::default()
+            },
+            WeatherSystem::GentleRain => ThreadProperties {
+                wisdom: 12,
+                connections: 10,
+                ..Default::default()
+            },
+            WeatherSystem::Blizzard => ThreadProperties {
+                constitution: 20,
+                intelligence: 4,
+                ..Default::default()
+            },
+            WeatherSystem::MagicalAurora => ThreadProperties {
+                intelligence: 15,
+                luck: 18,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// The stat the system forms with, contested against the land
+    pub fn stat(self) -> Stat {
+        match self {
+            WeatherSystem::Thunderstorm => Stat::Strength,
+            WeatherSystem::GentleRain => Stat::Wisdom,
+            WeatherSystem::Blizzard => Stat::Constitution,
+            WeatherSystem::MagicalAurora => Stat::Luck,
+        }
+    }
+
+    /// How far from its centre the system reaches
+    pub fn radius(self) -> i32 {
+        match self {
+            WeatherSystem::Thunderstorm => 3,
+            WeatherSystem::GentleRain => 4,
+            WeatherSystem::Blizzard => 5,
+            WeatherSystem::MagicalAurora => 6,
+        }
+    }
+
+    /// How clear the air is inside, from 0 (nothing can be seen) to 1
+    pub fn clarity(self) -> f32 {
+        match self {
+            WeatherSystem::Thunderstorm => 0.6,
+            WeatherSystem::GentleRain => 0.8,
+            WeatherSystem::Blizzard => 0.4,
+            WeatherSystem::MagicalAurora => 1.0,
+        }
+    }
+
+    /// What the system adds to the rolls of Threads inside it contesting
+    /// with `stat`
+    pub fn modifier(self, stat: Stat) -> i32 {
+        match (self, stat) {
+            // Awe and terror, but hard to keep footing or look closely
+            (WeatherSystem::Thunderstorm, Stat::Charisma) => 2,
+            (WeatherSystem::Thunderstorm, Stat::Dexterity | Stat::Wisdom) => -2,
+            // Talkative company
+            (WeatherSystem::GentleRain, Stat::Charisma | Stat::Connections) => 2,
+            // Survival strips away everything but endurance
+            (WeatherSystem::Blizzard, Stat::Constitution) => 2,
+            (WeatherSystem::Blizzard, Stat::Dexterity | Stat::Intelligence | Stat::Wisdom) => -3,
+            // Discovery and unexpected chances
+            (WeatherSystem::MagicalAurora, Stat::Intelligence | Stat::Wisdom) => 2,
+            (WeatherSystem::MagicalAurora, Stat::Luck) => 3,
+            _ => 0,
+        }
+    }
+
+    /// Whether being caught in the system is dangerous
+    pub fn is_severe(self) -> bool {
+        matches!(self, WeatherSystem::Thunderstorm | WeatherSystem::Blizzard)
+    }
+
+    /// The weather a biome breeds
+    pub fn bred_by(biome: Biome) -> &'static [WeatherSystem] {
+        match biome {
+            Biome::Plains => &[WeatherSystem::Thunderstorm, WeatherSystem::GentleRain],
+            Biome::Mountains => &[WeatherSystem::Thunderstorm, WeatherSystem::Blizzard],
+            Biome::Forest | Biome::Swamp => &[WeatherSystem::GentleRain],
+            Biome::Desert => &[WeatherSystem::Thunderstorm],
+            Biome::Tundra => &[WeatherSystem::Blizzard, WeatherSystem::MagicalAurora],
+        }
+    }
 }
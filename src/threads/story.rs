@@ -0,0 +1,356 @@
+//! Stories - Threads that want to reach their end
+//!
+//! A story runs through stages, the major beats of its arc, one at a time.
+//! Each stage sets goals of four kinds, each tied to a biome; a goal is met
+//! by enough distinct Threads in that biome taking part in the right world
+//! event, and the stage ends when all its goals are met.
+//!
+//! A story left without its hero resolves itself one way or another; once
+//! it has an [`Outcome`] nothing moves it on.
+
+use super::{Biome, ThreadId};
+use crate::weaver::properties::Stat;
+use serde::{Deserialize, Serialize};
+
+/// The kinds of goal a stage can set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GoalKind {
+    /// Learn about the land and those in it
+    Knowledge,
+    /// Get to know its people
+    Relationship,
+    /// Obtain what the story needs
+    Resource,
+    /// Come through its hardships
+    Challenge,
+}
+
+impl GoalKind {
+    pub const ALL: [GoalKind; 4] = [
+        GoalKind::Knowledge,
+        GoalKind::Relationship,
+        GoalKind::Resource,
+        GoalKind::Challenge,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GoalKind::Knowledge => "knowledge",
+            GoalKind::Relationship => "relationship",
+            GoalKind::Resource => "resource",
+            GoalKind::Challenge => "challenge",
+        }
+    }
+
+    /// The stats that make a story favour goals of this kind, the first
+    /// deciding how much they ask
+    pub fn drives(self) -> [Stat; 2] {
+        match self {
+            GoalKind::Knowledge => [Stat::Intelligence, Stat::Wisdom],
+            GoalKind::Relationship => [Stat::Charisma, Stat::Connections],
+            GoalKind::Resource => [Stat::Resources, Stat::Luck],
+            GoalKind::Challenge => [Stat::Strength, Stat::Constitution],
+        }
+    }
+}
+
+/// Something that happened in the world that a story may be waiting for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorldEvent {
+    /// The player learned all there is to see of a Thread
+    Studied { subject: ThreadId, biome: Option<Biome> },
+    /// The player stood beside a peaceful NPC
+    Met { npc: ThreadId, biome: Option<Biome> },
+    /// The player took an item
+    Obtained { item: ThreadId, biome: Option<Biome> },
+    /// The player came through a trial: hard ground or severe weather
+    Endured { trial: ThreadId, biome: Option<Biome> },
+}
+
+impl WorldEvent {
+    /// The kind of goal the event counts towards
+    pub fn goal_kind(&self) -> GoalKind {
+        match self {
+            WorldEvent::Studied { .. } => GoalKind::Knowledge,
+            WorldEvent::Met { .. } => GoalKind::Relationship,
+            WorldEvent::Obtained { .. } => GoalKind::Resource,
+            WorldEvent::Endured { .. } => GoalKind::Challenge,
+        }
+    }
+
+    /// The Thread the event happened to
+    pub fn thread(&self) -> ThreadId {
+        match self {
+            WorldEvent::Studied { subject: id, .. }
+            | WorldEvent::Met { npc: id, .. }
+            | WorldEvent::Obtained { item: id, .. }
+            | WorldEvent::Endured { trial: id, .. } => *id,
+        }
+    }
+
+    /// The land where it happened, None in the wilderness
+    pub fn biome(&self) -> Option<Biome> {
+        match self {
+            WorldEvent::Studied { biome, .. }
+            | WorldEvent::Met { biome, .. }
+            | WorldEvent::Obtained { biome, .. }
+            | WorldEvent::Endured { biome, .. } => *biome,
+        }
+    }
+}
+
+/// One achievement a stage asks for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Goal {
+    pub kind: GoalKind,
+    pub biome: Biome,
+    /// How many distinct Threads must take part
+    pub needed: u32,
+    /// The Threads that have taken part so far
+    #[serde(default)]
+    pub counted: Vec<ThreadId>,
+    /// The regions the story claimed for the goal when it was woven
+    #[serde(default)]
+    pub regions: Vec<ThreadId>,
+}
+
+impl Goal {
+    pub fn new(kind: GoalKind, biome: Biome, needed: u32) -> Self {
+        Self {
+            kind,
+            biome,
+            needed,
+            counted: Vec::new(),
+            regions: Vec::new(),
+        }
+    }
+
+    /// What the goal asks, e.g. "Brave the Mountains"
+    pub fn describe(&self) -> String {
+        let biome = self.biome.name();
+        match self.kind {
+            GoalKind::Knowledge => format!("Study the {}", biome),
+            GoalKind::Relationship => format!("Meet the folk of the {}", biome),
+            GoalKind::Resource => format!("Find what the {} hides", biome),
+            GoalKind::Challenge => format!("Brave the {}", biome),
+        }
+    }
+
+    pub fn progress(&self) -> u32 {
+        self.counted.len() as u32
+    }
+
+    pub fn is_met(&self) -> bool {
+        self.progress() >= self.needed
+    }
+
+    /// Count `event` towards the goal, returning whether it did
+    pub fn record(&mut self, event: &WorldEvent) -> bool {
+        let counts = !self.is_met()
+            && event.goal_kind() == self.kind
+            && event.biome() == Some(self.biome)
+            && !self.counted.contains(&event.thread());
+        if counts {
+            self.counted.push(event.thread());
+        }
+        counts
+    }
+}
+
+/// A major beat of a story
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stage {
+    pub name: String,
+    pub goals: Vec<Goal>,
+}
+
+impl Stage {
+    pub fn is_complete(&self) -> bool {
+        self.goals.iter().all(Goal::is_met)
+    }
+}
+
+/// How a world event moved a story on
+#[derive(Debug, Clone, PartialEq)]
+pub enum Advance {
+    /// A goal of the current stage was met
+    GoalMet(String),
+    /// The stage is over and the next, if any, begins
+    StageCompleted(String),
+    /// The last stage is over
+    Completed,
+}
+
+/// How a story ended without its hero
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    /// It came to its end anyway, for the better
+    Success,
+    /// It fell apart, leaving damage behind
+    Failure,
+    /// Nothing came of it; it lives on as a Neglected legend
+    Stagnation,
+}
+
+impl Outcome {
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Failure => "failure",
+            Outcome::Stagnation => "stagnation",
+        }
+    }
+
+    /// What became of the story, e.g. "fell apart"
+    pub fn describe(self) -> &'static str {
+        match self {
+            Outcome::Success => "ended well",
+            Outcome::Failure => "fell apart",
+            Outcome::Stagnation => "faded into a Neglected legend",
+        }
+    }
+}
+
+/// A story and how far it has come
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Story {
+    pub title: String,
+    pub stages: Vec<Stage>,
+    /// Index of the stage under way; the length of `stages` once complete
+    pub stage: usize,
+    /// Whether the story has shaped the land yet
+    #[serde(default)]
+    pub woven: bool,
+    /// How it resolved itself, if it had to
+    #[serde(default)]
+    pub outcome: Option<Outcome>,
+}
+
+impl Story {
+    /// The stage under way, None once the story is complete
+    pub fn current(&self) -> Option<&Stage> {
+        self.stages.get(self.stage)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.stage >= self.stages.len()
+    }
+
+    /// Whether the story is done with, complete or resolved
+    pub fn is_over(&self) -> bool {
+        self.is_complete() || self.outcome.is_some()
+    }
+
+    /// Count `event` towards the current stage's goals, moving on to the
+    /// next stage once every goal is met
+    pub fn record(&mut self, event: &WorldEvent) -> Vec<Advance> {
+        if self.outcome.is_some() {
+            return Vec::new();
+        }
+        let Some(stage) = self.stages.get_mut(self.stage) else {
+            return Vec::new();
+        };
+        let mut advances: Vec<Advance> = stage
+            .goals
+            .iter_mut()
+            .filter_map(|goal| (goal.record(event) && goal.is_met()).then(|| Advance::GoalMet(goal.describe())))
+            .collect();
+        if !advances.is_empty() && stage.is_complete() {
+            advances.push(Advance::StageCompleted(stage.name.clone()));
+            self.stage += 1;
+            if self.is_complete() {
+                advances.push(Advance::Completed);
+            }
+        }
+        advances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story() -> Story {
+        Story {
+            title: "The Quiet Road".to_string(),
+            stages: vec![
+                Stage {
+                    name: "The Call".to_string(),
+                    goals: vec![
+                        Goal::new(GoalKind::Knowledge, Biome::Forest, 2),
+                        Goal::new(GoalKind::Challenge, Biome::Mountains, 1),
+                    ],
+                },
+                Stage {
+                    name: "The Reckoning".to_string(),
+                    goals: vec![Goal::new(GoalKind::Relationship, Biome::Plains, 1)],
+                },
+            ],
+            stage: 0,
+            woven: false,
+            outcome: None,
+        }
+    }
+
+    fn studied(id: u64, biome: Biome) -> WorldEvent {
+        WorldEvent::Studied {
+            subject: ThreadId(id),
+            biome: Some(biome),
+        }
+    }
+
+    #[test]
+    fn goals_count_distinct_threads_in_their_biome() {
+        let mut goal = Goal::new(GoalKind::Knowledge, Biome::Forest, 2);
+        assert!(goal.record(&studied(1, Biome::Forest)));
+        assert!(!goal.record(&studied(1, Biome::Forest)));
+        assert!(!goal.record(&studied(2, Biome::Plains)));
+        let met = WorldEvent::Met {
+            npc: ThreadId(3),
+            biome: Some(Biome::Forest),
+        };
+        assert!(!goal.record(&met));
+        assert!(!goal.is_met());
+        assert!(goal.record(&studied(4, Biome::Forest)));
+        assert!(goal.is_met());
+        assert_eq!(goal.progress(), 2);
+        assert_eq!(goal.describe(), "Study the Forest");
+    }
+
+    #[test]
+    fn stages_advance_when_every_goal_is_met() {
+        let mut story = story();
+        assert_eq!(story.record(&studied(1, Biome::Forest)), vec![]);
+        assert_eq!(
+            story.record(&studied(2, Biome::Forest)),
+            vec![Advance::GoalMet("Study the Forest".to_string())]
+        );
+        let climb = WorldEvent::Endured {
+            trial: ThreadId(5),
+            biome: Some(Biome::Mountains),
+        };
+        assert_eq!(
+            story.record(&climb),
+            vec![
+                Advance::GoalMet("Brave the Mountains".to_string()),
+                Advance::StageCompleted("The Call".to_string()),
+            ]
+        );
+        assert_eq!(story.current().map(|stage| stage.name.as_str()), Some("The Reckoning"));
+
+        let meeting = WorldEvent::Met {
+            npc: ThreadId(6),
+            biome: Some(Biome::Plains),
+        };
+        assert_eq!(
+            story.record(&meeting),
+            vec![
+                Advance::GoalMet("Meet the folk of the Plains".to_string()),
+                Advance::StageCompleted("The Reckoning".to_string()),
+                Advance::Completed,
+            ]
+        );
+        assert!(story.is_complete());
+        assert_eq!(story.record(&meeting), vec![]);
+    }
+}
@@ -2,6 +2,9 @@
 //!
 //! All Thread interactions are resolved through mathematical contests using
 //! the universal formula: 50% + (stat_difference × 5%) + d20 + luck_modifier
+//!
+//! Surroundings such as weather add a modifier to one side's roll; see
+//! [`Contestant`].
 
 use super::properties::{Stat, ThreadProperties};
 use super::states::ThreadStates;
@@ -16,6 +19,36 @@ pub enum ContestResult {
     Failure,
 }
 
+/// One side of a contest: a Thread, the stat it contests with, and any
+/// modifier its surroundings add to its roll
+#[derive(Debug, Clone, Copy)]
+pub struct Contestant<'a> {
+    pub properties: &'a ThreadProperties,
+    pub states: &'a ThreadStates,
+    pub stat: Stat,
+    /// Added to the roll, e.g. by the weather the Thread stands in
+    pub modifier: i32,
+}
+
+impl<'a> Contestant<'a> {
+    pub fn new(properties: &'a ThreadProperties, states: &'a ThreadStates, stat: Stat) -> Self {
+        Self {
+            properties,
+            states,
+            stat,
+            modifier: 0,
+        }
+    }
+
+    /// The same contestant with `modifier` added to its roll
+    pub fn with_modifier(self, modifier: i32) -> Self {
+        Self {
+            modifier: self.modifier + modifier,
+            ..self
+        }
+    }
+}
+
 /// Resolve a contest between two Threads using specified stats
 ///
 /// Uses opposed rolls: Both sides roll d20 + stat + luck modifier
@@ -28,27 +61,39 @@ pub fn resolve_contest(
     defender_states: &ThreadStates,
     defender_stat: Stat,
 ) -> ContestResult {
-    let mut rng = rand::thread_rng();
+    contest(
+        &mut rand::thread_rng(),
+        Contestant::new(initiator_props, initiator_states, initiator_stat),
+        Contestant::new(defender_props, defender_states, defender_stat),
+    )
+}
 
+/// Resolve a contest between two contestants, rolling with `rng`
+///
+/// The same as [`resolve_contest`], with each side's modifier added to its
+/// roll. A seeded `rng` makes the outcome repeatable.
+pub fn contest(rng: &mut impl Rng, initiator: Contestant, defender: Contestant) -> ContestResult {
     // Both sides roll d20
     let initiator_d20: i32 = rng.gen_range(1..=20);
     let defender_d20: i32 = rng.gen_range(1..=20);
 
     // Get stats
-    let initiator_stat_value = initiator_props.get_stat(initiator_stat) as i32;
-    let defender_stat_value = defender_props.get_stat(defender_stat) as i32;
+    let initiator_stat_value = initiator.properties.get_stat(initiator.stat) as i32;
+    let defender_stat_value = defender.properties.get_stat(defender.stat) as i32;
 
     // Calculate luck modifiers (±1 per 5 points of luck)
-    let initiator_luck_mod = initiator_props.luck as i32 / 5;
-    let defender_luck_mod = defender_props.luck as i32 / 5;
+    let initiator_luck_mod = initiator.properties.luck as i32 / 5;
+    let defender_luck_mod = defender.properties.luck as i32 / 5;
 
     // State modifiers (future: implement state effects on contests)
     let (initiator_state_mod, defender_state_mod) =
-        calculate_state_modifiers(initiator_states, defender_states);
+        calculate_state_modifiers(initiator.states, defender.states);
 
     // Calculate total rolls
-    let initiator_total = initiator_d20 + initiator_stat_value + initiator_luck_mod + initiator_state_mod;
-    let defender_total = defender_d20 + defender_stat_value + defender_luck_mod + defender_state_mod;
+    let initiator_total =
+        initiator_d20 + initiator_stat_value + initiator_luck_mod + initiator_state_mod + initiator.modifier;
+    let defender_total =
+        defender_d20 + defender_stat_value + defender_luck_mod + defender_state_mod + defender.modifier;
 
     // Higher roll wins, ties go to defender
     if initiator_total > defender_total {
@@ -125,4 +170,25 @@ mod tests {
         let success_rate = (successes as f32 / iterations as f32) * 100.0;
         assert!(success_rate > 60.0);
     }
+
+    #[test]
+    fn modifiers_shift_the_odds_and_seeded_rolls_repeat() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let props = ThreadProperties::default();
+        let states = ThreadStates::default();
+        let plain = Contestant::new(&props, &states, Stat::Wisdom);
+        let successes = |bonus: i32| {
+            let mut rng = StdRng::seed_from_u64(7);
+            (0..1000)
+                .filter(|_| contest(&mut rng, plain.with_modifier(bonus), plain) == ContestResult::Success)
+                .count()
+        };
+        assert!(successes(5) > successes(0));
+        assert!(successes(-5) < successes(0));
+        assert_eq!(successes(3), successes(3));
+        // Beyond the reach of any roll
+        assert_eq!(successes(20), 1000);
+    }
 }
@@ -11,3 +11,6 @@ pub mod states;
 
 /// Contest Resolution - Universal mathematical system for Thread interactions
 pub mod contests;
+
+/// Perception - How much one Thread can learn about another
+pub mod perception;
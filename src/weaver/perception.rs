@@ -0,0 +1,86 @@
+//! Perception - How much one Thread can learn about another
+//!
+//! Anyone can see what a Thread is and its obvious states. Its stats and
+//! subtler states are only noticed by winning a contest of the observer's
+//! Wisdom against the observed Thread's Dexterity.
+
+use super::contests::{contest, ContestResult, Contestant};
+use super::properties::{Stat, ThreadProperties};
+use super::states::ThreadStates;
+
+/// States at or above this level are obvious without a contest
+pub const OBVIOUS_STATE: f32 = 0.5;
+
+/// How much an observer perceives of a Thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Perception {
+    /// Kind, name and obvious states only
+    Surface,
+    /// Every stat and state
+    Full,
+}
+
+impl Perception {
+    /// Whether a state at `value` can be seen at this level of perception
+    pub fn reveals_state(self, value: f32) -> bool {
+        match self {
+            Perception::Full => value > 0.0,
+            Perception::Surface => value >= OBVIOUS_STATE,
+        }
+    }
+}
+
+/// Contest the observer's Wisdom against the observed Thread's Dexterity,
+/// each with the modifier of its surroundings
+pub fn perceive(
+    observer_props: &ThreadProperties,
+    observer_states: &ThreadStates,
+    observer_modifier: i32,
+    observed_props: &ThreadProperties,
+    observed_states: &ThreadStates,
+    observed_modifier: i32,
+) -> Perception {
+    let observer = Contestant::new(observer_props, observer_states, Stat::Wisdom).with_modifier(observer_modifier);
+    let observed = Contestant::new(observed_props, observed_states, Stat::Dexterity).with_modifier(observed_modifier);
+    match contest(&mut rand::thread_rng(), observer, observed) {
+        ContestResult::Success => Perception::Full,
+        ContestResult::Failure => Perception::Surface,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surface_perception_only_reveals_obvious_states() {
+        assert!(Perception::Surface.reveals_state(0.7));
+        assert!(!Perception::Surface.reveals_state(0.2));
+        assert!(Perception::Full.reveals_state(0.2));
+        assert!(!Perception::Full.reveals_state(0.0));
+    }
+
+    #[test]
+    fn wise_observers_see_through_nimble_threads_more_often() {
+        let wise = ThreadProperties {
+            wisdom: 20,
+            ..Default::default()
+        };
+        let scheming_forest = ThreadProperties {
+            dexterity: 18,
+            ..Default::default()
+        };
+        let dull = ThreadProperties {
+            wisdom: 2,
+            ..Default::default()
+        };
+        let states = ThreadStates::default();
+
+        let full = |observer: &ThreadProperties| {
+            (0..1000)
+                .filter(|_| perceive(observer, &states, 0, &scheming_forest, &states, 0) == Perception::Full)
+                .count()
+        };
+        assert!(full(&wise) > full(&dull));
+    }
+}
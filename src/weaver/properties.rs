@@ -3,8 +3,10 @@
 //! Every Thread in the game (players, NPCs, regions, items, weather) possesses
 //! the same 10 properties on a 0-20 scale.
 
+use serde::{Deserialize, Serialize};
+
 /// The universal 10-stat foundation that all Threads share
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct ThreadProperties {
     // Physical
     pub strength: u8,
@@ -57,6 +59,23 @@ impl ThreadProperties {
             Stat::Luck => self.luck,
         }
     }
+
+    /// Set a specific stat by name
+    pub fn set_stat(&mut self, stat: Stat, value: u8) {
+        let field = match stat {
+            Stat::Strength => &mut self.strength,
+            Stat::Dexterity => &mut self.dexterity,
+            Stat::Constitution => &mut self.constitution,
+            Stat::Intelligence => &mut self.intelligence,
+            Stat::Wisdom => &mut self.wisdom,
+            Stat::Charisma => &mut self.charisma,
+            Stat::Connections => &mut self.connections,
+            Stat::Resources => &mut self.resources,
+            Stat::Reputation => &mut self.reputation,
+            Stat::Luck => &mut self.luck,
+        };
+        *field = value;
+    }
 }
 
 impl Default for ThreadProperties {
@@ -87,6 +106,38 @@ pub enum Stat {
     Luck,
 }
 
+impl Stat {
+    /// All ten stats in display order
+    pub const ALL: [Stat; 10] = [
+        Stat::Strength,
+        Stat::Dexterity,
+        Stat::Constitution,
+        Stat::Intelligence,
+        Stat::Wisdom,
+        Stat::Charisma,
+        Stat::Connections,
+        Stat::Resources,
+        Stat::Reputation,
+        Stat::Luck,
+    ];
+
+    /// Three-letter abbreviation for compact displays
+    pub fn abbrev(self) -> &'static str {
+        match self {
+            Stat::Strength => "Str",
+            Stat::Dexterity => "Dex",
+            Stat::Constitution => "Con",
+            Stat::Intelligence => "Int",
+            Stat::Wisdom => "Wis",
+            Stat::Charisma => "Cha",
+            Stat::Connections => "Cnx",
+            Stat::Resources => "Res",
+            Stat::Reputation => "Rep",
+            Stat::Luck => "Lck",
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
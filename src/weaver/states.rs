@@ -3,8 +3,10 @@
 //! The 10 states that can modify any Thread's weaving capabilities over time.
 //! States flow between Thread types creating temporal continuity.
 
+use serde::{Deserialize, Serialize};
+
 /// Thread States modify how Threads interact through The Weaver
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct ThreadStates {
     // Negative states (0.0 = none, 1.0 = maximum)
     pub damaged: f32,
@@ -38,6 +40,33 @@ impl ThreadStates {
         }
     }
 
+    /// Every state with its name, negative states first
+    pub fn named(&self) -> [(&'static str, f32); 10] {
+        [
+            ("Damaged", self.damaged),
+            ("Corrupted", self.corrupted),
+            ("Stressed", self.stressed),
+            ("Neglected", self.neglected),
+            ("Enhanced", self.enhanced),
+            ("Experienced", self.experienced),
+            ("Connected", self.connected),
+            ("Prestigious", self.prestigious),
+            ("Blessed", self.blessed),
+            ("Adapted", self.adapted),
+        ]
+    }
+
+    /// The strongest state present, if any
+    pub fn strongest(&self) -> Option<(&'static str, f32)> {
+        self.named()
+            .into_iter()
+            .filter(|(_, value)| *value > 0.0)
+            .fold(None, |best, state| match best {
+                Some((_, value)) if value >= state.1 => best,
+                _ => Some(state),
+            })
+    }
+
     /// Clamp all state values to [0.0, 1.0] range
     pub fn clamp(&mut self) {
         self.damaged = self.damaged.clamp(0.0, 1.0);
@@ -81,4 +110,16 @@ mod tests {
         assert_eq!(states.damaged, 1.0);
         assert_eq!(states.enhanced, 0.0);
     }
+
+    #[test]
+    fn strongest_state_wins_ties_in_order() {
+        assert_eq!(ThreadStates::default().strongest(), None);
+        let states = ThreadStates {
+            stressed: 0.4,
+            blessed: 0.7,
+            damaged: 0.7,
+            ..Default::default()
+        };
+        assert_eq!(states.strongest(), Some(("Damaged", 0.7)));
+    }
 }
@@ -4,29 +4,36 @@
 //! can move around using vi-style hjkl commands.
 //!
 //! Uses screenshot-based testing: captures the rendered terminal state
-//! and validates what users actually see.
+//! and validates what users actually see. Rather than sleeping for fixed
+//! times, the harness polls the screen until the expected state appears.
+//! Screen layout details are covered faster by the headless snapshots in
+//! tests/screen_snapshots.rs.
 
 use portable_pty::{CommandBuilder, NativePtySystem, PtySize, PtySystem};
 use std::io::{Read, Write};
-use std::time::Duration;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
 
 const SCREEN_ROWS: usize = 24;
 const SCREEN_COLS: usize = 80;
-const INITIAL_RENDER_DELAY: Duration = Duration::from_millis(1000);
-const INPUT_PROCESSING_DELAY: Duration = Duration::from_millis(300);
-const SCREEN_READ_TIMEOUT: Duration = Duration::from_millis(200);
+/// Allows for `cargo run` building the game before the first frame
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(120);
+/// Longest wait for the screen to show the result of a key press
+const SCREEN_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+const KEY_GAP: Duration = Duration::from_millis(50);
 
 #[test]
 fn player_can_move_with_hjkl_commands() {
     let mut game = VitalisGame::start();
 
     // Player starts at world origin
-    assert_eq!(game.world_position(), (0, 0), "Player should start at origin");
+    assert_eq!(game.wait_for_position((0, 0)), (0, 0), "Player should start at origin");
 
     // Move right with 'l'
     game.press('l');
     assert_eq!(
-        game.world_position(),
+        game.wait_for_position((1, 0)),
         (1, 0),
         "Player should move right to (1, 0)"
     );
@@ -34,7 +41,7 @@ fn player_can_move_with_hjkl_commands() {
     // Move down with 'j'
     game.press('j');
     assert_eq!(
-        game.world_position(),
+        game.wait_for_position((1, 1)),
         (1, 1),
         "Player should move down to (1, 1)"
     );
@@ -45,12 +52,12 @@ fn player_can_use_count_prefixes_for_movement() {
     let mut game = VitalisGame::start();
 
     // Player starts at world origin
-    assert_eq!(game.world_position(), (0, 0), "Player should start at origin");
+    assert_eq!(game.wait_for_position((0, 0)), (0, 0), "Player should start at origin");
 
     // Move right 5 times with '5l'
     game.type_text("5l");
     assert_eq!(
-        game.world_position(),
+        game.wait_for_position((5, 0)),
         (5, 0),
         "Player should move right 5 times to (5, 0)"
     );
@@ -58,7 +65,7 @@ fn player_can_use_count_prefixes_for_movement() {
     // Move down 3 times with '3j'
     game.type_text("3j");
     assert_eq!(
-        game.world_position(),
+        game.wait_for_position((5, 3)),
         (5, 3),
         "Player should move down 3 times to (5, 3)"
     );
@@ -66,7 +73,7 @@ fn player_can_use_count_prefixes_for_movement() {
     // Move left 2 times with '2h'
     game.type_text("2h");
     assert_eq!(
-        game.world_position(),
+        game.wait_for_position((3, 3)),
         (3, 3),
         "Player should move left 2 times to (3, 3)"
     );
@@ -74,7 +81,7 @@ fn player_can_use_count_prefixes_for_movement() {
     // Move up 10 times with '10k'
     game.type_text("10k");
     assert_eq!(
-        game.world_position(),
+        game.wait_for_position((3, -7)),
         (3, -7),
         "Player should move up 10 times to (3, -7)"
     );
@@ -88,6 +95,9 @@ fn player_quits_with_colon_q_command() {
     game.press('q');
     assert!(game.is_running(), "Game should still be running after 'q' key");
 
+    // 'q' is the start of 'q:' (command-line window); cancel it with ESC
+    game.press('\x1b');
+
     // Enter command mode with ':'
     game.press(':');
 
@@ -97,10 +107,7 @@ fn player_quits_with_colon_q_command() {
     // Press Enter to execute
     game.press('\r');
 
-    // Give the game time to process quit and exit
-    std::thread::sleep(Duration::from_millis(500));
-
-    assert!(!game.is_running(), "Game should quit after :q command");
+    assert!(game.wait_for_exit(), "Game should quit after :q command");
 }
 
 #[test]
@@ -112,10 +119,7 @@ fn player_quits_with_colon_quit_command() {
     game.type_text("quit");
     game.press('\r');
 
-    // Give the game time to process quit and exit
-    std::thread::sleep(Duration::from_millis(500));
-
-    assert!(!game.is_running(), "Game should quit after :quit command");
+    assert!(game.wait_for_exit(), "Game should quit after :quit command");
 }
 
 #[test]
@@ -134,17 +138,32 @@ fn player_can_backspace_in_ex_mode() {
     game.type_text("it");
     game.press('\r');
 
-    // Give the game time to process quit and exit
-    std::thread::sleep(Duration::from_millis(500));
+    assert!(game.wait_for_exit(), "Game should quit after typing :quit with backspaces");
+}
+
+#[test]
+fn unknown_ex_command_is_reported_on_command_line() {
+    let mut game = VitalisGame::start();
+
+    // The player is drawn on the map
+    assert!(find_char(&game.screen.get_screen(), '@').is_some(), "Player should be drawn");
+
+    game.press(':');
+    game.type_text("frobnicate");
+    game.press('\r');
 
-    assert!(!game.is_running(), "Game should quit after typing :quit with backspaces");
+    assert!(
+        game.wait_for(|screen| screen_text(screen).contains("E492: Not an editor command: frobnicate")),
+        "Error should be shown on the command line"
+    );
+    assert!(game.is_running(), "Unknown commands should not quit");
 }
 
 /// Test harness for running and interacting with the Vitalis game
 struct VitalisGame {
     screen: ScreenBuffer,
     writer: Box<dyn Write + Send>,
-    reader: Box<dyn Read + Send>,
+    output: Receiver<Vec<u8>>,
     child: Box<dyn portable_pty::Child + Send + Sync>,
 }
 
@@ -163,34 +182,31 @@ impl VitalisGame {
         let mut cmd = CommandBuilder::new("cargo");
         cmd.arg("run");
         cmd.arg("--quiet");
+        // Ignore the developer's own vitalisrc and command history
+        cmd.args(["--", "-u", "NONE", "-i", "NONE"]);
         cmd.cwd(std::env::current_dir().expect("Failed to get current directory"));
 
         let child = pair.slave.spawn_command(cmd).expect("Failed to spawn game");
-        let mut reader = pair.master.try_clone_reader().expect("Failed to clone reader");
+        let reader = pair.master.try_clone_reader().expect("Failed to clone reader");
         let writer = pair.master.take_writer().expect("Failed to get writer");
 
-        let mut screen = ScreenBuffer::new();
-
-        // Wait for initial render
-        std::thread::sleep(INITIAL_RENDER_DELAY);
-        screen.update(&mut reader);
-
-        Self {
-            screen,
+        let mut game = Self {
+            screen: ScreenBuffer::new(),
             writer,
-            reader,
+            output: spawn_reader(reader),
             child,
-        }
+        };
+
+        // Wait for the first frame
+        let started = game.wait_for_within(STARTUP_TIMEOUT, |screen| find_char(screen, '@').is_some());
+        assert!(started, "Game did not draw the player in time");
+        game
     }
 
     fn press(&mut self, key: char) {
-        self.writer
-            .write_all(key.to_string().as_bytes())
-            .expect("Failed to send key");
-        self.writer.flush().expect("Failed to flush");
-
-        std::thread::sleep(INPUT_PROCESSING_DELAY);
-        self.screen.update(&mut self.reader);
+        self.type_text(&key.to_string());
+        // Keep keys apart so an Esc is not read as Alt with the next key
+        std::thread::sleep(KEY_GAP);
     }
 
     fn type_text(&mut self, text: &str) {
@@ -198,14 +214,33 @@ impl VitalisGame {
             .write_all(text.as_bytes())
             .expect("Failed to send text");
         self.writer.flush().expect("Failed to flush");
+        self.screen.update(&self.output);
+    }
 
-        std::thread::sleep(INPUT_PROCESSING_DELAY);
-        self.screen.update(&mut self.reader);
+    /// Poll the screen until `condition` holds, giving up after a while
+    fn wait_for(&mut self, condition: impl Fn(&[Vec<char>]) -> bool) -> bool {
+        self.wait_for_within(SCREEN_TIMEOUT, condition)
+    }
+
+    fn wait_for_within(&mut self, timeout: Duration, condition: impl Fn(&[Vec<char>]) -> bool) -> bool {
+        let start = Instant::now();
+        loop {
+            self.screen.update(&self.output);
+            if condition(&self.screen.screen) {
+                return true;
+            }
+            if start.elapsed() > timeout {
+                return false;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
     }
 
-    fn world_position(&self) -> (i32, i32) {
+    /// Wait for the player to reach `expected`, returning where they are
+    fn wait_for_position(&mut self, expected: (i32, i32)) -> (i32, i32) {
+        self.wait_for(|screen| extract_world_position(screen) == Some(expected));
         extract_world_position(&self.screen.get_screen())
-            .expect("Could not find player position in status bar")
+            .expect("Could not find player position on the mode line")
     }
 
     fn is_running(&mut self) -> bool {
@@ -217,16 +252,46 @@ impl VitalisGame {
         }
     }
 
-    fn screen_text(&self) -> String {
-        let screen = self.screen.get_screen();
-        screen
-            .iter()
-            .map(|row| row.iter().collect::<String>())
-            .collect::<Vec<_>>()
-            .join("\n")
+    /// Wait for the game to exit, returning whether it did
+    fn wait_for_exit(&mut self) -> bool {
+        let start = Instant::now();
+        while self.is_running() {
+            if start.elapsed() > SCREEN_TIMEOUT {
+                return false;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        true
     }
 }
 
+/// Read the game's output on a thread so the tests never block on it
+fn spawn_reader(mut reader: Box<dyn Read + Send>) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buffer = [0u8; 8192];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if sender.send(buffer[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    receiver
+}
+
+fn screen_text(screen: &[Vec<char>]) -> String {
+    screen
+        .iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn find_char(screen: &[Vec<char>], target: char) -> Option<(usize, usize)> {
     for (row_idx, row) in screen.iter().enumerate() {
         for (col_idx, &ch) in row.iter().enumerate() {
@@ -253,18 +318,30 @@ impl Drop for VitalisGame {
 /// Persistent screen buffer that accumulates terminal updates
 struct ScreenBuffer {
     screen: Vec<Vec<char>>,
+    /// Bytes of a UTF-8 character split across two reads
+    partial: Vec<u8>,
 }
 
 impl ScreenBuffer {
     fn new() -> Self {
         Self {
             screen: vec![vec![' '; SCREEN_COLS]; SCREEN_ROWS],
+            partial: Vec::new(),
         }
     }
 
-    fn update(&mut self, reader: &mut (dyn Read + Send)) {
-        let output = read_available_output(reader, SCREEN_READ_TIMEOUT);
-        self.apply_ansi_updates(&output);
+    /// Apply everything the game has written so far
+    fn update(&mut self, output: &Receiver<Vec<u8>>) {
+        while let Ok(bytes) = output.try_recv() {
+            self.partial.extend(bytes);
+        }
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(text) => text.len(),
+            Err(err) => err.valid_up_to(),
+        };
+        let text = String::from_utf8_lossy(&self.partial[..valid]).into_owned();
+        self.partial.drain(..valid);
+        self.apply_ansi_updates(&text);
     }
 
     fn get_screen(&self) -> Vec<Vec<char>> {
@@ -279,37 +356,15 @@ impl ScreenBuffer {
         while let Some(ch) = chars.next() {
             if ch == '\x1b' {
                 handle_escape_sequence(&mut chars, &mut cursor_row, &mut cursor_col);
-            } else if !ch.is_control() || ch == ' ' {
-                if cursor_row < SCREEN_ROWS && cursor_col < SCREEN_COLS {
-                    self.screen[cursor_row][cursor_col] = ch;
-                    cursor_col += 1;
-                }
-            }
-        }
-    }
-}
-
-fn read_available_output(reader: &mut (dyn Read + Send), timeout: Duration) -> String {
-    let mut output = String::new();
-    let mut buffer = [0u8; 8192];
-    let start = std::time::Instant::now();
-
-    while start.elapsed() < timeout {
-        match reader.read(&mut buffer) {
-            Ok(0) => break,
-            Ok(n) => {
-                if let Ok(s) = std::str::from_utf8(&buffer[..n]) {
-                    output.push_str(s);
-                }
-            }
-            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                std::thread::sleep(Duration::from_millis(10));
+            } else if (!ch.is_control() || ch == ' ')
+                && cursor_row < SCREEN_ROWS
+                && cursor_col < SCREEN_COLS
+            {
+                self.screen[cursor_row][cursor_col] = ch;
+                cursor_col += 1;
             }
-            Err(_) => break,
         }
     }
-
-    output
 }
 
 fn handle_escape_sequence(
@@ -382,28 +437,17 @@ fn skip_dec_private_mode(chars: &mut std::iter::Peekable<std::str::Chars>) {
 }
 
 fn extract_world_position(screen: &[Vec<char>]) -> Option<(i32, i32)> {
-    // Status bar is in the first few rows
-    for row_idx in 0..3 {
-        let line: String = screen[row_idx].iter().collect();
-
-        if let Some(x_pos) = line.find("x: ") {
-            let after_x = &line[x_pos + 3..];
-            let x_str: String = after_x
-                .chars()
-                .take_while(|c| c.is_numeric() || *c == '-')
-                .collect();
-
-            if let Ok(x) = x_str.parse::<i32>() {
-                if let Some(y_pos) = after_x.find("y: ") {
-                    let after_y = &after_x[y_pos + 3..];
-                    let y_str: String = after_y
-                        .chars()
-                        .take_while(|c| c.is_numeric() || *c == '-')
-                        .collect();
-
-                    if let Ok(y) = y_str.parse::<i32>() {
-                        return Some((x, y));
-                    }
+    // The mode line shows world coordinates as [x,y]
+    for row in screen.iter().rev() {
+        let line: String = row.iter().collect();
+        for (start, _) in line.match_indices('[') {
+            let Some(end) = line[start..].find(']') else {
+                continue;
+            };
+            let coords = &line[start + 1..start + end];
+            if let Some((x, y)) = coords.split_once(',') {
+                if let (Ok(x), Ok(y)) = (x.parse::<i32>(), y.parse::<i32>()) {
+                    return Some((x, y));
                 }
             }
         }
@@ -437,16 +481,19 @@ mod unit_tests {
     }
 
     #[test]
-    fn extract_world_position_parses_status_bar() {
+    fn extract_world_position_parses_mode_line() {
         let mut screen = vec![vec![' '; SCREEN_COLS]; SCREEN_ROWS];
-        let status_line = "│ Wanderer | Pos: Some(Position { x: 42, y: 17 })";
-        for (i, ch) in status_line.chars().enumerate() {
-            if i < SCREEN_COLS {
-                screen[1][i] = ch;
+        let message_line = "│ » Moved [3/27] │";
+        let mode_line = "│ -- NORMAL --          [42,-17] │";
+        for (row, text) in [(2, message_line), (20, mode_line)] {
+            for (i, ch) in text.chars().enumerate() {
+                if i < SCREEN_COLS {
+                    screen[row][i] = ch;
+                }
             }
         }
 
         let pos = extract_world_position(&screen);
-        assert_eq!(pos, Some((42, 17)), "Should extract position from status");
+        assert_eq!(pos, Some((42, -17)), "Should extract position from mode line");
     }
 }
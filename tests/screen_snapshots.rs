@@ -0,0 +1,97 @@
+//! Screen snapshots - the whole screen rendered headlessly after key input
+//!
+//! Snapshots live in tests/snapshots. When a change to the screen is
+//! intended, rerun with VITALIS_UPDATE_SNAPSHOTS=1 and review the diff.
+
+use vitalis::loom::Loom;
+use vitalis::patterns::headless::Headless;
+use vitalis::tapestry::calendar::TURNS_PER_HOUR;
+
+/// Every snapshot's world is woven from the same seed
+const SEED: u64 = 1;
+
+fn new_game() -> Headless {
+    Headless::new(Loom::new_game_with_seed(SEED))
+}
+
+#[test]
+fn start_screen() {
+    new_game().assert_snapshot("start_screen");
+}
+
+#[test]
+fn moving_scrolls_the_map() {
+    let mut game = new_game();
+    game.keys("4l2k");
+    game.assert_snapshot("moved_4l2k");
+}
+
+#[test]
+fn unknown_command_error() {
+    let mut game = new_game();
+    game.keys(":frobnicate<CR>");
+    game.assert_snapshot("unknown_command_error");
+}
+
+#[test]
+fn ex_command_line_being_typed() {
+    let mut game = new_game();
+    game.keys(":set ch");
+    game.assert_snapshot("ex_command_line");
+}
+
+#[test]
+fn block_visual_selection() {
+    let mut game = new_game();
+    game.keys("<C-v>2l2j");
+    game.assert_snapshot("block_visual");
+}
+
+#[test]
+fn looking_at_yourself() {
+    let mut game = new_game();
+    game.keys("x");
+    game.assert_snapshot("look_self");
+}
+
+#[test]
+fn help_index() {
+    let mut game = new_game();
+    game.keys(":help<CR>");
+    game.assert_snapshot("help_index");
+}
+
+#[test]
+fn small_terminal() {
+    let mut game = Headless::with_size(Loom::new_game_with_seed(SEED), 30, 12);
+    game.keys("2h");
+    game.assert_snapshot("small_terminal");
+}
+
+#[test]
+fn zoomed_out() {
+    let mut game = new_game();
+    game.keys("zozo");
+    game.assert_snapshot("zoomed_out");
+}
+
+#[test]
+fn atlas_overview() {
+    let mut game = new_game();
+    game.keys("3lm;2j:atlas<CR>");
+    game.assert_snapshot("atlas");
+}
+
+#[test]
+fn minimap_in_the_corner() {
+    let mut game = new_game();
+    game.keys(":set minimap<CR>6l");
+    game.assert_snapshot("minimap");
+}
+
+#[test]
+fn night_on_the_status_bar() {
+    let mut game = new_game();
+    game.loom.tapestry.pass_time(14 * TURNS_PER_HOUR);
+    game.assert_snapshot("night_status");
+}